@@ -0,0 +1,74 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Account, AccountError};
+use snarkvm::{
+    console::types::Field,
+    prelude::{Network, ViewKey},
+};
+
+/// The domain separator for the subkey derivation.
+const SUBKEY_DOMAIN: &str = "AleoSubkey0";
+
+impl<N: Network> Account<N> {
+    /// Returns the view key derived from the account private key for the given index.
+    ///
+    /// The derivation is deterministic, so the same index always yields the same view key,
+    /// and distinct indices yield distinct view keys, without storing a private key per index.
+    pub fn derive_subkey(&self, index: u32) -> Result<ViewKey<N>, AccountError> {
+        // Construct the preimage from the private key seed and the index.
+        let preimage = [Field::new_domain_separator(SUBKEY_DOMAIN), self.private_key.seed(), Field::from_u32(index)];
+        // Hash the preimage to a scalar.
+        let scalar =
+            N::hash_to_scalar_psd4(&preimage).map_err(|error| AccountError::SubkeyDerivationFailed(error.to_string()))?;
+        Ok(ViewKey::from_scalar(scalar))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::prelude::{TestRng, Testnet3};
+
+    use std::collections::HashSet;
+
+    type CurrentNetwork = Testnet3;
+
+    #[test]
+    fn test_derive_subkey_is_deterministic() {
+        let mut rng = TestRng::default();
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+
+        // Check that the same index yields the same view key.
+        for index in [0, 1, u32::MAX] {
+            assert_eq!(account.derive_subkey(index).unwrap(), account.derive_subkey(index).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_derive_subkey_is_distinct() {
+        let mut rng = TestRng::default();
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+
+        // Check that distinct indices yield distinct view keys.
+        let subkeys = (0..100).map(|index| account.derive_subkey(index).unwrap().to_string()).collect::<HashSet<_>>();
+        assert_eq!(subkeys.len(), 100);
+        // Check that the view keys differ from the account view key.
+        assert!(!subkeys.contains(&account.view_key().to_string()));
+
+        // Check that another account derives different view keys.
+        let other = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+        assert_ne!(account.derive_subkey(0).unwrap(), other.derive_subkey(0).unwrap());
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Account, AccountError};
+use snarkvm::prelude::{CryptoRng, Network, Rng};
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    ChaCha20Poly1305,
+    Key,
+    Nonce,
+};
+use core::str::FromStr;
+use zeroize::Zeroizing;
+
+/// The version of the encrypted private key format.
+const ENCRYPTION_VERSION: u8 = 1;
+/// The number of bytes in the salt.
+const SALT_SIZE: usize = 16;
+/// The number of bytes in the nonce.
+const NONCE_SIZE: usize = 12;
+
+impl<N: Network> Account<N> {
+    /// Returns the private key, encrypted under the given password.
+    ///
+    /// The encryption key is derived from the password using Argon2, and the checksummed private key
+    /// is encrypted using ChaCha20-Poly1305. The output is a hex string of `version || salt || nonce || ciphertext`.
+    pub fn to_encrypted<R: Rng + CryptoRng>(&self, password: &str, rng: &mut R) -> Result<String, AccountError> {
+        // Sample a random salt and nonce.
+        let salt: [u8; SALT_SIZE] = rng.gen();
+        let nonce: [u8; NONCE_SIZE] = rng.gen();
+        // Derive the encryption key.
+        let cipher = cipher(password, &salt)?;
+        // Encrypt the checksummed private key. The plaintext is zeroized once it is dropped.
+        let plaintext = Zeroizing::new(self.private_key.to_string());
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .map_err(|_| AccountError::EncryptionFailed)?;
+
+        // Construct the output.
+        let mut output = Vec::with_capacity(1 + SALT_SIZE + NONCE_SIZE + ciphertext.len());
+        output.push(ENCRYPTION_VERSION);
+        output.extend_from_slice(&salt);
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        Ok(output.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+
+    /// Initializes an account from the given private key, encrypted under the given password.
+    pub fn from_encrypted(password: &str, ciphertext: &str) -> Result<Self, AccountError> {
+        // Decode the input.
+        if ciphertext.len() % 2 != 0 || !ciphertext.is_ascii() {
+            return Err(AccountError::InvalidCiphertext);
+        }
+        let bytes = (0..ciphertext.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&ciphertext[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| AccountError::InvalidCiphertext)?;
+
+        // Ensure the input is well-formed.
+        if bytes.len() <= 1 + SALT_SIZE + NONCE_SIZE {
+            return Err(AccountError::InvalidCiphertext);
+        }
+        // Ensure the version is supported.
+        if bytes[0] != ENCRYPTION_VERSION {
+            return Err(AccountError::InvalidMetadata(format!("unsupported version {}", bytes[0])));
+        }
+        let (salt, rest) = bytes[1..].split_at(SALT_SIZE);
+        let (nonce, ciphertext) = rest.split_at(NONCE_SIZE);
+
+        // Derive the encryption key.
+        let cipher = cipher(password, salt)?;
+        // Decrypt the checksummed private key. This fails if the password is wrong.
+        // The plaintext is zeroized once it is dropped.
+        let plaintext = Zeroizing::new(
+            cipher.decrypt(Nonce::from_slice(nonce), ciphertext).map_err(|_| AccountError::DecryptionFailed)?,
+        );
+        let plaintext = core::str::from_utf8(&plaintext).map_err(|_| AccountError::DecryptionFailed)?;
+
+        // Initialize the account.
+        Self::from_str(plaintext).map_err(|error| AccountError::InvalidPrivateKey(error.to_string()))
+    }
+}
+
+/// Returns the cipher for the encryption key derived from the given password and salt.
+/// The derived key is zeroized once the cipher is initialized.
+fn cipher(password: &str, salt: &[u8]) -> Result<ChaCha20Poly1305, AccountError> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, key.as_mut())
+        .map_err(|error| AccountError::KeyDerivationFailed(error.to_string()))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(key.as_ref())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::prelude::{TestRng, Testnet3};
+
+    type CurrentNetwork = Testnet3;
+
+    #[test]
+    fn test_encrypted_roundtrip() {
+        let mut rng = TestRng::default();
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+
+        // Encrypt and decrypt the private key.
+        let ciphertext = account.to_encrypted("password", &mut rng).unwrap();
+        let candidate = Account::<CurrentNetwork>::from_encrypted("password", &ciphertext).unwrap();
+        assert_eq!(account.private_key(), candidate.private_key());
+        assert_eq!(account.address(), candidate.address());
+    }
+
+    #[test]
+    fn test_encrypted_wrong_password() {
+        let mut rng = TestRng::default();
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+
+        // Encrypt the private key, and decrypt it with the wrong password.
+        let ciphertext = account.to_encrypted("password", &mut rng).unwrap();
+        let result = Account::<CurrentNetwork>::from_encrypted("wrong password", &ciphertext);
+        assert_eq!(result.unwrap_err(), AccountError::DecryptionFailed);
+    }
+
+    #[test]
+    fn test_encrypted_nonce_differs() {
+        let mut rng = TestRng::default();
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+
+        // Encrypt the same private key twice.
+        let first = account.to_encrypted("password", &mut rng).unwrap();
+        let second = account.to_encrypted("password", &mut rng).unwrap();
+        assert_ne!(first, second);
+
+        // Check that the nonces differ.
+        let nonce = |ciphertext: &str| ciphertext[2 * (1 + SALT_SIZE)..2 * (1 + SALT_SIZE + NONCE_SIZE)].to_string();
+        assert_ne!(nonce(&first), nonce(&second));
+    }
+
+    #[test]
+    fn test_encrypted_malformed() {
+        let mut rng = TestRng::default();
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+        let ciphertext = account.to_encrypted("password", &mut rng).unwrap();
+
+        // Check that malformed inputs are rejected.
+        let result = Account::<CurrentNetwork>::from_encrypted("password", &ciphertext[1..]);
+        assert_eq!(result.unwrap_err(), AccountError::InvalidCiphertext);
+        let result = Account::<CurrentNetwork>::from_encrypted("password", "zz");
+        assert_eq!(result.unwrap_err(), AccountError::InvalidCiphertext);
+    }
+
+    #[test]
+    fn test_encrypted_unsupported_version() {
+        let mut rng = TestRng::default();
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+        let ciphertext = account.to_encrypted("password", &mut rng).unwrap();
+
+        // Replace the version, and check that the metadata is rejected.
+        let ciphertext = format!("{:02x}{}", ENCRYPTION_VERSION + 1, &ciphertext[2..]);
+        let result = Account::<CurrentNetwork>::from_encrypted("password", &ciphertext);
+        let expected = AccountError::InvalidMetadata(format!("unsupported version {}", ENCRYPTION_VERSION + 1));
+        assert_eq!(result.unwrap_err(), expected);
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt;
+
+/// The errors that can occur when handling an account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccountError {
+    /// The encrypted private key is malformed.
+    InvalidCiphertext,
+    /// The encrypted private key could not be decrypted, e.g. due to a wrong password.
+    DecryptionFailed,
+    /// The private key could not be encrypted.
+    EncryptionFailed,
+    /// The key derivation from the password failed.
+    KeyDerivationFailed(String),
+    /// The private key is invalid.
+    InvalidPrivateKey(String),
+    /// The subkey derivation from the private key failed.
+    SubkeyDerivationFailed(String),
+    /// The encoded private key is malformed.
+    InvalidEncoding(String),
+    /// The given component of the private key does not match the one derived from its seed.
+    ChecksumMismatch { component: &'static str },
+    /// The metadata of the encrypted private key is invalid, e.g. an unsupported version.
+    InvalidMetadata(String),
+    /// The private key or its derived keys could not be generated.
+    KeyGenerationFailed(String),
+}
+
+impl fmt::Display for AccountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCiphertext => write!(f, "The encrypted private key is malformed"),
+            Self::DecryptionFailed => write!(f, "Failed to decrypt the private key (wrong password?)"),
+            Self::EncryptionFailed => write!(f, "Failed to encrypt the private key"),
+            Self::KeyDerivationFailed(error) => write!(f, "Failed to derive the encryption key - {error}"),
+            Self::InvalidPrivateKey(error) => write!(f, "The private key is invalid - {error}"),
+            Self::SubkeyDerivationFailed(error) => write!(f, "Failed to derive the subkey - {error}"),
+            Self::InvalidEncoding(error) => write!(f, "The encoded private key is malformed - {error}"),
+            Self::ChecksumMismatch { component } => {
+                write!(f, "The '{component}' of the private key does not match the one derived from its seed")
+            }
+            Self::InvalidMetadata(error) => write!(f, "The encrypted private key has invalid metadata - {error}"),
+            Self::KeyGenerationFailed(error) => write!(f, "Failed to generate the key - {error}"),
+        }
+    }
+}
+
+impl std::error::Error for AccountError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let cases = [
+            (AccountError::DecryptionFailed, "Failed to decrypt the private key (wrong password?)"),
+            (AccountError::EncryptionFailed, "Failed to encrypt the private key"),
+            (AccountError::InvalidPrivateKey("bad seed".into()), "The private key is invalid - bad seed"),
+            (
+                AccountError::ChecksumMismatch { component: "r_sig" },
+                "The 'r_sig' of the private key does not match the one derived from its seed",
+            ),
+            (
+                AccountError::InvalidMetadata("unsupported version 2".into()),
+                "The encrypted private key has invalid metadata - unsupported version 2",
+            ),
+            (AccountError::KeyGenerationFailed("no entropy".into()), "Failed to generate the key - no entropy"),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(error.to_string(), expected);
+        }
+    }
+}
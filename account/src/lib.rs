@@ -14,6 +14,17 @@
 
 #![forbid(unsafe_code)]
 
+mod derivation;
+
+mod encryption;
+
+mod error;
+pub use error::*;
+
+pub mod private_key;
+
+mod qr;
+
 use snarkvm::{
     console::{network::prelude::*, types::Field},
     prelude::*,
@@ -35,8 +46,22 @@ pub struct Account<N: Network> {
 
 impl<N: Network> Account<N> {
     /// Samples a new account.
+    ///
+    /// Candidate private keys are sampled until one derives a valid account, up to a bounded number of attempts.
+    /// The account only depends on the given RNG, so a seeded RNG always yields the same account.
     pub fn new<R: Rng + CryptoRng>(rng: &mut R) -> Result<Self> {
-        Self::try_from(PrivateKey::new(rng)?)
+        let mut last_error = None;
+        for _ in 0..private_key::MAXIMUM_SAMPLING_ATTEMPTS {
+            // Sample a candidate, and keep it if the keys derived from it are valid.
+            match Self::sample_candidate(rng).and_then(Self::try_from) {
+                Ok(account) => return Ok(account),
+                Err(error) => last_error = Some(error),
+            }
+        }
+        match last_error {
+            Some(error) => Err(error.context("Failed to sample a valid private key")),
+            None => bail!("Failed to sample a valid private key"),
+        }
     }
 
     /// Returns the account private key.
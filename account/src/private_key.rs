@@ -0,0 +1,522 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Account, AccountError};
+use snarkvm::{
+    console::types::{Field, Scalar},
+    prelude::{Address, FromBytes, Network, PrivateKey, ToBytes, Uniform, Zero},
+};
+
+use core::str::FromStr;
+use rand::{rngs::OsRng, CryptoRng, Rng};
+
+/// The human-readable prefix of an encoded private key.
+pub const PRIVATE_KEY_PREFIX: &str = "APrivateKey1";
+/// The number of characters in an encoded private key.
+pub const PRIVATE_KEY_ENCODED_LENGTH: usize = 59;
+/// The number of bytes in each component of a private key.
+pub const PRIVATE_KEY_COMPONENT_LENGTH: usize = 32;
+/// The number of bytes in the raw components of a private key, i.e. the seed, `sk_sig`, and `r_sig`.
+pub const PRIVATE_KEY_BYTES_LENGTH: usize = 3 * PRIVATE_KEY_COMPONENT_LENGTH;
+/// The maximum number of candidate private keys sampled for a new account, before giving up.
+pub const MAXIMUM_SAMPLING_ATTEMPTS: usize = 16;
+
+/// The sizes of the elements that the components of a private key are encoded with, as fixed by the parameters
+/// of a network. A private key is only usable under parameters with the same sizes as the ones it was created under.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct KeyParameters {
+    /// The number of bytes in a field element, i.e. the seed.
+    pub field_length: usize,
+    /// The number of bytes in a scalar element, i.e. `sk_sig` and `r_sig`.
+    pub scalar_length: usize,
+}
+
+impl KeyParameters {
+    /// Returns the parameters of the given network.
+    pub fn of<N: Network>() -> Self {
+        let length = |bytes: anyhow::Result<Vec<u8>>| bytes.map_or(0, |bytes| bytes.len());
+        Self {
+            field_length: length(Field::<N>::zero().to_bytes_le()),
+            scalar_length: length(Scalar::<N>::zero().to_bytes_le()),
+        }
+    }
+}
+
+/// The characters of the base58 alphabet.
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Checks the encoding of the given private key string, without parsing the private key.
+///
+/// This verifies the prefix, the length, and the alphabet of the string, and is intended
+/// as an inexpensive filter on untrusted input. It does not guarantee the key is valid.
+pub fn verify_encoding(private_key: &str) -> Result<(), AccountError> {
+    // Ensure the string has the private key prefix.
+    if !private_key.starts_with(PRIVATE_KEY_PREFIX) {
+        return Err(AccountError::InvalidEncoding(format!("expected the prefix '{PRIVATE_KEY_PREFIX}'")));
+    }
+    // Ensure the string has the expected length.
+    if private_key.len() != PRIVATE_KEY_ENCODED_LENGTH {
+        return Err(AccountError::InvalidEncoding(format!(
+            "expected {PRIVATE_KEY_ENCODED_LENGTH} characters, found {}",
+            private_key.len()
+        )));
+    }
+    // Ensure the string only contains base58 characters.
+    if let Some(c) = private_key.chars().find(|c| !BASE58_ALPHABET.contains(*c)) {
+        return Err(AccountError::InvalidEncoding(format!("'{c}' is not a base58 character")));
+    }
+    Ok(())
+}
+
+impl<N: Network> Account<N> {
+    /// Samples a candidate private key, without validating the keys derived from it.
+    ///
+    /// The seed is drawn from the given RNG, and the signature components are derived from it.
+    /// Use `new` to sample a private key that derives a valid account.
+    pub fn sample_candidate<R: Rng + CryptoRng>(rng: &mut R) -> anyhow::Result<PrivateKey<N>> {
+        PrivateKey::try_from(Field::<N>::rand(rng))
+    }
+
+    /// Samples a new account, using the randomness of the operating system.
+    ///
+    /// Unlike `new`, the caller cannot supply the RNG, so a deterministic or weak RNG cannot be used by mistake.
+    pub fn secure_random() -> Result<Self, AccountError> {
+        let failed = |error: anyhow::Error| AccountError::KeyGenerationFailed(error.to_string());
+        // Sample the private key from the operating system's CSPRNG.
+        let private_key = PrivateKey::new(&mut OsRng).map_err(failed)?;
+        Self::try_from(private_key).map_err(failed)
+    }
+
+    /// Initializes a new account from the components of a private key.
+    ///
+    /// The private key is rederived from the seed, and the given signature components must match it.
+    pub fn from_components(seed: Field<N>, sk_sig: Scalar<N>, r_sig: Scalar<N>) -> Result<Self, AccountError> {
+        // Derive the private key from the seed.
+        let private_key =
+            PrivateKey::try_from(seed).map_err(|error| AccountError::KeyGenerationFailed(error.to_string()))?;
+        // Ensure the signature components are consistent with the seed.
+        if private_key.sk_sig() != sk_sig {
+            return Err(AccountError::ChecksumMismatch { component: "sk_sig" });
+        }
+        if private_key.r_sig() != r_sig {
+            return Err(AccountError::ChecksumMismatch { component: "r_sig" });
+        }
+        Self::try_from(private_key).map_err(|error| AccountError::KeyGenerationFailed(error.to_string()))
+    }
+
+    /// Initializes a new account from the raw components of a private key, without the encoding prefix.
+    ///
+    /// The bytes hold the seed, `sk_sig`, and `r_sig`, in this order, each in little-endian.
+    pub fn from_private_key_bytes(bytes: &[u8]) -> Result<Self, AccountError> {
+        // Ensure the bytes have the expected length.
+        if bytes.len() != PRIVATE_KEY_BYTES_LENGTH {
+            return Err(AccountError::InvalidPrivateKey(format!(
+                "expected {PRIVATE_KEY_BYTES_LENGTH} bytes, found {}",
+                bytes.len()
+            )));
+        }
+        // Read the components.
+        let (seed, rest) = bytes.split_at(PRIVATE_KEY_COMPONENT_LENGTH);
+        let (sk_sig, r_sig) = rest.split_at(PRIVATE_KEY_COMPONENT_LENGTH);
+        let invalid = |error: anyhow::Error| AccountError::InvalidPrivateKey(error.to_string());
+        let seed = Field::from_bytes_le(seed).map_err(invalid)?;
+        let sk_sig = Scalar::from_bytes_le(sk_sig).map_err(invalid)?;
+        let r_sig = Scalar::from_bytes_le(r_sig).map_err(invalid)?;
+        // Ensure the components are consistent.
+        Self::from_components(seed, sk_sig, r_sig)
+    }
+
+    /// Returns the raw components of the private key, without the encoding prefix.
+    ///
+    /// The bytes hold the seed, `sk_sig`, and `r_sig`, in this order, each in little-endian.
+    pub fn to_private_key_bytes(&self) -> [u8; PRIVATE_KEY_BYTES_LENGTH] {
+        let private_key = self.private_key();
+        let components = [
+            private_key.seed().to_bytes_le(),
+            private_key.sk_sig().to_bytes_le(),
+            private_key.r_sig().to_bytes_le(),
+        ];
+        // Write the components.
+        let mut bytes = [0u8; PRIVATE_KEY_BYTES_LENGTH];
+        for (chunk, component) in bytes.chunks_exact_mut(PRIVATE_KEY_COMPONENT_LENGTH).zip(components) {
+            chunk.copy_from_slice(&component.expect("Failed to serialize a private key component"));
+        }
+        bytes
+    }
+
+    /// Returns `true` if the private key owns the given address.
+    ///
+    /// The address is rederived from the private key, and compared in constant time.
+    pub fn owns_address(&self, address: &Address<N>) -> Result<bool, AccountError> {
+        let failed = |error: anyhow::Error| AccountError::KeyGenerationFailed(error.to_string());
+        // Derive the address from the private key.
+        let derived = Address::try_from(self.private_key()).map_err(failed)?;
+        // Compare the addresses in constant time.
+        Ok(constant_time_eq(&derived.to_bytes_le().map_err(failed)?, &address.to_bytes_le().map_err(failed)?))
+    }
+
+    /// Returns `true` if the private key is usable under the given parameters.
+    ///
+    /// Unlike parsing, which only checks the key under the parameters of its own network, this ensures
+    /// the components have the sizes of the given parameters, and that the key rederives from them.
+    pub fn is_compatible(&self, parameters: &KeyParameters) -> bool {
+        let private_key = self.private_key();
+        // Ensure each component has the size of its element under the given parameters.
+        let sizes = [
+            (private_key.seed().to_bytes_le(), parameters.field_length),
+            (private_key.sk_sig().to_bytes_le(), parameters.scalar_length),
+            (private_key.r_sig().to_bytes_le(), parameters.scalar_length),
+        ];
+        if !sizes.into_iter().all(|(bytes, length)| matches!(bytes, Ok(bytes) if bytes.len() == length)) {
+            return false;
+        }
+        // Ensure the private key, and the keys derived from it, rederive from the components.
+        let rederived = Self::from_private_key_bytes(&self.to_private_key_bytes());
+        matches!(rederived, Ok(account) if account.address() == self.address())
+    }
+
+    /// Initializes a new account from a private key string, validating the private key before returning.
+    ///
+    /// Unlike `from_str`, the encoding is checked first, and the string must be the canonical encoding
+    /// of the parsed private key, so a structurally-correct encoding of an invalid seed is rejected early.
+    pub fn from_str_validated(private_key: &str) -> Result<Self, AccountError> {
+        // Ensure the encoding is well-formed.
+        verify_encoding(private_key)?;
+        // Parse the private key.
+        let parsed = PrivateKey::<N>::from_str(private_key)
+            .map_err(|error| AccountError::InvalidPrivateKey(error.to_string()))?;
+        // Ensure the string is the canonical encoding of the private key, i.e. the seed was not reduced.
+        if parsed.to_string() != private_key {
+            return Err(AccountError::InvalidPrivateKey("the seed is not a canonical field element".to_string()));
+        }
+        Self::try_from(parsed).map_err(|error| AccountError::InvalidPrivateKey(error.to_string()))
+    }
+}
+
+/// Returns `true` if the given bytes are equal, in time that only depends on their lengths.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::prelude::{One, TestRng, Testnet3};
+
+    use core::ops::Range;
+
+    type CurrentNetwork = Testnet3;
+
+    const PRIVATE_KEY: &str = "APrivateKey1zkp61PAYmrYEKLtRWeWhUoDpFnGLNuHrCciSqN49T86dw3p";
+
+    /// A frozen encoding of the private key derived from a seed, and the keys derived from it.
+    struct TestVector {
+        seed: &'static str,
+        private_key: &'static str,
+        view_key: &'static str,
+        address: &'static str,
+    }
+
+    /// The frozen test vectors. These must never change, as they pin the encoding of private keys.
+    const TEST_VECTORS: [TestVector; 2] = [
+        TestVector {
+            seed: "1231275789field",
+            private_key: "APrivateKey1zkp2n22c19hNdGF8wuEoQcuiyuWbquY6up4CtG5DYKqPX2X",
+            view_key: "AViewKey1pNxZHn79XVJ4D2WG5Vn2YWsAzf5wzAs3dAuQtUAmUFF7",
+            address: "aleo1uxl69laseuv3876ksh8k0nd7tvpgjt6ccrgccedpjk9qwyfensxst9ftg5",
+        },
+        TestVector {
+            seed: "38868010450269069756484274649022187108349082664538872491798902858296683054657field",
+            private_key: "APrivateKey1zkp61PAYmrYEKLtRWeWhUoDpFnGLNuHrCciSqN49T86dw3p",
+            view_key: "AViewKey1eYEGtb78FVg38SSYyzAeXnBdnWCba5t5YxUxtkTtvNAE",
+            address: "aleo1zecnqchckrzw7dlsyf65g6z5le2rmys403ecwmcafrag0e030yxqrnlg8j",
+        },
+    ];
+
+    /// The number of bytes in a decoded private key.
+    const DECODED_LENGTH: usize = 43;
+    /// The byte range of the prefix in a decoded private key.
+    const PREFIX_RANGE: Range<usize> = 0..11;
+    /// The byte range of the seed in a decoded private key.
+    const SEED_RANGE: Range<usize> = 11..43;
+
+    /// Returns the seed of the given test vector.
+    fn sample_seed(vector: &TestVector) -> Field<CurrentNetwork> {
+        Field::from_str(vector.seed).unwrap()
+    }
+
+    /// Returns the decoded bytes of the given private key string.
+    fn decode(private_key: &str) -> Vec<u8> {
+        bs58::decode(private_key).into_vec().unwrap()
+    }
+
+    #[test]
+    fn test_private_key_test_vectors() {
+        for vector in &TEST_VECTORS {
+            // Check that the seed derives the exact private key encoding.
+            let private_key = PrivateKey::<CurrentNetwork>::try_from(sample_seed(vector)).unwrap();
+            assert_eq!(private_key.to_string(), vector.private_key);
+            // Check that the encoding parses back into the same private key.
+            assert_eq!(PrivateKey::<CurrentNetwork>::from_str(vector.private_key).unwrap(), private_key);
+
+            // Check that the derived keys are unchanged.
+            let account = Account::try_from(private_key).unwrap();
+            assert_eq!(account.view_key().to_string(), vector.view_key);
+            assert_eq!(account.address().to_string(), vector.address);
+        }
+    }
+
+    #[test]
+    fn test_private_key_byte_offsets() {
+        // Check that the byte ranges cover the decoded private key, without gaps.
+        assert_eq!(PREFIX_RANGE.end, SEED_RANGE.start);
+        assert_eq!(SEED_RANGE.end, DECODED_LENGTH);
+
+        let decoded = TEST_VECTORS.iter().map(|vector| decode(vector.private_key)).collect::<Vec<_>>();
+        for (vector, bytes) in TEST_VECTORS.iter().zip(&decoded) {
+            // Check that `Display` writes the seed at the expected offset.
+            assert_eq!(bytes.len(), DECODED_LENGTH);
+            assert_eq!(bytes[SEED_RANGE], sample_seed(vector).to_bytes_le().unwrap()[..]);
+            // Check that `Display` writes the same prefix for every private key.
+            assert_eq!(bytes[PREFIX_RANGE], decoded[0][PREFIX_RANGE]);
+        }
+
+        // Splice the seed of the second vector into the first, and check that `FromStr` reads the same range.
+        let mut bytes = decoded[0].clone();
+        bytes[SEED_RANGE].copy_from_slice(&decoded[1][SEED_RANGE]);
+        let private_key = PrivateKey::<CurrentNetwork>::from_str(&bs58::encode(bytes).into_string()).unwrap();
+        assert_eq!(private_key.to_string(), TEST_VECTORS[1].private_key);
+
+        // Check that `FromStr` rejects a private key with a modified prefix.
+        let mut bytes = decoded[0].clone();
+        bytes[PREFIX_RANGE.start] ^= 1;
+        assert!(PrivateKey::<CurrentNetwork>::from_str(&bs58::encode(bytes).into_string()).is_err());
+    }
+
+    #[test]
+    fn test_verify_encoding() {
+        // Check that a valid encoding passes.
+        assert!(verify_encoding(PRIVATE_KEY).is_ok());
+    }
+
+    #[test]
+    fn test_verify_encoding_rejects_wrong_prefix() {
+        // Replace the prefix.
+        let private_key = PRIVATE_KEY.replacen("APrivateKey1", "AViewKey1zzz", 1);
+        assert_eq!(private_key.len(), PRIVATE_KEY_ENCODED_LENGTH);
+        // Check that the encoding is rejected.
+        assert!(matches!(verify_encoding(&private_key), Err(AccountError::InvalidEncoding(_))));
+    }
+
+    #[test]
+    fn test_verify_encoding_rejects_wrong_length() {
+        // Check that a truncated encoding is rejected.
+        let private_key = &PRIVATE_KEY[..PRIVATE_KEY_ENCODED_LENGTH - 1];
+        assert!(matches!(verify_encoding(private_key), Err(AccountError::InvalidEncoding(_))));
+        // Check that an extended encoding is rejected.
+        let private_key = format!("{PRIVATE_KEY}1");
+        assert!(matches!(verify_encoding(&private_key), Err(AccountError::InvalidEncoding(_))));
+    }
+
+    #[test]
+    fn test_verify_encoding_rejects_non_base58() {
+        // Replace the last character with a character outside the base58 alphabet.
+        let private_key = format!("{}0", &PRIVATE_KEY[..PRIVATE_KEY_ENCODED_LENGTH - 1]);
+        assert!(matches!(verify_encoding(&private_key), Err(AccountError::InvalidEncoding(_))));
+    }
+
+    #[test]
+    fn test_from_components() {
+        let mut rng = TestRng::default();
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+        let private_key = account.private_key();
+
+        // Check that the components of a valid private key assemble the same account.
+        let assembled = Account::from_components(private_key.seed(), private_key.sk_sig(), private_key.r_sig()).unwrap();
+        assert_eq!(assembled.private_key(), private_key);
+        assert_eq!(assembled.address(), account.address());
+    }
+
+    #[test]
+    fn test_from_components_rejects_tampered_components() {
+        let mut rng = TestRng::default();
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+        let private_key = account.private_key();
+
+        // Tamper with `r_sig`, and check that the components are rejected.
+        let r_sig = private_key.r_sig() + Scalar::one();
+        let result = Account::from_components(private_key.seed(), private_key.sk_sig(), r_sig);
+        assert_eq!(result.unwrap_err(), AccountError::ChecksumMismatch { component: "r_sig" });
+
+        // Tamper with `sk_sig`, and check that the components are rejected.
+        let sk_sig = private_key.sk_sig() + Scalar::one();
+        let result = Account::from_components(private_key.seed(), sk_sig, private_key.r_sig());
+        assert_eq!(result.unwrap_err(), AccountError::ChecksumMismatch { component: "sk_sig" });
+    }
+
+    #[test]
+    fn test_private_key_bytes_round_trip() {
+        let mut rng = TestRng::default();
+        for _ in 0..10 {
+            let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+            let private_key = account.private_key();
+
+            // Check that the bytes hold the components, in order.
+            let bytes = account.to_private_key_bytes();
+            assert_eq!(bytes[..32], private_key.seed().to_bytes_le().unwrap()[..]);
+            assert_eq!(bytes[32..64], private_key.sk_sig().to_bytes_le().unwrap()[..]);
+            assert_eq!(bytes[64..], private_key.r_sig().to_bytes_le().unwrap()[..]);
+
+            // Check that the bytes assemble the same account.
+            let assembled = Account::<CurrentNetwork>::from_private_key_bytes(&bytes).unwrap();
+            assert_eq!(assembled.private_key(), private_key);
+            assert_eq!(assembled.address(), account.address());
+        }
+    }
+
+    #[test]
+    fn test_private_key_bytes_reject_wrong_length() {
+        let mut rng = TestRng::default();
+        let bytes = Account::<CurrentNetwork>::new(&mut rng).unwrap().to_private_key_bytes();
+
+        // Check that empty, truncated, and extended bytes are rejected.
+        let extended = [bytes.as_slice(), &[0]].concat();
+        for bytes in [&bytes[..0], &bytes[..PRIVATE_KEY_BYTES_LENGTH - 1], extended.as_slice()] {
+            let result = Account::<CurrentNetwork>::from_private_key_bytes(bytes);
+            assert!(matches!(result, Err(AccountError::InvalidPrivateKey(_))));
+        }
+    }
+
+    #[test]
+    fn test_private_key_bytes_reject_tampered_components() {
+        let mut rng = TestRng::default();
+        let mut bytes = Account::<CurrentNetwork>::new(&mut rng).unwrap().to_private_key_bytes();
+
+        // Tamper with `r_sig`, and check that the bytes are rejected.
+        bytes[PRIVATE_KEY_BYTES_LENGTH - PRIVATE_KEY_COMPONENT_LENGTH] ^= 1;
+        let result = Account::<CurrentNetwork>::from_private_key_bytes(&bytes);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_owns_address() {
+        let mut rng = TestRng::default();
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+        let other = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+
+        // Check that the private key owns its own address.
+        assert!(account.owns_address(&account.address()).unwrap());
+        // Check that the private key does not own the address of another account.
+        assert!(!account.owns_address(&other.address()).unwrap());
+        assert!(!other.owns_address(&account.address()).unwrap());
+    }
+
+    #[test]
+    fn test_secure_random() {
+        let parameters = KeyParameters::of::<CurrentNetwork>();
+        let first = Account::<CurrentNetwork>::secure_random().unwrap();
+        let second = Account::<CurrentNetwork>::secure_random().unwrap();
+
+        // Check that the sampled keys are valid.
+        for account in [&first, &second] {
+            assert!(account.is_compatible(&parameters));
+            assert!(account.owns_address(&account.address()).unwrap());
+        }
+        // Check that consecutive calls sample distinct keys.
+        assert_ne!(first.private_key(), second.private_key());
+        assert_ne!(first.address(), second.address());
+    }
+
+    #[test]
+    fn test_sample_candidate() {
+        // Check that a candidate only depends on the RNG, and matches the key sampled by snarkVM.
+        let candidate = Account::<CurrentNetwork>::sample_candidate(&mut TestRng::fixed(1)).unwrap();
+        assert_eq!(Account::<CurrentNetwork>::sample_candidate(&mut TestRng::fixed(1)).unwrap(), candidate);
+        assert_eq!(PrivateKey::<CurrentNetwork>::new(&mut TestRng::fixed(1)).unwrap(), candidate);
+        // Check that the candidate is not validated, but derives a valid account.
+        assert_eq!(Account::try_from(candidate).unwrap().private_key(), &candidate);
+    }
+
+    #[test]
+    fn test_new_samples_valid_keys() {
+        let parameters = KeyParameters::of::<CurrentNetwork>();
+        let mut rng = TestRng::default();
+        for _ in 0..10 {
+            // Check that the sampled key is valid.
+            let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+            assert!(account.is_compatible(&parameters));
+            assert!(account.owns_address(&account.address()).unwrap());
+        }
+        // Check that a seeded RNG always samples the same key, which is the first valid candidate.
+        let account = Account::<CurrentNetwork>::new(&mut TestRng::fixed(1)).unwrap();
+        let again = Account::<CurrentNetwork>::new(&mut TestRng::fixed(1)).unwrap();
+        assert_eq!(again.private_key(), account.private_key());
+        let candidate = Account::<CurrentNetwork>::sample_candidate(&mut TestRng::fixed(1)).unwrap();
+        assert_eq!(account.private_key(), &candidate);
+    }
+
+    #[test]
+    fn test_owns_address_test_vectors() {
+        for vector in &TEST_VECTORS {
+            // Check that the private key owns the frozen address.
+            let account = Account::<CurrentNetwork>::from_str(vector.private_key).unwrap();
+            assert!(account.owns_address(&Address::from_str(vector.address).unwrap()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_from_str_validated() {
+        for vector in &TEST_VECTORS {
+            // Check that a valid private key passes, and derives the same account as `from_str`.
+            let account = Account::<CurrentNetwork>::from_str_validated(vector.private_key).unwrap();
+            assert_eq!(account.private_key().to_string(), vector.private_key);
+            assert_eq!(account.address().to_string(), vector.address);
+        }
+    }
+
+    #[test]
+    fn test_from_str_validated_rejects_invalid_seed() {
+        // Replace the seed with bytes that exceed the field modulus, keeping the encoding structurally correct.
+        let mut bytes = decode(PRIVATE_KEY);
+        bytes[SEED_RANGE].fill(u8::MAX);
+        let private_key = bs58::encode(bytes).into_string();
+        assert!(verify_encoding(&private_key).is_ok());
+
+        // Check that the validated parser rejects the private key.
+        let result = Account::<CurrentNetwork>::from_str_validated(&private_key);
+        assert!(matches!(result, Err(AccountError::InvalidPrivateKey(_))));
+    }
+
+    #[test]
+    fn test_from_str_validated_rejects_invalid_encoding() {
+        // Check that a malformed encoding is rejected before parsing.
+        let result = Account::<CurrentNetwork>::from_str_validated(&PRIVATE_KEY[..PRIVATE_KEY_ENCODED_LENGTH - 1]);
+        assert!(matches!(result, Err(AccountError::InvalidEncoding(_))));
+    }
+
+    #[test]
+    fn test_is_compatible() {
+        let mut rng = TestRng::default();
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+
+        // Check that the private key is compatible with the parameters of its network.
+        let parameters = KeyParameters::of::<CurrentNetwork>();
+        assert_eq!(parameters, KeyParameters { field_length: 32, scalar_length: 32 });
+        assert!(account.is_compatible(&parameters));
+
+        // Check that the private key is incompatible with parameters of different sizes.
+        assert!(!account.is_compatible(&KeyParameters { field_length: 48, ..parameters }));
+        assert!(!account.is_compatible(&KeyParameters { scalar_length: 64, ..parameters }));
+    }
+}
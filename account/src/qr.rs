@@ -0,0 +1,147 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Account, AccountError};
+use snarkvm::{
+    console::types::Field,
+    prelude::{FromBytes, Network, PrivateKey, ToBytes},
+};
+
+/// The version of the QR payload format.
+const QR_PAYLOAD_VERSION: u8 = 1;
+/// The number of bytes in the seed of a private key.
+const SEED_SIZE: usize = 32;
+/// The number of bytes in the checksum of a QR payload.
+const CHECKSUM_SIZE: usize = 4;
+/// The number of bytes in a QR payload.
+const QR_PAYLOAD_SIZE: usize = 1 + SEED_SIZE + CHECKSUM_SIZE;
+
+impl<N: Network> Account<N> {
+    /// Returns the private key as a compact binary payload, intended for QR encoding in byte mode.
+    ///
+    /// The payload consists of a version byte, the private key seed, and a CRC-32 checksum of both,
+    /// so that a transcription error is detected when the private key is restored.
+    pub fn to_qr_payload(&self) -> Result<Vec<u8>, AccountError> {
+        // Encode the version and the seed.
+        let mut payload = Vec::with_capacity(QR_PAYLOAD_SIZE);
+        payload.push(QR_PAYLOAD_VERSION);
+        self.private_key
+            .seed()
+            .write_le(&mut payload)
+            .map_err(|error| AccountError::InvalidPrivateKey(error.to_string()))?;
+        // Append the checksum.
+        let checksum = crc32(&payload);
+        payload.extend_from_slice(&checksum.to_le_bytes());
+        Ok(payload)
+    }
+
+    /// Initializes a new account from a payload produced by `to_qr_payload`.
+    pub fn from_qr_payload(payload: &[u8]) -> Result<Self, AccountError> {
+        // Ensure the payload has the expected length.
+        if payload.len() != QR_PAYLOAD_SIZE {
+            return Err(AccountError::InvalidEncoding(format!(
+                "expected a QR payload of {QR_PAYLOAD_SIZE} bytes, found {}",
+                payload.len()
+            )));
+        }
+        // Ensure the checksum matches.
+        let (contents, checksum) = payload.split_at(QR_PAYLOAD_SIZE - CHECKSUM_SIZE);
+        if crc32(contents).to_le_bytes() != checksum {
+            return Err(AccountError::InvalidEncoding("the QR payload checksum does not match".to_string()));
+        }
+        // Ensure the version is supported.
+        if contents[0] != QR_PAYLOAD_VERSION {
+            return Err(AccountError::InvalidEncoding(format!("unsupported QR payload version {}", contents[0])));
+        }
+        // Decode the seed, and derive the private key from it.
+        let seed = Field::<N>::read_le(&contents[1..])
+            .map_err(|error| AccountError::InvalidPrivateKey(error.to_string()))?;
+        let private_key =
+            PrivateKey::try_from(seed).map_err(|error| AccountError::KeyGenerationFailed(error.to_string()))?;
+        Self::try_from(private_key).map_err(|error| AccountError::KeyGenerationFailed(error.to_string()))
+    }
+}
+
+/// Returns the CRC-32 (IEEE) checksum of the given bytes.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::prelude::{TestRng, Testnet3};
+
+    type CurrentNetwork = Testnet3;
+
+    #[test]
+    fn test_crc32() {
+        // Check the standard check value of CRC-32.
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn test_qr_payload_roundtrip() {
+        let mut rng = TestRng::default();
+
+        for _ in 0..10 {
+            let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+            // Encode and decode the private key.
+            let payload = account.to_qr_payload().unwrap();
+            let candidate = Account::<CurrentNetwork>::from_qr_payload(&payload).unwrap();
+            assert_eq!(account.private_key(), candidate.private_key());
+            assert_eq!(account.address(), candidate.address());
+        }
+    }
+
+    #[test]
+    fn test_qr_payload_is_smaller_than_the_string() {
+        let mut rng = TestRng::default();
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+
+        // Check that the payload is no larger than the base58 encoding.
+        let payload = account.to_qr_payload().unwrap();
+        assert_eq!(payload.len(), QR_PAYLOAD_SIZE);
+        assert!(payload.len() <= account.private_key().to_string().len());
+    }
+
+    #[test]
+    fn test_qr_payload_rejects_corruption() {
+        let mut rng = TestRng::default();
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+        let payload = account.to_qr_payload().unwrap();
+
+        // Check that a flipped bit anywhere in the payload is detected.
+        for index in 0..payload.len() {
+            let mut corrupted = payload.clone();
+            corrupted[index] ^= 1;
+            let result = Account::<CurrentNetwork>::from_qr_payload(&corrupted);
+            assert!(matches!(result, Err(AccountError::InvalidEncoding(..))));
+        }
+        // Check that a truncated payload is rejected.
+        let result = Account::<CurrentNetwork>::from_qr_payload(&payload[1..]);
+        assert!(matches!(result, Err(AccountError::InvalidEncoding(..))));
+    }
+}
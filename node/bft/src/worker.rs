@@ -35,11 +35,23 @@ use snarkvm::{
 use futures::StreamExt;
 use indexmap::{IndexMap, IndexSet};
 use parking_lot::Mutex;
-use std::{future::Future, net::SocketAddr, sync::Arc, time::Duration};
+use std::{fmt, future::Future, net::SocketAddr, sync::Arc, time::Duration};
 use tokio::{sync::oneshot, task::JoinHandle, time::timeout};
 
 const MAX_TRANSMISSIONS_PER_WORKER: usize = MAX_TRANSMISSIONS_PER_BATCH / MAX_WORKERS as usize;
 
+/// The error returned when an unconfirmed solution or transaction is not well-formed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidTransmission(pub String);
+
+impl fmt::Display for InvalidTransmission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidTransmission {}
+
 #[derive(Clone)]
 pub struct Worker<N: Network> {
     /// The worker ID.
@@ -314,7 +326,8 @@ impl<N: Network> Worker<N> {
         }
         // Check that the solution is well-formed and unique.
         if let Err(e) = self.ledger.check_solution_basic(puzzle_commitment, prover_solution).await {
-            bail!("Invalid unconfirmed solution '{}': {e}", fmt_id(puzzle_commitment));
+            let error = format!("Invalid unconfirmed solution '{}': {e}", fmt_id(puzzle_commitment));
+            return Err(InvalidTransmission(error).into());
         }
         // Adds the prover solution to the ready queue.
         self.ready.insert(puzzle_commitment, transmission);
@@ -339,7 +352,8 @@ impl<N: Network> Worker<N> {
         }
         // Check that the transaction is well-formed and unique.
         if let Err(e) = self.ledger.check_transaction_basic(transaction_id, transaction).await {
-            bail!("Invalid unconfirmed transaction '{}': {e}", fmt_id(transaction_id));
+            let error = format!("Invalid unconfirmed transaction '{}': {e}", fmt_id(transaction_id));
+            return Err(InvalidTransmission(error).into());
         }
         // Adds the transaction to the ready queue.
         self.ready.insert(&transaction_id, transmission);
@@ -734,7 +748,7 @@ mod tests {
                 Data::Buffer(Bytes::from((0..512).map(|_| rng.gen::<u8>()).collect::<Vec<_>>())),
             )
             .await;
-        assert!(result.is_err());
+        assert!(result.unwrap_err().downcast_ref::<InvalidTransmission>().is_some());
         assert!(!worker.pending.contains(transmission_id));
         assert!(!worker.ready.contains(transmission_id));
     }
@@ -17,6 +17,13 @@
 #[macro_use]
 extern crate tracing;
 
+mod mempool_stats;
+pub use mempool_stats::*;
+
+mod transaction_reject;
+pub use transaction_reject::TransactionRejectReason;
+use transaction_reject::classify_primary_error;
+
 use snarkos_account::Account;
 use snarkos_node_bft::{
     helpers::{
@@ -46,7 +53,7 @@ use anyhow::Result;
 use indexmap::IndexMap;
 use lru::LruCache;
 use parking_lot::Mutex;
-use std::{future::Future, net::SocketAddr, num::NonZeroUsize, sync::Arc};
+use std::{collections::{HashMap, HashSet}, future::Future, net::SocketAddr, num::NonZeroUsize, sync::Arc};
 use tokio::{
     sync::{oneshot, OnceCell},
     task::JoinHandle,
@@ -68,6 +75,8 @@ pub struct Consensus<N: Network> {
     seen_solutions: Arc<Mutex<LruCache<PuzzleCommitment<N>, ()>>>,
     /// The recently-seen unconfirmed transactions.
     seen_transactions: Arc<Mutex<LruCache<N::TransactionID, ()>>>,
+    /// The tracker of the solutions and transactions admitted to the memory pool.
+    mempool_tracker: Arc<MempoolTracker<TransmissionID<N>>>,
     /// The spawned handles.
     handles: Arc<Mutex<Vec<JoinHandle<()>>>>,
 }
@@ -94,6 +103,7 @@ impl<N: Network> Consensus<N> {
             transactions_queue: Default::default(),
             seen_solutions: Arc::new(Mutex::new(LruCache::new(NonZeroUsize::new(1 << 16).unwrap()))),
             seen_transactions: Arc::new(Mutex::new(LruCache::new(NonZeroUsize::new(1 << 16).unwrap()))),
+            mempool_tracker: Arc::new(MempoolTracker::new(1 << 16)),
             handles: Default::default(),
         })
     }
@@ -149,6 +159,18 @@ impl<N: Network> Consensus<N> {
     pub fn num_unconfirmed_transactions(&self) -> usize {
         self.bft.num_unconfirmed_transactions()
     }
+
+    /// Returns a snapshot of the solutions and transactions admitted to the memory pool by this node,
+    /// which are still waiting in the queues or unconfirmed in the BFT.
+    pub fn mempool_stats(&self) -> MempoolStats {
+        // Retrieve the pending transmission IDs.
+        let mut pending = self.unconfirmed_transmission_ids().collect::<HashSet<_>>();
+        pending.extend(self.solutions_queue.lock().keys().map(|id| TransmissionID::from(*id)));
+        pending.extend(self.transactions_queue.lock().keys().map(TransmissionID::from));
+        // Stop tracking the entries that are no longer pending.
+        self.mempool_tracker.retain(|id| pending.contains(id));
+        self.mempool_tracker.stats()
+    }
 }
 
 impl<N: Network> Consensus<N> {
@@ -191,9 +213,11 @@ impl<N: Network> Consensus<N> {
             }
             // Add the solution to the memory pool.
             trace!("Received unconfirmed solution '{}' in the queue", fmt_id(solution_id));
+            let num_bytes = solution.to_bytes_le().map(|bytes| bytes.len()).unwrap_or_default();
             if self.solutions_queue.lock().insert(solution_id, solution).is_some() {
                 bail!("Solution '{}' already exists in the memory pool", fmt_id(solution_id));
             }
+            self.mempool_tracker.insert(solution_id.into(), MempoolEntryKind::Solution, num_bytes);
         }
 
         // If the memory pool of this node is full, return early.
@@ -225,31 +249,78 @@ impl<N: Network> Consensus<N> {
     }
 
     /// Adds the given unconfirmed transaction to the memory pool.
+    /// A rejection of the transaction is returned as a `TransactionRejectReason` error.
     pub async fn add_unconfirmed_transaction(&self, transaction: Transaction<N>) -> Result<()> {
-        // Process the unconfirmed transaction.
-        {
-            let transaction_id = transaction.id();
+        self.add_unconfirmed_transactions(vec![transaction]).await.into_iter().next().unwrap_or(Ok(()))
+    }
 
-            // Check if the transaction was recently seen.
-            if self.seen_transactions.lock().put(transaction_id, ()).is_some() {
-                // If the transaction was recently seen, return early.
-                return Ok(());
+    /// Adds the given unconfirmed transactions to the memory pool, locking the queue once for the whole batch.
+    /// Returns the result for each transaction, in the given order.
+    pub async fn add_unconfirmed_transactions(&self, transactions: Vec<Transaction<N>>) -> Vec<Result<()>> {
+        // Check the unconfirmed transactions.
+        let mut results = Vec::with_capacity(transactions.len());
+        let mut admitted = Vec::with_capacity(transactions.len());
+        for transaction in transactions {
+            match self.check_unconfirmed_transaction(&transaction).await {
+                Ok(()) => {
+                    admitted.push((results.len(), transaction));
+                    results.push(Ok(()));
+                }
+                Err(error) => results.push(Err(error)),
             }
-            // Check if the transaction already exists in the ledger.
-            if self.ledger.contains_transmission(&TransmissionID::from(&transaction_id))? {
-                bail!("Transaction '{}' already exists in the ledger", fmt_id(transaction_id));
+        }
+        // Add the admitted transactions to the memory pool.
+        let mut indices = HashMap::with_capacity(admitted.len());
+        {
+            let mut queue = self.transactions_queue.lock();
+            for (index, transaction) in admitted {
+                let transaction_id = transaction.id();
+                trace!("Received unconfirmed transaction '{}' in the queue", fmt_id(transaction_id));
+                let num_bytes = transaction.to_bytes_le().map(|bytes| bytes.len()).unwrap_or_default();
+                if queue.insert(transaction_id, transaction).is_some() {
+                    trace!("Transaction '{}' already exists in the memory pool", fmt_id(transaction_id));
+                    results[index] = Err(TransactionRejectReason::AlreadyInMemoryPool.into());
+                    continue;
+                }
+                let id = TransmissionID::from(&transaction_id);
+                self.mempool_tracker.insert(id, MempoolEntryKind::Transaction, num_bytes);
+                indices.insert(transaction_id, index);
             }
-            // Add the transaction to the memory pool.
-            trace!("Received unconfirmed transaction '{}' in the queue", fmt_id(transaction_id));
-            if self.transactions_queue.lock().insert(transaction_id, transaction).is_some() {
-                bail!("Transaction '{}' already exists in the memory pool", fmt_id(transaction_id));
+        }
+        // Send the queued transactions to the primary, and report its rejections of the given transactions.
+        for (transaction_id, error) in self.process_transactions_queue().await {
+            if let Some(index) = indices.get(&transaction_id) {
+                results[*index] = Err(classify_primary_error(error));
             }
         }
+        results
+    }
 
+    /// Checks the given unconfirmed transaction, before it is added to the memory pool.
+    /// A recently-seen transaction is rejected as already in the memory pool.
+    async fn check_unconfirmed_transaction(&self, transaction: &Transaction<N>) -> Result<()> {
+        let transaction_id = transaction.id();
+
+        // Check if the transaction was recently seen.
+        if self.seen_transactions.lock().put(transaction_id, ()).is_some() {
+            trace!("Transaction '{}' was recently seen", fmt_id(transaction_id));
+            return Err(TransactionRejectReason::AlreadyInMemoryPool.into());
+        }
+        // Check if the transaction already exists in the ledger.
+        if self.ledger.contains_transmission(&TransmissionID::from(&transaction_id))? {
+            trace!("Transaction '{}' already exists in the ledger", fmt_id(transaction_id));
+            return Err(TransactionRejectReason::AlreadyInLedger.into());
+        }
+        Ok(())
+    }
+
+    /// Sends the queued transactions to the primary, up to the capacity of the memory pool.
+    /// Returns the transactions rejected by the primary, with the errors.
+    async fn process_transactions_queue(&self) -> Vec<(N::TransactionID, anyhow::Error)> {
         // If the memory pool of this node is full, return early.
         let num_unconfirmed = self.num_unconfirmed_transmissions();
         if num_unconfirmed > MAX_TRANSMISSIONS_PER_BATCH {
-            return Ok(());
+            return Vec::new();
         }
         // Retrieve the transactions.
         let transactions = {
@@ -263,6 +334,7 @@ impl<N: Network> Consensus<N> {
             queue.drain(..num_transactions).collect::<Vec<_>>()
         };
         // Iterate over the transactions.
+        let mut rejected = Vec::new();
         for (_, transaction) in transactions.into_iter() {
             let transaction_id = transaction.id();
             trace!("Adding unconfirmed transaction '{}' to the memory pool...", fmt_id(transaction_id));
@@ -271,9 +343,10 @@ impl<N: Network> Consensus<N> {
                 self.primary_sender().send_unconfirmed_transaction(transaction_id, Data::Object(transaction)).await
             {
                 warn!("Failed to add unconfirmed transaction '{}' to the memory pool - {e}", fmt_id(transaction_id));
+                rejected.push((transaction_id, e));
             }
         }
-        Ok(())
+        rejected
     }
 }
 
@@ -379,3 +452,75 @@ impl<N: Network> Consensus<N> {
         self.handles.lock().iter().for_each(|handle| handle.abort());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkos_node_bft::{helpers::init_primary_channels, InvalidTransmission};
+    use snarkos_node_bft_ledger_service::MockLedgerService;
+    use snarkvm::ledger::block::Block;
+
+    type CurrentNetwork = Testnet3;
+
+    /// Initializes a consensus with a mock ledger, whose primary accepts every unconfirmed transaction
+    /// if `is_valid` is `true`, or rejects every one as not well-formed otherwise.
+    fn sample_consensus(rng: &mut TestRng, is_valid: bool) -> Consensus<CurrentNetwork> {
+        let committee = snarkvm::ledger::committee::test_helpers::sample_committee(rng);
+        let account = Account::new(rng).unwrap();
+        let ledger = Arc::new(MockLedgerService::new(committee));
+        let consensus = Consensus::new(account, ledger, None, &[], None).unwrap();
+        // Answer every unconfirmed transaction sent to the primary.
+        let (primary_sender, mut primary_receiver) = init_primary_channels();
+        assert!(consensus.primary_sender.set(primary_sender).is_ok());
+        tokio::spawn(async move {
+            while let Some((_, _, callback)) = primary_receiver.rx_unconfirmed_transaction.recv().await {
+                let result = match is_valid {
+                    true => Ok(()),
+                    false => Err(InvalidTransmission("Invalid unconfirmed transaction".to_string()).into()),
+                };
+                callback.send(result).ok();
+            }
+        });
+        consensus
+    }
+
+    /// Returns a transaction from the genesis block.
+    fn sample_transaction() -> Transaction<CurrentNetwork> {
+        let genesis = Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap();
+        genesis.transactions().iter().next().unwrap().transaction().clone()
+    }
+
+    /// Returns the reason of the given rejection, if the result is one.
+    fn reject_reason(result: &Result<()>) -> Option<&TransactionRejectReason> {
+        result.as_ref().err().and_then(|error| error.downcast_ref::<TransactionRejectReason>())
+    }
+
+    #[tokio::test]
+    async fn test_recently_seen_transaction_is_rejected() {
+        let rng = &mut TestRng::default();
+        let transaction = sample_transaction();
+
+        // Check that a transaction is admitted once, and rejected as already in the memory pool afterwards.
+        let consensus = sample_consensus(rng, true);
+        assert!(consensus.add_unconfirmed_transaction(transaction.clone()).await.is_ok());
+        let result = consensus.add_unconfirmed_transaction(transaction.clone()).await;
+        assert_eq!(reject_reason(&result), Some(&TransactionRejectReason::AlreadyInMemoryPool));
+
+        // Check that a transaction repeated within a batch is admitted once.
+        let consensus = sample_consensus(rng, true);
+        let results = consensus.add_unconfirmed_transactions(vec![transaction.clone(), transaction]).await;
+        assert!(results[0].is_ok());
+        assert_eq!(reject_reason(&results[1]), Some(&TransactionRejectReason::AlreadyInMemoryPool));
+    }
+
+    #[tokio::test]
+    async fn test_transaction_rejected_by_the_primary_is_invalid() {
+        let rng = &mut TestRng::default();
+        let consensus = sample_consensus(rng, false);
+
+        // Check that a transaction the primary finds not well-formed is rejected as invalid.
+        let result = consensus.add_unconfirmed_transaction(sample_transaction()).await;
+        assert!(matches!(reject_reason(&result), Some(TransactionRejectReason::Invalid(_))));
+        assert!(reject_reason(&result).unwrap().is_sender_fault());
+    }
+}
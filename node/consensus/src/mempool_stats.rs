@@ -0,0 +1,131 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use indexmap::IndexMap;
+use parking_lot::Mutex;
+use std::{
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+/// A snapshot of the pending entries in the memory pool, which were admitted by this node.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MempoolStats {
+    /// The number of pending solutions.
+    pub num_solutions: usize,
+    /// The number of pending transactions.
+    pub num_transactions: usize,
+    /// The total size in bytes of the pending entries.
+    pub num_bytes: usize,
+    /// The time since the oldest pending entry was admitted, if there is one.
+    pub oldest_entry_age: Option<Duration>,
+}
+
+/// The kind of an entry in the memory pool.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MempoolEntryKind {
+    Solution,
+    Transaction,
+}
+
+/// A tracker of the entries admitted to the memory pool, from the oldest to the most recently admitted.
+pub struct MempoolTracker<K: Hash + Eq> {
+    /// The maximum number of tracked entries.
+    capacity: usize,
+    /// The map of entry IDs to their kind, size in bytes, and admission time.
+    entries: Mutex<IndexMap<K, (MempoolEntryKind, usize, Instant)>>,
+}
+
+impl<K: Hash + Eq> MempoolTracker<K> {
+    /// Initializes a new tracker, which tracks up to the given number of entries.
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: Default::default() }
+    }
+
+    /// Records the admission of the given entry, evicting the oldest entries if the tracker is full.
+    pub fn insert(&self, id: K, kind: MempoolEntryKind, num_bytes: usize) {
+        let mut entries = self.entries.lock();
+        entries.entry(id).or_insert((kind, num_bytes, Instant::now()));
+        while entries.len() > self.capacity {
+            entries.shift_remove_index(0);
+        }
+    }
+
+    /// Retains the entries that are still pending, according to the given predicate.
+    pub fn retain<F: FnMut(&K) -> bool>(&self, mut is_pending: F) {
+        self.entries.lock().retain(|id, _| is_pending(id));
+    }
+
+    /// Returns a snapshot of the tracked entries.
+    pub fn stats(&self) -> MempoolStats {
+        let entries = self.entries.lock();
+        let mut stats = MempoolStats::default();
+        for (kind, num_bytes, _) in entries.values() {
+            match kind {
+                MempoolEntryKind::Solution => stats.num_solutions += 1,
+                MempoolEntryKind::Transaction => stats.num_transactions += 1,
+            }
+            stats.num_bytes += num_bytes;
+        }
+        // The entries are ordered by admission time, so the first entry is the oldest.
+        stats.oldest_entry_age = entries.first().map(|(_, (_, _, admitted_at))| admitted_at.elapsed());
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mempool_stats() {
+        let tracker = MempoolTracker::new(8);
+        assert_eq!(tracker.stats(), MempoolStats::default());
+
+        // Insert some solutions and transactions.
+        tracker.insert(0u64, MempoolEntryKind::Solution, 100);
+        tracker.insert(1, MempoolEntryKind::Transaction, 1_000);
+        tracker.insert(2, MempoolEntryKind::Transaction, 2_000);
+        // Check that a duplicate insertion is ignored.
+        tracker.insert(2, MempoolEntryKind::Transaction, 2_000);
+
+        // Check that the stats reflect the entries.
+        let stats = tracker.stats();
+        assert_eq!(stats.num_solutions, 1);
+        assert_eq!(stats.num_transactions, 2);
+        assert_eq!(stats.num_bytes, 3_100);
+        assert!(stats.oldest_entry_age.is_some());
+
+        // Remove the entries that are no longer pending.
+        tracker.retain(|id| *id != 0);
+        let stats = tracker.stats();
+        assert_eq!(stats.num_solutions, 0);
+        assert_eq!(stats.num_transactions, 2);
+        assert_eq!(stats.num_bytes, 3_000);
+    }
+
+    #[test]
+    fn test_mempool_tracker_capacity() {
+        let tracker = MempoolTracker::new(2);
+
+        // Insert more entries than the capacity.
+        for id in 0u64..3 {
+            tracker.insert(id, MempoolEntryKind::Transaction, 10);
+        }
+        // Check that the oldest entry was evicted.
+        assert_eq!(tracker.stats().num_transactions, 2);
+        tracker.retain(|id| *id == 0);
+        assert_eq!(tracker.stats().num_transactions, 0);
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snarkos_node_bft::InvalidTransmission;
+
+use core::fmt;
+
+/// The reason an unconfirmed transaction was rejected from the memory pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransactionRejectReason {
+    /// The transaction already exists in the ledger.
+    AlreadyInLedger,
+    /// The transaction already exists in the memory pool.
+    AlreadyInMemoryPool,
+    /// The transaction is not well-formed.
+    Invalid(String),
+}
+
+impl TransactionRejectReason {
+    /// Returns `true` if the sender of the transaction is at fault for the rejection.
+    pub const fn is_sender_fault(&self) -> bool {
+        matches!(self, Self::Invalid(..))
+    }
+}
+
+impl fmt::Display for TransactionRejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlreadyInLedger => write!(f, "the transaction already exists in the ledger"),
+            Self::AlreadyInMemoryPool => write!(f, "the transaction already exists in the memory pool"),
+            Self::Invalid(error) => write!(f, "the transaction is invalid - {error}"),
+        }
+    }
+}
+
+impl std::error::Error for TransactionRejectReason {}
+
+/// Classifies the given error from the primary, which rejected an unconfirmed transaction.
+/// A transaction that is not well-formed is rejected as invalid, and any other error is returned as is.
+pub(crate) fn classify_primary_error(error: anyhow::Error) -> anyhow::Error {
+    match error.downcast_ref::<InvalidTransmission>() {
+        Some(invalid) => TransactionRejectReason::Invalid(invalid.to_string()).into(),
+        None => error,
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+use snarkvm::prelude::{FromBytes, ToBytes};
+
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+
+/// A proof-of-work puzzle that a node under load demands from a connecting peer, before the handshake proceeds.
+///
+/// A nonce solves the challenge if the SHA-256 hash of the salt followed by the nonce
+/// starts with at least `difficulty` zero bits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdmissionChallenge {
+    /// The random salt, which prevents precomputed solutions.
+    pub salt: u64,
+    /// The required number of leading zero bits.
+    pub difficulty: u8,
+}
+
+impl AdmissionChallenge {
+    /// The maximum difficulty a node is willing to solve.
+    pub const MAXIMUM_DIFFICULTY: u8 = 24;
+
+    /// Returns `true` if the given nonce solves the challenge.
+    pub fn is_solved_by(&self, nonce: u64) -> bool {
+        let digest = Sha256::new().chain_update(self.salt.to_le_bytes()).chain_update(nonce.to_le_bytes()).finalize();
+        // Count the leading zero bits of the digest.
+        let mut zero_bits = 0;
+        for byte in digest {
+            zero_bits += byte.leading_zeros();
+            if byte != 0 {
+                break;
+            }
+        }
+        zero_bits >= self.difficulty as u32
+    }
+
+    /// Returns the first nonce that solves the challenge, or `None` if the difficulty is excessive.
+    pub fn solve(&self) -> Option<u64> {
+        if self.difficulty > Self::MAXIMUM_DIFFICULTY {
+            return None;
+        }
+        (0..=u64::MAX).find(|nonce| self.is_solved_by(*nonce))
+    }
+}
+
+impl MessageTrait for AdmissionChallenge {
+    /// Returns the message name.
+    #[inline]
+    fn name(&self) -> Cow<'static, str> {
+        "AdmissionChallenge".into()
+    }
+}
+
+impl ToBytes for AdmissionChallenge {
+    fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        self.salt.write_le(&mut writer)?;
+        self.difficulty.write_le(writer)
+    }
+}
+
+impl FromBytes for AdmissionChallenge {
+    fn read_le<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let salt = u64::read_le(&mut reader)?;
+        let difficulty = u8::read_le(reader)?;
+
+        Ok(Self { salt, difficulty })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::AdmissionChallenge;
+    use snarkvm::utilities::{FromBytes, ToBytes};
+
+    use bytes::{Buf, BufMut, BytesMut};
+    use proptest::prelude::{any, BoxedStrategy, Strategy};
+    use test_strategy::proptest;
+
+    pub fn any_admission_challenge() -> BoxedStrategy<AdmissionChallenge> {
+        (any::<u64>(), any::<u8>()).prop_map(|(salt, difficulty)| AdmissionChallenge { salt, difficulty }).boxed()
+    }
+
+    #[proptest]
+    fn admission_challenge_roundtrip(#[strategy(any_admission_challenge())] challenge: AdmissionChallenge) {
+        let mut bytes = BytesMut::default().writer();
+        challenge.write_le(&mut bytes).unwrap();
+        let decoded = AdmissionChallenge::read_le(&mut bytes.into_inner().reader()).unwrap();
+        assert_eq!(challenge, decoded);
+    }
+
+    #[test]
+    fn admission_challenge_solve() {
+        for difficulty in [0, 1, 8, 12] {
+            let challenge = AdmissionChallenge { salt: 42, difficulty };
+            // Check that the solution is accepted.
+            let nonce = challenge.solve().unwrap();
+            assert!(challenge.is_solved_by(nonce));
+            // Check that no smaller nonce solves the challenge.
+            assert!((0..nonce).all(|nonce| !challenge.is_solved_by(nonce)));
+        }
+        // Check that an excessive difficulty is not attempted.
+        let challenge = AdmissionChallenge { salt: 42, difficulty: AdmissionChallenge::MAXIMUM_DIFFICULTY + 1 };
+        assert_eq!(challenge.solve(), None);
+    }
+}
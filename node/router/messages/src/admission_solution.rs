@@ -0,0 +1,70 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+use snarkvm::prelude::{FromBytes, ToBytes};
+
+use std::borrow::Cow;
+
+/// The solution to an `AdmissionChallenge`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdmissionSolution {
+    /// The nonce that solves the challenge.
+    pub nonce: u64,
+}
+
+impl MessageTrait for AdmissionSolution {
+    /// Returns the message name.
+    #[inline]
+    fn name(&self) -> Cow<'static, str> {
+        "AdmissionSolution".into()
+    }
+}
+
+impl ToBytes for AdmissionSolution {
+    fn write_le<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        self.nonce.write_le(writer)
+    }
+}
+
+impl FromBytes for AdmissionSolution {
+    fn read_le<R: io::Read>(reader: R) -> io::Result<Self> {
+        let nonce = u64::read_le(reader)?;
+
+        Ok(Self { nonce })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::AdmissionSolution;
+    use snarkvm::utilities::{FromBytes, ToBytes};
+
+    use bytes::{Buf, BufMut, BytesMut};
+    use proptest::prelude::{any, BoxedStrategy, Strategy};
+    use test_strategy::proptest;
+
+    pub fn any_admission_solution() -> BoxedStrategy<AdmissionSolution> {
+        any::<u64>().prop_map(|nonce| AdmissionSolution { nonce }).boxed()
+    }
+
+    #[proptest]
+    fn admission_solution_roundtrip(#[strategy(any_admission_solution())] solution: AdmissionSolution) {
+        let mut bytes = BytesMut::default().writer();
+        solution.write_le(&mut bytes).unwrap();
+        let decoded = AdmissionSolution::read_le(&mut bytes.into_inner().reader()).unwrap();
+        assert_eq!(solution, decoded);
+    }
+}
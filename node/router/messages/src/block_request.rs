@@ -26,6 +26,28 @@ pub struct BlockRequest {
     pub end_height: u32,
 }
 
+impl BlockRequest {
+    /// Returns the number of blocks in the requested range.
+    pub const fn num_blocks(&self) -> u32 {
+        self.end_height.saturating_sub(self.start_height)
+    }
+
+    /// Returns the block request, with its range clamped to at most the given number of blocks.
+    pub fn clamp(self, max_blocks: u32) -> Self {
+        let end_height = self.end_height.min(self.start_height.saturating_add(max_blocks));
+        Self { start_height: self.start_height, end_height }
+    }
+
+    /// Returns the consecutive block requests of at most `size` blocks each, that cover the requested range.
+    pub fn chunks(self, size: u32) -> impl Iterator<Item = Self> {
+        let size = size.max(1);
+        (self.start_height..self.end_height).step_by(size as usize).map(move |start_height| Self {
+            start_height,
+            end_height: self.end_height.min(start_height.saturating_add(size)),
+        })
+    }
+}
+
 impl MessageTrait for BlockRequest {
     /// Returns the message name.
     #[inline]
@@ -82,4 +104,38 @@ pub mod prop_tests {
         let decoded = BlockRequest::read_le(&mut bytes.into_inner().reader()).unwrap();
         assert_eq![decoded, block_request];
     }
+
+    #[test]
+    fn test_block_request_clamp() {
+        let request = BlockRequest { start_height: 5, end_height: 10 };
+        assert_eq!(request.clamp(10), request);
+        assert_eq!(request.clamp(5), request);
+        assert_eq!(request.clamp(2), BlockRequest { start_height: 5, end_height: 7 });
+
+        // Check that an over-large range is clamped.
+        let request = BlockRequest { start_height: u32::MAX - 5, end_height: u32::MAX };
+        assert_eq!(request.clamp(100), request);
+        let request = BlockRequest { start_height: 0, end_height: u32::MAX };
+        assert_eq!(request.clamp(100).num_blocks(), 100);
+    }
+
+    #[test]
+    fn test_block_request_chunks() {
+        let request = BlockRequest { start_height: 5, end_height: 10 };
+
+        // Check that the chunks cover exactly the requested heights.
+        let chunks = request.chunks(1).collect::<Vec<_>>();
+        assert_eq!(chunks.len(), 5);
+        for (chunk, height) in chunks.iter().zip(5..10) {
+            assert_eq!(*chunk, BlockRequest { start_height: height, end_height: height + 1 });
+        }
+
+        // Check that the last chunk may be smaller.
+        let chunks = request.chunks(2).collect::<Vec<_>>();
+        assert_eq!(chunks, vec![
+            BlockRequest { start_height: 5, end_height: 7 },
+            BlockRequest { start_height: 7, end_height: 9 },
+            BlockRequest { start_height: 9, end_height: 10 },
+        ]);
+    }
 }
@@ -25,6 +25,9 @@ pub struct ChallengeRequest<N: Network> {
     pub node_type: NodeType,
     pub address: Address<N>,
     pub nonce: u64,
+    /// The message types the sender understands. Peers that predate capabilities are assumed to understand
+    /// the legacy message types only.
+    pub capabilities: CapabilitySet,
 }
 
 impl<N: Network> MessageTrait for ChallengeRequest<N> {
@@ -42,6 +45,7 @@ impl<N: Network> ToBytes for ChallengeRequest<N> {
         self.node_type.write_le(&mut writer)?;
         self.address.write_le(&mut writer)?;
         self.nonce.write_le(&mut writer)?;
+        self.capabilities.write_le(&mut writer)?;
         Ok(())
     }
 }
@@ -53,20 +57,40 @@ impl<N: Network> FromBytes for ChallengeRequest<N> {
         let node_type = NodeType::read_le(&mut reader)?;
         let address = Address::<N>::read_le(&mut reader)?;
         let nonce = u64::read_le(&mut reader)?;
-
-        Ok(Self { version, listener_port, node_type, address, nonce })
+        // The capabilities are appended to the message, and are missing from the requests of older peers.
+        let mut capability_bytes = [0u8; 4];
+        let capabilities = match reader.read_exact(&mut capability_bytes) {
+            Ok(()) => CapabilitySet::from_bits(u32::from_le_bytes(capability_bytes)),
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Message::<N>::legacy_capabilities(),
+            Err(error) => return Err(error),
+        };
+
+        Ok(Self { version, listener_port, node_type, address, nonce, capabilities })
     }
 }
 
 impl<N: Network> ChallengeRequest<N> {
     pub fn new(listener_port: u16, node_type: NodeType, address: Address<N>, nonce: u64) -> Self {
-        Self { version: Message::<N>::VERSION, listener_port, node_type, address, nonce }
+        Self {
+            version: Message::<N>::VERSION,
+            listener_port,
+            node_type,
+            address,
+            nonce,
+            capabilities: Message::<N>::capabilities(),
+        }
+    }
+
+    /// Advertises the given capabilities, instead of every message type.
+    pub fn with_capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.capabilities = capabilities;
+        self
     }
 }
 
 #[cfg(test)]
 pub mod prop_tests {
-    use crate::{ChallengeRequest, NodeType};
+    use crate::{CapabilitySet, ChallengeRequest, CodecUpgrade, Message, NodeType, PeerRequest};
     use snarkvm::{
         console::prelude::{FromBytes, ToBytes},
         prelude::{Address, TestRng, Uniform},
@@ -94,13 +118,14 @@ pub mod prop_tests {
     }
 
     pub fn any_challenge_request() -> BoxedStrategy<ChallengeRequest<CurrentNetwork>> {
-        (any_valid_address(), any::<u64>(), any::<u32>(), any::<u16>(), any_node_type())
-            .prop_map(|(address, nonce, version, listener_port, node_type)| ChallengeRequest {
+        (any_valid_address(), any::<u64>(), any::<u32>(), any::<u16>(), any_node_type(), any::<u32>())
+            .prop_map(|(address, nonce, version, listener_port, node_type, capabilities)| ChallengeRequest {
                 address,
                 nonce,
                 version,
                 listener_port,
                 node_type,
+                capabilities: CapabilitySet::from_bits(capabilities),
             })
             .boxed()
     }
@@ -114,4 +139,20 @@ pub mod prop_tests {
             ChallengeRequest::read_le(buf.into_inner().reader()).unwrap();
         assert_eq!(original, deserialized);
     }
+
+    #[test]
+    fn challenge_request_without_capabilities() {
+        let address = Address::rand(&mut TestRng::default());
+        let original = ChallengeRequest::<CurrentNetwork>::new(4130, NodeType::Client, address, 1)
+            .with_capabilities(CapabilitySet::empty());
+        let bytes = original.to_bytes_le().unwrap();
+
+        // Check that a request from an older peer, without capabilities, is assumed to support the legacy types only.
+        let deserialized = ChallengeRequest::<CurrentNetwork>::read_le(&bytes[..bytes.len() - 4]).unwrap();
+        assert_eq!(deserialized.capabilities, Message::<CurrentNetwork>::legacy_capabilities());
+        assert!(deserialized.capabilities.supports(&Message::<CurrentNetwork>::PeerRequest(PeerRequest)));
+        let codec_upgrade = Message::<CurrentNetwork>::CodecUpgrade(CodecUpgrade { version: 2, is_ack: false });
+        assert!(!deserialized.capabilities.supports(&codec_upgrade));
+        assert_eq!(deserialized.nonce, original.nonce);
+    }
 }
@@ -0,0 +1,77 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+use snarkvm::prelude::{FromBytes, ToBytes};
+
+use std::borrow::Cow;
+
+/// A request to switch the codec of the connection to the given version, or its acknowledgement.
+///
+/// The codec of each side switches right after this message, so the frames that follow
+/// a request or an acknowledgement are encoded with the new codec version.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodecUpgrade {
+    /// The new codec version.
+    pub version: u8,
+    /// `true` if this message acknowledges an upgrade requested by the peer.
+    pub is_ack: bool,
+}
+
+impl MessageTrait for CodecUpgrade {
+    /// Returns the message name.
+    #[inline]
+    fn name(&self) -> Cow<'static, str> {
+        "CodecUpgrade".into()
+    }
+}
+
+impl ToBytes for CodecUpgrade {
+    fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        self.version.write_le(&mut writer)?;
+        self.is_ack.write_le(writer)
+    }
+}
+
+impl FromBytes for CodecUpgrade {
+    fn read_le<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let version = u8::read_le(&mut reader)?;
+        let is_ack = bool::read_le(reader)?;
+
+        Ok(Self { version, is_ack })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::CodecUpgrade;
+    use snarkvm::utilities::{FromBytes, ToBytes};
+
+    use bytes::{Buf, BufMut, BytesMut};
+    use proptest::prelude::{any, BoxedStrategy, Strategy};
+    use test_strategy::proptest;
+
+    pub fn any_codec_upgrade() -> BoxedStrategy<CodecUpgrade> {
+        (any::<u8>(), any::<bool>()).prop_map(|(version, is_ack)| CodecUpgrade { version, is_ack }).boxed()
+    }
+
+    #[proptest]
+    fn codec_upgrade_roundtrip(#[strategy(any_codec_upgrade())] upgrade: CodecUpgrade) {
+        let mut bytes = BytesMut::default().writer();
+        upgrade.write_le(&mut bytes).unwrap();
+        let decoded = CodecUpgrade::read_le(&mut bytes.into_inner().reader()).unwrap();
+        assert_eq!(upgrade, decoded);
+    }
+}
@@ -51,17 +51,19 @@ impl FromBytes for Disconnect {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Disconnect, DisconnectReason};
+    use crate::{Disconnect, DisconnectReason, Message, MessageCodec};
     use snarkvm::{
         console::prelude::{FromBytes, ToBytes},
         prelude::{Rng, TestRng},
     };
 
     use bytes::{Buf, BufMut, BytesMut};
+    use tokio_util::codec::{Decoder, Encoder};
+
+    type CurrentNetwork = snarkvm::prelude::Testnet3;
 
     #[test]
     fn disconnect_roundtrip() {
-        // TODO switch to an iteration method that doesn't require manually updating this vec if variants are added
         let all_reasons = [
             DisconnectReason::ExceededForkRange,
             DisconnectReason::InvalidChallengeResponse,
@@ -78,6 +80,11 @@ mod tests {
             DisconnectReason::TooManyPeers,
             DisconnectReason::YouNeedToSyncFirst,
             DisconnectReason::YourPortIsClosed(TestRng::default().gen()),
+            DisconnectReason::SelfConnection,
+            DisconnectReason::RateLimitExceeded,
+            DisconnectReason::SlowConsumer,
+            DisconnectReason::UnsolvedAdmissionChallenge,
+            DisconnectReason::ConnectionLifetimeExpired,
         ];
 
         for reason in all_reasons.iter() {
@@ -90,6 +97,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn disconnect_reason_exhaustive_roundtrip() {
+        let mut rng = TestRng::default();
+
+        // Decode every possible wire code.
+        let mut reasons = Vec::new();
+        for code in 0..=u8::MAX {
+            let mut bytes = vec![code];
+            bytes.extend_from_slice(&rng.gen::<u16>().to_le_bytes());
+            if let Ok(reason) = DisconnectReason::read_le(&bytes[..]) {
+                reasons.push((code, reason));
+            }
+        }
+
+        // Ensure the decoded wire codes are contiguous, so that no variant is skipped.
+        let codes = reasons.iter().map(|(code, _)| *code).collect::<Vec<_>>();
+        assert_eq!(codes, (0..reasons.len() as u8).collect::<Vec<_>>());
+
+        for (code, reason) in reasons {
+            // Ensure every variant is covered, as this match must be updated when a variant is added.
+            let expected_code = match reason {
+                DisconnectReason::ExceededForkRange => 0,
+                DisconnectReason::InvalidChallengeResponse => 1,
+                DisconnectReason::InvalidForkDepth => 2,
+                DisconnectReason::INeedToSyncFirst => 3,
+                DisconnectReason::NoReasonGiven => 4,
+                DisconnectReason::ProtocolViolation => 5,
+                DisconnectReason::OutdatedClientVersion => 6,
+                DisconnectReason::PeerHasDisconnected => 7,
+                DisconnectReason::PeerRefresh => 8,
+                DisconnectReason::ShuttingDown => 9,
+                DisconnectReason::SyncComplete => 10,
+                DisconnectReason::TooManyFailures => 11,
+                DisconnectReason::TooManyPeers => 12,
+                DisconnectReason::YouNeedToSyncFirst => 13,
+                DisconnectReason::YourPortIsClosed(..) => 14,
+                DisconnectReason::SelfConnection => 15,
+                DisconnectReason::RateLimitExceeded => 16,
+                DisconnectReason::SlowConsumer => 17,
+                DisconnectReason::UnsolvedAdmissionChallenge => 18,
+                DisconnectReason::ConnectionLifetimeExpired => 19,
+            };
+            assert_eq!(code, expected_code);
+            assert_eq!(reason.code(), expected_code);
+
+            // Ensure the reason survives the message codec.
+            let message = Message::<CurrentNetwork>::from(reason);
+            let mut codec = MessageCodec::<CurrentNetwork>::default();
+            let mut buffer = BytesMut::new();
+            codec.encode(message.clone(), &mut buffer).unwrap();
+            assert_eq!(codec.decode(&mut buffer).unwrap(), Some(message));
+        }
+    }
+
     #[test]
     #[should_panic]
     fn disconnect_invalid_data_panics() {
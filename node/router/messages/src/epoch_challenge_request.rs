@@ -0,0 +1,72 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+use snarkvm::prelude::{FromBytes, ToBytes};
+
+use std::borrow::Cow;
+
+/// A request for the epoch challenge of the given epoch, which may precede the latest one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EpochChallengeRequest {
+    /// The number of the requested epoch.
+    pub epoch_number: u32,
+}
+
+impl MessageTrait for EpochChallengeRequest {
+    /// Returns the message name.
+    #[inline]
+    fn name(&self) -> Cow<'static, str> {
+        "EpochChallengeRequest".into()
+    }
+}
+
+impl ToBytes for EpochChallengeRequest {
+    fn write_le<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        self.epoch_number.write_le(writer)
+    }
+}
+
+impl FromBytes for EpochChallengeRequest {
+    fn read_le<R: io::Read>(reader: R) -> io::Result<Self> {
+        let epoch_number = u32::read_le(reader)?;
+
+        Ok(Self { epoch_number })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::EpochChallengeRequest;
+    use snarkvm::utilities::{FromBytes, ToBytes};
+
+    use bytes::{Buf, BufMut, BytesMut};
+    use proptest::prelude::{any, BoxedStrategy, Strategy};
+    use test_strategy::proptest;
+
+    pub fn any_epoch_challenge_request() -> BoxedStrategy<EpochChallengeRequest> {
+        any::<u32>().prop_map(|epoch_number| EpochChallengeRequest { epoch_number }).boxed()
+    }
+
+    #[proptest]
+    fn epoch_challenge_request_roundtrip(
+        #[strategy(any_epoch_challenge_request())] request: EpochChallengeRequest,
+    ) {
+        let mut bytes = BytesMut::default().writer();
+        request.write_le(&mut bytes).unwrap();
+        let decoded = EpochChallengeRequest::read_le(&mut bytes.into_inner().reader()).unwrap();
+        assert_eq!(request, decoded);
+    }
+}
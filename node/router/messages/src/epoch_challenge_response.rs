@@ -0,0 +1,93 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+use snarkvm::prelude::{FromBytes, ToBytes};
+
+use std::borrow::Cow;
+
+/// The response to an `EpochChallengeRequest`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EpochChallengeResponse<N: Network> {
+    /// The number of the requested epoch.
+    pub epoch_number: u32,
+    /// The epoch challenge of the requested epoch, or `None` if the request was declined,
+    /// e.g. because the epoch is not one of the recent epochs of the ledger.
+    pub epoch_challenge: Option<EpochChallenge<N>>,
+}
+
+impl<N: Network> MessageTrait for EpochChallengeResponse<N> {
+    /// Returns the message name.
+    #[inline]
+    fn name(&self) -> Cow<'static, str> {
+        "EpochChallengeResponse".into()
+    }
+}
+
+impl<N: Network> ToBytes for EpochChallengeResponse<N> {
+    fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        self.epoch_number.write_le(&mut writer)?;
+        match &self.epoch_challenge {
+            Some(epoch_challenge) => {
+                true.write_le(&mut writer)?;
+                epoch_challenge.write_le(&mut writer)
+            }
+            None => false.write_le(&mut writer),
+        }
+    }
+}
+
+impl<N: Network> FromBytes for EpochChallengeResponse<N> {
+    fn read_le<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let epoch_number = u32::read_le(&mut reader)?;
+        let epoch_challenge = match bool::read_le(&mut reader)? {
+            true => Some(EpochChallenge::read_le(&mut reader)?),
+            false => None,
+        };
+
+        Ok(Self { epoch_number, epoch_challenge })
+    }
+}
+
+#[cfg(test)]
+pub mod prop_tests {
+    use crate::{puzzle_response::prop_tests::any_epoch_challenge, EpochChallengeResponse};
+    use snarkvm::console::prelude::{FromBytes, ToBytes};
+
+    use bytes::{Buf, BufMut, BytesMut};
+    use proptest::{
+        option::of,
+        prelude::{any, BoxedStrategy, Strategy},
+    };
+    use test_strategy::proptest;
+
+    type CurrentNetwork = snarkvm::prelude::Testnet3;
+
+    pub fn any_epoch_challenge_response() -> BoxedStrategy<EpochChallengeResponse<CurrentNetwork>> {
+        (any::<u32>(), of(any_epoch_challenge()))
+            .prop_map(|(epoch_number, epoch_challenge)| EpochChallengeResponse { epoch_number, epoch_challenge })
+            .boxed()
+    }
+
+    #[proptest]
+    fn epoch_challenge_response_roundtrip(
+        #[strategy(any_epoch_challenge_response())] original: EpochChallengeResponse<CurrentNetwork>,
+    ) {
+        let mut buf = BytesMut::default().writer();
+        original.write_le(&mut buf).unwrap();
+        let deserialized = EpochChallengeResponse::<CurrentNetwork>::read_le(buf.into_inner().reader()).unwrap();
+        assert_eq!(original, deserialized);
+    }
+}
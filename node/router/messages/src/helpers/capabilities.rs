@@ -0,0 +1,117 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::Message;
+use snarkvm::prelude::{FromBytes, Network, ToBytes};
+
+use std::io;
+
+/// The set of message types a node understands, as a bitset indexed by message ID.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CapabilitySet(u32);
+
+impl CapabilitySet {
+    /// The bit advertising support for compressed frames, which lies beyond the range of message IDs.
+    pub const COMPRESSION: u16 = u32::BITS as u16 - 1;
+
+    /// Returns an empty capability set.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Returns the capability set of the given bits.
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the bits of the capability set.
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns the capability set with the given message ID added.
+    pub const fn with(self, id: u16) -> Self {
+        match id < u32::BITS as u16 {
+            true => Self(self.0 | (1 << id)),
+            false => self,
+        }
+    }
+
+    /// Returns the capability set with the given message ID removed.
+    pub const fn without(self, id: u16) -> Self {
+        match id < u32::BITS as u16 {
+            true => Self(self.0 & !(1 << id)),
+            false => self,
+        }
+    }
+
+    /// Returns `true` if the capability set contains the given message ID.
+    pub const fn contains(&self, id: u16) -> bool {
+        id < u32::BITS as u16 && self.0 & (1 << id) != 0
+    }
+
+    /// Returns `true` if the capability set advertises support for compressed frames.
+    pub const fn supports_compression(&self) -> bool {
+        self.contains(Self::COMPRESSION)
+    }
+
+    /// Returns `true` if the capability set contains the type of the given message.
+    pub fn supports<N: Network>(&self, message: &Message<N>) -> bool {
+        self.contains(message.id())
+    }
+}
+
+impl ToBytes for CapabilitySet {
+    fn write_le<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        self.0.write_le(writer)
+    }
+}
+
+impl FromBytes for CapabilitySet {
+    fn read_le<R: io::Read>(reader: R) -> io::Result<Self> {
+        Ok(Self(u32::read_le(reader)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MESSAGE_TYPE_NAMES;
+
+    type CurrentNetwork = snarkvm::prelude::Testnet3;
+
+    #[test]
+    fn test_capability_set() {
+        let capabilities = CapabilitySet::empty().with(0).with(12);
+        assert!(capabilities.contains(0));
+        assert!(capabilities.contains(12));
+        assert!(!capabilities.contains(1));
+        assert!(!capabilities.without(12).contains(12));
+        // Check that out-of-range message IDs are never contained.
+        assert!(!capabilities.with(u16::MAX).contains(u16::MAX));
+        assert_eq!(CapabilitySet::from_bits(capabilities.bits()), capabilities);
+        // Check that compression is advertised separately from the message types.
+        assert!(!capabilities.supports_compression());
+        assert!(capabilities.with(CapabilitySet::COMPRESSION).supports_compression());
+    }
+
+    #[test]
+    fn test_message_capabilities() {
+        // Check that the node understands every message type, and nothing else.
+        let capabilities = Message::<CurrentNetwork>::capabilities();
+        for id in 0..u32::BITS as u16 {
+            assert_eq!(capabilities.contains(id), (id as usize) < MESSAGE_TYPE_NAMES.len());
+        }
+    }
+}
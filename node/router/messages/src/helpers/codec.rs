@@ -12,11 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::Message;
+use crate::{Message, MessageTraffic};
+use snarkos_node_tcp::ConnectionSide;
 use snarkvm::prelude::{FromBytes, Network, ToBytes};
 
-use ::bytes::{Buf, BufMut, BytesMut};
+use ::bytes::{Buf, BufMut, Bytes, BytesMut};
 use core::marker::PhantomData;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use std::{
+    io::{Read, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 use tokio_util::codec::{Decoder, Encoder, LengthDelimitedCodec};
 
 /// The maximum size of a message that can be transmitted during the handshake.
@@ -25,9 +34,35 @@ const MAXIMUM_HANDSHAKE_MESSAGE_SIZE: usize = 1024 * 1024; // 1 MiB
 /// The maximum size of a message that can be transmitted in the network.
 pub(crate) const MAXIMUM_MESSAGE_SIZE: usize = 128 * 1024 * 1024; // 128 MiB
 
+/// The preamble that opens an oriented connection, sent by the initiator before its first message.
+const CODEC_PREAMBLE: [u8; 4] = *b"ALEO";
+
+/// The marker that opens a compressed frame, in place of a message ID.
+const COMPRESSED_FRAME_MARKER: u16 = u16::MAX;
+
+/// The minimum size of a message that is compressed, on connections that negotiated compression.
+const COMPRESSION_THRESHOLD: usize = 1024; // 1 KiB
+
+/// A callback invoked whenever a frame cannot be deserialized into a message.
+pub type MalformedFrameHandler = Box<dyn FnMut() + Send>;
+
 /// The codec used to decode and encode network `Message`s.
 pub struct MessageCodec<N: Network> {
     codec: LengthDelimitedCodec,
+    /// The codec version negotiated with the peer.
+    version: u8,
+    /// The counters of bytes sent and received, if they are tracked.
+    traffic: Option<Arc<MessageTraffic>>,
+    /// The callback for malformed frames, if they are tolerated.
+    on_malformed_frame: Option<MalformedFrameHandler>,
+    /// Whether the bytes of the pending frame have arrived over multiple reads.
+    is_partial: bool,
+    /// The side of the connection this codec is oriented for, if the connection opens with a preamble.
+    side: Option<ConnectionSide>,
+    /// Whether the preamble has yet to be sent (by an initiator) or received (by a responder).
+    is_preamble_pending: bool,
+    /// Whether compression is still in use, if it was negotiated with the peer.
+    compression: Option<Arc<AtomicBool>>,
     _phantom: PhantomData<N>,
 }
 
@@ -37,12 +72,70 @@ impl<N: Network> MessageCodec<N> {
         codec.codec.set_max_frame_length(MAXIMUM_HANDSHAKE_MESSAGE_SIZE);
         codec
     }
+
+    /// Uses the given codec version, as negotiated with the peer.
+    pub fn with_version(mut self, version: u8) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Returns the codec version.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Orients the codec for the given side of the connection, from the perspective of this node.
+    /// An initiator opens the connection with a preamble, and a responder expects the preamble before the first
+    /// message. Without a side, no preamble is sent or expected.
+    pub fn with_side(mut self, side: Option<ConnectionSide>) -> Self {
+        self.side = side;
+        self.is_preamble_pending = side.is_some();
+        self
+    }
+
+    /// Returns the side of the connection this codec is oriented for, if any.
+    pub fn side(&self) -> Option<ConnectionSide> {
+        self.side
+    }
+
+    /// Records the number of bytes sent and received by this codec into the given counters.
+    pub fn with_traffic(mut self, traffic: Arc<MessageTraffic>) -> Self {
+        self.traffic = Some(traffic);
+        self
+    }
+
+    /// Compresses large messages, and decompresses compressed frames, as negotiated with the peer.
+    /// The given flag is shared by both directions of the connection; once a compressed frame fails to decompress,
+    /// the flag is cleared, and the messages that follow are sent uncompressed.
+    pub fn with_compression(mut self, compression: Option<Arc<AtomicBool>>) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Returns `true` if compression was negotiated with the peer, and is still in use.
+    pub fn is_compressing(&self) -> bool {
+        matches!(&self.compression, Some(compression) if compression.load(Ordering::Relaxed))
+    }
+
+    /// Skips the frames that cannot be deserialized into a message, reporting each one to the given callback.
+    /// By default, a malformed frame is a decoding error.
+    pub fn with_malformed_frame_handler<F: FnMut() + Send + 'static>(mut self, handler: F) -> Self {
+        self.on_malformed_frame = Some(Box::new(handler));
+        self
+    }
 }
 
 impl<N: Network> Default for MessageCodec<N> {
     fn default() -> Self {
         Self {
             codec: LengthDelimitedCodec::builder().max_frame_length(MAXIMUM_MESSAGE_SIZE).little_endian().new_codec(),
+            version: 0,
+            traffic: None,
+            on_malformed_frame: None,
+            is_partial: false,
+            side: None,
+            is_preamble_pending: false,
+            compression: None,
             _phantom: Default::default(),
         }
     }
@@ -52,15 +145,43 @@ impl<N: Network> Encoder<Message<N>> for MessageCodec<N> {
     type Error = std::io::Error;
 
     fn encode(&mut self, message: Message<N>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        // Retrieve the message ID.
+        let id = message.id();
+        // Retrieve the codec version to switch to after this message, if it is a codec upgrade.
+        let upgrade_version = upgrade_version(&message);
         // Serialize the payload directly into dst.
         message
-            .write_le(&mut dst.writer())
+            .write_le_with_version(self.version, &mut dst.writer())
             // This error should never happen, the conversion is for greater compatibility.
             .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "serialization error"))?;
 
-        let serialized_message = dst.split_to(dst.len()).freeze();
+        let mut serialized_message = dst.split_to(dst.len()).freeze();
+
+        // Record the number of bytes sent.
+        if let Some(traffic) = &self.traffic {
+            traffic.record_sent(id, serialized_message.len());
+        }
+
+        // Compress the message, if compression is in use and the message is large enough to benefit from it.
+        if self.is_compressing() && serialized_message.len() >= COMPRESSION_THRESHOLD {
+            let compressed = compress(&serialized_message)?;
+            if compressed.len() < serialized_message.len() {
+                serialized_message = compressed;
+            }
+        }
 
-        self.codec.encode(serialized_message, dst)
+        // Open the connection with the preamble, if this codec is oriented for the initiator.
+        if self.side == Some(ConnectionSide::Initiator) && core::mem::take(&mut self.is_preamble_pending) {
+            dst.put_slice(&CODEC_PREAMBLE);
+        }
+
+        self.codec.encode(serialized_message, dst)?;
+
+        // Switch the codec version, if this message is a codec upgrade.
+        if let Some(version) = upgrade_version {
+            self.version = version;
+        }
+        Ok(())
     }
 }
 
@@ -69,20 +190,362 @@ impl<N: Network> Decoder for MessageCodec<N> {
     type Item = Message<N>;
 
     fn decode(&mut self, source: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        // Decode a frame containing bytes belonging to a message.
-        let bytes = match self.codec.decode(source)? {
-            Some(bytes) => bytes,
-            None => return Ok(None),
-        };
-
-        // Convert the bytes to a message, or fail if it is not valid.
-        let reader = bytes.reader();
-        match Message::read_le(reader) {
-            Ok(message) => Ok(Some(message)),
-            Err(error) => {
-                error!("Failed to deserialize a message: {}", error);
-                Err(std::io::ErrorKind::InvalidData.into())
+        // Expect the preamble ahead of the first message, if this codec is oriented for the responder.
+        if self.side == Some(ConnectionSide::Responder) && self.is_preamble_pending {
+            if source.len() < CODEC_PREAMBLE.len() {
+                return Ok(None);
+            }
+            if source.split_to(CODEC_PREAMBLE.len())[..] != CODEC_PREAMBLE {
+                error!("Received an invalid connection preamble");
+                return Err(std::io::ErrorKind::InvalidData.into());
+            }
+            self.is_preamble_pending = false;
+        }
+
+        loop {
+            // Decode a frame containing bytes belonging to a message.
+            let num_available = source.len();
+            let bytes = match self.codec.decode(source)? {
+                Some(bytes) => bytes,
+                None => {
+                    // If a part of the frame has arrived, the rest of it arrives over subsequent reads.
+                    if !source.is_empty() || source.len() < num_available {
+                        self.is_partial = true;
+                    }
+                    return Ok(None);
+                }
+            };
+            // Determine whether the frame was reassembled from multiple reads.
+            let is_reassembled = core::mem::take(&mut self.is_partial);
+
+            // Decompress the frame, if it is compressed and compression was negotiated with the peer.
+            let bytes = match &self.compression {
+                Some(compression) if bytes.starts_with(&COMPRESSED_FRAME_MARKER.to_le_bytes()) => {
+                    match decompress(&bytes[2..], self.codec.max_frame_length()) {
+                        Ok(bytes) => bytes,
+                        Err(error) => {
+                            // Fall back to uncompressed messages, warning only the first time.
+                            if compression.swap(false, Ordering::Relaxed) {
+                                warn!("Failed to decompress a message ({error}) - falling back to uncompressed");
+                            }
+                            // Report the corrupt frame as malformed, so that a peer cannot send them indefinitely.
+                            match &mut self.on_malformed_frame {
+                                Some(handler) => {
+                                    handler();
+                                    continue;
+                                }
+                                None => return Err(std::io::ErrorKind::InvalidData.into()),
+                            }
+                        }
+                    }
+                }
+                _ => bytes.freeze(),
+            };
+
+            // Retrieve the number of bytes received, before compression.
+            let num_bytes = bytes.len();
+
+            // Convert the bytes to a message, or fail if it is not valid.
+            let reader = bytes.reader();
+            match Message::read_le_with_version(self.version, reader) {
+                Ok(message) => {
+                    // Record the number of bytes received, and whether the message was reassembled.
+                    if let Some(traffic) = &self.traffic {
+                        traffic.record_received(message.id(), num_bytes);
+                        if is_reassembled {
+                            traffic.record_reassembled();
+                        }
+                    }
+                    // Switch the codec version, if this message is a codec upgrade.
+                    if let Some(version) = upgrade_version(&message) {
+                        self.version = version;
+                    }
+                    return Ok(Some(message));
+                }
+                Err(error) => {
+                    error!("Failed to deserialize a message: {}", error);
+                    // If malformed frames are tolerated, report the frame and proceed to the next one.
+                    match &mut self.on_malformed_frame {
+                        Some(handler) => handler(),
+                        None => return Err(std::io::ErrorKind::InvalidData.into()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns a compressed frame with the given serialized message.
+fn compress(serialized_message: &[u8]) -> std::io::Result<Bytes> {
+    let mut encoder = DeflateEncoder::new(COMPRESSED_FRAME_MARKER.to_le_bytes().to_vec(), Compression::fast());
+    encoder.write_all(serialized_message)?;
+    Ok(encoder.finish()?.into())
+}
+
+/// Returns the serialized message in the given compressed frame, or fails if it is corrupt or exceeds the given size.
+fn decompress(compressed: &[u8], max_size: usize) -> std::io::Result<Bytes> {
+    let mut serialized_message = Vec::new();
+    DeflateDecoder::new(compressed).take(max_size as u64 + 1).read_to_end(&mut serialized_message)?;
+    match serialized_message.len() <= max_size {
+        true => Ok(serialized_message.into()),
+        false => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "decompressed message is too large")),
+    }
+}
+
+/// Returns the codec version that the given message switches to, if it is an upgrade to a supported codec version.
+fn upgrade_version<N: Network>(message: &Message<N>) -> Option<u8> {
+    match message {
+        Message::CodecUpgrade(upgrade) if upgrade.version <= Message::<N>::MAXIMUM_CODEC_VERSION => Some(upgrade.version),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CodecUpgrade, PeerRequest, PeerResponse};
+
+    use std::{net::SocketAddr, sync::atomic::AtomicUsize};
+
+    type CurrentNetwork = snarkvm::prelude::Testnet3;
+
+    /// Appends a frame with the given payload to the given buffer.
+    fn push_frame(buffer: &mut BytesMut, payload: &[u8]) {
+        buffer.put_u32_le(payload.len() as u32);
+        buffer.put_slice(payload);
+    }
+
+    #[test]
+    fn test_malformed_frame_is_an_error() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+
+        // Check that a malformed frame fails to decode.
+        let mut buffer = BytesMut::new();
+        push_frame(&mut buffer, &[u8::MAX; 4]);
+        assert!(codec.decode(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn test_malformed_frames_are_reported() {
+        let num_malformed = Arc::new(AtomicUsize::new(0));
+        let mut codec = MessageCodec::<CurrentNetwork>::default().with_malformed_frame_handler({
+            let num_malformed = num_malformed.clone();
+            move || {
+                num_malformed.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        // Prepare two malformed frames, followed by a valid message.
+        let message = Message::<CurrentNetwork>::PeerRequest(PeerRequest);
+        let mut buffer = BytesMut::new();
+        push_frame(&mut buffer, &[u8::MAX; 4]);
+        push_frame(&mut buffer, &[]);
+        codec.encode(message.clone(), &mut buffer).unwrap();
+
+        // Check that the malformed frames are skipped and reported.
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(message));
+        assert_eq!(num_malformed.load(Ordering::SeqCst), 2);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_decode_single_byte_reads() {
+        let traffic = Arc::new(MessageTraffic::default());
+        let mut codec = MessageCodec::<CurrentNetwork>::default().with_version(1).with_traffic(traffic.clone());
+
+        // Encode a large message.
+        let peers = (0..2000).map(|port| SocketAddr::from(([127, 0, 0, 1], port))).collect::<Vec<_>>();
+        let message = Message::<CurrentNetwork>::PeerResponse(PeerResponse { peers });
+        let mut encoded = BytesMut::new();
+        codec.encode(message.clone(), &mut encoded).unwrap();
+
+        // Feed the message to the decoder one byte at a time.
+        let mut buffer = BytesMut::new();
+        let (last_byte, bytes) = encoded.split_last().unwrap();
+        for byte in bytes {
+            buffer.put_u8(*byte);
+            assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+        }
+        // Check that the last byte completes the original message.
+        buffer.put_u8(*last_byte);
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(message.clone()));
+        assert!(buffer.is_empty());
+        assert_eq!(traffic.num_reassembled_messages(), 1);
+
+        // Check that a message received in a single read is not counted as reassembled.
+        codec.encode(message.clone(), &mut buffer).unwrap();
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(message));
+        assert_eq!(traffic.num_reassembled_messages(), 1);
+    }
+
+    #[test]
+    fn test_codec_versions() {
+        // Prepare a peer response with more peers than codec version 0 allows.
+        let peers = (0..300).map(|port| SocketAddr::from(([127, 0, 0, 1], port))).collect::<Vec<_>>();
+        let message = Message::<CurrentNetwork>::PeerResponse(PeerResponse { peers: peers.clone() });
+
+        for version in [0, 1] {
+            let mut codec = MessageCodec::<CurrentNetwork>::default().with_version(version);
+            let mut buffer = BytesMut::new();
+            codec.encode(message.clone(), &mut buffer).unwrap();
+            let Some(Message::PeerResponse(response)) = codec.decode(&mut buffer).unwrap() else {
+                panic!("Expected a peer response");
+            };
+            // Check that codec version 0 truncates the peers, while codec version 1 carries all of them.
+            match version {
+                0 => assert_eq!(response.peers[..], peers[..u8::MAX as usize]),
+                _ => assert_eq!(response.peers, peers),
             }
         }
+
+        // Check that the other message types are encoded identically across codec versions.
+        let message = Message::<CurrentNetwork>::PeerRequest(PeerRequest);
+        let (mut v0, mut v1) = (BytesMut::new(), BytesMut::new());
+        MessageCodec::<CurrentNetwork>::default().with_version(0).encode(message.clone(), &mut v0).unwrap();
+        MessageCodec::<CurrentNetwork>::default().with_version(1).encode(message.clone(), &mut v1).unwrap();
+        assert_eq!(v0, v1);
+        assert_eq!(MessageCodec::<CurrentNetwork>::default().with_version(0).decode(&mut v1).unwrap(), Some(message));
+    }
+
+    #[test]
+    fn test_codec_version_negotiation() {
+        // Check that peers on the minimum version use codec version 0, and upgraded peers use the later versions.
+        assert_eq!(Message::<CurrentNetwork>::codec_version(Message::<CurrentNetwork>::MINIMUM_VERSION), 0);
+        assert_eq!(Message::<CurrentNetwork>::codec_version(12), 1);
+        assert_eq!(Message::<CurrentNetwork>::codec_version(13), 1);
+        assert_eq!(Message::<CurrentNetwork>::codec_version(Message::<CurrentNetwork>::VERSION), 2);
+    }
+
+    #[test]
+    fn test_codec_upgrade_switches_version() {
+        let mut sender = MessageCodec::<CurrentNetwork>::default().with_version(1);
+        let mut receiver = MessageCodec::<CurrentNetwork>::default().with_version(1);
+
+        // Send a codec upgrade, followed by a peer response.
+        let upgrade = Message::<CurrentNetwork>::CodecUpgrade(CodecUpgrade { version: 0, is_ack: false });
+        let response = Message::<CurrentNetwork>::PeerResponse(PeerResponse { peers: vec![] });
+        let mut buffer = BytesMut::new();
+        sender.encode(upgrade.clone(), &mut buffer).unwrap();
+        sender.encode(response.clone(), &mut buffer).unwrap();
+        // Check that the sender switched after the upgrade.
+        assert_eq!(sender.version(), 0);
+
+        // Check that the receiver switches after the upgrade, and decodes the peer response with the new version.
+        assert_eq!(receiver.decode(&mut buffer).unwrap(), Some(upgrade));
+        assert_eq!(receiver.version(), 0);
+        assert_eq!(receiver.decode(&mut buffer).unwrap(), Some(response));
+
+        // Check that an upgrade to an unsupported version does not switch the codec.
+        let upgrade = Message::<CurrentNetwork>::CodecUpgrade(CodecUpgrade { version: u8::MAX, is_ack: false });
+        sender.encode(upgrade.clone(), &mut buffer).unwrap();
+        assert_eq!(receiver.decode(&mut buffer).unwrap(), Some(upgrade));
+        assert_eq!((sender.version(), receiver.version()), (0, 0));
+    }
+
+    #[test]
+    fn test_compressed_frames() {
+        let compression = Arc::new(AtomicBool::new(true));
+        let mut sender = MessageCodec::<CurrentNetwork>::default().with_version(1).with_compression(Some(compression));
+        let mut receiver =
+            MessageCodec::<CurrentNetwork>::default().with_version(1).with_compression(Some(Default::default()));
+
+        // Check that a large message is compressed, and decompressed by the receiver.
+        let peers = (0..2000).map(|port| SocketAddr::from(([127, 0, 0, 1], port))).collect::<Vec<_>>();
+        let message = Message::<CurrentNetwork>::PeerResponse(PeerResponse { peers });
+        let (mut compressed, mut uncompressed) = (BytesMut::new(), BytesMut::new());
+        sender.encode(message.clone(), &mut compressed).unwrap();
+        MessageCodec::<CurrentNetwork>::default().with_version(1).encode(message.clone(), &mut uncompressed).unwrap();
+        assert!(compressed.len() < uncompressed.len());
+        assert_eq!(receiver.decode(&mut compressed).unwrap(), Some(message.clone()));
+
+        // Check that a small message is sent as is.
+        let request = Message::<CurrentNetwork>::PeerRequest(PeerRequest);
+        let mut buffer = BytesMut::new();
+        sender.encode(request.clone(), &mut buffer).unwrap();
+        assert_eq!(buffer[4..6], request.id().to_le_bytes());
+
+        // Check that a corrupt compressed frame is an error, and compression falls back to uncompressed.
+        let compression = Arc::new(AtomicBool::new(true));
+        let mut receiver = MessageCodec::<CurrentNetwork>::default().with_compression(Some(compression.clone()));
+        let mut buffer = BytesMut::new();
+        push_frame(&mut buffer, &[u8::MAX, u8::MAX, 1, 2, 3]);
+        assert!(receiver.decode(&mut buffer).is_err());
+        assert!(!compression.load(Ordering::SeqCst));
+        assert!(!receiver.is_compressing());
+
+        // Check that a compressed frame is malformed, if compression was not negotiated.
+        let mut buffer = BytesMut::new();
+        sender.encode(message, &mut buffer).unwrap();
+        assert!(MessageCodec::<CurrentNetwork>::default().with_version(1).decode(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn test_corrupt_compressed_frames_are_reported() {
+        const NUM_CORRUPT_FRAMES: usize = 10;
+
+        let num_malformed = Arc::new(AtomicUsize::new(0));
+        let mut codec = MessageCodec::<CurrentNetwork>::default()
+            .with_compression(Some(Arc::new(AtomicBool::new(true))))
+            .with_malformed_frame_handler({
+                let num_malformed = num_malformed.clone();
+                move || {
+                    num_malformed.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+
+        // Prepare corrupt compressed frames, followed by a valid message.
+        let message = Message::<CurrentNetwork>::PeerRequest(PeerRequest);
+        let mut buffer = BytesMut::new();
+        for _ in 0..NUM_CORRUPT_FRAMES {
+            push_frame(&mut buffer, &[u8::MAX, u8::MAX, 1, 2, 3]);
+        }
+        codec.encode(message.clone(), &mut buffer).unwrap();
+
+        // Check that every corrupt frame is reported, including those after the fallback to uncompressed.
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(message));
+        assert_eq!(num_malformed.load(Ordering::SeqCst), NUM_CORRUPT_FRAMES);
+        assert!(!codec.is_compressing());
+        assert!(buffer.is_empty());
+    }
+
+    /// Returns a codec on version 1, oriented for the given side of the connection.
+    fn oriented_codec(side: ConnectionSide) -> MessageCodec<CurrentNetwork> {
+        MessageCodec::default().with_version(1).with_side(Some(side))
+    }
+
+    #[test]
+    fn test_oriented_codecs() {
+        let mut initiator = oriented_codec(ConnectionSide::Initiator);
+        let mut responder = oriented_codec(ConnectionSide::Responder);
+
+        // Check that the initiator opens with the preamble, and the responder does not.
+        let message = Message::<CurrentNetwork>::PeerRequest(PeerRequest);
+        let (mut from_initiator, mut from_responder) = (BytesMut::new(), BytesMut::new());
+        initiator.encode(message.clone(), &mut from_initiator).unwrap();
+        responder.encode(message.clone(), &mut from_responder).unwrap();
+        assert_eq!(from_initiator[..CODEC_PREAMBLE.len()], CODEC_PREAMBLE);
+        assert_eq!(from_initiator.len(), from_responder.len() + CODEC_PREAMBLE.len());
+        // Check that the preamble is only sent once.
+        initiator.encode(message.clone(), &mut from_initiator).unwrap();
+        assert_eq!(from_initiator.len(), 2 * from_responder.len() + CODEC_PREAMBLE.len());
+
+        // Check that the codecs interoperate, with the responder consuming the preamble.
+        assert_eq!(responder.decode(&mut from_initiator).unwrap(), Some(message.clone()));
+        assert_eq!(responder.decode(&mut from_initiator).unwrap(), Some(message.clone()));
+        assert!(from_initiator.is_empty());
+        assert_eq!(initiator.decode(&mut from_responder).unwrap(), Some(message.clone()));
+
+        // Check that a responder rejects a connection without the preamble.
+        let mut responder = oriented_codec(ConnectionSide::Responder);
+        let mut buffer = BytesMut::new();
+        MessageCodec::<CurrentNetwork>::default().with_version(1).encode(message, &mut buffer).unwrap();
+        assert!(responder.decode(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn test_codec_orientation_negotiation() {
+        // Check that peers before version 13 use symmetric codecs, and upgraded peers use oriented codecs.
+        assert!(!Message::<CurrentNetwork>::is_oriented(12));
+        assert!(Message::<CurrentNetwork>::is_oriented(Message::<CurrentNetwork>::VERSION));
     }
 }
@@ -49,29 +49,52 @@ pub enum DisconnectReason {
     YouNeedToSyncFirst,
     /// The peer's listening port is closed.
     YourPortIsClosed(u16),
+    /// The peer is this node.
+    SelfConnection,
+    /// The peer exceeded a rate limit.
+    RateLimitExceeded,
+    /// The peer is not reading the messages sent to it fast enough.
+    SlowConsumer,
+    /// The peer did not solve the admission challenge demanded while the node is under load.
+    UnsolvedAdmissionChallenge,
+    /// The connection reached its maximum lifetime, and the peer may reconnect.
+    ConnectionLifetimeExpired,
+}
+
+impl DisconnectReason {
+    /// Returns the on-wire code of the disconnect reason.
+    pub const fn code(&self) -> u8 {
+        match self {
+            Self::ExceededForkRange => 0,
+            Self::InvalidChallengeResponse => 1,
+            Self::InvalidForkDepth => 2,
+            Self::INeedToSyncFirst => 3,
+            Self::NoReasonGiven => 4,
+            Self::ProtocolViolation => 5,
+            Self::OutdatedClientVersion => 6,
+            Self::PeerHasDisconnected => 7,
+            Self::PeerRefresh => 8,
+            Self::ShuttingDown => 9,
+            Self::SyncComplete => 10,
+            Self::TooManyFailures => 11,
+            Self::TooManyPeers => 12,
+            Self::YouNeedToSyncFirst => 13,
+            Self::YourPortIsClosed(..) => 14,
+            Self::SelfConnection => 15,
+            Self::RateLimitExceeded => 16,
+            Self::SlowConsumer => 17,
+            Self::UnsolvedAdmissionChallenge => 18,
+            Self::ConnectionLifetimeExpired => 19,
+        }
+    }
 }
 
 impl ToBytes for DisconnectReason {
     fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        self.code().write_le(&mut writer)?;
         match self {
-            Self::ExceededForkRange => 0u8.write_le(writer),
-            Self::InvalidChallengeResponse => 1u8.write_le(writer),
-            Self::InvalidForkDepth => 2u8.write_le(writer),
-            Self::INeedToSyncFirst => 3u8.write_le(writer),
-            Self::NoReasonGiven => 4u8.write_le(writer),
-            Self::ProtocolViolation => 5u8.write_le(writer),
-            Self::OutdatedClientVersion => 6u8.write_le(writer),
-            Self::PeerHasDisconnected => 7u8.write_le(writer),
-            Self::PeerRefresh => 8u8.write_le(writer),
-            Self::ShuttingDown => 9u8.write_le(writer),
-            Self::SyncComplete => 10u8.write_le(writer),
-            Self::TooManyFailures => 11u8.write_le(writer),
-            Self::TooManyPeers => 12u8.write_le(writer),
-            Self::YouNeedToSyncFirst => 13u8.write_le(writer),
-            Self::YourPortIsClosed(port) => {
-                14u8.write_le(&mut writer)?;
-                port.write_le(writer)
-            }
+            Self::YourPortIsClosed(port) => port.write_le(writer),
+            _ => Ok(()),
         }
     }
 }
@@ -97,6 +120,11 @@ impl FromBytes for DisconnectReason {
                 let port = u16::read_le(reader)?;
                 Ok(Self::YourPortIsClosed(port))
             }
+            15 => Ok(Self::SelfConnection),
+            16 => Ok(Self::RateLimitExceeded),
+            17 => Ok(Self::SlowConsumer),
+            18 => Ok(Self::UnsolvedAdmissionChallenge),
+            19 => Ok(Self::ConnectionLifetimeExpired),
             _ => Err(error("Invalid disconnect reason")),
         }
     }
@@ -0,0 +1,125 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use crate::Message;
+use snarkvm::prelude::Network;
+
+/// A feature of the network protocol, introduced in a given version of the protocol.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// Upgrading the codec of an established connection, with `CodecUpgrade` messages.
+    CodecUpgrade,
+    /// Codecs oriented by the side of the connection.
+    OrientedCodecs,
+    /// Puzzle responses without an epoch challenge.
+    UnavailablePuzzleResponse,
+    /// Admission challenges demanded during the handshake, with `AdmissionChallenge` messages.
+    AdmissionChallenge,
+    /// Epoch challenges of recent epochs served on request, with `EpochChallengeRequest` messages.
+    EpochChallenges,
+}
+
+impl Feature {
+    /// The features of the network protocol.
+    pub const ALL: [Feature; 5] = [
+        Feature::CodecUpgrade,
+        Feature::OrientedCodecs,
+        Feature::UnavailablePuzzleResponse,
+        Feature::AdmissionChallenge,
+        Feature::EpochChallenges,
+    ];
+
+    /// Returns the version of the network protocol that introduced the feature.
+    pub const fn min_version(&self) -> u32 {
+        match self {
+            Self::CodecUpgrade => 12,
+            Self::OrientedCodecs => 13,
+            Self::UnavailablePuzzleResponse => 14,
+            Self::AdmissionChallenge | Self::EpochChallenges => 15,
+        }
+    }
+
+    /// Returns the feature that the given message type belongs to, or `None` if every peer understands it.
+    pub fn of_message<N: Network>(message: &Message<N>) -> Option<Self> {
+        match message {
+            Message::CodecUpgrade(..) => Some(Self::CodecUpgrade),
+            Message::AdmissionChallenge(..) | Message::AdmissionSolution(..) => Some(Self::AdmissionChallenge),
+            Message::EpochChallengeRequest(..) | Message::EpochChallengeResponse(..) => Some(Self::EpochChallenges),
+            _ => None,
+        }
+    }
+}
+
+/// The set of features a peer supports, as derived from its version of the network protocol.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FeatureFlags(u32);
+
+impl FeatureFlags {
+    /// Returns the features supported on the given version of the network protocol.
+    pub fn from_version(version: u32) -> Self {
+        Self(
+            Feature::ALL
+                .iter()
+                .filter(|feature| version >= feature.min_version())
+                .fold(0, |bits, feature| bits | (1 << *feature as u32)),
+        )
+    }
+
+    /// Returns `true` if the given feature is supported.
+    pub const fn contains(&self, feature: Feature) -> bool {
+        self.0 & (1 << feature as u32) != 0
+    }
+
+    /// Returns `true` if the given message type is understood, i.e. it belongs to no feature or a supported one.
+    pub fn supports<N: Network>(&self, message: &Message<N>) -> bool {
+        Feature::of_message(message).map_or(true, |feature| self.contains(feature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CodecUpgrade, EpochChallengeRequest, PeerRequest};
+
+    type CurrentNetwork = snarkvm::prelude::Testnet3;
+
+    #[test]
+    fn test_feature_flags_from_version() {
+        // Check that the oldest supported version has none of the features, and the current one has all of them.
+        let oldest = FeatureFlags::from_version(Message::<CurrentNetwork>::MINIMUM_VERSION);
+        let current = FeatureFlags::from_version(Message::<CurrentNetwork>::VERSION);
+        for feature in Feature::ALL {
+            assert!(!oldest.contains(feature));
+            assert!(current.contains(feature));
+        }
+        // Check that a feature is supported from the version that introduced it.
+        let flags = FeatureFlags::from_version(13);
+        assert!(flags.contains(Feature::CodecUpgrade));
+        assert!(flags.contains(Feature::OrientedCodecs));
+        assert!(!flags.contains(Feature::UnavailablePuzzleResponse));
+    }
+
+    #[test]
+    fn test_feature_flags_support_messages() {
+        let flags = FeatureFlags::from_version(14);
+        // Check that the messages every peer understands are supported.
+        assert!(flags.supports(&Message::<CurrentNetwork>::PeerRequest(PeerRequest)));
+        // Check that the messages of the supported features are supported, and the newer ones are not.
+        let upgrade = Message::<CurrentNetwork>::CodecUpgrade(CodecUpgrade { version: 0, is_ack: false });
+        assert!(flags.supports(&upgrade));
+        let request = Message::<CurrentNetwork>::EpochChallengeRequest(EpochChallengeRequest { epoch_number: 0 });
+        assert!(!flags.supports(&request));
+    }
+}
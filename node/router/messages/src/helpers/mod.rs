@@ -12,11 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod capabilities;
+pub use capabilities::CapabilitySet;
+
 mod codec;
-pub use codec::MessageCodec;
+pub use codec::{MalformedFrameHandler, MessageCodec};
 
 mod disconnect;
 pub use disconnect::DisconnectReason;
 
+mod features;
+pub use features::{Feature, FeatureFlags};
+
 mod node_type;
 pub use node_type::*;
+
+mod traffic;
+pub use traffic::*;
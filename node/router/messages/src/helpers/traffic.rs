@@ -0,0 +1,144 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::Message;
+use snarkvm::prelude::Network;
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// The number of message types.
+const NUM_MESSAGE_TYPES: usize = 18;
+
+/// The names of the message types, indexed by message ID.
+pub const MESSAGE_TYPE_NAMES: [&str; NUM_MESSAGE_TYPES] = [
+    "BlockRequest",
+    "BlockResponse",
+    "ChallengeRequest",
+    "ChallengeResponse",
+    "Disconnect",
+    "PeerRequest",
+    "PeerResponse",
+    "Ping",
+    "Pong",
+    "PuzzleRequest",
+    "PuzzleResponse",
+    "UnconfirmedSolution",
+    "UnconfirmedTransaction",
+    "CodecUpgrade",
+    "AdmissionChallenge",
+    "AdmissionSolution",
+    "EpochChallengeRequest",
+    "EpochChallengeResponse",
+];
+
+/// The number of encoded bytes sent and received, per message type.
+#[derive(Debug, Default)]
+pub struct MessageTraffic {
+    /// The number of bytes sent, indexed by message ID.
+    sent: [AtomicU64; NUM_MESSAGE_TYPES],
+    /// The number of bytes received, indexed by message ID.
+    received: [AtomicU64; NUM_MESSAGE_TYPES],
+    /// The number of received messages that arrived over multiple reads.
+    reassembled: AtomicU64,
+}
+
+impl MessageTraffic {
+    /// Records the given number of bytes sent, for the given message ID.
+    pub fn record_sent(&self, id: u16, num_bytes: usize) {
+        if let Some(counter) = self.sent.get(id as usize) {
+            counter.fetch_add(num_bytes as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Records the given number of bytes received, for the given message ID.
+    pub fn record_received(&self, id: u16, num_bytes: usize) {
+        if let Some(counter) = self.received.get(id as usize) {
+            counter.fetch_add(num_bytes as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Records a received message that arrived over multiple reads.
+    pub fn record_reassembled(&self) {
+        self.reassembled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of received messages that arrived over multiple reads.
+    pub fn num_reassembled_messages(&self) -> u64 {
+        self.reassembled.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of bytes sent for the given message.
+    pub fn bytes_sent<N: Network>(&self, message: &Message<N>) -> u64 {
+        self.sent[message.id() as usize].load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of bytes received for the given message.
+    pub fn bytes_received<N: Network>(&self, message: &Message<N>) -> u64 {
+        self.received[message.id() as usize].load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of bytes (sent, received), for each message type.
+    pub fn bytes_by_type(&self) -> HashMap<&'static str, (u64, u64)> {
+        MESSAGE_TYPE_NAMES
+            .iter()
+            .zip(self.sent.iter().zip(self.received.iter()))
+            .map(|(name, (sent, received))| (*name, (sent.load(Ordering::Relaxed), received.load(Ordering::Relaxed))))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MessageCodec, PeerRequest};
+    use snarkvm::prelude::ToBytes;
+
+    use bytes::BytesMut;
+    use std::sync::Arc;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    type CurrentNetwork = snarkvm::prelude::Testnet3;
+
+    #[test]
+    fn test_message_traffic() {
+        let traffic = Arc::new(MessageTraffic::default());
+        let mut codec = MessageCodec::<CurrentNetwork>::default().with_traffic(traffic.clone());
+
+        // Check that the counters are empty.
+        let message = Message::<CurrentNetwork>::PeerRequest(PeerRequest);
+        assert_eq!(traffic.bytes_sent(&message), 0);
+        assert_eq!(traffic.bytes_received(&message), 0);
+
+        // Send the message.
+        let num_bytes = message.to_bytes_le().unwrap().len() as u64;
+        let mut buffer = BytesMut::new();
+        codec.encode(message.clone(), &mut buffer).unwrap();
+        // Check that the sent bytes increased by the encoded size.
+        assert_eq!(traffic.bytes_sent(&message), num_bytes);
+        assert_eq!(traffic.bytes_by_type()["PeerRequest"], (num_bytes, 0));
+
+        // Receive the message.
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(message.clone()));
+        // Check that the received bytes increased by the encoded size.
+        assert_eq!(traffic.bytes_received(&message), num_bytes);
+        assert_eq!(traffic.bytes_by_type()["PeerRequest"], (num_bytes, num_bytes));
+
+        // Check that the other message types are unaffected.
+        assert_eq!(traffic.bytes_by_type()["Ping"], (0, 0));
+        assert_eq!(traffic.bytes_by_type().len(), NUM_MESSAGE_TYPES);
+    }
+}
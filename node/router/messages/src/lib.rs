@@ -20,6 +20,12 @@ extern crate tracing;
 pub mod helpers;
 pub use helpers::*;
 
+mod admission_challenge;
+pub use admission_challenge::AdmissionChallenge;
+
+mod admission_solution;
+pub use admission_solution::AdmissionSolution;
+
 mod block_request;
 pub use block_request::BlockRequest;
 
@@ -32,9 +38,18 @@ pub use challenge_request::ChallengeRequest;
 mod challenge_response;
 pub use challenge_response::ChallengeResponse;
 
+mod codec_upgrade;
+pub use codec_upgrade::CodecUpgrade;
+
 mod disconnect;
 pub use disconnect::Disconnect;
 
+mod epoch_challenge_request;
+pub use epoch_challenge_request::EpochChallengeRequest;
+
+mod epoch_challenge_response;
+pub use epoch_challenge_response::EpochChallengeResponse;
+
 mod peer_request;
 pub use peer_request::PeerRequest;
 
@@ -101,6 +116,11 @@ pub enum Message<N: Network> {
     PuzzleResponse(PuzzleResponse<N>),
     UnconfirmedSolution(UnconfirmedSolution<N>),
     UnconfirmedTransaction(UnconfirmedTransaction<N>),
+    CodecUpgrade(CodecUpgrade),
+    AdmissionChallenge(AdmissionChallenge),
+    AdmissionSolution(AdmissionSolution),
+    EpochChallengeRequest(EpochChallengeRequest),
+    EpochChallengeResponse(EpochChallengeResponse<N>),
 }
 
 impl<N: Network> From<DisconnectReason> for Message<N> {
@@ -110,8 +130,44 @@ impl<N: Network> From<DisconnectReason> for Message<N> {
 }
 
 impl<N: Network> Message<N> {
-    /// The version of the network protocol; it can be incremented in order to force users to update.
-    pub const VERSION: u32 = 11;
+    /// The version of the network protocol.
+    pub const VERSION: u32 = 15;
+    /// The minimum supported version of the network protocol; it can be incremented in order to force users to update.
+    pub const MINIMUM_VERSION: u32 = 11;
+    /// The latest codec version.
+    pub const MAXIMUM_CODEC_VERSION: u8 = 2;
+
+    /// Returns the codec version to use with a peer on the given version of the network protocol.
+    /// Peers on version 11 predate the codec versioning, and use codec version 0.
+    /// Peers on versions 12 and 13 predate unavailable puzzle responses, and use codec version 1.
+    pub const fn codec_version(peer_version: u32) -> u8 {
+        match peer_version {
+            14.. => Self::MAXIMUM_CODEC_VERSION,
+            12..=13 => 1,
+            _ => 0,
+        }
+    }
+
+    /// Returns `true` if the codecs of a connection with a peer on the given version of the network protocol
+    /// are oriented by the side of the connection, with the initiator opening the connection with a preamble.
+    /// Peers before version 13 use symmetric codecs.
+    pub const fn is_oriented(peer_version: u32) -> bool {
+        peer_version >= 13
+    }
+
+    /// The number of message types understood by the peers that predate capabilities, with the IDs 0 to 12.
+    pub const NUM_LEGACY_MESSAGE_TYPES: usize = 13;
+
+    /// Returns the message types understood by this node, which are advertised to peers during the handshake.
+    pub fn capabilities() -> CapabilitySet {
+        CapabilitySet::from_bits((1 << MESSAGE_TYPE_NAMES.len()) - 1)
+    }
+
+    /// Returns the message types understood by the peers that predate capabilities,
+    /// which exclude the later message types, such as `CodecUpgrade`.
+    pub fn legacy_capabilities() -> CapabilitySet {
+        CapabilitySet::from_bits((1 << Self::NUM_LEGACY_MESSAGE_TYPES) - 1)
+    }
 
     /// Returns the message name.
     #[inline]
@@ -130,10 +186,16 @@ impl<N: Network> Message<N> {
             Self::PuzzleResponse(message) => message.name(),
             Self::UnconfirmedSolution(message) => message.name(),
             Self::UnconfirmedTransaction(message) => message.name(),
+            Self::CodecUpgrade(message) => message.name(),
+            Self::AdmissionChallenge(message) => message.name(),
+            Self::AdmissionSolution(message) => message.name(),
+            Self::EpochChallengeRequest(message) => message.name(),
+            Self::EpochChallengeResponse(message) => message.name(),
         }
     }
 
-    /// Returns the message ID.
+    /// Returns the message ID, which identifies the type of the message on the wire.
+    /// The ID is the same for all messages of a type; use `content_id` to identify a message by its contents.
     #[inline]
     pub fn id(&self) -> u16 {
         match self {
@@ -150,6 +212,59 @@ impl<N: Network> Message<N> {
             Self::PuzzleResponse(..) => 10,
             Self::UnconfirmedSolution(..) => 11,
             Self::UnconfirmedTransaction(..) => 12,
+            Self::CodecUpgrade(..) => 13,
+            Self::AdmissionChallenge(..) => 14,
+            Self::AdmissionSolution(..) => 15,
+            Self::EpochChallengeRequest(..) => 16,
+            Self::EpochChallengeResponse(..) => 17,
+        }
+    }
+
+    /// Returns the content ID of the message, which is a stable hash of its canonical encoding.
+    /// Messages with the same contents have the same content ID, whether they were received or built locally.
+    pub fn content_id(&self) -> io::Result<u64> {
+        let mut hasher = ContentHasher::default();
+        self.write_le(&mut hasher)?;
+        Ok(hasher.0)
+    }
+
+    /// Returns `true` if the message may be dropped when the node is under load.
+    #[inline]
+    pub fn is_sheddable(&self) -> bool {
+        matches!(self, Self::UnconfirmedSolution(..) | Self::UnconfirmedTransaction(..))
+    }
+}
+
+impl<N: Network> Message<N> {
+    /// Writes the message using the given codec version.
+    pub fn write_le_with_version<W: io::Write>(&self, codec_version: u8, mut writer: W) -> io::Result<()> {
+        match self {
+            // Codec version 1 allows for more peers in a peer response.
+            Self::PeerResponse(message) if codec_version >= 1 => {
+                self.id().write_le(&mut writer)?;
+                message.write_le_v1(writer)
+            }
+            // Codec version 2 allows for a puzzle response without an epoch challenge.
+            Self::PuzzleResponse(message) if codec_version >= 2 => {
+                self.id().write_le(&mut writer)?;
+                message.write_le_v2(writer)
+            }
+            _ => self.write_le(writer),
+        }
+    }
+
+    /// Reads a message using the given codec version.
+    pub fn read_le_with_version<R: io::Read>(codec_version: u8, mut reader: R) -> io::Result<Self> {
+        // Read the message ID.
+        let mut id_bytes = [0u8; 2];
+        reader.read_exact(&mut id_bytes)?;
+
+        match u16::from_le_bytes(id_bytes) {
+            // Codec version 1 allows for more peers in a peer response.
+            6 if codec_version >= 1 => Ok(Self::PeerResponse(PeerResponse::read_le_v1(reader)?)),
+            // Codec version 2 allows for a puzzle response without an epoch challenge.
+            10 if codec_version >= 2 => Ok(Self::PuzzleResponse(PuzzleResponse::read_le_v2(reader)?)),
+            _ => Self::read_le(io::Read::chain(&id_bytes[..], reader)),
         }
     }
 }
@@ -172,6 +287,11 @@ impl<N: Network> ToBytes for Message<N> {
             Self::PuzzleResponse(message) => message.write_le(writer),
             Self::UnconfirmedSolution(message) => message.write_le(writer),
             Self::UnconfirmedTransaction(message) => message.write_le(writer),
+            Self::CodecUpgrade(message) => message.write_le(writer),
+            Self::AdmissionChallenge(message) => message.write_le(writer),
+            Self::AdmissionSolution(message) => message.write_le(writer),
+            Self::EpochChallengeRequest(message) => message.write_le(writer),
+            Self::EpochChallengeResponse(message) => message.write_le(writer),
         }
     }
 }
@@ -198,9 +318,44 @@ impl<N: Network> FromBytes for Message<N> {
             10 => Self::PuzzleResponse(PuzzleResponse::read_le(reader)?),
             11 => Self::UnconfirmedSolution(UnconfirmedSolution::read_le(reader)?),
             12 => Self::UnconfirmedTransaction(UnconfirmedTransaction::read_le(reader)?),
-            13.. => return Err(error("Unknown message ID {id}")),
+            13 => Self::CodecUpgrade(CodecUpgrade::read_le(reader)?),
+            14 => Self::AdmissionChallenge(AdmissionChallenge::read_le(reader)?),
+            15 => Self::AdmissionSolution(AdmissionSolution::read_le(reader)?),
+            16 => Self::EpochChallengeRequest(EpochChallengeRequest::read_le(reader)?),
+            17 => Self::EpochChallengeResponse(EpochChallengeResponse::read_le(reader)?),
+            18.. => return Err(error("Unknown message ID {id}")),
         };
 
         Ok(message)
     }
 }
+
+/// A 64-bit FNV-1a hasher over the encoding of a message.
+/// Unlike the standard library hashers, its output is stable across releases and platforms.
+struct ContentHasher(u64);
+
+impl ContentHasher {
+    /// The FNV-1a offset basis.
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    /// The FNV-1a prime.
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+}
+
+impl Default for ContentHasher {
+    fn default() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+}
+
+impl io::Write for ContentHasher {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(Self::PRIME);
+        }
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -31,6 +31,29 @@ impl MessageTrait for PeerResponse {
     }
 }
 
+impl PeerResponse {
+    /// Writes the peer response for codec version 1, which allows for up to `u16::MAX` peers.
+    pub fn write_le_v1<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        // Restrict the maximum number of peers to share.
+        (self.peers.len().min(u16::MAX as usize) as u16).write_le(&mut writer)?;
+        for peer in self.peers.iter().take(u16::MAX as usize) {
+            peer.write_le(&mut writer)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the peer response for codec version 1, which allows for up to `u16::MAX` peers.
+    pub fn read_le_v1<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let count = u16::read_le(&mut reader)?;
+        let mut peers = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            peers.push(SocketAddr::read_le(&mut reader)?);
+        }
+
+        Ok(Self { peers })
+    }
+}
+
 impl ToBytes for PeerResponse {
     fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
         // Restrict the maximum number of peers to share.
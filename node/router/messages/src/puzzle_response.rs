@@ -23,7 +23,8 @@ use std::borrow::Cow;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct PuzzleResponse<N: Network> {
-    pub epoch_challenge: EpochChallenge<N>,
+    /// The latest epoch challenge, or `None` if the ledger does not have an epoch challenge yet.
+    pub epoch_challenge: Option<EpochChallenge<N>>,
     pub block_header: Data<Header<N>>,
 }
 
@@ -35,16 +36,43 @@ impl<N: Network> MessageTrait for PuzzleResponse<N> {
     }
 }
 
+impl<N: Network> PuzzleResponse<N> {
+    /// Writes the puzzle response for codec version 2, which marks whether the epoch challenge is available.
+    pub fn write_le_v2<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        match &self.epoch_challenge {
+            Some(epoch_challenge) => {
+                true.write_le(&mut writer)?;
+                epoch_challenge.write_le(&mut writer)?;
+            }
+            None => false.write_le(&mut writer)?,
+        }
+        self.block_header.write_le(&mut writer)
+    }
+
+    /// Reads the puzzle response for codec version 2, which marks whether the epoch challenge is available.
+    pub fn read_le_v2<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let epoch_challenge = match bool::read_le(&mut reader)? {
+            true => Some(EpochChallenge::read_le(&mut reader)?),
+            false => None,
+        };
+        Ok(Self { epoch_challenge, block_header: Data::read_le(reader)? })
+    }
+}
+
 impl<N: Network> ToBytes for PuzzleResponse<N> {
     fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
-        self.epoch_challenge.write_le(&mut writer)?;
+        // Codecs before version 2 cannot mark an unavailable epoch challenge.
+        let Some(epoch_challenge) = &self.epoch_challenge else {
+            return Err(error("An unavailable puzzle response requires codec version 2"));
+        };
+        epoch_challenge.write_le(&mut writer)?;
         self.block_header.write_le(&mut writer)
     }
 }
 
 impl<N: Network> FromBytes for PuzzleResponse<N> {
     fn read_le<R: io::Read>(mut reader: R) -> io::Result<Self> {
-        Ok(Self { epoch_challenge: EpochChallenge::read_le(&mut reader)?, block_header: Data::read_le(reader)? })
+        Ok(Self { epoch_challenge: Some(EpochChallenge::read_le(&mut reader)?), block_header: Data::read_le(reader)? })
     }
 }
 
@@ -75,7 +103,10 @@ pub mod prop_tests {
 
     pub fn any_puzzle_response() -> BoxedStrategy<PuzzleResponse<CurrentNetwork>> {
         (any_epoch_challenge(), any_genesis_header())
-            .prop_map(|(epoch_challenge, bh)| PuzzleResponse { epoch_challenge, block_header: Data::Object(bh) })
+            .prop_map(|(epoch_challenge, bh)| PuzzleResponse {
+                epoch_challenge: Some(epoch_challenge),
+                block_header: Data::Object(bh),
+            })
             .boxed()
     }
 
@@ -91,4 +122,32 @@ pub mod prop_tests {
             deserialized.block_header.deserialize_blocking().unwrap(),
         );
     }
+
+    #[proptest]
+    fn puzzle_response_v2_roundtrip(
+        #[strategy(any_puzzle_response())] original: PuzzleResponse<CurrentNetwork>,
+        is_available: bool,
+    ) {
+        let original = match is_available {
+            true => original,
+            false => PuzzleResponse { epoch_challenge: None, ..original },
+        };
+        let mut buf = BytesMut::default().writer();
+        original.write_le_v2(&mut buf).unwrap();
+
+        let deserialized = PuzzleResponse::<CurrentNetwork>::read_le_v2(buf.into_inner().reader()).unwrap();
+        assert_eq!(original.epoch_challenge, deserialized.epoch_challenge);
+        assert_eq!(
+            original.block_header.deserialize_blocking().unwrap(),
+            deserialized.block_header.deserialize_blocking().unwrap(),
+        );
+    }
+
+    #[proptest]
+    fn unavailable_puzzle_response_requires_v2(
+        #[strategy(any_puzzle_response())] original: PuzzleResponse<CurrentNetwork>,
+    ) {
+        let response = PuzzleResponse { epoch_challenge: None, ..original };
+        assert!(response.write_le(Vec::new()).is_err());
+    }
 }
@@ -50,7 +50,7 @@ impl<N: Network> FromBytes for UnconfirmedSolution<N> {
 
 #[cfg(test)]
 pub mod prop_tests {
-    use crate::{ProverSolution, PuzzleCommitment, UnconfirmedSolution};
+    use crate::{Message, ProverSolution, PuzzleCommitment, UnconfirmedSolution};
     use snarkvm::{
         algorithms::polycommit::kzg10::{KZGCommitment, KZGProof},
         ledger::{coinbase::PartialSolution, narwhal::Data},
@@ -102,4 +102,32 @@ pub mod prop_tests {
             deserialized.solution.deserialize_blocking().unwrap(),
         );
     }
+
+    #[proptest]
+    fn unconfirmed_solution_content_id(
+        #[strategy(any_unconfirmed_solution())] original: UnconfirmedSolution<CurrentNetwork>,
+    ) {
+        let message = Message::UnconfirmedSolution(original);
+
+        // Re-encode the message, and check that the content ID is unchanged.
+        let bytes = message.to_bytes_le().unwrap();
+        let decoded = Message::<CurrentNetwork>::read_le(&bytes[..]).unwrap();
+        assert_eq!(message.content_id().unwrap(), decoded.content_id().unwrap());
+    }
+
+    #[test]
+    fn distinct_solutions_have_distinct_content_ids() {
+        let mut rng = TestRng::default();
+        let mut content_ids = std::collections::HashSet::new();
+        for _ in 0..100 {
+            // Sample a distinct solution.
+            let private_key = PrivateKey::<CurrentNetwork>::new(&mut rng).unwrap();
+            let address = Address::try_from(private_key).unwrap();
+            let partial_solution = PartialSolution::new(address, rng.gen(), KZGCommitment(rng.gen()));
+            let solution = ProverSolution::new(partial_solution, KZGProof { w: rng.gen(), random_v: None });
+            let message = UnconfirmedSolution { solution_id: solution.commitment(), solution: Data::Object(solution) };
+            // Check that the content ID does not collide with the previous solutions.
+            assert!(content_ids.insert(Message::UnconfirmedSolution(message).content_id().unwrap()));
+        }
+    }
 }
@@ -0,0 +1,167 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::Router;
+use snarkvm::prelude::Network;
+
+use indexmap::IndexSet;
+use parking_lot::Mutex;
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+/// The default number of outbound connections maintained by the connection manager.
+pub const DEFAULT_TARGET_OUTBOUND_PEERS: usize = 8;
+
+/// A manager that keeps a target number of outbound connections, redialing as soon as a connected peer drops.
+/// Candidates are drawn from the pinned peers, the peers it previously maintained, and the candidate peers,
+/// which include the saved and discovered peers. Candidates that fail to connect with a transient error
+/// are retried with a backoff, while those that fail with a permanent error are no longer dialed.
+pub struct ConnectionManager<N: Network> {
+    /// The router of the node.
+    router: Router<N>,
+    /// The target number of outbound connections.
+    target: AtomicUsize,
+    /// The peers dialed by the manager, from the least to the most recently connected.
+    outbound: Mutex<IndexSet<SocketAddr>>,
+    /// The map of failed candidates to their number of consecutive failures, and the time of their next attempt.
+    backoff: Mutex<HashMap<SocketAddr, (u32, Instant)>>,
+    /// The candidates that failed to connect with a permanent error, which are no longer dialed.
+    excluded: Mutex<HashSet<SocketAddr>>,
+}
+
+impl<N: Network> ConnectionManager<N> {
+    /// The duration in seconds between maintenance passes, in the absence of disconnects.
+    const MAINTENANCE_INTERVAL_IN_SECS: u64 = 5;
+    /// The backoff in milliseconds after the first failed attempt to connect to a candidate.
+    const INITIAL_BACKOFF_IN_MS: u64 = 500;
+    /// The maximum backoff in seconds between attempts to connect to a candidate.
+    const MAXIMUM_BACKOFF_IN_SECS: u64 = 60;
+
+    /// Initializes a new connection manager with the given target number of outbound connections.
+    pub fn new(router: Router<N>, target: usize) -> Self {
+        Self {
+            router,
+            target: AtomicUsize::new(target),
+            outbound: Default::default(),
+            backoff: Default::default(),
+            excluded: Default::default(),
+        }
+    }
+
+    /// Returns the target number of outbound connections.
+    pub fn target(&self) -> usize {
+        self.target.load(Ordering::Relaxed)
+    }
+
+    /// Sets the target number of outbound connections.
+    pub fn set_target(&self, target: usize) {
+        self.target.store(target, Ordering::Relaxed);
+    }
+
+    /// Returns the connected peers that were dialed by the manager.
+    pub fn outbound_peers(&self) -> Vec<SocketAddr> {
+        self.outbound.lock().iter().copied().filter(|peer_ip| self.router.is_connected(peer_ip)).collect()
+    }
+
+    /// Returns the number of connected peers that were dialed by the manager.
+    pub fn number_of_outbound_peers(&self) -> usize {
+        self.outbound_peers().len()
+    }
+
+    /// Returns the candidates that failed to connect with a permanent error, and are no longer dialed.
+    pub fn excluded_peers(&self) -> Vec<SocketAddr> {
+        self.excluded.lock().iter().copied().collect()
+    }
+
+    /// Maintains the target number of outbound connections until the node shuts down.
+    /// A maintenance pass runs as soon as a peer disconnects, and periodically otherwise.
+    pub async fn run(&self) {
+        loop {
+            self.maintain().await;
+            tokio::select! {
+                _ = self.router.shutdown_token().cancelled() => return,
+                _ = self.router.disconnected() => {}
+                _ = tokio::time::sleep(Duration::from_secs(Self::MAINTENANCE_INTERVAL_IN_SECS)) => {}
+            }
+        }
+    }
+
+    /// Dials candidates until the target number of outbound connections is reached, or the candidates run out.
+    pub async fn maintain(&self) {
+        // Retain the outbound peers that are still connected, and retrieve the dropped ones.
+        let dropped = {
+            let mut outbound = self.outbound.lock();
+            let dropped = outbound.iter().copied().filter(|peer_ip| !self.router.is_connected(peer_ip)).collect();
+            outbound.retain(|peer_ip| self.router.is_connected(peer_ip));
+            dropped
+        };
+        // Compute the number of missing outbound connections.
+        let num_deficient = self.target().saturating_sub(self.number_of_outbound_peers());
+        if num_deficient == 0 {
+            return;
+        }
+        // Dial the candidates concurrently.
+        let handles = self
+            .candidates(dropped)
+            .into_iter()
+            .filter_map(|peer_ip| self.router.dial(peer_ip).ok().map(|handle| (peer_ip, handle)))
+            .take(num_deficient)
+            .collect::<Vec<_>>();
+        // Record the outcome of each attempt.
+        for (peer_ip, handle) in handles {
+            match handle.await {
+                Ok(Ok(())) if self.router.is_connected(&peer_ip) => {
+                    debug!("Maintaining an outbound connection to '{peer_ip}'");
+                    self.backoff.lock().remove(&peer_ip);
+                    self.outbound.lock().insert(peer_ip);
+                }
+                // Stop dialing the candidate, if the failure will not go away when retried.
+                Ok(Err(error)) if !error.is_transient() => {
+                    debug!("No longer dialing '{peer_ip}' - {error}");
+                    self.backoff.lock().remove(&peer_ip);
+                    self.excluded.lock().insert(peer_ip);
+                }
+                _ => self.insert_backoff(peer_ip),
+            }
+        }
+    }
+
+    /// Returns the candidates to dial, starting with the pinned peers and the given dropped peers.
+    fn candidates(&self, dropped: Vec<SocketAddr>) -> IndexSet<SocketAddr> {
+        let now = self.router.clock().now();
+        let backoff = self.backoff.lock();
+        let excluded = self.excluded.lock();
+        self.router
+            .pinned_peers()
+            .into_iter()
+            .chain(dropped)
+            .chain(self.router.candidate_peers())
+            .filter(|peer_ip| !self.router.is_connected(peer_ip) && !excluded.contains(peer_ip))
+            .filter(|peer_ip| !matches!(backoff.get(peer_ip), Some((_, next_attempt)) if *next_attempt > now))
+            .collect()
+    }
+
+    /// Doubles the backoff of the given candidate, up to the maximum backoff.
+    fn insert_backoff(&self, peer_ip: SocketAddr) {
+        let mut backoff = self.backoff.lock();
+        let (num_failures, next_attempt) = backoff.entry(peer_ip).or_insert((0, self.router.clock().now()));
+        let delay = Duration::from_millis(Self::INITIAL_BACKOFF_IN_MS.saturating_mul(1 << (*num_failures).min(16)));
+        *num_failures = num_failures.saturating_add(1);
+        *next_attempt = self.router.clock().now() + delay.min(Duration::from_secs(Self::MAXIMUM_BACKOFF_IN_SECS));
+    }
+}
@@ -13,9 +13,23 @@
 // limitations under the License.
 
 use crate::{
-    messages::{ChallengeRequest, ChallengeResponse, DisconnectReason, Message, MessageCodec, MessageTrait},
+    messages::{
+        AdmissionChallenge,
+        AdmissionSolution,
+        ChallengeRequest,
+        ChallengeResponse,
+        DisconnectReason,
+        Feature,
+        FeatureFlags,
+        Message,
+        MessageCodec,
+        MessageTrait,
+    },
+    HandshakeError,
+    ListenerPolicy,
     Peer,
     Router,
+    ViolationTier,
 };
 use snarkos_node_tcp::{ConnectionSide, Tcp, P2P};
 use snarkvm::{
@@ -25,9 +39,10 @@ use snarkvm::{
 
 use anyhow::{bail, Result};
 use futures::SinkExt;
+use parking_lot::Mutex;
 use rand::{rngs::OsRng, Rng};
-use std::{io, net::SocketAddr};
-use tokio::net::TcpStream;
+use std::{collections::HashSet, io, net::SocketAddr};
+use tokio::{net::TcpStream, task::spawn_blocking};
 use tokio_stream::StreamExt;
 use tokio_util::codec::Framed;
 
@@ -41,32 +56,42 @@ impl<N: Network> P2P for Router<N> {
 /// A macro unwrapping the expected handshake message or returning an error for unexpected messages.
 #[macro_export]
 macro_rules! expect_message {
-    ($msg_ty:path, $framed:expr, $peer_addr:expr) => {
-        match $framed.try_next().await? {
+    // Matches a message that was already received.
+    (@received $msg_ty:path, $message:expr, $peer_addr:expr) => {
+        match $message {
             // Received the expected message, proceed.
             Some($msg_ty(data)) => {
                 trace!("Received '{}' from '{}'", data.name(), $peer_addr);
                 data
             }
             // Received a disconnect message, abort.
-            Some(Message::Disconnect(reason)) => {
-                return Err(error(format!("'{}' disconnected: {reason:?}", $peer_addr)))
+            Some(Message::Disconnect(message)) => {
+                return Err($crate::HandshakeError::Disconnected($peer_addr, message.reason).into())
             }
             // Received an unexpected message, abort.
             Some(ty) => {
-                return Err(error(format!(
-                    "'{}' did not follow the handshake protocol: received {:?} instead of {}",
-                    $peer_addr,
-                    ty.name(),
-                    stringify!($msg_ty),
-                )))
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "'{}' did not follow the handshake protocol: received {:?} instead of {}",
+                        $peer_addr,
+                        ty.name(),
+                        stringify!($msg_ty),
+                    ),
+                ))
             }
             // Received nothing.
             None => {
-                return Err(error(format!("'{}' disconnected before sending {:?}", $peer_addr, stringify!($msg_ty),)))
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::ConnectionAborted,
+                    format!("'{}' disconnected before sending {:?}", $peer_addr, stringify!($msg_ty)),
+                ))
             }
         }
     };
+    ($msg_ty:path, $framed:expr, $peer_addr:expr) => {
+        $crate::expect_message!(@received $msg_ty, $framed.try_next().await?, $peer_addr)
+    };
 }
 
 /// Send the given message to the peer.
@@ -79,15 +104,64 @@ async fn send<N: Network>(
     framed.send(message).await
 }
 
+/// Returns the error for a handshake that was aborted with the given disconnect reason.
+/// An invalid challenge response is a protocol failure by the peer, and is returned as invalid data.
+fn dropped(peer_addr: SocketAddr, reason: DisconnectReason) -> io::Error {
+    HandshakeError::Dropped(peer_addr, reason).into()
+}
+
+/// A guard for an outstanding challenge request nonce, which is released when the handshake ends.
+struct NonceGuard<'a> {
+    nonces: &'a Mutex<HashSet<u64>>,
+    nonce: u64,
+}
+
+impl<'a> NonceGuard<'a> {
+    /// Registers the given nonce as outstanding.
+    fn new(nonces: &'a Mutex<HashSet<u64>>, nonce: u64) -> Self {
+        nonces.lock().insert(nonce);
+        Self { nonces, nonce }
+    }
+}
+
+impl Drop for NonceGuard<'_> {
+    fn drop(&mut self) {
+        self.nonces.lock().remove(&self.nonce);
+    }
+}
+
 impl<N: Network> Router<N> {
     /// Executes the handshake protocol.
+    ///
+    /// If the peer hangs up during the handshake, the error is of kind `ConnectionAborted`.
+    /// If the peer violates the handshake protocol, the error is of kind `InvalidData`, and the peer is restricted.
+    /// The error is classified as transient or permanent, see `HandshakeError::is_transient`.
     pub async fn handshake<'a>(
         &'a self,
         peer_addr: SocketAddr,
         stream: &'a mut TcpStream,
         peer_side: ConnectionSide,
         genesis_header: Header<N>,
-    ) -> io::Result<(SocketAddr, Framed<&mut TcpStream, MessageCodec<N>>)> {
+    ) -> Result<(SocketAddr, Framed<&mut TcpStream, MessageCodec<N>>), HandshakeError> {
+        // Track the rate of inbound connections, which determines whether an admission challenge is demanded.
+        if peer_side == ConnectionSide::Initiator {
+            self.record_inbound_connection();
+        }
+
+        // Wait briefly for a handshake permit, or reject the connection if too many handshakes are in progress.
+        let Some(_permit) = self.handshake_limiter.acquire().await else {
+            if peer_side == ConnectionSide::Initiator {
+                // Inform the peer that this node is too busy to accept the connection.
+                let mut framed = Framed::new(&mut *stream, MessageCodec::<N>::handshake());
+                let _ = send(&mut framed, peer_addr, DisconnectReason::TooManyPeers.into()).await;
+            } else {
+                // Remove the address from the collection of connecting peers.
+                self.connecting_peers.lock().remove(&peer_addr);
+            }
+            let error = format!("Dropping connection with '{peer_addr}' (too many concurrent handshakes)");
+            return Err(HandshakeError::Io(peer_addr, io::ErrorKind::Other, error));
+        };
+
         // If this is an inbound connection, we log it, but don't know the listening address yet.
         // Otherwise, we can immediately register the listening address.
         let mut peer_ip = if peer_side == ConnectionSide::Initiator {
@@ -99,11 +173,31 @@ impl<N: Network> Router<N> {
         };
 
         // Perform the handshake; we pass on a mutable reference to peer_ip in case the process is broken at any point in time.
-        let handshake_result = if peer_side == ConnectionSide::Responder {
-            self.handshake_inner_initiator(peer_addr, &mut peer_ip, stream, genesis_header).await
-        } else {
-            self.handshake_inner_responder(peer_addr, &mut peer_ip, stream, genesis_header).await
-        };
+        // The handshake is abandoned if it does not complete within the configured timeout.
+        let start = self.clock().now();
+        let peer_ip_ref = &mut peer_ip;
+        let handshake_result = tokio::time::timeout(self.handshake_timeout(), async move {
+            if peer_side == ConnectionSide::Responder {
+                self.handshake_inner_initiator(peer_addr, peer_ip_ref, stream, genesis_header).await
+            } else {
+                self.handshake_inner_responder(peer_addr, peer_ip_ref, stream, genesis_header).await
+            }
+        })
+        .await
+        .unwrap_or_else(|_| Err(HandshakeError::TimedOut(peer_addr).into()))
+        .map_err(|error| HandshakeError::from_io(peer_addr, error));
+
+        // Record a violation if the peer violated the handshake protocol, but not if it merely hung up.
+        if let (Err(error), Some(ip)) = (&handshake_result, peer_ip) {
+            match error.kind() {
+                io::ErrorKind::ConnectionAborted => debug!("{error}"),
+                io::ErrorKind::InvalidData => match self.record_violation(ip) {
+                    ViolationTier::Probation => warn!("Placing '{ip}' on probation - {error}"),
+                    ViolationTier::Restricted => warn!("Restricting '{ip}' - {error}"),
+                },
+                _ => (),
+            }
+        }
 
         // Remove the address from the collection of connecting peers (if the handshake got to the point where it's known).
         if let Some(ip) = peer_ip {
@@ -113,6 +207,10 @@ impl<N: Network> Router<N> {
         // If the handshake succeeded, announce it.
         if let Ok((ref peer_ip, _)) = handshake_result {
             info!("Connected to '{peer_ip}'");
+            // Record the duration of the handshake.
+            self.record_handshake_duration(self.clock().elapsed(start));
+            // Invoke the handshake callbacks.
+            self.run_handshake_hooks(*peer_ip, peer_side);
         }
 
         handshake_result
@@ -138,14 +236,28 @@ impl<N: Network> Router<N> {
 
         // Sample a random nonce.
         let our_nonce = rng.gen();
+        // Register the nonce, so that this node recognizes the challenge request if it is connecting to itself.
+        let _nonce_guard = NonceGuard::new(&self.handshake_nonces, our_nonce);
         // Send a challenge request to the peer.
-        let our_request = ChallengeRequest::new(self.local_ip().port(), self.node_type, self.address(), our_nonce);
+        let our_request = ChallengeRequest::new(self.local_ip().port(), self.node_type, self.address(), our_nonce)
+            .with_capabilities(self.capabilities());
         send(&mut framed, peer_addr, Message::ChallengeRequest(our_request)).await?;
 
         /* Step 2: Receive the peer's challenge response followed by the challenge request. */
 
-        // Listen for the challenge response message.
-        let peer_response = expect_message!(Message::ChallengeResponse, framed, peer_addr);
+        // Listen for the challenge response message, solving an admission challenge first if the peer demands one.
+        let peer_response = match framed.try_next().await? {
+            Some(Message::AdmissionChallenge(challenge)) => {
+                trace!("Received '{}' from '{peer_addr}'", challenge.name());
+                // Solve the challenge on a blocking thread, as it is CPU-bound.
+                let Ok(Some(nonce)) = spawn_blocking(move || challenge.solve()).await else {
+                    return Err(error(format!("Failed to solve the admission challenge from '{peer_addr}'")));
+                };
+                send(&mut framed, peer_addr, Message::AdmissionSolution(AdmissionSolution { nonce })).await?;
+                expect_message!(Message::ChallengeResponse, framed, peer_addr)
+            }
+            message => expect_message!(@received Message::ChallengeResponse, message, peer_addr),
+        };
         // Listen for the challenge request message.
         let peer_request = expect_message!(Message::ChallengeRequest, framed, peer_addr);
 
@@ -155,12 +267,13 @@ impl<N: Network> Router<N> {
             .await
         {
             send(&mut framed, peer_addr, reason.into()).await?;
-            return Err(error(format!("Dropped '{peer_addr}' for reason: {reason:?}")));
+            return Err(dropped(peer_addr, reason));
         }
         // Verify the challenge request. If a disconnect reason was returned, send the disconnect message and abort.
-        if let Some(reason) = self.verify_challenge_request(peer_addr, &peer_request) {
+        let (peer_side, policy) = (ConnectionSide::Responder, ListenerPolicy::Standard);
+        if let Some(reason) = self.verify_challenge_request(peer_addr, &peer_request, peer_side, policy) {
             send(&mut framed, peer_addr, reason.into()).await?;
-            return Err(error(format!("Dropped '{peer_addr}' for reason: {reason:?}")));
+            return Err(dropped(peer_addr, reason));
         }
         /* Step 3: Send the challenge response. */
 
@@ -172,8 +285,10 @@ impl<N: Network> Router<N> {
         let our_response = ChallengeResponse { genesis_header, signature: Data::Object(our_signature) };
         send(&mut framed, peer_addr, Message::ChallengeResponse(our_response)).await?;
 
-        // Add the peer to the router.
-        self.insert_connected_peer(Peer::new(peer_ip, &peer_request), peer_addr);
+        // Add the peer to the router, evicting a connected peer to make room for it, if needed.
+        let peer = Peer::new(peer_ip, &peer_request, ConnectionSide::Responder, self.clock().now());
+        self.evict_for_newcomer(&peer, policy);
+        self.insert_connected_peer(peer, peer_addr);
 
         Ok((peer_ip, framed))
     }
@@ -186,6 +301,8 @@ impl<N: Network> Router<N> {
         stream: &'a mut TcpStream,
         genesis_header: Header<N>,
     ) -> io::Result<(SocketAddr, Framed<&mut TcpStream, MessageCodec<N>>)> {
+        // Retrieve the admission policy of the listener that accepted the connection.
+        let policy = stream.local_addr().map(|local_addr| self.listener_policy(local_addr)).unwrap_or_default();
         // Construct the stream.
         let mut framed = Framed::new(stream, MessageCodec::<N>::handshake());
 
@@ -194,24 +311,68 @@ impl<N: Network> Router<N> {
         // Listen for the challenge request message.
         let peer_request = expect_message!(Message::ChallengeRequest, framed, peer_addr);
 
+        // Ensure the challenge request was not sent by this node.
+        if self.handshake_nonces.lock().contains(&peer_request.nonce) {
+            let reason = DisconnectReason::SelfConnection;
+            send(&mut framed, peer_addr, reason.into()).await?;
+            return Err(HandshakeError::SelfConnect(peer_addr).into());
+        }
+
         // Obtain the peer's listening address.
         *peer_ip = Some(SocketAddr::new(peer_addr.ip(), peer_request.listener_port));
         let peer_ip = peer_ip.unwrap();
 
         // Knowing the peer's listening address, ensure it is allowed to connect.
-        if let Err(forbidden_message) = self.ensure_peer_is_allowed(peer_ip) {
+        if let Err(forbidden_message) = self.ensure_peer_is_allowed(peer_ip, policy) {
             return Err(error(format!("{forbidden_message}")));
         }
+        // Reject the connection if accepting new connections is paused, unless the peer is pinned or always allowed.
+        let is_exempt = self.is_pinned(&peer_ip) || policy == ListenerPolicy::AlwaysAllow;
+        if !is_exempt && self.is_accepting_paused() {
+            debug!("Dropping '{peer_addr}' (accepting new connections is paused)");
+            let reason = DisconnectReason::TooManyPeers;
+            send(&mut framed, peer_addr, reason.into()).await?;
+            return Err(dropped(peer_addr, reason));
+        }
         // Verify the challenge request. If a disconnect reason was returned, send the disconnect message and abort.
-        if let Some(reason) = self.verify_challenge_request(peer_addr, &peer_request) {
+        let peer_side = ConnectionSide::Initiator;
+        if let Some(reason) = self.verify_challenge_request(peer_addr, &peer_request, peer_side, policy) {
             send(&mut framed, peer_addr, reason.into()).await?;
-            return Err(error(format!("Dropped '{peer_addr}' for reason: {reason:?}")));
+            return Err(dropped(peer_addr, reason));
+        }
+        // Reject the connection if the host is overloaded, unless the peer is pinned or always allowed.
+        if !is_exempt && self.is_overloaded() {
+            warn!("Dropping '{peer_addr}' (the host is overloaded)");
+            let reason = DisconnectReason::TooManyPeers;
+            send(&mut framed, peer_addr, reason.into()).await?;
+            return Err(dropped(peer_addr, reason));
         }
-        /* Step 2: Send the challenge response followed by own challenge request. */
 
         // Initialize an RNG.
         let rng = &mut OsRng;
 
+        // If the node is under load, demand a proof of work before the expensive signature steps.
+        if policy != ListenerPolicy::AlwaysAllow && self.is_under_connection_load() {
+            // Turn away the peers that predate admission challenges, as they cannot solve one.
+            if !FeatureFlags::from_version(peer_request.version).contains(Feature::AdmissionChallenge) {
+                let reason = DisconnectReason::TooManyPeers;
+                send(&mut framed, peer_addr, reason.into()).await?;
+                return Err(dropped(peer_addr, reason));
+            }
+            let challenge = AdmissionChallenge { salt: rng.gen(), difficulty: self.config().admission_difficulty };
+            self.record_admission_challenge();
+            send(&mut framed, peer_addr, Message::AdmissionChallenge(challenge.clone())).await?;
+            // Listen for the admission solution message, and verify it.
+            let solution = expect_message!(Message::AdmissionSolution, framed, peer_addr);
+            if !challenge.is_solved_by(solution.nonce) {
+                let reason = DisconnectReason::UnsolvedAdmissionChallenge;
+                send(&mut framed, peer_addr, reason.into()).await?;
+                return Err(dropped(peer_addr, reason));
+            }
+        }
+
+        /* Step 2: Send the challenge response followed by own challenge request. */
+
         // Sign the counterparty nonce.
         let Ok(our_signature) = self.account.sign_bytes(&peer_request.nonce.to_le_bytes(), rng) else {
             return Err(error(format!("Failed to sign the challenge request nonce from '{peer_addr}'")));
@@ -223,7 +384,8 @@ impl<N: Network> Router<N> {
         // Sample a random nonce.
         let our_nonce = rng.gen();
         // Send the challenge request.
-        let our_request = ChallengeRequest::new(self.local_ip().port(), self.node_type, self.address(), our_nonce);
+        let our_request = ChallengeRequest::new(self.local_ip().port(), self.node_type, self.address(), our_nonce)
+            .with_capabilities(self.capabilities());
         send(&mut framed, peer_addr, Message::ChallengeRequest(our_request)).await?;
 
         /* Step 3: Receive the challenge response. */
@@ -236,16 +398,36 @@ impl<N: Network> Router<N> {
             .await
         {
             send(&mut framed, peer_addr, reason.into()).await?;
-            return Err(error(format!("Dropped '{peer_addr}' for reason: {reason:?}")));
+            return Err(dropped(peer_addr, reason));
         }
-        // Add the peer to the router.
-        self.insert_connected_peer(Peer::new(peer_ip, &peer_request), peer_addr);
+        // Add the peer to the router, evicting a connected peer to make room for it, if needed.
+        let peer = Peer::new(peer_ip, &peer_request, ConnectionSide::Initiator, self.clock().now());
+        self.evict_for_newcomer(&peer, policy);
+        self.insert_connected_peer(peer, peer_addr);
 
         Ok((peer_ip, framed))
     }
 
-    /// Ensure the peer is allowed to connect.
-    fn ensure_peer_is_allowed(&self, peer_ip: SocketAddr) -> Result<()> {
+    /// Returns `true` if the node has reached the maximum number of connected peers, for the given peer.
+    /// The pinned peers may connect beyond the maximum.
+    fn is_at_capacity_for(&self, peer_ip: &SocketAddr) -> bool {
+        !self.is_pinned(peer_ip) && self.number_of_connected_peers() >= self.max_connected_peers()
+    }
+
+    /// Disconnects from the connected peer chosen by the eviction strategy, if the node is at capacity
+    /// for the given newly handshaken peer. The peers admitted beyond the peer limits evict no one.
+    fn evict_for_newcomer(&self, newcomer: &Peer<N>, policy: ListenerPolicy) {
+        if policy == ListenerPolicy::AlwaysAllow || !self.is_at_capacity_for(&newcomer.ip()) {
+            return;
+        }
+        if let Some(evicted) = self.select_eviction(newcomer) {
+            info!("Evicting '{evicted}' to admit '{}' (maximum peers reached)", newcomer.ip());
+            self.disconnect(evicted);
+        }
+    }
+
+    /// Ensure the peer is allowed to connect, under the given admission policy.
+    fn ensure_peer_is_allowed(&self, peer_ip: SocketAddr, policy: ListenerPolicy) -> Result<()> {
         // Ensure the peer IP is not this node.
         if self.is_local_ip(&peer_ip) {
             bail!("Dropping connection request from '{peer_ip}' (attempted to self-connect)")
@@ -258,10 +440,25 @@ impl<N: Network> Router<N> {
         if self.is_connected(&peer_ip) {
             bail!("Dropping connection request from '{peer_ip}' (already connected)")
         }
+        // Admit the peer regardless of its history, if it is always allowed.
+        if policy == ListenerPolicy::AlwaysAllow {
+            return Ok(());
+        }
         // Ensure the peer is not restricted.
         if self.is_restricted(&peer_ip) {
             bail!("Dropping connection request from '{peer_ip}' (restricted)")
         }
+        // Ensure the peer is not cooling down after a protocol violation.
+        if self.is_cooling_down(&peer_ip) {
+            bail!("Dropping connection request from '{peer_ip}' (on probation)")
+        }
+        // Ensure the peer is not reconnecting too often, regardless of how its previous connections ended.
+        if self.is_soft_banned(&peer_ip) {
+            bail!("Dropping connection request from '{peer_ip}' (soft-banned for reconnecting too often)")
+        }
+        if self.record_connection_attempt(peer_ip) {
+            bail!("Dropping connection request from '{peer_ip}' (reconnecting too often)")
+        }
         // Ensure the peer is not spamming connection attempts.
         if !peer_ip.ip().is_loopback() {
             // Add this connection attempt and retrieve the number of attempts.
@@ -276,20 +473,40 @@ impl<N: Network> Router<N> {
         Ok(())
     }
 
-    /// Verifies the given challenge request. Returns a disconnect reason if the request is invalid.
+    /// Verifies the given challenge request, under the given admission policy.
+    /// Returns a disconnect reason if the request is invalid.
     fn verify_challenge_request(
         &self,
         peer_addr: SocketAddr,
         message: &ChallengeRequest<N>,
+        peer_side: ConnectionSide,
+        policy: ListenerPolicy,
     ) -> Option<DisconnectReason> {
         // Retrieve the components of the challenge request.
-        let &ChallengeRequest { version, listener_port: _, node_type: _, address: _, nonce: _ } = message;
+        let &ChallengeRequest { version, listener_port, node_type: _, address: _, nonce: _, capabilities: _ } = message;
 
         // Ensure the message protocol version is not outdated.
-        if version < Message::<N>::VERSION {
+        if version < Message::<N>::MINIMUM_VERSION {
             warn!("Dropping '{peer_addr}' on version {version} (outdated)");
             return Some(DisconnectReason::OutdatedClientVersion);
         }
+        // Admit the peer beyond the peer limits, if it is always allowed.
+        if policy == ListenerPolicy::AlwaysAllow {
+            return None;
+        }
+        // Ensure the node has not reached the maximum number of connected peers, unless the peer is pinned,
+        // or the eviction strategy would make room for the peer, once the handshake completes.
+        let peer_ip = SocketAddr::new(peer_addr.ip(), listener_port);
+        let can_evict = || self.select_eviction(&Peer::new(peer_ip, message, peer_side, self.clock().now())).is_some();
+        if self.is_at_capacity_for(&peer_ip) && !can_evict() {
+            warn!("Dropping '{peer_addr}' (maximum peers reached)");
+            return Some(DisconnectReason::TooManyPeers);
+        }
+        // Ensure the group of the peer has not reached the maximum number of connected peers.
+        if self.is_peer_group_full(peer_addr) {
+            warn!("Dropping '{peer_addr}' (too many peers in its group)");
+            return Some(DisconnectReason::TooManyPeers);
+        }
         None
     }
 
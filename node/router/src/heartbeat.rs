@@ -15,7 +15,7 @@
 use crate::{
     messages::{DisconnectReason, Message, PeerRequest},
     Outbound,
-    Router,
+    RouterConfig,
 };
 use snarkvm::prelude::Network;
 
@@ -41,6 +41,14 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
     /// The maximum number of peers permitted to maintain connections with.
     const MAXIMUM_NUMBER_OF_PEERS: usize = 21;
 
+    /// Replaces the live configuration of the router, and applies it to the existing connections.
+    fn reload_config(&self, config: RouterConfig) {
+        info!("Reloading the router configuration");
+        self.router().set_config(config);
+        // Disconnect from the surplus peers, if the maximum number of peers was lowered.
+        self.handle_connected_peers();
+    }
+
     /// Handles the heartbeat request.
     fn heartbeat(&self) {
         self.safety_check_minimum_number_of_peers();
@@ -48,8 +56,12 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
 
         // Remove any stale connected peers.
         self.remove_stale_connected_peers();
+        // Remove any connected peers that exceeded the maximum connection lifetime.
+        self.remove_expired_connected_peers();
         // Remove the oldest connected peer.
         self.remove_oldest_connected_peer();
+        // Keep the pinned peers connected, ahead of the other peers.
+        self.handle_pinned_peers();
         // Keep the number of connected peers within the allowed range.
         self.handle_connected_peers();
         // Keep the bootstrap peers within the allowed range.
@@ -85,10 +97,10 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
     /// This function removes any connected peers that have not communicated within the predefined time.
     fn remove_stale_connected_peers(&self) {
         // Check if any connected peer is stale.
-        for peer in self.router().get_connected_peers() {
+        for peer in self.router().peers_snapshot().peers() {
             // Disconnect if the peer has not communicated back within the predefined time.
-            let elapsed = peer.last_seen().elapsed().as_secs();
-            if elapsed > Router::<N>::RADIO_SILENCE_IN_SECS {
+            let elapsed = self.router().clock().elapsed(peer.last_seen()).as_secs();
+            if elapsed > self.router().idle_timeout().as_secs() {
                 warn!("Peer {} has not communicated in {elapsed} seconds", peer.ip());
                 // Disconnect from this peer.
                 self.router().disconnect(peer.ip());
@@ -96,6 +108,22 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
         }
     }
 
+    /// This function removes any connected peers that exceeded the maximum connection lifetime, if one is set,
+    /// so that they reconnect with a fresh handshake. Pinned peers are kept connected.
+    fn remove_expired_connected_peers(&self) {
+        // Skip if the connection lifetime is unlimited.
+        let Some(max_lifetime) = self.router().max_connection_lifetime() else {
+            return;
+        };
+        for peer in self.router().peers_snapshot().peers() {
+            // Disconnect if the connection has been open for longer than the maximum lifetime.
+            if !self.router().is_pinned(&peer.ip()) && self.router().clock().elapsed(peer.first_seen()) > max_lifetime {
+                info!("Disconnecting from '{}' (the connection lifetime expired)", peer.ip());
+                self.send_disconnect(peer.ip(), DisconnectReason::ConnectionLifetimeExpired);
+            }
+        }
+    }
+
     /// This function removes the oldest connected peer, to keep the connections fresh.
     /// This function only triggers if the router is above the minimum number of connected peers.
     fn remove_oldest_connected_peer(&self) {
@@ -109,21 +137,20 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
         // Retrieve the bootstrap peers.
         let bootstrap = self.router().bootstrap_peers();
 
-        // Find the oldest connected peer, that is neither trusted nor a bootstrap peer.
+        // Find the oldest connected peer, that is neither trusted, pinned, nor a bootstrap peer.
         let oldest_peer = self
             .router()
-            .get_connected_peers()
-            .iter()
+            .peers_snapshot()
+            .peers()
             .filter(|peer| !trusted.contains(&peer.ip()) && !bootstrap.contains(&peer.ip()))
+            .filter(|peer| !self.router().is_pinned(&peer.ip()))
             .min_by_key(|peer| peer.last_seen())
             .map(|peer| peer.ip());
 
         // Disconnect from the oldest connected peer, if one exists.
         if let Some(oldest) = oldest_peer {
             info!("Disconnecting from '{oldest}' (periodic refresh of peers)");
-            let _ = self.send(oldest, Message::Disconnect(DisconnectReason::PeerRefresh.into()));
-            // Disconnect from this peer.
-            self.router().disconnect(oldest);
+            self.send_disconnect(oldest, DisconnectReason::PeerRefresh);
         }
     }
 
@@ -132,8 +159,10 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
     fn handle_connected_peers(&self) {
         // Obtain the number of connected peers.
         let num_connected = self.router().number_of_connected_peers();
-        // Compute the number of surplus peers.
-        let num_surplus = num_connected.saturating_sub(Self::MAXIMUM_NUMBER_OF_PEERS);
+        // Compute the number of surplus peers. The pinned peers do not count towards the maximum.
+        let num_unpinned = num_connected.saturating_sub(self.router().number_of_connected_pinned_peers());
+        let max_peers = Self::MAXIMUM_NUMBER_OF_PEERS.min(self.router().max_connected_peers());
+        let num_surplus = num_unpinned.saturating_sub(max_peers);
         // Compute the number of deficit peers.
         let num_deficient = Self::MEDIAN_NUMBER_OF_PEERS.saturating_sub(num_connected);
 
@@ -156,6 +185,7 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
                 .connected_peers()
                 .into_iter()
                 .filter(|peer_ip| !trusted.contains(peer_ip) && !bootstrap.contains(peer_ip))
+                .filter(|peer_ip| !self.router().is_pinned(peer_ip))
                 .choose_multiple(rng, num_surplus);
 
             // Proceed to send disconnect requests to these peers.
@@ -170,9 +200,7 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
                 }
 
                 info!("Disconnecting from '{peer_ip}' (exceeded maximum connections)");
-                self.send(peer_ip, Message::Disconnect(DisconnectReason::TooManyPeers.into()));
-                // Disconnect from this peer.
-                self.router().disconnect(peer_ip);
+                self.send_disconnect(peer_ip, DisconnectReason::TooManyPeers);
             }
         }
 
@@ -220,9 +248,7 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
             // Proceed to send disconnect requests to these bootstrap peers.
             for peer_ip in connected_bootstrap.into_iter().choose_multiple(rng, num_surplus) {
                 info!("Disconnecting from '{peer_ip}' (exceeded maximum bootstrap)");
-                self.send(peer_ip, Message::Disconnect(DisconnectReason::TooManyPeers.into()));
-                // Disconnect from this peer.
-                self.router().disconnect(peer_ip);
+                self.send_disconnect(peer_ip, DisconnectReason::TooManyPeers);
             }
         }
     }
@@ -239,6 +265,16 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
         }
     }
 
+    /// This function attempts to connect to any disconnected pinned peers.
+    fn handle_pinned_peers(&self) {
+        for peer_ip in self.router().pinned_peers() {
+            // If the peer is not connected, attempt to connect to it.
+            if !self.router().is_connected(&peer_ip) {
+                self.router().connect(peer_ip);
+            }
+        }
+    }
+
     /// This function updates the coinbase puzzle if network has updated.
     fn handle_puzzle_request(&self) {
         // No-op
@@ -0,0 +1,177 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::hash::{BuildHasher, Hash, Hasher};
+use std::collections::hash_map::RandomState;
+
+/// A rotating bloom filter, used to probabilistically track items across all peers.
+///
+/// The filter holds two generations of bits. Once the current generation has absorbed
+/// `capacity` insertions, it replaces the previous generation and a fresh one is started.
+/// This bounds the memory footprint, and keeps the false positive rate near the target.
+#[derive(Debug)]
+pub struct BloomFilter {
+    /// The bits of the current generation.
+    current: Vec<u64>,
+    /// The bits of the previous generation.
+    previous: Vec<u64>,
+    /// The number of insertions into the current generation.
+    num_insertions: usize,
+    /// The number of insertions per generation, before rotating.
+    capacity: usize,
+    /// The number of hash functions.
+    num_hashes: u32,
+    /// The randomly-keyed hasher state.
+    state: RandomState,
+}
+
+impl BloomFilter {
+    /// Initializes a new bloom filter for the given capacity (per generation) and false positive rate.
+    pub fn new(capacity: usize, false_positive_rate: f64) -> Self {
+        let capacity = capacity.max(1);
+        let false_positive_rate = false_positive_rate.clamp(f64::EPSILON, 0.5);
+        // Compute the optimal number of bits, i.e. `-n * ln(p) / ln(2)^2`.
+        let num_bits = -(capacity as f64) * false_positive_rate.ln() / (core::f64::consts::LN_2.powi(2));
+        let num_words = (num_bits / 64.0).ceil().max(1.0) as usize;
+        // Compute the optimal number of hash functions, i.e. `(m / n) * ln(2)`.
+        let num_hashes = ((num_words * 64) as f64 / capacity as f64 * core::f64::consts::LN_2).round().max(1.0) as u32;
+
+        Self {
+            current: vec![0; num_words],
+            previous: vec![0; num_words],
+            num_insertions: 0,
+            capacity,
+            num_hashes,
+            state: RandomState::new(),
+        }
+    }
+
+    /// Returns `true` if the given item was (probably) inserted before.
+    pub fn contains<T: Hash>(&self, item: &T) -> bool {
+        let indices = self.indices(item);
+        Self::check(&self.current, &indices) || Self::check(&self.previous, &indices)
+    }
+
+    /// Inserts the given item, returning `true` if the item was (probably) inserted before.
+    pub fn insert<T: Hash>(&mut self, item: &T) -> bool {
+        let indices = self.indices(item);
+        // If the item is in the current generation, there is nothing more to do.
+        if Self::check(&self.current, &indices) {
+            return true;
+        }
+        // Check if the item is in the previous generation.
+        let seen_before = Self::check(&self.previous, &indices);
+        // Insert the item into the current generation, so it survives the next rotation.
+        for index in indices {
+            self.current[index / 64] |= 1 << (index % 64);
+        }
+        self.num_insertions += 1;
+        // Rotate the generations, if the current generation is at capacity.
+        if self.num_insertions >= self.capacity {
+            self.previous = core::mem::replace(&mut self.current, vec![0; self.previous.len()]);
+            self.num_insertions = 0;
+        }
+        seen_before
+    }
+
+    /// Returns the number of bytes used by the filter.
+    pub fn size_in_bytes(&self) -> usize {
+        (self.current.len() + self.previous.len()) * core::mem::size_of::<u64>()
+    }
+}
+
+impl BloomFilter {
+    /// Returns the bit indices for the given item, using double hashing.
+    fn indices<T: Hash>(&self, item: &T) -> Vec<usize> {
+        // Compute the two base hashes.
+        let mut hasher = self.state.build_hasher();
+        item.hash(&mut hasher);
+        let h1 = hasher.finish();
+        hasher.write_u8(0xff);
+        // Ensure the second hash is odd, so the probe sequence covers the filter.
+        let h2 = hasher.finish() | 1;
+
+        let num_bits = (self.current.len() * 64) as u64;
+        (0..self.num_hashes as u64).map(|i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits) as usize).collect()
+    }
+
+    /// Returns `true` if all of the given bit indices are set.
+    fn check(bits: &[u64], indices: &[usize]) -> bool {
+        indices.iter().all(|index| bits[index / 64] & (1 << (index % 64)) != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_contains() {
+        let mut filter = BloomFilter::new(1000, 0.001);
+
+        // Check that the filter is empty.
+        assert!(!filter.contains(&1u64));
+
+        // Insert an item.
+        assert!(!filter.insert(&1u64));
+        // Check that the filter contains the item.
+        assert!(filter.contains(&1u64));
+        // Insert the same item again.
+        assert!(filter.insert(&1u64));
+
+        // Check that an unrelated item is not contained.
+        assert!(!filter.contains(&2u64));
+    }
+
+    #[test]
+    fn test_rotation() {
+        const CAPACITY: usize = 100;
+
+        let mut filter = BloomFilter::new(CAPACITY, 0.001);
+        let size_in_bytes = filter.size_in_bytes();
+
+        // Fill the first generation, which rotates it into the previous generation.
+        for i in 0..CAPACITY as u64 {
+            filter.insert(&i);
+        }
+        // Check that the items are still found in the previous generation.
+        assert!(filter.contains(&0u64));
+
+        // Fill the second generation, which evicts the first generation.
+        for i in CAPACITY as u64..2 * CAPACITY as u64 {
+            filter.insert(&i);
+        }
+        // Check that the evicted items are (very likely) forgotten.
+        let num_remembered = (0..CAPACITY as u64).filter(|i| filter.contains(i)).count();
+        assert!(num_remembered < CAPACITY / 10);
+
+        // Check that the memory footprint is bounded.
+        assert_eq!(filter.size_in_bytes(), size_in_bytes);
+    }
+
+    #[test]
+    fn test_false_positive_rate() {
+        const CAPACITY: usize = 10_000;
+
+        let mut filter = BloomFilter::new(CAPACITY, 0.01);
+        // Insert items without triggering a rotation.
+        for i in 0..CAPACITY as u64 - 1 {
+            filter.insert(&i);
+        }
+        // Count the false positives.
+        let num_false_positives = (CAPACITY as u64..2 * CAPACITY as u64).filter(|i| filter.contains(i)).count();
+        // Check that the false positive rate is within a reasonable margin of the target.
+        assert!(num_false_positives < CAPACITY * 3 / 100, "Too many false positives ({num_false_positives})");
+    }
+}
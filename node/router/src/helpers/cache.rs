@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::BloomFilter;
 use crate::messages::BlockRequest;
 use snarkvm::prelude::{coinbase::PuzzleCommitment, Network};
 
@@ -27,12 +28,30 @@ use time::{Duration, OffsetDateTime};
 
 /// The maximum number of items to store in a cache map.
 const MAX_CACHE_SIZE: usize = 1 << 17;
+/// The target false positive rate of the node-wide message filter.
+const GLOBAL_FILTER_FALSE_POSITIVE_RATE: f64 = 0.0001;
 
 /// A helper containing the peer IP and solution commitment.
 type SolutionKey<N> = (SocketAddr, PuzzleCommitment<N>);
 /// A helper containing the peer IP and transaction ID.
 type TransactionKey<N> = (SocketAddr, <N as Network>::TransactionID);
 
+/// A helper to separate the domains of the items in the node-wide filter.
+#[derive(Copy, Clone, Debug, Hash)]
+enum GlobalKey {
+    Solution,
+    Transaction,
+}
+
+/// The ID of a gossiped message.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MessageId<N: Network> {
+    /// The commitment of an unconfirmed solution.
+    Solution(PuzzleCommitment<N>),
+    /// The ID of an unconfirmed transaction.
+    Transaction(N::TransactionID),
+}
+
 #[derive(Debug)]
 pub struct Cache<N: Network> {
     /// The map of peer connections to their recent timestamps.
@@ -41,6 +60,14 @@ pub struct Cache<N: Network> {
     seen_inbound_messages: RwLock<IndexMap<SocketAddr, VecDeque<OffsetDateTime>>>,
     /// The map of peer IPs to their recent timestamps.
     seen_inbound_puzzle_requests: RwLock<IndexMap<SocketAddr, VecDeque<OffsetDateTime>>>,
+    /// The map of peer IPs to the timestamps of their recent malformed frames.
+    seen_inbound_malformed_frames: RwLock<IndexMap<SocketAddr, VecDeque<OffsetDateTime>>>,
+    /// The map of peer IPs to the timestamps of their recent strikes.
+    seen_inbound_strikes: RwLock<IndexMap<SocketAddr, VecDeque<OffsetDateTime>>>,
+    /// The map of peer IPs to the timestamps of the solutions they resent.
+    seen_inbound_duplicate_solutions: RwLock<IndexMap<SocketAddr, VecDeque<OffsetDateTime>>>,
+    /// The map of peer IPs to the timestamps of the blocks served for their block requests.
+    seen_inbound_served_blocks: RwLock<IndexMap<SocketAddr, VecDeque<OffsetDateTime>>>,
     /// The map of solution commitments to their last seen timestamp.
     seen_inbound_solutions: RwLock<LinkedHashMap<SolutionKey<N>, OffsetDateTime>>,
     /// The map of transaction IDs to their last seen timestamp.
@@ -53,6 +80,8 @@ pub struct Cache<N: Network> {
     seen_outbound_solutions: RwLock<LinkedHashMap<SolutionKey<N>, OffsetDateTime>>,
     /// The map of transaction IDs to their last seen timestamp.
     seen_outbound_transactions: RwLock<LinkedHashMap<TransactionKey<N>, OffsetDateTime>>,
+    /// The node-wide filter of solution commitments and transaction IDs, seen from any peer.
+    seen_global_messages: RwLock<BloomFilter>,
 }
 
 impl<N: Network> Default for Cache<N> {
@@ -69,12 +98,17 @@ impl<N: Network> Cache<N> {
             seen_inbound_connections: Default::default(),
             seen_inbound_messages: Default::default(),
             seen_inbound_puzzle_requests: Default::default(),
+            seen_inbound_malformed_frames: Default::default(),
+            seen_inbound_strikes: Default::default(),
+            seen_inbound_duplicate_solutions: Default::default(),
+            seen_inbound_served_blocks: Default::default(),
             seen_inbound_solutions: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
             seen_inbound_transactions: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
             seen_outbound_block_requests: Default::default(),
             seen_outbound_puzzle_requests: Default::default(),
             seen_outbound_solutions: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
             seen_outbound_transactions: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
+            seen_global_messages: RwLock::new(BloomFilter::new(MAX_CACHE_SIZE, GLOBAL_FILTER_FALSE_POSITIVE_RATE)),
         }
     }
 }
@@ -95,6 +129,38 @@ impl<N: Network> Cache<N> {
         Self::retain_and_insert(&self.seen_inbound_puzzle_requests, peer_ip, 60)
     }
 
+    /// Inserts a new malformed frame for the given peer IP, returning the number of recent malformed frames.
+    pub fn insert_inbound_malformed_frame(&self, peer_ip: SocketAddr, interval_in_secs: i64) -> usize {
+        Self::retain_and_insert(&self.seen_inbound_malformed_frames, peer_ip, interval_in_secs)
+    }
+
+    /// Inserts a new strike for the given peer IP, returning the number of recent strikes.
+    pub fn insert_inbound_strike(&self, peer_ip: SocketAddr, interval_in_secs: i64) -> usize {
+        Self::retain_and_insert(&self.seen_inbound_strikes, peer_ip, interval_in_secs)
+    }
+
+    /// Returns the number of recent strikes for the given peer IP.
+    pub fn num_inbound_strikes(&self, peer_ip: SocketAddr, interval_in_secs: i64) -> usize {
+        let now = OffsetDateTime::now_utc();
+        self.seen_inbound_strikes.read().get(&peer_ip).map_or(0, |timestamps| {
+            timestamps.iter().filter(|t| now - **t <= Duration::seconds(interval_in_secs)).count()
+        })
+    }
+
+    /// Inserts a duplicate solution from the given peer IP, returning the number of recent duplicate solutions.
+    pub fn insert_inbound_duplicate_solution(&self, peer_ip: SocketAddr, interval_in_secs: i64) -> usize {
+        Self::retain_and_insert(&self.seen_inbound_duplicate_solutions, peer_ip, interval_in_secs)
+    }
+
+    /// Inserts the given number of blocks served to the given peer IP, returning the number of recently served blocks.
+    pub fn insert_inbound_served_blocks(&self, peer_ip: SocketAddr, num_blocks: u32, interval_in_secs: i64) -> usize {
+        let mut num_served = 0;
+        for _ in 0..num_blocks {
+            num_served = Self::retain_and_insert(&self.seen_inbound_served_blocks, peer_ip, interval_in_secs);
+        }
+        num_served
+    }
+
     /// Inserts a solution commitment into the cache, returning the previously seen timestamp if it existed.
     pub fn insert_inbound_solution(
         &self,
@@ -112,6 +178,44 @@ impl<N: Network> Cache<N> {
     ) -> Option<OffsetDateTime> {
         Self::refresh_and_insert(&self.seen_inbound_transactions, (peer_ip, transaction))
     }
+
+    /// Returns `true` if the solution commitment was (probably) seen from any peer.
+    pub fn contains_global_solution(&self, solution: &PuzzleCommitment<N>) -> bool {
+        self.seen_global_messages.read().contains(&(GlobalKey::Solution, solution))
+    }
+
+    /// Inserts a solution commitment into the node-wide filter, returning `true` if it was (probably) seen before.
+    pub fn insert_global_solution(&self, solution: &PuzzleCommitment<N>) -> bool {
+        self.seen_global_messages.write().insert(&(GlobalKey::Solution, solution))
+    }
+
+    /// Returns `true` if the transaction ID was (probably) seen from any peer.
+    pub fn contains_global_transaction(&self, transaction: &N::TransactionID) -> bool {
+        self.seen_global_messages.read().contains(&(GlobalKey::Transaction, transaction))
+    }
+
+    /// Inserts a transaction ID into the node-wide filter, returning `true` if it was (probably) seen before.
+    pub fn insert_global_transaction(&self, transaction: &N::TransactionID) -> bool {
+        self.seen_global_messages.write().insert(&(GlobalKey::Transaction, transaction))
+    }
+
+    /// Returns `true` if the message was (probably) seen from any peer, or seen from one of the given peers.
+    pub fn contains_message(&self, id: &MessageId<N>, peer_ips: &[SocketAddr]) -> bool {
+        match id {
+            MessageId::Solution(solution) => {
+                self.contains_global_solution(solution) || {
+                    let seen = self.seen_inbound_solutions.read();
+                    peer_ips.iter().any(|peer_ip| seen.contains_key(&(*peer_ip, *solution)))
+                }
+            }
+            MessageId::Transaction(transaction) => {
+                self.contains_global_transaction(transaction) || {
+                    let seen = self.seen_inbound_transactions.read();
+                    peer_ips.iter().any(|peer_ip| seen.contains_key(&(*peer_ip, *transaction)))
+                }
+            }
+        }
+    }
 }
 
 impl<N: Network> Cache<N> {
@@ -128,10 +232,30 @@ impl<N: Network> Cache<N> {
         requests.len()
     }
 
-    /// Removes the block request for the given peer IP, returning `true` if the request was present.
-    pub fn remove_outbound_block_request(&self, peer_ip: SocketAddr, request: &BlockRequest) -> bool {
+    /// Removes the given response range from the matching block request for the given peer IP,
+    /// returning `true` if a matching request was present.
+    /// If the response only covers the front of the request, the remainder of the request stays in the cache.
+    pub fn remove_outbound_block_request(&self, peer_ip: SocketAddr, response: &BlockRequest) -> bool {
         let mut map_write = self.seen_outbound_block_requests.write();
-        if let Some(requests) = map_write.get_mut(&peer_ip) { requests.remove(request) } else { false }
+        let Some(requests) = map_write.get_mut(&peer_ip) else {
+            return false;
+        };
+        // Find the block request that starts with the given response range.
+        let matching = requests
+            .iter()
+            .find(|request| request.start_height == response.start_height && response.end_height <= request.end_height)
+            .copied();
+        match matching {
+            Some(request) => {
+                requests.remove(&request);
+                // Keep the remainder of the block request, if it is not yet complete.
+                if response.end_height < request.end_height {
+                    requests.insert(BlockRequest { start_height: response.end_height, end_height: request.end_height });
+                }
+                true
+            }
+            None => false,
+        }
     }
 
     /// Returns `true` if the cache contains a puzzle request from the given peer.
@@ -289,6 +413,73 @@ mod tests {
         assert_eq!(cache.seen_inbound_transactions.read().len(), 1);
     }
 
+    #[test]
+    fn test_global_solution() {
+        let cache = Cache::<CurrentNetwork>::default();
+        let peer_a = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
+        let peer_b = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 5678);
+        let solution = PuzzleCommitment::<CurrentNetwork>::default();
+
+        // Check that the filter is empty.
+        assert!(!cache.contains_global_solution(&solution));
+
+        // Receive the solution from the first peer.
+        assert!(cache.insert_inbound_solution(peer_a, solution).is_none());
+        assert!(!cache.insert_global_solution(&solution));
+
+        // Receive the same solution from the second peer.
+        assert!(cache.insert_inbound_solution(peer_b, solution).is_none());
+        // Check that the solution is only processed once.
+        assert!(cache.contains_global_solution(&solution));
+        assert!(cache.insert_global_solution(&solution));
+
+        // Check that the domains of solutions and transactions are separate.
+        assert!(!cache.contains_global_transaction(&Default::default()));
+    }
+
+    #[test]
+    fn test_global_transaction() {
+        let cache = Cache::<CurrentNetwork>::default();
+        let transaction = Default::default();
+
+        // Check that the filter is empty.
+        assert!(!cache.contains_global_transaction(&transaction));
+
+        // Insert a transaction.
+        assert!(!cache.insert_global_transaction(&transaction));
+
+        // Check that the filter contains the transaction.
+        assert!(cache.contains_global_transaction(&transaction));
+
+        // Insert the same transaction again.
+        assert!(cache.insert_global_transaction(&transaction));
+    }
+
+    #[test]
+    fn test_contains_message() {
+        let cache = Cache::<CurrentNetwork>::default();
+        let peer_a = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
+        let peer_b = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 5678);
+        let transaction = MessageId::Transaction(Default::default());
+        let solution = MessageId::Solution(Default::default());
+
+        // Check that the cache is empty.
+        assert!(!cache.contains_message(&transaction, &[peer_a, peer_b]));
+
+        // Receive the transaction from the first peer.
+        assert!(cache.insert_inbound_transaction(peer_a, Default::default()).is_none());
+        // Check that the transaction is only seen from the first peer.
+        assert!(cache.contains_message(&transaction, &[peer_a, peer_b]));
+        assert!(!cache.contains_message(&transaction, &[peer_b]));
+
+        // Mark the transaction as seen across all peers.
+        assert!(!cache.insert_global_transaction(&Default::default()));
+        assert!(cache.contains_message(&transaction, &[]));
+
+        // Check that the solution was not seen.
+        assert!(!cache.contains_message(&solution, &[peer_a, peer_b]));
+    }
+
     #[test]
     fn test_outbound_solution() {
         let cache = Cache::<CurrentNetwork>::default();
@@ -332,4 +523,29 @@ mod tests {
         // Check that the cache still contains the transaction.
         assert_eq!(cache.seen_outbound_transactions.read().len(), 1);
     }
+
+    #[test]
+    fn test_outbound_block_request() {
+        let cache = Cache::<CurrentNetwork>::default();
+        let peer_ip = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
+        let request = BlockRequest { start_height: 5, end_height: 10 };
+
+        // Check that a response to an unknown request is rejected.
+        assert!(!cache.remove_outbound_block_request(peer_ip, &request));
+
+        // Insert the block request.
+        assert_eq!(cache.insert_outbound_block_request(peer_ip, request), 1);
+
+        // Check that the request is served, one block at a time.
+        for height in 5..10 {
+            let remainder = BlockRequest { start_height: height, end_height: 10 };
+            let response = BlockRequest { start_height: height, end_height: height + 1 };
+            assert!(cache.contains_outbound_block_request(&peer_ip, &remainder));
+            assert!(cache.remove_outbound_block_request(peer_ip, &response));
+        }
+        // Check that the request is complete.
+        let last = BlockRequest { start_height: 9, end_height: 10 };
+        assert!(!cache.contains_outbound_block_request(&peer_ip, &last));
+        assert!(!cache.remove_outbound_block_request(peer_ip, &last));
+    }
 }
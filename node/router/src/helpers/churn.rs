@@ -0,0 +1,102 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use super::MonotonicClock;
+
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+/// The recent connection attempts of each peer, used to soft-ban the peers that reconnect in rapid succession.
+/// Unlike probation, the attempts are counted regardless of how the previous connections ended.
+#[derive(Debug, Default)]
+pub struct ChurnLimiter {
+    /// The start of the current window, and the number of connection attempts within it, of each peer IP.
+    attempts: Mutex<HashMap<SocketAddr, (Instant, usize)>>,
+    /// The soft-banned peer IPs, with the timestamps of their bans.
+    bans: Mutex<HashMap<SocketAddr, Instant>>,
+    /// The clock used to measure the window and the bans.
+    clock: MonotonicClock,
+}
+
+impl ChurnLimiter {
+    /// Records a connection attempt by the given peer IP, and returns `true` if it exceeds the given
+    /// number of attempts within the window, in which case the peer is soft-banned.
+    pub fn record_attempt(&self, peer_ip: SocketAddr, max_attempts: usize, window: Duration) -> bool {
+        let mut attempts = self.attempts.lock();
+        // Purge the peers whose window elapsed.
+        attempts.retain(|_, (window_start, _)| self.clock.elapsed(*window_start) < window);
+        // Count this attempt in the window of the peer, starting a new window if there is none.
+        let (_, num_attempts) = attempts.entry(peer_ip).or_insert((self.clock.now(), 0));
+        *num_attempts += 1;
+        // Soft-ban the peer, if it surpassed the limit.
+        if *num_attempts > max_attempts {
+            attempts.remove(&peer_ip);
+            self.bans.lock().insert(peer_ip, self.clock.now());
+            return true;
+        }
+        false
+    }
+
+    /// Returns `true` if the given peer IP was soft-banned within the given ban duration.
+    pub fn is_banned(&self, peer_ip: &SocketAddr, ban_duration: Duration) -> bool {
+        let mut bans = self.bans.lock();
+        // Purge the bans that expired.
+        bans.retain(|_, banned_at| self.clock.elapsed(*banned_at) < ban_duration);
+        bans.contains_key(peer_ip)
+    }
+
+    /// Lifts the soft-ban on the given peer IP, and forgets its recent attempts.
+    pub fn remove(&self, peer_ip: &SocketAddr) {
+        self.attempts.lock().remove(peer_ip);
+        self.bans.lock().remove(peer_ip);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_churn_is_banned() {
+        let limiter = ChurnLimiter::default();
+        let peer_ip = SocketAddr::from(([127, 0, 0, 1], 4130));
+        let window = Duration::from_secs(60);
+
+        // Check that the attempts up to the limit are allowed, and the next one bans the peer.
+        for _ in 0..3 {
+            assert!(!limiter.record_attempt(peer_ip, 3, window));
+        }
+        assert!(!limiter.is_banned(&peer_ip, window));
+        assert!(limiter.record_attempt(peer_ip, 3, window));
+        assert!(limiter.is_banned(&peer_ip, window));
+        // Check that the ban expires.
+        assert!(!limiter.is_banned(&peer_ip, Duration::ZERO));
+    }
+
+    #[test]
+    fn test_churn_outside_window_is_allowed() {
+        let limiter = ChurnLimiter::default();
+        let peer_ip = SocketAddr::from(([127, 0, 0, 1], 4130));
+
+        // Check that attempts that fall out of the window are not counted.
+        for _ in 0..10 {
+            assert!(!limiter.record_attempt(peer_ip, 1, Duration::ZERO));
+        }
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::SocketAddr;
+
+/// A classifier that assigns peers to groups, such as their ASN or region,
+/// so that the router can limit the number of connected peers per group.
+pub trait PeerClassifier: Send + Sync {
+    /// Returns the opaque group ID of the given peer.
+    fn classify(&self, peer_ip: SocketAddr) -> u64;
+}
+
+/// The default classifier, which assigns every peer to a single group.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SingleGroupClassifier;
+
+impl PeerClassifier for SingleGroupClassifier {
+    fn classify(&self, _peer_ip: SocketAddr) -> u64 {
+        0
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// A source of the current instant.
+pub trait Clock: Send + Sync {
+    /// Returns the current instant.
+    fn now(&self) -> Instant;
+}
+
+/// The clock of the system, backed by `Instant::now`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock for timeouts and round-trip times, which never measures a negative duration.
+/// If the underlying clock appears to go backwards, the elapsed time is zero, instead of a panic or a huge duration.
+#[derive(Clone)]
+pub struct MonotonicClock {
+    /// The underlying clock.
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for MonotonicClock {
+    /// Initializes a new clock, backed by the clock of the system.
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+impl fmt::Debug for MonotonicClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MonotonicClock").finish_non_exhaustive()
+    }
+}
+
+impl MonotonicClock {
+    /// Initializes a new clock, backed by the given clock.
+    pub fn new<C: Clock + 'static>(clock: C) -> Self {
+        Self { clock: Arc::new(clock) }
+    }
+
+    /// Returns the current instant.
+    pub fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    /// Returns the time elapsed since the given instant, or zero if the instant appears to be in the future.
+    pub fn elapsed(&self, since: Instant) -> Duration {
+        Self::duration_between(since, self.now())
+    }
+
+    /// Returns the duration from the earlier instant to the later one, or zero if they appear to be reversed.
+    pub fn duration_between(earlier: Instant, later: Instant) -> Duration {
+        later.saturating_duration_since(earlier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use parking_lot::Mutex;
+
+    /// A clock that returns the instants it is set to.
+    struct FakeClock(Arc<Mutex<Instant>>);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            *self.0.lock()
+        }
+    }
+
+    #[test]
+    fn test_backwards_jump_saturates() {
+        let start = Instant::now() + Duration::from_secs(60);
+        let time = Arc::new(Mutex::new(start));
+        let clock = MonotonicClock::new(FakeClock(time.clone()));
+
+        // Move the clock forwards.
+        *time.lock() = start + Duration::from_secs(5);
+        assert_eq!(clock.elapsed(start), Duration::from_secs(5));
+
+        // Move the clock backwards, before the start.
+        *time.lock() = start - Duration::from_secs(30);
+        // Check that the elapsed time saturates at zero.
+        assert_eq!(clock.elapsed(start), Duration::ZERO);
+        assert_eq!(MonotonicClock::duration_between(start, clock.now()), Duration::ZERO);
+
+        // Check that a stalled clock measures no elapsed time either.
+        *time.lock() = start;
+        assert_eq!(clock.elapsed(start), Duration::ZERO);
+    }
+}
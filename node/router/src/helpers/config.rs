@@ -0,0 +1,119 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashSet, time::Duration};
+
+/// The live configuration of the router, which can be reloaded without restarting the node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RouterConfig {
+    /// The maximum number of connected peers. It cannot exceed the connection limit of the TCP stack.
+    pub max_peers: usize,
+    /// The maximum number of messages a peer may send within the message interval, before it is disconnected.
+    pub max_messages_per_interval: usize,
+    /// The duration in seconds over which the messages of a peer are counted.
+    pub message_interval_in_secs: i64,
+    /// The duration after which a connected peer that has not sent any message is disconnected.
+    pub idle_timeout: Duration,
+    /// The maximum duration of a connection from the completion of its handshake, after which the peer is
+    /// disconnected, so that it reconnects with a fresh handshake. Pinned peers are exempt. If `None`, unlimited.
+    pub max_connection_lifetime: Option<Duration>,
+    /// The maximum duration of a handshake.
+    pub handshake_timeout: Duration,
+    /// The names of the message types that are dropped on receipt.
+    pub dropped_messages: HashSet<String>,
+    /// The maximum number of peers each propagated message is sent to.
+    pub propagation_fanout: usize,
+    /// The maximum number of connected peers in each group of the peer classifier.
+    pub max_peers_per_group: usize,
+    /// The maximum number of outbound messages queued for a peer, before it is disconnected as a slow consumer.
+    pub max_outbound_backlog: usize,
+    /// The maximum duration of writing a single message to a peer, before it is disconnected as a slow consumer.
+    pub write_timeout: Duration,
+    /// The window over which the messages queued for a peer are coalesced into a single write, if any.
+    pub write_coalescing_window: Option<Duration>,
+    /// The maximum number of bytes buffered across all peers, awaiting the rest of an inbound message.
+    /// Once it is exceeded, the peer with the largest buffer is disconnected.
+    pub max_inbound_buffer_bytes: usize,
+    /// The maximum number of puzzle requests a peer may have pending while a response to it is in flight,
+    /// before it is disconnected, as it is likely not consuming the responses.
+    pub max_pending_puzzle_requests: usize,
+    /// The maximum duration of reading the puzzle state from the ledger, before the puzzle request is declined.
+    pub ledger_read_timeout: Duration,
+    /// If `true`, a puzzle request received while the ledger has no epoch challenge is answered with a puzzle
+    /// response without one. Otherwise, it is declined. In both cases, the peer remains connected.
+    pub send_unavailable_puzzle_responses: bool,
+    /// The duration after a protocol violation during which a peer may not reconnect.
+    pub probation_cooldown: Duration,
+    /// The duration after a protocol violation during which the peer's violations are remembered.
+    pub probation_period: Duration,
+    /// The number of protocol violations within the probation period that only disconnect the peer;
+    /// a further violation restricts it. If zero, the first violation restricts the peer.
+    pub tolerated_violations: usize,
+    /// The number of inbound connections per second, above which connecting peers must solve an admission challenge.
+    pub admission_rate_threshold: usize,
+    /// The difficulty of the admission challenge, in leading zero bits.
+    pub admission_difficulty: u8,
+    /// The maximum number of connection attempts a peer may make within the churn window, before it is soft-banned.
+    pub max_connection_attempts: usize,
+    /// The duration over which the connection attempts of a peer are counted.
+    pub churn_window: Duration,
+    /// The duration during which a soft-banned peer may not connect.
+    pub churn_ban_duration: Duration,
+    /// The maximum number of inbound messages handled at once across all peers, before unconfirmed solutions and
+    /// transactions are shed. As each connection handles one message at a time, it must be below the number of
+    /// connected peers for any message to be shed.
+    pub max_in_flight_messages: usize,
+    /// The maximum number of blocks served for a single block request, each in its own block response.
+    /// A larger request is clamped, which leaves the clamped-off tail in the requester's block request cache.
+    pub max_blocks_per_request: u32,
+    /// The maximum number of blocks served to a peer within the block request interval, before it is disconnected.
+    pub max_blocks_per_interval: usize,
+}
+
+impl RouterConfig {
+    /// Initializes a new router configuration with the given maximum number of peers, and the default values.
+    pub fn new(max_peers: usize) -> Self {
+        Self {
+            max_peers,
+            max_messages_per_interval: 1000,
+            message_interval_in_secs: 5,
+            idle_timeout: Duration::from_secs(150), // 2.5 minutes
+            max_connection_lifetime: None,
+            handshake_timeout: Duration::from_millis(3_000),
+            dropped_messages: Default::default(),
+            propagation_fanout: 8,
+            max_peers_per_group: usize::MAX,
+            max_outbound_backlog: 512,
+            write_timeout: Duration::from_secs(10),
+            write_coalescing_window: None,
+            max_inbound_buffer_bytes: 1024 * 1024 * 1024, // 1 GiB
+            max_pending_puzzle_requests: 5,
+            ledger_read_timeout: Duration::from_secs(1),
+            send_unavailable_puzzle_responses: true,
+            probation_cooldown: Duration::from_secs(30),
+            probation_period: Duration::from_secs(600), // 10 minutes
+            tolerated_violations: 1,
+            admission_rate_threshold: 64,
+            admission_difficulty: 16,
+            max_connection_attempts: 10,
+            churn_window: Duration::from_secs(60),
+            churn_ban_duration: Duration::from_secs(300), // 5 minutes
+            max_in_flight_messages: 64,
+            // A syncing peer catches up in spans of 20 blocks, while the served blocks are counted against the
+            // interval limit below, which bounds the responses a peer can draw to one full request per second.
+            max_blocks_per_request: 20,
+            max_blocks_per_interval: 1_200,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt;
+use std::net::SocketAddr;
+
+/// The reason a connection attempt to a peer was not made.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectError {
+    /// The peer is this node.
+    SelfConnect(SocketAddr),
+    /// The node has reached the maximum number of connected peers.
+    MaximumPeersReached(SocketAddr),
+    /// The node is already connected to the peer.
+    AlreadyConnected(SocketAddr),
+    /// The peer is restricted.
+    Restricted(SocketAddr),
+    /// The peer recently violated the protocol, and is on probation.
+    OnProbation(SocketAddr),
+    /// The peer reconnected too often, and is temporarily soft-banned.
+    SoftBanned(SocketAddr),
+    /// The node is already dialing the peer, or shaking hands with it as the initiator.
+    AlreadyConnecting(SocketAddr),
+}
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SelfConnect(peer_ip) => {
+                write!(f, "Dropping connection attempt to '{peer_ip}' (attempted to self-connect)")
+            }
+            Self::MaximumPeersReached(peer_ip) => {
+                write!(f, "Dropping connection attempt to '{peer_ip}' (maximum peers reached)")
+            }
+            Self::AlreadyConnected(peer_ip) => {
+                write!(f, "Dropping connection attempt to '{peer_ip}' (already connected)")
+            }
+            Self::Restricted(peer_ip) => write!(f, "Dropping connection attempt to '{peer_ip}' (restricted)"),
+            Self::OnProbation(peer_ip) => write!(f, "Dropping connection attempt to '{peer_ip}' (on probation)"),
+            Self::SoftBanned(peer_ip) => write!(f, "Dropping connection attempt to '{peer_ip}' (soft-banned)"),
+            Self::AlreadyConnecting(peer_ip) => {
+                write!(f, "Dropping connection attempt to '{peer_ip}' (already shaking hands as the initiator)")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConnectError {}
@@ -0,0 +1,54 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt;
+use std::net::SocketAddr;
+
+/// The reason an inbound message was dropped or rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeadLetterReason {
+    /// The peer exceeded the message rate limit.
+    RateLimited,
+    /// The message type is filtered out by the router configuration.
+    Filtered,
+    /// The message was shed, as the node is saturated.
+    Shed,
+    /// The solution or transaction was already seen from another peer, possibly as a false positive.
+    AlreadySeen,
+    /// The message was rejected by its handler.
+    Rejected(String),
+}
+
+impl fmt::Display for DeadLetterReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RateLimited => write!(f, "the peer exceeded the message rate limit"),
+            Self::Filtered => write!(f, "the message type is filtered out"),
+            Self::Shed => write!(f, "the node is saturated"),
+            Self::AlreadySeen => write!(f, "the message was already seen from another peer"),
+            Self::Rejected(error) => write!(f, "the message was rejected - {error}"),
+        }
+    }
+}
+
+/// An inbound message that was dropped or rejected, for inspection by the integrator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeadLetter {
+    /// The listening address of the peer that sent the message.
+    pub peer_ip: SocketAddr,
+    /// The name of the message type.
+    pub message_kind: String,
+    /// The reason the message was dropped or rejected.
+    pub reason: DeadLetterReason,
+}
@@ -0,0 +1,101 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::messages::DisconnectReason;
+
+use parking_lot::Mutex;
+use std::{collections::VecDeque, net::SocketAddr, time::Instant};
+
+/// A record of a disconnect from a peer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DisconnectRecord {
+    /// The IP address of the peer.
+    pub addr: SocketAddr,
+    /// The reason for the disconnect.
+    pub reason: DisconnectReason,
+    /// The timestamp of the disconnect.
+    pub at: Instant,
+}
+
+/// A bounded log of the most recent disconnects, which evicts the oldest records when full.
+#[derive(Debug)]
+pub struct DisconnectLog {
+    /// The maximum number of records, and the records from oldest to newest.
+    inner: Mutex<(usize, VecDeque<DisconnectRecord>)>,
+}
+
+impl DisconnectLog {
+    /// Initializes a new disconnect log with the given capacity.
+    pub fn new(capacity: usize) -> Self {
+        Self { inner: Mutex::new((capacity, VecDeque::with_capacity(capacity))) }
+    }
+
+    /// Appends a record of a disconnect from the given peer IP, for the given reason.
+    pub fn insert(&self, addr: SocketAddr, reason: DisconnectReason) {
+        let (capacity, records) = &mut *self.inner.lock();
+        records.push_back(DisconnectRecord { addr, reason, at: Instant::now() });
+        while records.len() > *capacity {
+            records.pop_front();
+        }
+    }
+
+    /// Returns up to the given number of the most recent records, from oldest to newest.
+    pub fn recent(&self, limit: usize) -> Vec<DisconnectRecord> {
+        let (_, records) = &*self.inner.lock();
+        records.iter().skip(records.len().saturating_sub(limit)).copied().collect()
+    }
+
+    /// Returns the maximum number of records.
+    pub fn capacity(&self) -> usize {
+        self.inner.lock().0
+    }
+
+    /// Sets the maximum number of records, evicting the oldest records if needed.
+    pub fn set_capacity(&self, capacity: usize) {
+        let (current, records) = &mut *self.inner.lock();
+        *current = capacity;
+        while records.len() > capacity {
+            records.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disconnect_log_is_bounded() {
+        let log = DisconnectLog::new(2);
+        let addr = |port| SocketAddr::from(([127, 0, 0, 1], port));
+
+        // Fill the log beyond its capacity.
+        log.insert(addr(1), DisconnectReason::PeerRefresh);
+        log.insert(addr(2), DisconnectReason::TooManyPeers);
+        log.insert(addr(3), DisconnectReason::ProtocolViolation);
+
+        // Check that the oldest record was evicted.
+        let records = log.recent(10);
+        assert_eq!(records.iter().map(|record| record.addr).collect::<Vec<_>>(), vec![addr(2), addr(3)]);
+        assert_eq!(records[1].reason, DisconnectReason::ProtocolViolation);
+        assert!(records[0].at <= records[1].at);
+
+        // Check that the limit returns the most recent records.
+        assert_eq!(log.recent(1), vec![records[1]]);
+
+        // Shrink the log.
+        log.set_capacity(1);
+        assert_eq!(log.recent(10), vec![records[1]]);
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use crate::Peer;
+use snarkvm::prelude::Network;
+
+use std::net::SocketAddr;
+
+/// A strategy for choosing the connected peer to evict, when a new peer connects to a node at capacity.
+pub trait EvictionStrategy<N: Network>: Send + Sync {
+    /// Returns the connected peer to evict in favour of the newcomer, or `None` to reject the newcomer.
+    /// The given peers are the ones that may be evicted; the pinned, trusted, and bootstrap peers are exempt.
+    fn select_eviction(&self, newcomer: &Peer<N>, peers: &[Peer<N>]) -> Option<SocketAddr>;
+}
+
+/// The default strategy, which keeps the connected peers and rejects the newcomer.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RejectNewcomer;
+
+impl<N: Network> EvictionStrategy<N> for RejectNewcomer {
+    fn select_eviction(&self, _newcomer: &Peer<N>, _peers: &[Peer<N>]) -> Option<SocketAddr> {
+        None
+    }
+}
+
+/// A strategy that evicts the peer with the lowest score, unless the newcomer scores no higher than it.
+#[derive(Copy, Clone, Debug)]
+pub struct EvictLowestScore<F>(pub F);
+
+impl<N: Network, F: Fn(&Peer<N>) -> i64 + Send + Sync> EvictionStrategy<N> for EvictLowestScore<F> {
+    fn select_eviction(&self, newcomer: &Peer<N>, peers: &[Peer<N>]) -> Option<SocketAddr> {
+        // Find the peer with the lowest score.
+        let (lowest, score) = peers.iter().map(|peer| (peer, (self.0)(peer))).min_by_key(|(_, score)| *score)?;
+        // Evict the peer only if the newcomer is strictly better, to avoid churning between equals.
+        match (self.0)(newcomer) > score {
+            true => Some(lowest.ip()),
+            false => None,
+        }
+    }
+}
+
+/// A strategy that evicts the peer that has been connected for the longest time.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EvictOldest;
+
+impl<N: Network> EvictionStrategy<N> for EvictOldest {
+    fn select_eviction(&self, _newcomer: &Peer<N>, peers: &[Peer<N>]) -> Option<SocketAddr> {
+        peers.iter().min_by_key(|peer| peer.first_seen()).map(|peer| peer.ip())
+    }
+}
+
+/// A strategy that evicts the peer that has not sent a message for the longest time.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EvictMostIdle;
+
+impl<N: Network> EvictionStrategy<N> for EvictMostIdle {
+    fn select_eviction(&self, _newcomer: &Peer<N>, peers: &[Peer<N>]) -> Option<SocketAddr> {
+        peers.iter().min_by_key(|peer| peer.last_seen()).map(|peer| peer.ip())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{ChallengeRequest, NodeType};
+    use snarkos_account::Account;
+    use snarkos_node_tcp::ConnectionSide;
+    use snarkvm::prelude::Testnet3;
+
+    use std::{
+        str::FromStr,
+        time::{Duration, Instant},
+    };
+
+    type CurrentNetwork = Testnet3;
+
+    /// Returns a sample peer with the given listener port.
+    fn sample_peer(port: u16) -> Peer<CurrentNetwork> {
+        let account =
+            Account::<CurrentNetwork>::from_str("APrivateKey1zkp2oVPTci9kKcUprnbzMwq95Di1MQERpYBhEeqvkrDirK1").unwrap();
+        let request = ChallengeRequest::new(port, NodeType::Client, account.address(), 0);
+        Peer::new(SocketAddr::from(([1, 2, 3, 4], port)), &request, ConnectionSide::Initiator, Instant::now())
+    }
+
+    /// Returns sample peers on the ports 1 to 3, connected in that order.
+    fn sample_peers() -> Vec<Peer<CurrentNetwork>> {
+        (1..=3).map(sample_peer).collect()
+    }
+
+    #[test]
+    fn test_reject_newcomer() {
+        let peers = sample_peers();
+        assert_eq!(RejectNewcomer.select_eviction(&sample_peer(4), &peers), None);
+    }
+
+    #[test]
+    fn test_evict_lowest_score() {
+        // Score the peers by their height.
+        let strategy = EvictLowestScore(|peer: &Peer<CurrentNetwork>| peer.height().map_or(-1, i64::from));
+
+        // Craft peers at the heights 20, 10, and 30.
+        let mut peers = sample_peers();
+        for (peer, height) in peers.iter_mut().zip([20, 10, 30]) {
+            peer.set_height(height);
+        }
+
+        // Check that a newcomer of a greater height evicts the lowest peer.
+        let mut newcomer = sample_peer(4);
+        newcomer.set_height(15);
+        assert_eq!(strategy.select_eviction(&newcomer, &peers), Some(peers[1].ip()));
+        // Check that a newcomer of an equal or lower height is rejected.
+        newcomer.set_height(10);
+        assert_eq!(strategy.select_eviction(&newcomer, &peers), None);
+        assert_eq!(strategy.select_eviction(&sample_peer(4), &peers), None);
+        // Check that a newcomer is rejected if there is no peer to evict.
+        newcomer.set_height(100);
+        assert_eq!(strategy.select_eviction(&newcomer, &[]), None);
+    }
+
+    #[test]
+    fn test_evict_oldest() {
+        let peers = sample_peers();
+        assert_eq!(EvictOldest.select_eviction(&sample_peer(4), &peers), Some(peers[0].ip()));
+        assert_eq!(EvictOldest.select_eviction(&sample_peer(4), &peers[1..]), Some(peers[1].ip()));
+        assert_eq!(EvictOldest.select_eviction(&sample_peer(4), &[]), None);
+    }
+
+    #[test]
+    fn test_evict_most_idle() {
+        // Craft peers that last sent a message in the order 2, 1, 3.
+        let mut peers = sample_peers();
+        let now = Instant::now();
+        for (peer, secs) in peers.iter_mut().zip([20, 10, 30]) {
+            peer.set_last_seen(now + Duration::from_secs(secs));
+        }
+
+        // Check that the peer that has been silent the longest is evicted.
+        assert_eq!(EvictMostIdle.select_eviction(&sample_peer(4), &peers), Some(peers[1].ip()));
+        assert_eq!(EvictMostIdle.select_eviction(&sample_peer(4), &[]), None);
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+
+/// A governor that reports the load of the host, so that the router rejects new inbound connections
+/// when the host is overloaded, rather than degrade the existing connections.
+pub trait LoadGovernor: Send + Sync {
+    /// Returns `true` if the host is too loaded to accept a new inbound connection.
+    fn is_overloaded(&self) -> bool;
+}
+
+/// The default governor, which always accepts new connections.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct UnlimitedGovernor;
+
+impl LoadGovernor for UnlimitedGovernor {
+    fn is_overloaded(&self) -> bool {
+        false
+    }
+}
+
+/// A governor that reads a load signal from the given closure, and reports an overload above the given threshold.
+pub struct ThresholdGovernor<F> {
+    /// The load signal.
+    load: F,
+    /// The load above which the host is overloaded.
+    threshold: f64,
+}
+
+impl<F: Fn() -> f64 + Send + Sync> ThresholdGovernor<F> {
+    /// Initializes a new governor with the given load signal and threshold.
+    pub fn new(load: F, threshold: f64) -> Self {
+        Self { load, threshold }
+    }
+}
+
+impl<F: Fn() -> f64 + Send + Sync> LoadGovernor for ThresholdGovernor<F> {
+    fn is_overloaded(&self) -> bool {
+        (self.load)() > self.threshold
+    }
+}
+
+/// Returns the load average of the host over the last minute, or `None` if it is unavailable (e.g. off Linux).
+pub fn load_average() -> Option<f64> {
+    let loadavg = fs::read_to_string("/proc/loadavg").ok()?;
+    loadavg.split_whitespace().next()?.parse().ok()
+}
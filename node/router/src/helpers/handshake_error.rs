@@ -0,0 +1,110 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::messages::DisconnectReason;
+
+use core::fmt;
+use std::{io, net::SocketAddr};
+
+/// The reason a handshake with a peer failed.
+/// Transient failures may succeed when retried, while permanent failures will not.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HandshakeError {
+    /// The handshake did not complete within the handshake timeout.
+    TimedOut(SocketAddr),
+    /// The peer closed or reset the connection during the handshake.
+    HungUp(SocketAddr, String),
+    /// The peer disconnected from this node during the handshake, for the given reason.
+    Disconnected(SocketAddr, DisconnectReason),
+    /// This node dropped the peer during the handshake, for the given reason.
+    Dropped(SocketAddr, DisconnectReason),
+    /// The peer is this node.
+    SelfConnect(SocketAddr),
+    /// The peer did not follow the handshake protocol.
+    ProtocolViolation(SocketAddr, String),
+    /// The handshake failed with an I/O error of the given kind.
+    Io(SocketAddr, io::ErrorKind, String),
+}
+
+impl HandshakeError {
+    /// Returns the handshake error for the given I/O error from a handshake with the given peer.
+    pub fn from_io(peer_addr: SocketAddr, error: io::Error) -> Self {
+        // Return the handshake error carried by the I/O error, if there is one.
+        if let Some(error) = error.get_ref().and_then(|inner| inner.downcast_ref::<Self>()) {
+            return error.clone();
+        }
+        match error.kind() {
+            io::ErrorKind::TimedOut => Self::TimedOut(peer_addr),
+            io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe => Self::HungUp(peer_addr, error.to_string()),
+            io::ErrorKind::InvalidData => Self::ProtocolViolation(peer_addr, error.to_string()),
+            kind => Self::Io(peer_addr, kind, error.to_string()),
+        }
+    }
+
+    /// Returns `true` if the handshake may succeed when retried.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::TimedOut(..) | Self::HungUp(..) | Self::Io(..) => true,
+            Self::Disconnected(_, reason) | Self::Dropped(_, reason) => !matches!(
+                reason,
+                DisconnectReason::OutdatedClientVersion
+                    | DisconnectReason::InvalidChallengeResponse
+                    | DisconnectReason::ProtocolViolation
+                    | DisconnectReason::SelfConnection
+            ),
+            Self::SelfConnect(..) | Self::ProtocolViolation(..) => false,
+        }
+    }
+
+    /// Returns the I/O error kind of the handshake error.
+    /// A protocol failure by the peer is of kind `InvalidData`, and a hang up of kind `ConnectionAborted`.
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            Self::TimedOut(..) => io::ErrorKind::TimedOut,
+            Self::HungUp(..) | Self::Disconnected(..) => io::ErrorKind::ConnectionAborted,
+            Self::Dropped(_, DisconnectReason::InvalidChallengeResponse | DisconnectReason::ProtocolViolation) => {
+                io::ErrorKind::InvalidData
+            }
+            Self::Dropped(..) | Self::SelfConnect(..) => io::ErrorKind::Other,
+            Self::ProtocolViolation(..) => io::ErrorKind::InvalidData,
+            Self::Io(_, kind, _) => *kind,
+        }
+    }
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TimedOut(peer_addr) => write!(f, "Handshake with '{peer_addr}' timed out"),
+            Self::HungUp(peer_addr, error) => write!(f, "'{peer_addr}' hung up during the handshake ({error})"),
+            Self::Disconnected(peer_addr, reason) => write!(f, "'{peer_addr}' disconnected: {reason:?}"),
+            Self::Dropped(peer_addr, reason) => write!(f, "Dropped '{peer_addr}' for reason: {reason:?}"),
+            Self::SelfConnect(peer_addr) => {
+                write!(f, "Dropped '{peer_addr}' for reason: SelfConnection (attempted to self-connect)")
+            }
+            Self::ProtocolViolation(_, error) | Self::Io(_, _, error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for HandshakeError {}
+
+impl From<HandshakeError> for io::Error {
+    fn from(error: HandshakeError) -> Self {
+        io::Error::new(error.kind(), error)
+    }
+}
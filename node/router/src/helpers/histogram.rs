@@ -0,0 +1,163 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use parking_lot::Mutex;
+use std::time::Duration;
+
+/// The number of sub-buckets per power of two, which bounds the relative error of a recorded value to 1/32.
+const SUB_BUCKET_BITS: u32 = 5;
+/// The number of sub-buckets per power of two.
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+/// The values (in microseconds) below this bound are recorded exactly.
+const EXACT_BOUND: u64 = 2 * SUB_BUCKETS as u64;
+/// The largest exponent of a recorded value; larger values are saturated (about 71 minutes).
+const MAXIMUM_EXPONENT: u32 = 31;
+/// The total number of buckets.
+const NUM_BUCKETS: usize = EXACT_BOUND as usize + (MAXIMUM_EXPONENT - SUB_BUCKET_BITS) as usize * SUB_BUCKETS;
+
+/// A bounded-memory histogram of durations, with logarithmic buckets in the style of HDR histograms.
+///
+/// Durations are recorded in microseconds. Each power of two is split into equally-sized buckets,
+/// so the percentiles are reported with a relative error of at most 1/32, using a fixed number of buckets.
+#[derive(Debug)]
+pub struct DurationHistogram {
+    /// The number of recorded durations in each bucket, and the total number of recorded durations.
+    inner: Mutex<(Vec<u64>, u64)>,
+}
+
+impl Default for DurationHistogram {
+    fn default() -> Self {
+        Self { inner: Mutex::new((vec![0; NUM_BUCKETS], 0)) }
+    }
+}
+
+impl DurationHistogram {
+    /// Records the given duration.
+    pub fn record(&self, duration: Duration) {
+        let value = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+        let (buckets, count) = &mut *self.inner.lock();
+        buckets[bucket_index(value)] += 1;
+        *count += 1;
+    }
+
+    /// Returns the number of recorded durations.
+    pub fn len(&self) -> u64 {
+        self.inner.lock().1
+    }
+
+    /// Returns `true` if no durations were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the given percentile (between 0 and 100) of the recorded durations.
+    /// The duration is rounded down to the lower bound of its bucket, and is zero if no durations were recorded.
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        let (buckets, count) = &*self.inner.lock();
+        if *count == 0 {
+            return Duration::ZERO;
+        }
+        // Determine the rank of the percentile, among the recorded durations.
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0 * *count as f64).ceil() as u64).max(1);
+        // Find the bucket that contains the duration at the rank.
+        let mut cumulative = 0;
+        for (index, num_values) in buckets.iter().enumerate() {
+            cumulative += num_values;
+            if cumulative >= rank {
+                return Duration::from_micros(bucket_lower_bound(index));
+            }
+        }
+        Duration::from_micros(bucket_lower_bound(NUM_BUCKETS - 1))
+    }
+}
+
+/// Returns the index of the bucket for the given value.
+fn bucket_index(value: u64) -> usize {
+    if value < EXACT_BOUND {
+        return value as usize;
+    }
+    // Determine the power of two of the value, saturating at the maximum exponent.
+    let exponent = (63 - value.leading_zeros()).min(MAXIMUM_EXPONENT);
+    if exponent == MAXIMUM_EXPONENT && value >= 1 << (MAXIMUM_EXPONENT + 1) {
+        return NUM_BUCKETS - 1;
+    }
+    // Determine the sub-bucket, from the bits that follow the leading bit.
+    let sub_bucket = ((value >> (exponent - SUB_BUCKET_BITS)) as usize) & (SUB_BUCKETS - 1);
+    EXACT_BOUND as usize + (exponent - SUB_BUCKET_BITS - 1) as usize * SUB_BUCKETS + sub_bucket
+}
+
+/// Returns the smallest value in the bucket with the given index.
+fn bucket_lower_bound(index: usize) -> u64 {
+    if index < EXACT_BOUND as usize {
+        return index as u64;
+    }
+    let offset = index - EXACT_BOUND as usize;
+    let exponent = (offset / SUB_BUCKETS) as u32 + SUB_BUCKET_BITS + 1;
+    let sub_bucket = (offset % SUB_BUCKETS) as u64;
+    (1 << exponent) | (sub_bucket << (exponent - SUB_BUCKET_BITS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_bounds() {
+        // Check that every bucket contains its lower bound.
+        for index in 0..NUM_BUCKETS {
+            assert_eq!(bucket_index(bucket_lower_bound(index)), index);
+        }
+        // Check that the recorded values are within the relative error.
+        for value in [63, 64, 65, 100, 1_000, 12_345, 999_999, 3_000_000_000] {
+            let lower_bound = bucket_lower_bound(bucket_index(value));
+            assert!(lower_bound <= value);
+            assert!(value - lower_bound <= value / SUB_BUCKETS as u64);
+        }
+        // Check that large values saturate.
+        assert_eq!(bucket_index(u64::MAX), NUM_BUCKETS - 1);
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let histogram = DurationHistogram::default();
+        assert!(histogram.is_empty());
+        assert_eq!(histogram.percentile(50.0), Duration::ZERO);
+
+        // Record the exact durations from 1 to 100 microseconds, in reverse.
+        for micros in (1..=100).rev() {
+            histogram.record(Duration::from_micros(micros));
+        }
+        assert_eq!(histogram.len(), 100);
+        assert_eq!(histogram.percentile(50.0), Duration::from_micros(50));
+        assert_eq!(histogram.percentile(95.0).as_micros(), 94); // 95 is in the bucket [94, 96)
+        assert_eq!(histogram.percentile(99.0).as_micros(), 98); // 99 is in the bucket [98, 100)
+        assert_eq!(histogram.percentile(100.0), Duration::from_micros(100));
+    }
+
+    #[test]
+    fn test_percentiles_of_milliseconds() {
+        let histogram = DurationHistogram::default();
+
+        // Record the durations from 1 to 100 milliseconds.
+        for millis in 1..=100 {
+            histogram.record(Duration::from_millis(millis));
+        }
+        // Check that the percentiles are within the relative error.
+        for (percentile, expected) in [(50.0, 50_000), (95.0, 95_000), (99.0, 99_000)] {
+            let actual = histogram.percentile(percentile).as_micros() as u64;
+            assert!(actual <= expected);
+            assert!(expected - actual <= expected / SUB_BUCKETS as u64);
+        }
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use parking_lot::Mutex;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// A limiter on the number of concurrent expensive tasks, such as handshakes or puzzle serializations.
+#[derive(Debug)]
+pub struct ConcurrencyLimiter {
+    /// The semaphore of task permits.
+    permits: Arc<Semaphore>,
+    /// The maximum number of concurrent tasks, and the number of permits still owed to the semaphore.
+    state: Mutex<LimiterState>,
+    /// The duration to wait for a permit, before the task is rejected.
+    queue_timeout: Duration,
+}
+
+/// The limit of a [`ConcurrencyLimiter`].
+#[derive(Debug)]
+struct LimiterState {
+    /// The maximum number of concurrent tasks.
+    limit: usize,
+    /// The number of permits held by tasks in progress, which must be forgotten once they are released,
+    /// as the limit was lowered after they were acquired.
+    owed: usize,
+}
+
+impl ConcurrencyLimiter {
+    /// Initializes a new limiter with the given maximum number of concurrent tasks.
+    pub fn new(limit: usize, queue_timeout: Duration) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(limit)),
+            state: Mutex::new(LimiterState { limit, owed: 0 }),
+            queue_timeout,
+        }
+    }
+
+    /// Waits briefly for a permit, returning `None` if no permit became available.
+    pub async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let acquire = async {
+            loop {
+                let permit = self.permits.clone().acquire_owned().await.ok()?;
+                // Forget the permit instead, if it is owed since the limit was lowered.
+                let mut state = self.state.lock();
+                match state.owed {
+                    0 => return Some(permit),
+                    _ => {
+                        permit.forget();
+                        state.owed -= 1;
+                    }
+                }
+            }
+        };
+        tokio::time::timeout(self.queue_timeout, acquire).await.ok().flatten()
+    }
+
+    /// Returns the maximum number of concurrent tasks.
+    pub fn limit(&self) -> usize {
+        self.state.lock().limit
+    }
+
+    /// Sets the maximum number of concurrent tasks.
+    ///
+    /// Tasks that are already in progress are unaffected, but count towards the new limit.
+    pub fn set_limit(&self, limit: usize) {
+        let mut state = self.state.lock();
+        if limit >= state.limit {
+            // Cancel the owed permits first, and add the remaining permits to the semaphore.
+            let increase = limit - state.limit;
+            let cancelled = increase.min(state.owed);
+            state.owed -= cancelled;
+            self.permits.add_permits(increase - cancelled);
+        } else {
+            // Owe the removed permits, and forget the ones that are available right away.
+            state.owed += state.limit - limit;
+            self.reclaim(&mut state);
+        }
+        state.limit = limit;
+    }
+
+    /// Returns the number of tasks in progress.
+    pub fn num_in_flight(&self) -> usize {
+        let mut state = self.state.lock();
+        self.reclaim(&mut state);
+        // The semaphore holds the permits of the limit, plus the owed permits still held by tasks in progress.
+        (state.limit + state.owed).saturating_sub(self.permits.available_permits())
+    }
+
+    /// Forgets the owed permits that were released to the semaphore.
+    fn reclaim(&self, state: &mut LimiterState) {
+        while state.owed > 0 {
+            match self.permits.try_acquire() {
+                Ok(permit) => {
+                    permit.forget();
+                    state.owed -= 1;
+                }
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_concurrent_handshakes_are_bounded() {
+        const LIMIT: usize = 4;
+        const NUM_HANDSHAKES: usize = 32;
+
+        let limiter = Arc::new(ConcurrencyLimiter::new(LIMIT, Duration::from_secs(10)));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        // Fire many simultaneous handshakes.
+        let handles = (0..NUM_HANDSHAKES)
+            .map(|_| {
+                let (limiter, in_flight, peak) = (limiter.clone(), in_flight.clone(), peak.clone());
+                tokio::spawn(async move {
+                    let _permit = limiter.acquire().await.unwrap();
+                    // Record the number of handshakes in flight.
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(current, Ordering::SeqCst);
+                    // Simulate the handshake.
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        // Check that no more than the limit were in flight at once.
+        assert!(peak.load(Ordering::SeqCst) <= LIMIT);
+        assert_eq!(limiter.num_in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_excess_handshakes_are_rejected() {
+        let limiter = ConcurrencyLimiter::new(1, Duration::from_millis(50));
+
+        // Take the only permit.
+        let permit = limiter.acquire().await;
+        assert!(permit.is_some());
+        assert_eq!(limiter.num_in_flight(), 1);
+
+        // Check that the next handshake is rejected after the queue timeout.
+        assert!(limiter.acquire().await.is_none());
+
+        // Release the permit, and check that the next handshake proceeds.
+        drop(permit);
+        assert!(limiter.acquire().await.is_some());
+
+        // Raise the limit, and check that the number of permits is updated.
+        limiter.set_limit(2);
+        assert_eq!(limiter.limit(), 2);
+        assert_eq!(limiter.num_in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_set_limit_counts_tasks_in_progress() {
+        let limiter = ConcurrencyLimiter::new(3, Duration::from_millis(50));
+
+        // Start three tasks, and lower the limit while they are in progress.
+        let first = limiter.acquire().await.unwrap();
+        let second = limiter.acquire().await.unwrap();
+        let third = limiter.acquire().await.unwrap();
+        limiter.set_limit(1);
+        assert_eq!(limiter.limit(), 1);
+        assert_eq!(limiter.num_in_flight(), 3);
+
+        // Check that no task starts until the tasks in progress are below the new limit.
+        drop(first);
+        assert_eq!(limiter.num_in_flight(), 2);
+        assert!(limiter.acquire().await.is_none());
+        drop(second);
+        assert_eq!(limiter.num_in_flight(), 1);
+        assert!(limiter.acquire().await.is_none());
+        drop(third);
+        assert_eq!(limiter.num_in_flight(), 0);
+        let permit = limiter.acquire().await.unwrap();
+        assert_eq!(limiter.num_in_flight(), 1);
+        assert!(limiter.acquire().await.is_none());
+
+        // Raise the limit while a task is in progress, and check that it still counts towards the limit.
+        limiter.set_limit(2);
+        assert_eq!(limiter.num_in_flight(), 1);
+        let _second = limiter.acquire().await.unwrap();
+        assert_eq!(limiter.num_in_flight(), 2);
+        assert!(limiter.acquire().await.is_none());
+        drop(permit);
+        assert_eq!(limiter.num_in_flight(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_excess_serializations_are_declined() {
+        const LIMIT: usize = 2;
+        const NUM_SERIALIZATIONS: usize = 16;
+
+        let limiter = Arc::new(ConcurrencyLimiter::new(LIMIT, Duration::from_millis(10)));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let declined = Arc::new(AtomicUsize::new(0));
+
+        // Saturate the limiter with simultaneous serializations.
+        let handles = (0..NUM_SERIALIZATIONS)
+            .map(|_| {
+                let (limiter, in_flight) = (limiter.clone(), in_flight.clone());
+                let (peak, declined) = (peak.clone(), declined.clone());
+                tokio::spawn(async move {
+                    let Some(_permit) = limiter.acquire().await else {
+                        declined.fetch_add(1, Ordering::SeqCst);
+                        return;
+                    };
+                    // Record the number of serializations in flight.
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(current, Ordering::SeqCst);
+                    // Simulate a serialization that outlasts the queue timeout.
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        // Check that no more than the limit ran at once, and the excess was declined.
+        assert_eq!(peak.load(Ordering::SeqCst), LIMIT);
+        assert_eq!(declined.load(Ordering::SeqCst), NUM_SERIALIZATIONS - LIMIT);
+        assert_eq!(limiter.num_in_flight(), 0);
+    }
+}
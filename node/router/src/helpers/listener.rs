@@ -0,0 +1,24 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The admission policy for the peers connecting through a listener.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ListenerPolicy {
+    /// The peers are admitted under the usual rules.
+    #[default]
+    Standard,
+    /// The peers are always admitted, e.g. on a localhost admin listener. Like pinned peers, they are exempt
+    /// from the peer limits and the host load, and they are also exempt from restrictions and admission challenges.
+    AlwaysAllow,
+}
@@ -0,0 +1,66 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// A load signal for inbound messages, which sheds non-critical messages when the node is saturated.
+#[derive(Debug, Default)]
+pub struct LoadShedder {
+    /// The current number of in-flight messages.
+    in_flight: AtomicUsize,
+    /// The number of messages that were shed.
+    num_shed: AtomicU64,
+}
+
+impl LoadShedder {
+    /// Attempts to admit an inbound message, returning `None` if the message is shed.
+    ///
+    /// Critical messages are always admitted (and counted as in-flight), while sheddable messages
+    /// are only admitted if fewer than the given maximum number of messages are in flight.
+    pub fn try_admit(&self, is_sheddable: bool, max_in_flight: usize) -> Option<InFlight<'_>> {
+        if is_sheddable {
+            // Reserve an in-flight slot, if one is available.
+            let result = self.in_flight.fetch_update(Ordering::AcqRel, Ordering::Acquire, |in_flight| {
+                (in_flight < max_in_flight).then_some(in_flight + 1)
+            });
+            if result.is_err() {
+                self.num_shed.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        } else {
+            self.in_flight.fetch_add(1, Ordering::AcqRel);
+        }
+        Some(InFlight(&self.in_flight))
+    }
+
+    /// Returns the current number of in-flight messages.
+    pub fn num_in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of messages that were shed.
+    pub fn num_shed(&self) -> u64 {
+        self.num_shed.load(Ordering::Relaxed)
+    }
+}
+
+/// A guard for an in-flight message, which releases its slot when dropped.
+#[must_use]
+pub struct InFlight<'a>(&'a AtomicUsize);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
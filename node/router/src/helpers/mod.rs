@@ -12,11 +12,74 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod bloom;
+pub use bloom::BloomFilter;
+
 mod cache;
-pub use cache::Cache;
+pub use cache::{Cache, MessageId};
+
+mod churn;
+pub use churn::*;
+
+mod classifier;
+pub use classifier::*;
+
+mod clock;
+pub use clock::*;
+
+mod config;
+pub use config::*;
+
+mod connect_error;
+pub use connect_error::*;
+
+mod dead_letter;
+pub use dead_letter::*;
+
+mod disconnects;
+pub use disconnects::*;
+
+mod eviction;
+pub use eviction::*;
+
+mod governor;
+pub use governor::*;
+
+mod handshake_error;
+pub use handshake_error::*;
+
+mod histogram;
+pub use histogram::*;
+
+mod limiter;
+pub use limiter::*;
+
+mod listener;
+pub use listener::*;
+
+mod load;
+pub use load::*;
 
 mod peer;
 pub use peer::*;
 
+mod peer_context;
+pub use peer_context::*;
+
+mod peer_event;
+pub use peer_event::*;
+
+mod probation;
+pub use probation::*;
+
+mod recorder;
+pub use recorder::*;
+
 mod resolver;
 pub use resolver::*;
+
+mod snapshot;
+pub use snapshot::*;
+
+mod topology;
+pub use topology::*;
@@ -12,10 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::messages::{ChallengeRequest, NodeType};
+use super::MonotonicClock;
+use crate::messages::{CapabilitySet, ChallengeRequest, DisconnectReason, FeatureFlags, Message, NodeType};
+use snarkos_node_tcp::ConnectionSide;
 use snarkvm::prelude::{Address, Network};
 
-use std::{net::SocketAddr, time::Instant};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 /// The state for each connected peer.
 #[derive(Clone, Debug)]
@@ -26,24 +35,65 @@ pub struct Peer<N: Network> {
     address: Address<N>,
     /// The node type of the peer.
     node_type: NodeType,
+    /// The side of the connection of the peer; an initiator connected to this node.
+    side: ConnectionSide,
     /// The message version of the peer.
     version: u32,
+    /// The codec version of the connection with the peer, if it was upgraded after the handshake.
+    codec_version: Option<u8>,
+    /// The message types the peer understands, as advertised during the handshake.
+    capabilities: CapabilitySet,
+    /// Whether compressed frames are still in use with the peer, shared with the codecs of the connection.
+    compression: Arc<AtomicBool>,
+    /// The latest block height of the peer, as reported in its last ping.
+    height: Option<u32>,
+    /// The number of peers the peer reported in its last peer response.
+    num_reported_peers: Option<usize>,
     /// The timestamp of the first message received from the peer.
     first_seen: Instant,
     /// The timestamp of the last message received from this peer.
     last_seen: Instant,
+    /// The boolean flag indicating whether a puzzle request from this peer is in flight.
+    puzzle_request_in_flight: bool,
+    /// The number of puzzle requests from this peer, awaiting the delivery of the response in flight.
+    num_pending_puzzle_requests: usize,
+    /// The timestamp of the oldest unanswered liveness ping sent to this peer.
+    ping_sent_at: Option<Instant>,
+    /// The round-trip time of the last answered liveness ping.
+    rtt: Option<Duration>,
+    /// The number of consecutive liveness pings this peer has missed.
+    missed_pings: u32,
+    /// The reason for disconnecting from this peer, if a disconnect is underway.
+    disconnect_reason: Option<DisconnectReason>,
 }
 
 impl<N: Network> Peer<N> {
-    /// Initializes a new instance of `Peer`.
-    pub fn new(listening_ip: SocketAddr, challenge_request: &ChallengeRequest<N>) -> Self {
+    /// Initializes a new instance of `Peer`, first seen at the given instant of the router clock.
+    pub fn new(
+        listening_ip: SocketAddr,
+        challenge_request: &ChallengeRequest<N>,
+        side: ConnectionSide,
+        first_seen: Instant,
+    ) -> Self {
         Self {
             peer_ip: listening_ip,
             address: challenge_request.address,
             node_type: challenge_request.node_type,
+            side,
             version: challenge_request.version,
-            first_seen: Instant::now(),
-            last_seen: Instant::now(),
+            codec_version: None,
+            capabilities: challenge_request.capabilities,
+            compression: Arc::new(AtomicBool::new(challenge_request.capabilities.supports_compression())),
+            height: None,
+            num_reported_peers: None,
+            first_seen,
+            last_seen: first_seen,
+            puzzle_request_in_flight: false,
+            num_pending_puzzle_requests: 0,
+            ping_sent_at: None,
+            rtt: None,
+            missed_pings: 0,
+            disconnect_reason: None,
         }
     }
 
@@ -62,6 +112,11 @@ impl<N: Network> Peer<N> {
         self.node_type
     }
 
+    /// Returns the side of the connection of the peer.
+    pub const fn side(&self) -> ConnectionSide {
+        self.side
+    }
+
     /// Returns `true` if the peer is a validator.
     pub const fn is_validator(&self) -> bool {
         self.node_type.is_validator()
@@ -82,6 +137,51 @@ impl<N: Network> Peer<N> {
         self.version
     }
 
+    /// Returns the codec version of the connection with the peer.
+    /// Unless the codec was upgraded, this is the version negotiated during the handshake.
+    pub const fn codec_version(&self) -> u8 {
+        match self.codec_version {
+            Some(codec_version) => codec_version,
+            None => Message::<N>::codec_version(self.version),
+        }
+    }
+
+    /// Returns the message types the peer understands.
+    pub const fn capabilities(&self) -> CapabilitySet {
+        self.capabilities
+    }
+
+    /// Returns the features the peer supports, as derived from its message version.
+    pub fn features(&self) -> FeatureFlags {
+        FeatureFlags::from_version(self.version)
+    }
+
+    /// Returns `true` if the peer understands the type of the given message,
+    /// i.e. it advertised the message type and its message version includes the feature of the message.
+    pub fn supports(&self, message: &Message<N>) -> bool {
+        self.capabilities.supports(message) && self.features().supports(message)
+    }
+
+    /// Returns the flag indicating whether compressed frames are still in use with the peer.
+    pub fn compression(&self) -> &Arc<AtomicBool> {
+        &self.compression
+    }
+
+    /// Returns `true` if the peer advertised support for compressed frames, and they did not fail to decompress.
+    pub fn supports_compression(&self) -> bool {
+        self.capabilities.supports_compression() && self.compression.load(Ordering::Relaxed)
+    }
+
+    /// Returns the latest block height of the peer, if it has reported one.
+    pub const fn height(&self) -> Option<u32> {
+        self.height
+    }
+
+    /// Returns the number of peers the peer reported in its last peer response, if it sent one.
+    pub const fn num_reported_peers(&self) -> Option<usize> {
+        self.num_reported_peers
+    }
+
     /// Returns the first seen timestamp of the peer.
     pub fn first_seen(&self) -> Instant {
         self.first_seen
@@ -91,6 +191,31 @@ impl<N: Network> Peer<N> {
     pub fn last_seen(&self) -> Instant {
         self.last_seen
     }
+
+    /// Returns `true` if a puzzle request from the peer is in flight.
+    pub const fn is_puzzle_request_in_flight(&self) -> bool {
+        self.puzzle_request_in_flight
+    }
+
+    /// Returns the number of puzzle requests from the peer, awaiting the delivery of the response in flight.
+    pub const fn num_pending_puzzle_requests(&self) -> usize {
+        self.num_pending_puzzle_requests
+    }
+
+    /// Returns the round-trip time of the last answered liveness ping, if any.
+    pub const fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// Returns the number of consecutive liveness pings the peer has missed.
+    pub const fn missed_pings(&self) -> u32 {
+        self.missed_pings
+    }
+
+    /// Returns the reason for disconnecting from the peer, if a disconnect is underway.
+    pub const fn disconnect_reason(&self) -> Option<DisconnectReason> {
+        self.disconnect_reason
+    }
 }
 
 impl<N: Network> Peer<N> {
@@ -104,8 +229,60 @@ impl<N: Network> Peer<N> {
         self.version = version;
     }
 
+    /// Updates the codec version of the connection with the peer.
+    pub fn set_codec_version(&mut self, codec_version: u8) {
+        self.codec_version = Some(codec_version);
+    }
+
+    /// Updates the latest block height of the peer, returning `true` if it changed.
+    pub fn set_height(&mut self, height: u32) -> bool {
+        self.height.replace(height) != Some(height)
+    }
+
+    /// Updates the number of peers the peer reported in its last peer response.
+    pub fn set_num_reported_peers(&mut self, num_peers: usize) {
+        self.num_reported_peers = Some(num_peers);
+    }
+
     /// Updates the last seen timestamp of the peer.
     pub fn set_last_seen(&mut self, last_seen: Instant) {
         self.last_seen = last_seen;
     }
+
+    /// Updates the in-flight flag for puzzle requests from the peer.
+    /// Once the response in flight is delivered (or dropped), the pending puzzle requests are cleared.
+    pub fn set_puzzle_request_in_flight(&mut self, in_flight: bool) {
+        self.puzzle_request_in_flight = in_flight;
+        if !in_flight {
+            self.num_pending_puzzle_requests = 0;
+        }
+    }
+
+    /// Records a puzzle request from the peer, awaiting the delivery of the response in flight.
+    pub fn insert_pending_puzzle_request(&mut self) {
+        self.num_pending_puzzle_requests = self.num_pending_puzzle_requests.saturating_add(1);
+    }
+
+    /// Sets the reason for disconnecting from the peer, unless a reason was already set.
+    pub fn set_disconnect_reason(&mut self, reason: DisconnectReason) {
+        self.disconnect_reason.get_or_insert(reason);
+    }
+
+    /// Records a liveness ping sent to the peer, returning the number of consecutive missed pings.
+    /// If the previous ping is still unanswered, it is counted as missed.
+    pub fn insert_ping(&mut self, sent_at: Instant) -> u32 {
+        match self.ping_sent_at {
+            Some(_) => self.missed_pings += 1,
+            None => self.ping_sent_at = Some(sent_at),
+        }
+        self.missed_pings
+    }
+
+    /// Records a pong received from the peer, updating the round-trip time of the outstanding ping.
+    pub fn insert_pong(&mut self, received_at: Instant) {
+        if let Some(sent_at) = self.ping_sent_at.take() {
+            self.rtt = Some(MonotonicClock::duration_between(sent_at, received_at));
+        }
+        self.missed_pings = 0;
+    }
 }
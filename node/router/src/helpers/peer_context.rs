@@ -0,0 +1,46 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::messages::{CapabilitySet, NodeType};
+use snarkos_node_tcp::ConnectionSide;
+
+use std::net::SocketAddr;
+
+/// The metadata of the connection with a peer, for message handlers to make informed decisions about the peer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PeerContext {
+    /// The IP address of the peer, with the port set to the listener port.
+    pub peer_ip: SocketAddr,
+    /// The side of the connection of the peer; an initiator connected to this node.
+    pub side: ConnectionSide,
+    /// The node type of the peer.
+    pub node_type: NodeType,
+    /// The message version of the peer, as negotiated during the handshake.
+    pub version: u32,
+    /// The codec version of the connection with the peer.
+    pub codec_version: u8,
+    /// The message types the peer understands, as advertised during the handshake.
+    pub capabilities: CapabilitySet,
+    /// Whether the peer is pinned, and exempt from eviction.
+    pub is_pinned: bool,
+    /// Whether the peer violated the protocol recently, and is on probation.
+    pub is_on_probation: bool,
+}
+
+impl PeerContext {
+    /// Returns `true` if the peer connected to this node.
+    pub fn is_inbound(&self) -> bool {
+        self.side == ConnectionSide::Initiator
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MonotonicClock;
+
+use indexmap::IndexMap;
+use parking_lot::Mutex;
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+/// The tier a peer is placed in after violating the protocol.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ViolationTier {
+    /// The peer is disconnected, and may reconnect after a short cooldown.
+    Probation,
+    /// The peer exceeded the tolerated violations within the probation period, and is restricted.
+    Restricted,
+}
+
+/// The peers on probation, with the timestamps of their last violations and their violation counts.
+#[derive(Debug, Default)]
+pub struct ProbationList {
+    inner: Mutex<IndexMap<SocketAddr, (Instant, usize)>>,
+    /// The clock used to measure the probation periods.
+    clock: MonotonicClock,
+}
+
+impl ProbationList {
+    /// Records a violation by the given peer IP, and returns the resulting tier.
+    /// A peer that exceeds the tolerated violations within the probation period is escalated to restriction.
+    /// The violation history is retained by address, so it survives the peer disconnecting and reconnecting.
+    pub fn record_violation(
+        &self,
+        peer_ip: SocketAddr,
+        probation_period: Duration,
+        tolerated_violations: usize,
+    ) -> ViolationTier {
+        let mut peers = self.inner.lock();
+        // Purge the peers that have cleared their probation.
+        peers.retain(|_, (last_violation, _)| self.clock.elapsed(*last_violation) < probation_period);
+        // Count the violation, and refresh the start of the probation period.
+        let (last_violation, num_violations) = peers.entry(peer_ip).or_insert((self.clock.now(), 0));
+        *last_violation = self.clock.now();
+        *num_violations += 1;
+        // Escalate a peer that exceeded the tolerated violations, or keep it on probation.
+        match *num_violations > tolerated_violations {
+            true => {
+                peers.remove(&peer_ip);
+                ViolationTier::Restricted
+            }
+            false => ViolationTier::Probation,
+        }
+    }
+
+    /// Returns `true` if the given peer IP violated the protocol within the probation period.
+    pub fn contains(&self, peer_ip: &SocketAddr, probation_period: Duration) -> bool {
+        self.inner
+            .lock()
+            .get(peer_ip)
+            .map_or(false, |(last_violation, _)| self.clock.elapsed(*last_violation) < probation_period)
+    }
+
+    /// Returns `true` if the given peer IP violated the protocol within the cooldown, and may not reconnect yet.
+    pub fn is_cooling_down(&self, peer_ip: &SocketAddr, cooldown: Duration) -> bool {
+        self.contains(peer_ip, cooldown)
+    }
+
+    /// Removes the given peer IP from probation.
+    pub fn remove(&self, peer_ip: &SocketAddr) {
+        self.inner.lock().remove(peer_ip);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probation_escalates() {
+        let probation = ProbationList::default();
+        let peer_ip = SocketAddr::from(([127, 0, 0, 1], 4130));
+        let period = Duration::from_secs(60);
+
+        // Check that the first violation places the peer on probation, and the second one restricts it.
+        assert_eq!(probation.record_violation(peer_ip, period, 1), ViolationTier::Probation);
+        assert!(probation.contains(&peer_ip, period));
+        assert_eq!(probation.record_violation(peer_ip, period, 1), ViolationTier::Restricted);
+        assert!(!probation.contains(&peer_ip, period));
+    }
+
+    #[test]
+    fn test_probation_tolerates_violations() {
+        let probation = ProbationList::default();
+        let peer_ip = SocketAddr::from(([127, 0, 0, 1], 4130));
+        let period = Duration::from_secs(60);
+
+        // Check that no violations are tolerated when the threshold is zero.
+        assert_eq!(probation.record_violation(peer_ip, period, 0), ViolationTier::Restricted);
+        assert!(!probation.contains(&peer_ip, period));
+
+        // Check that the peer is only restricted once it exceeds the tolerated violations.
+        for _ in 0..3 {
+            assert_eq!(probation.record_violation(peer_ip, period, 3), ViolationTier::Probation);
+        }
+        assert_eq!(probation.record_violation(peer_ip, period, 3), ViolationTier::Restricted);
+    }
+
+    #[test]
+    fn test_probation_is_cleared() {
+        let probation = ProbationList::default();
+        let peer_ip = SocketAddr::from(([127, 0, 0, 1], 4130));
+
+        // Check that a violation after the probation period places the peer on probation again.
+        assert_eq!(probation.record_violation(peer_ip, Duration::ZERO, 1), ViolationTier::Probation);
+        assert!(!probation.contains(&peer_ip, Duration::ZERO));
+        assert_eq!(probation.record_violation(peer_ip, Duration::ZERO, 1), ViolationTier::Probation);
+    }
+}
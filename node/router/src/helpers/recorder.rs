@@ -0,0 +1,192 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::messages::Message;
+use snarkvm::prelude::Network;
+
+use anyhow::{bail, Result};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
+};
+use time::OffsetDateTime;
+
+/// An inbound message from a message log, alongside the peer that sent it and the time it was received.
+#[derive(Clone, Debug)]
+pub struct MessageRecord<N: Network> {
+    /// The UNIX timestamp in milliseconds at which the message was received.
+    pub timestamp: i64,
+    /// The (ambiguous) address of the peer that sent the message.
+    pub peer_addr: SocketAddr,
+    /// The message.
+    pub message: Message<N>,
+}
+
+/// A recorder that appends inbound messages to a message log, in a compact framed format.
+/// Once the log reaches its maximum size, it is rotated, replacing the previously rotated log.
+pub struct MessageRecorder {
+    /// The path to the message log.
+    path: PathBuf,
+    /// The maximum size of the message log in bytes.
+    max_file_bytes: u64,
+    /// The message log.
+    file: File,
+    /// The current size of the message log in bytes.
+    num_bytes: u64,
+}
+
+impl MessageRecorder {
+    /// Initializes a new recorder, which truncates the message log at the given path.
+    pub fn new(path: &Path, max_file_bytes: u64) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self { path: path.to_path_buf(), max_file_bytes, file, num_bytes: 0 })
+    }
+
+    /// Returns the path of the rotated message log, for the message log at the given path.
+    pub fn rotated_path(path: &Path) -> PathBuf {
+        let mut rotated_path = path.as_os_str().to_owned();
+        rotated_path.push(".1");
+        rotated_path.into()
+    }
+
+    /// Appends the given message from the given peer to the message log.
+    pub fn record<N: Network>(&mut self, peer_addr: SocketAddr, message: &Message<N>) -> Result<()> {
+        // Serialize the record.
+        let mut record = Vec::new();
+        let timestamp = OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000;
+        record.write_all(&(timestamp as i64).to_le_bytes())?;
+        write_socket_addr(&mut record, peer_addr)?;
+        message.write_le_with_version(Message::<N>::MAXIMUM_CODEC_VERSION, &mut record)?;
+
+        // Rotate the message log, if the record does not fit.
+        let num_bytes = 4 + record.len() as u64;
+        if self.num_bytes > 0 && self.num_bytes + num_bytes > self.max_file_bytes {
+            fs::rename(&self.path, Self::rotated_path(&self.path))?;
+            self.file = File::create(&self.path)?;
+            self.num_bytes = 0;
+        }
+
+        // Write the length-prefixed record.
+        let mut frame = (record.len() as u32).to_le_bytes().to_vec();
+        frame.extend_from_slice(&record);
+        self.file.write_all(&frame)?;
+        self.num_bytes += num_bytes;
+        Ok(())
+    }
+}
+
+/// Reads the inbound messages from the message log at the given path, in the order they were received.
+pub fn read_message_log<N: Network>(path: &Path) -> Result<Vec<MessageRecord<N>>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+    loop {
+        // Read the length of the next record, until the end of the message log.
+        let mut length = [0u8; 4];
+        match reader.read_exact(&mut length) {
+            Ok(()) => (),
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(error) => return Err(error.into()),
+        }
+        let mut record = vec![0u8; u32::from_le_bytes(length) as usize];
+        reader.read_exact(&mut record)?;
+
+        // Deserialize the record.
+        let mut record = &record[..];
+        let mut timestamp = [0u8; 8];
+        record.read_exact(&mut timestamp)?;
+        let peer_addr = read_socket_addr(&mut record)?;
+        let message = Message::read_le_with_version(Message::<N>::MAXIMUM_CODEC_VERSION, &mut record)?;
+        if !record.is_empty() {
+            bail!("Found {} trailing bytes in a message record", record.len())
+        }
+        records.push(MessageRecord { timestamp: i64::from_le_bytes(timestamp), peer_addr, message });
+    }
+    Ok(records)
+}
+
+/// Writes the given socket address, as the IP version, the IP address, and the port.
+fn write_socket_addr<W: Write>(writer: &mut W, addr: SocketAddr) -> io::Result<()> {
+    match addr.ip() {
+        IpAddr::V4(ip) => {
+            writer.write_all(&[4])?;
+            writer.write_all(&ip.octets())?;
+        }
+        IpAddr::V6(ip) => {
+            writer.write_all(&[6])?;
+            writer.write_all(&ip.octets())?;
+        }
+    }
+    writer.write_all(&addr.port().to_le_bytes())
+}
+
+/// Reads a socket address, written by `write_socket_addr`.
+fn read_socket_addr<R: Read>(reader: &mut R) -> io::Result<SocketAddr> {
+    let mut version = [0u8; 1];
+    reader.read_exact(&mut version)?;
+    let ip = match version[0] {
+        4 => {
+            let mut octets = [0u8; 4];
+            reader.read_exact(&mut octets)?;
+            IpAddr::V4(Ipv4Addr::from(octets))
+        }
+        6 => {
+            let mut octets = [0u8; 16];
+            reader.read_exact(&mut octets)?;
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        version => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid IP version {version}"))),
+    };
+    let mut port = [0u8; 2];
+    reader.read_exact(&mut port)?;
+    Ok(SocketAddr::new(ip, u16::from_le_bytes(port)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{PeerRequest, Pong};
+    use snarkvm::prelude::Testnet3;
+
+    type CurrentNetwork = Testnet3;
+
+    #[test]
+    fn test_message_log_rotation() {
+        let dir = std::env::temp_dir().join(format!("snarkos-recorder-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("messages.log");
+        let peer_addr = "[::1]:4130".parse().unwrap();
+
+        // Record a message, and check that it is read back.
+        let mut recorder = MessageRecorder::new(&path, 64).unwrap();
+        recorder.record::<CurrentNetwork>(peer_addr, &Message::PeerRequest(PeerRequest)).unwrap();
+        let records = read_message_log::<CurrentNetwork>(&path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].peer_addr, peer_addr);
+        assert!(matches!(records[0].message, Message::PeerRequest(_)));
+
+        // Record messages beyond the maximum size, and check that the message log was rotated.
+        for _ in 0..4 {
+            recorder.record::<CurrentNetwork>(peer_addr, &Message::Pong(Pong { is_fork: Some(true) })).unwrap();
+        }
+        assert!(fs::metadata(&path).unwrap().len() <= 64);
+        let rotated = read_message_log::<CurrentNetwork>(&MessageRecorder::rotated_path(&path)).unwrap();
+        let current = read_message_log::<CurrentNetwork>(&path).unwrap();
+        assert!(!rotated.is_empty());
+        assert!(current.iter().all(|record| matches!(record.message, Message::Pong(_))));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{messages::NodeType, Peer};
+use snarkvm::prelude::Network;
+
+use indexmap::IndexMap;
+use std::{net::SocketAddr, time::Instant};
+
+/// An immutable view of the connected peers, captured at a single point in time.
+#[derive(Clone, Debug)]
+pub struct PeerTableSnapshot<N: Network> {
+    /// The map of connected peer IPs to their peer state.
+    peers: IndexMap<SocketAddr, Peer<N>>,
+    /// The time at which the snapshot was taken.
+    taken_at: Instant,
+}
+
+impl<N: Network> PeerTableSnapshot<N> {
+    /// Initializes a new snapshot from the given connected peers.
+    pub fn new(peers: IndexMap<SocketAddr, Peer<N>>) -> Self {
+        Self { peers, taken_at: Instant::now() }
+    }
+
+    /// Returns the time at which the snapshot was taken.
+    pub const fn taken_at(&self) -> Instant {
+        self.taken_at
+    }
+
+    /// Returns the number of peers in the snapshot.
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Returns `true` if the snapshot contains no peers.
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Returns `true` if the snapshot contains the given peer IP.
+    pub fn contains(&self, peer_ip: &SocketAddr) -> bool {
+        self.peers.contains_key(peer_ip)
+    }
+
+    /// Returns the peer with the given peer IP, if it is in the snapshot.
+    pub fn get(&self, peer_ip: &SocketAddr) -> Option<&Peer<N>> {
+        self.peers.get(peer_ip)
+    }
+
+    /// Returns the peer IPs in the snapshot.
+    pub fn peer_ips(&self) -> impl '_ + Iterator<Item = SocketAddr> {
+        self.peers.keys().copied()
+    }
+
+    /// Returns the peers in the snapshot.
+    pub fn peers(&self) -> impl '_ + Iterator<Item = &Peer<N>> {
+        self.peers.values()
+    }
+
+    /// Returns the node type of each peer in the snapshot.
+    pub fn metrics(&self) -> Vec<(SocketAddr, NodeType)> {
+        self.peers.iter().map(|(ip, peer)| (*ip, peer.node_type())).collect()
+    }
+}
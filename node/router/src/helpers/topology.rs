@@ -0,0 +1,97 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::messages::NodeType;
+use snarkos_node_tcp::ConnectionSide;
+use snarkvm::prelude::{Address, Network};
+
+use indexmap::IndexSet;
+use std::net::SocketAddr;
+
+/// A declarative description of the peers a node maintains connections with, and the peers it avoids.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TopologyDesc {
+    /// The peers to stay connected to.
+    pub required: IndexSet<SocketAddr>,
+    /// The peers to disconnect from, and to drop from the candidate peers; they take precedence over required ones.
+    pub avoided: IndexSet<SocketAddr>,
+    /// Whether the connected peers that are not required are disconnected as well.
+    pub is_exclusive: bool,
+}
+
+impl TopologyDesc {
+    /// Initializes a topology that requires the given peers, and allows any other peers.
+    pub fn new(required: impl IntoIterator<Item = SocketAddr>) -> Self {
+        Self { required: required.into_iter().collect(), ..Default::default() }
+    }
+
+    /// Avoids the given peers.
+    pub fn with_avoided(mut self, avoided: impl IntoIterator<Item = SocketAddr>) -> Self {
+        self.avoided.extend(avoided);
+        self
+    }
+
+    /// Disconnects the connected peers that are not required.
+    pub fn exclusive(mut self) -> Self {
+        self.is_exclusive = true;
+        self
+    }
+
+    /// Returns `true` if the given connected peer is disallowed by the topology.
+    pub fn is_disallowed(&self, peer_ip: &SocketAddr) -> bool {
+        self.avoided.contains(peer_ip) || (self.is_exclusive && !self.required.contains(peer_ip))
+    }
+}
+
+/// The changes made to the connections of a node, to reconcile them with a topology.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TopologyChanges {
+    /// The required peers that were dialed, as they were not connected.
+    pub dialed: Vec<SocketAddr>,
+    /// The connected peers that were disconnected, as the topology disallows them.
+    pub disconnected: Vec<SocketAddr>,
+}
+
+/// The connections of a node at a single point in time, for tools to assemble a partial graph of the network.
+#[derive(Clone, Debug)]
+pub struct TopologySnapshot<N: Network> {
+    /// The listener IP of this node.
+    pub node_ip: SocketAddr,
+    /// The Aleo address of this node.
+    pub address: Address<N>,
+    /// The connected peers, as read under a single lock of the connected peers.
+    pub peers: Vec<TopologyPeer>,
+}
+
+/// A connected peer, as it appears in a topology snapshot.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TopologyPeer {
+    /// The IP address of the peer, with the port set to the listener port.
+    pub peer_ip: SocketAddr,
+    /// The side of the connection of the peer; an initiator connected to this node.
+    pub side: ConnectionSide,
+    /// The node type of the peer.
+    pub node_type: NodeType,
+    /// The message version of the peer.
+    pub version: u32,
+    /// The number of peers the peer reported in its last peer response, if it sent one.
+    pub num_peers: Option<usize>,
+}
+
+impl TopologyPeer {
+    /// Returns `true` if the peer connected to this node.
+    pub fn is_inbound(&self) -> bool {
+        self.side == ConnectionSide::Initiator
+    }
+}
@@ -16,7 +16,10 @@ use crate::{
     messages::{
         BlockRequest,
         BlockResponse,
-        DataBlocks,
+        CodecUpgrade,
+        DisconnectReason,
+        EpochChallengeResponse,
+        Feature,
         Message,
         PeerResponse,
         Ping,
@@ -24,6 +27,8 @@ use crate::{
         UnconfirmedSolution,
         UnconfirmedTransaction,
     },
+    read_message_log,
+    DeadLetterReason,
     Outbound,
     Peer,
 };
@@ -35,32 +40,159 @@ use snarkvm::prelude::{
 };
 
 use anyhow::{anyhow, bail, Result};
-use std::{net::SocketAddr, time::Instant};
+use std::{net::SocketAddr, path::Path, time::Duration};
 use tokio::task::spawn_blocking;
 
 #[async_trait]
 pub trait Inbound<N: Network>: Reading + Outbound<N> {
     /// The maximum number of puzzle requests per interval.
     const MAXIMUM_PUZZLE_REQUESTS_PER_INTERVAL: usize = 5;
+    /// The number of epochs before the latest one, whose epoch challenges are served on request.
+    const MAXIMUM_EPOCH_CHALLENGE_AGE: u32 = 8;
+    /// The maximum number of strikes per interval, before the peer is disconnected.
+    const MAXIMUM_STRIKES_PER_INTERVAL: usize = 5;
+    /// The maximum number of malformed frames per interval, before the peer is disconnected.
+    const MAXIMUM_MALFORMED_FRAMES_PER_INTERVAL: usize = 5;
+    /// The duration in seconds over which the malformed frames of a peer are counted.
+    const MALFORMED_FRAME_INTERVAL_IN_SECS: i64 = 60;
+    /// The duration in seconds over which the blocks served to a peer are counted.
+    const BLOCK_REQUEST_INTERVAL_IN_SECS: i64 = 60;
+    /// The maximum number of solutions a peer may resend per interval, before it is disconnected.
+    const MAXIMUM_DUPLICATE_SOLUTIONS_PER_INTERVAL: usize = 10;
+    /// The duration in seconds over which the duplicate solutions of a peer are counted.
+    const DUPLICATE_SOLUTION_INTERVAL_IN_SECS: i64 = 60;
+    /// The duration in milliseconds to wait for a peer to acknowledge a codec upgrade.
+    const CODEC_UPGRADE_TIMEOUT_IN_MS: u64 = 3_000;
     /// The duration in seconds to sleep in between ping requests with a connected peer.
     const PING_SLEEP_IN_SECS: u64 = 9; // 9 seconds
 
+    /// Handles a frame from the peer that could not be deserialized into a message.
+    /// Disconnects from the peer if it has sent too many malformed frames recently.
+    fn malformed_frame(&self, peer_addr: SocketAddr) {
+        // Retrieve the listener IP for the peer.
+        let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) else {
+            return;
+        };
+        // Insert the malformed frame for the peer, and fetch the recent frequency.
+        let frequency =
+            self.router().cache.insert_inbound_malformed_frame(peer_ip, Self::MALFORMED_FRAME_INTERVAL_IN_SECS);
+        // Check if the number of malformed frames is within the limit.
+        if frequency > Self::MAXIMUM_MALFORMED_FRAMES_PER_INTERVAL {
+            warn!("Disconnecting from '{peer_ip}' - sent {frequency} malformed messages");
+            self.send_disconnect(peer_ip, DisconnectReason::ProtocolViolation);
+        }
+    }
+
+    /// Replays the messages from the message log at the given path through the inbound handler,
+    /// in the order they were received, and returns the outcome of each message.
+    /// Note that the replayed messages are recorded again, if recording is enabled.
+    async fn replay(&self, path: &Path) -> Result<Vec<Result<()>>> {
+        let records = read_message_log::<N>(path)?;
+        let mut outcomes = Vec::with_capacity(records.len());
+        for record in records {
+            outcomes.push(self.inbound(record.peer_addr, record.message).await);
+        }
+        Ok(outcomes)
+    }
+
+    /// Upgrades the codec of the connection with the given peer to the given version, without reconnecting.
+    /// Disconnects from the peer if it does not acknowledge the upgrade in time.
+    async fn upgrade_peer_codec(&self, peer_ip: SocketAddr, version: u8) -> Result<()> {
+        // Ensure the codec version is supported.
+        if version > Message::<N>::MAXIMUM_CODEC_VERSION {
+            bail!("Codec version {version} is not supported")
+        }
+        // Ensure the peer supports codec upgrades, as peers that predate the codec versioning do not.
+        match self.router().get_connected_peer(&peer_ip) {
+            Some(peer) if peer.features().contains(Feature::CodecUpgrade) => (),
+            Some(_) => bail!("Peer '{peer_ip}' does not support codec upgrades"),
+            None => bail!("Peer '{peer_ip}' is not connected"),
+        }
+
+        // Register the codec upgrade, and send it to the peer.
+        let acknowledgement = self.router().insert_codec_upgrade(peer_ip);
+        self.send(peer_ip, Message::CodecUpgrade(CodecUpgrade { version, is_ack: false }));
+
+        // Wait for the peer to acknowledge the codec upgrade.
+        let timeout = Duration::from_millis(Self::CODEC_UPGRADE_TIMEOUT_IN_MS);
+        if let Ok(Ok(())) = tokio::time::timeout(timeout, acknowledgement).await {
+            debug!("Upgraded the codec with '{peer_ip}' to version {version}");
+            return Ok(());
+        }
+        // Otherwise, disconnect from the peer, as the codecs of the connection may be out of sync.
+        self.router().remove_codec_upgrade(peer_ip);
+        warn!("Disconnecting from '{peer_ip}' - the codec upgrade was not acknowledged");
+        self.send_disconnect(peer_ip, DisconnectReason::ProtocolViolation);
+        bail!("Peer '{peer_ip}' did not acknowledge the codec upgrade")
+    }
+
+    /// Handles the inbound message from the peer, aborting if the node shuts down in the meantime.
+    /// Returns `None` if the handling was cancelled, in which case the peer is not at fault.
+    async fn inbound_until_shutdown(&self, peer_addr: SocketAddr, message: Message<N>) -> Option<Result<()>> {
+        let token = self.router().shutdown_token().clone();
+        // Stop handling the message as soon as the node shuts down.
+        let result = tokio::select! {
+            biased;
+            _ = token.cancelled() => None,
+            result = self.inbound(peer_addr, message) => Some(result),
+        };
+        // Ignore failures that coincide with the shutdown, as they are likely caused by it.
+        match result {
+            Some(Err(error)) if token.is_cancelled() => {
+                debug!("Ignoring an error from '{peer_addr}' during shutdown - {error}");
+                None
+            }
+            result => result,
+        }
+    }
+
     /// Handles the inbound message from the peer.
     async fn inbound(&self, peer_addr: SocketAddr, message: Message<N>) -> Result<()> {
+        // Record the message, if recording is enabled.
+        self.router().record_inbound_message(peer_addr, &message);
+
         // Retrieve the listener IP for the peer.
         let peer_ip = match self.router().resolve_to_listener(&peer_addr) {
             Some(peer_ip) => peer_ip,
             None => bail!("Unable to resolve the (ambiguous) peer address '{peer_addr}'"),
         };
 
-        // Drop the peer, if they have sent more than 1000 messages in the last 5 seconds.
-        let num_messages = self.router().cache.insert_inbound_message(peer_ip, 5);
-        if num_messages >= 1000 {
+        // Drop the peer, if they have sent too many messages in the last interval.
+        let (max_messages, interval_in_secs) = self.router().message_rate_limit();
+        let num_messages = self.router().cache.insert_inbound_message(peer_ip, interval_in_secs);
+        if num_messages >= max_messages {
+            self.router().insert_dead_letter(peer_ip, &message.name(), DeadLetterReason::RateLimited);
             bail!("Dropping '{peer_ip}' for spamming messages (num_messages = {num_messages})")
         }
 
+        // Drop the message, if its type is filtered out.
+        if self.router().is_dropped_message(&message.name()) {
+            trace!("Dropping '{}' from '{peer_ip}' (filtered out)", message.name());
+            self.router().insert_dead_letter(peer_ip, &message.name(), DeadLetterReason::Filtered);
+            return Ok(());
+        }
+
+        // Shed the message, if it is not critical and the node is saturated.
+        let max_in_flight = self.router().max_in_flight_messages();
+        let Some(_in_flight) = self.router().load_shedder().try_admit(message.is_sheddable(), max_in_flight) else {
+            trace!("Shedding '{}' from '{peer_ip}' (node is saturated)", message.name());
+            self.router().insert_dead_letter(peer_ip, &message.name(), DeadLetterReason::Shed);
+            return Ok(());
+        };
+
         trace!("Received '{}' from '{peer_ip}'", message.name());
 
+        // Handle the message, and record it as a dead letter if it was rejected.
+        let message_kind = message.name();
+        let result = self.handle_inbound(peer_ip, message).await;
+        if let Err(error) = &result {
+            self.router().insert_dead_letter(peer_ip, &message_kind, DeadLetterReason::Rejected(error.to_string()));
+        }
+        result
+    }
+
+    /// Handles the inbound message from the peer, once it has been admitted.
+    async fn handle_inbound(&self, peer_ip: SocketAddr, message: Message<N>) -> Result<()> {
         // This match statement handles the inbound message by deserializing the message,
         // checking the message is valid, and then calling the appropriate (trait) handler.
         match message {
@@ -71,13 +203,26 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 if start_height >= end_height {
                     bail!("Block request from '{peer_ip}' has an invalid range ({start_height}..{end_height})")
                 }
-                // Ensure that the block request is within the allowed bounds.
-                if end_height - start_height > DataBlocks::<N>::MAXIMUM_NUMBER_OF_BLOCKS as u32 {
-                    bail!("Block request from '{peer_ip}' has an excessive range ({start_height}..{end_height})")
+                // Clamp the block request to the allowed number of blocks. The requester is not told,
+                // so the clamped-off tail stays in its block request cache, until it requests it again.
+                let request = message.clamp(self.router().max_blocks_per_request());
+                if request != message {
+                    debug!("Clamping the block request from '{peer_ip}' ({message} to {request})");
+                }
+                // Disconnect from the peer, if too many blocks were served to it recently.
+                let num_blocks = self.router().cache.insert_inbound_served_blocks(
+                    peer_ip,
+                    request.num_blocks(),
+                    Self::BLOCK_REQUEST_INTERVAL_IN_SECS,
+                );
+                if num_blocks > self.router().max_blocks_per_interval() {
+                    warn!("Disconnecting from '{peer_ip}' - requested {num_blocks} blocks");
+                    self.send_disconnect(peer_ip, DisconnectReason::RateLimitExceeded);
+                    return Ok(());
                 }
 
                 let node = self.clone();
-                match spawn_blocking(move || node.block_request(peer_ip, message)).await? {
+                match spawn_blocking(move || node.block_request(peer_ip, request)).await? {
                     true => Ok(()),
                     false => bail!("Peer '{peer_ip}' sent an invalid block request"),
                 }
@@ -101,24 +246,34 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                     false => bail!("Peer '{peer_ip}' sent an invalid block response"),
                 }
             }
-            Message::ChallengeRequest(..) | Message::ChallengeResponse(..) => {
+            Message::ChallengeRequest(..)
+            | Message::ChallengeResponse(..)
+            | Message::AdmissionChallenge(..)
+            | Message::AdmissionSolution(..) => {
                 // Disconnect as the peer is not following the protocol.
                 bail!("Peer '{peer_ip}' is not following the protocol")
             }
             Message::Disconnect(message) => {
+                // Record the reason given by the peer.
+                self.router().set_disconnect_reason(peer_ip, message.reason);
                 bail!("{:?}", message.reason)
             }
             Message::PeerRequest(..) => match self.peer_request(peer_ip) {
                 true => Ok(()),
                 false => bail!("Peer '{peer_ip}' sent an invalid peer request"),
             },
-            Message::PeerResponse(message) => match self.peer_response(peer_ip, &message.peers) {
-                true => Ok(()),
-                false => bail!("Peer '{peer_ip}' sent an invalid peer response"),
-            },
+            Message::PeerResponse(message) => {
+                // Record the number of peers the peer reported, for the topology snapshots.
+                self.router().update_reported_peers(peer_ip, message.peers.len());
+                // Process the peer response.
+                match self.peer_response(peer_ip, &message.peers) {
+                    true => Ok(()),
+                    false => bail!("Peer '{peer_ip}' sent an invalid peer response"),
+                }
+            }
             Message::Ping(message) => {
                 // Ensure the message protocol version is not outdated.
-                if message.version < Message::<N>::VERSION {
+                if message.version < Message::<N>::MINIMUM_VERSION {
                     bail!("Dropping '{peer_ip}' on message version {} (outdated)", message.version);
                 }
 
@@ -140,11 +295,15 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                         // Update the node type of the peer.
                         peer.set_node_type(message.node_type);
                         // Update the last seen timestamp of the peer.
-                        peer.set_last_seen(Instant::now());
+                        peer.set_last_seen(self.router().clock().now());
                     })
                 {
                     bail!("[Ping] {error}");
                 }
+                // Update the latest block height of the peer.
+                if let Some(block_locators) = &message.block_locators {
+                    self.router().update_peer_height(peer_ip, block_locators.latest_locator_height());
+                }
 
                 // TODO (howardwu): For this case, check that the peer is not within NUM_RECENTS, and disconnect.
                 //  As the validator, you should disconnect any node type that is not caught up.
@@ -161,10 +320,15 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                     false => bail!("Peer '{peer_ip}' sent an invalid ping"),
                 }
             }
-            Message::Pong(message) => match self.pong(peer_ip, message) {
-                true => Ok(()),
-                false => bail!("Peer '{peer_ip}' sent an invalid pong"),
-            },
+            Message::Pong(message) => {
+                // Update the liveness of the peer.
+                self.router().insert_pong(peer_ip);
+                // Process the pong message.
+                match self.pong(peer_ip, message) {
+                    true => Ok(()),
+                    false => bail!("Peer '{peer_ip}' sent an invalid pong"),
+                }
+            }
             Message::PuzzleRequest(..) => {
                 // Insert the puzzle request for the peer, and fetch the recent frequency.
                 let frequency = self.router().cache.insert_inbound_puzzle_request(peer_ip);
@@ -172,8 +336,36 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 if frequency > Self::MAXIMUM_PUZZLE_REQUESTS_PER_INTERVAL {
                     bail!("Peer '{peer_ip}' is not following the protocol (excessive puzzle requests)")
                 }
+                // Decline the puzzle request while syncing, as the latest puzzle state may be stale.
+                if self.router().is_syncing() {
+                    trace!("Declining 'PuzzleRequest' from '{peer_ip}' (syncing)");
+                    self.router().decline_puzzle_request();
+                    return Ok(());
+                }
+                // Mark the puzzle request as pending, until the response in flight is delivered.
+                let is_new = self.router().insert_puzzle_request_in_flight(peer_ip);
+                // Disconnect from the peer, if too many of its puzzle requests are pending, as it is likely
+                // not consuming the responses.
+                let num_pending = self.router().num_pending_puzzle_requests(&peer_ip);
+                if num_pending > self.router().max_pending_puzzle_requests() {
+                    warn!("Disconnecting from '{peer_ip}' - {num_pending} puzzle requests are pending");
+                    self.send_disconnect(peer_ip, DisconnectReason::RateLimitExceeded);
+                    return Ok(());
+                }
+                // Coalesce the puzzle request with the one in flight, as the peer will receive its response.
+                if !is_new {
+                    trace!("Coalescing 'PuzzleRequest' from '{peer_ip}' (a response is already in flight)");
+                    return Ok(());
+                }
+                // Wait briefly for a serialization permit, as serializing the puzzle response is expensive.
+                let Some(_permit) = self.router().acquire_puzzle_serialization().await else {
+                    trace!("Declining 'PuzzleRequest' from '{peer_ip}' (too many concurrent serializations)");
+                    self.router().remove_puzzle_request_in_flight(peer_ip);
+                    self.router().decline_puzzle_request();
+                    return Ok(());
+                };
                 // Process the puzzle request.
-                match self.puzzle_request(peer_ip) {
+                match self.puzzle_request(peer_ip).await {
                     true => Ok(()),
                     false => bail!("Peer '{peer_ip}' sent an invalid puzzle request"),
                 }
@@ -186,13 +378,18 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 // Decrement the number of puzzle requests.
                 self.router().cache.decrement_outbound_puzzle_requests(peer_ip);
 
+                // Ignore the puzzle response, if the peer does not have an epoch challenge yet.
+                let Some(epoch_challenge) = message.epoch_challenge else {
+                    debug!("Peer '{peer_ip}' does not have an epoch challenge yet");
+                    return Ok(());
+                };
                 // Perform the deferred non-blocking deserialization of the block header.
                 let header = match message.block_header.deserialize().await {
                     Ok(header) => header,
                     Err(error) => bail!("[PuzzleResponse] {error}"),
                 };
                 // Process the puzzle response.
-                match self.puzzle_response(peer_ip, message.epoch_challenge, header) {
+                match self.puzzle_response(peer_ip, epoch_challenge, header) {
                     true => Ok(()),
                     false => bail!("Peer '{peer_ip}' sent an invalid puzzle response"),
                 }
@@ -202,9 +399,27 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 let serialized = message.clone();
                 // Update the timestamp for the unconfirmed solution.
                 let seen_before = self.router().cache.insert_inbound_solution(peer_ip, message.solution_id).is_some();
-                // Determine whether to propagate the solution.
+                // If the peer resent the solution, count it against the peer and skip it.
                 if seen_before {
-                    bail!("Skipping 'UnconfirmedSolution' from '{peer_ip}'")
+                    let num_duplicates = self
+                        .router()
+                        .cache
+                        .insert_inbound_duplicate_solution(peer_ip, Self::DUPLICATE_SOLUTION_INTERVAL_IN_SECS);
+                    // Disconnect from the peer, if it has resent too many solutions recently.
+                    if num_duplicates > Self::MAXIMUM_DUPLICATE_SOLUTIONS_PER_INTERVAL {
+                        warn!("Disconnecting from '{peer_ip}' - resent {num_duplicates} solutions");
+                        self.send_disconnect(peer_ip, DisconnectReason::RateLimitExceeded);
+                        return Ok(());
+                    }
+                    trace!("Skipping 'UnconfirmedSolution' from '{peer_ip}' (resent by the peer)");
+                    return Ok(());
+                }
+                // Drop the solution early, if it was already seen from another peer.
+                // The filter may report false positives, so the drop is recorded as a dead letter.
+                if self.router().cache.contains_global_solution(&message.solution_id) {
+                    trace!("Skipping 'UnconfirmedSolution' from '{peer_ip}' (seen from another peer)");
+                    self.router().insert_dead_letter(peer_ip, "UnconfirmedSolution", DeadLetterReason::AlreadySeen);
+                    return Ok(());
                 }
                 // Perform the deferred non-blocking deserialization of the solution.
                 let solution = match message.solution.deserialize().await {
@@ -215,7 +430,8 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 if message.solution_id != solution.commitment() {
                     bail!("Peer '{peer_ip}' is not following the 'UnconfirmedSolution' protocol")
                 }
-                // Handle the unconfirmed solution.
+                // Handle the unconfirmed solution. The handler marks the solution as seen across all peers,
+                // once it is accepted, as the commitment does not bind the proof.
                 match self.unconfirmed_solution(peer_ip, serialized, solution).await {
                     true => Ok(()),
                     false => bail!("Peer '{peer_ip}' sent an invalid unconfirmed solution"),
@@ -231,6 +447,13 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 if seen_before {
                     bail!("Skipping 'UnconfirmedTransaction' from '{peer_ip}'")
                 }
+                // Drop the transaction early, if it was already seen from another peer.
+                // The filter may report false positives, so the drop is recorded as a dead letter.
+                if self.router().cache.contains_global_transaction(&message.transaction_id) {
+                    trace!("Skipping 'UnconfirmedTransaction' from '{peer_ip}' (seen from another peer)");
+                    self.router().insert_dead_letter(peer_ip, "UnconfirmedTransaction", DeadLetterReason::AlreadySeen);
+                    return Ok(());
+                }
                 // Perform the deferred non-blocking deserialization of the transaction.
                 let transaction = match message.transaction.deserialize().await {
                     Ok(transaction) => transaction,
@@ -240,12 +463,56 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 if message.transaction_id != transaction.id() {
                     bail!("Peer '{peer_ip}' is not following the 'UnconfirmedTransaction' protocol")
                 }
-                // Handle the unconfirmed transaction.
+                // Handle the unconfirmed transaction. The handler marks the transaction as seen across all peers,
+                // once it is accepted, so that a temporary rejection does not drop the later copies.
                 match self.unconfirmed_transaction(peer_ip, serialized, transaction).await {
                     true => Ok(()),
                     false => bail!("Peer '{peer_ip}' sent an invalid unconfirmed transaction"),
                 }
             }
+            Message::CodecUpgrade(message) => {
+                let CodecUpgrade { version, is_ack } = message;
+
+                // Ensure the codec version is supported.
+                if version > Message::<N>::MAXIMUM_CODEC_VERSION {
+                    bail!("Peer '{peer_ip}' requested an unsupported codec version ({version})")
+                }
+                // Record the codec version, as the codecs of this connection have switched.
+                self.router().set_codec_version(peer_ip, version);
+                match is_ack {
+                    // Complete the codec upgrade requested by this node.
+                    true => {
+                        if !self.router().acknowledge_codec_upgrade(peer_ip) {
+                            debug!("Peer '{peer_ip}' acknowledged a codec upgrade that is no longer pending");
+                        }
+                    }
+                    // Acknowledge the codec upgrade requested by the peer.
+                    false => {
+                        self.send(peer_ip, Message::CodecUpgrade(CodecUpgrade { version, is_ack: true }));
+                    }
+                }
+                Ok(())
+            }
+            Message::EpochChallengeRequest(message) => {
+                // Count the request against the puzzle requests of the peer, as it is served from the same state.
+                let frequency = self.router().cache.insert_inbound_puzzle_request(peer_ip);
+                // Check if the number of puzzle requests is within the limit.
+                if frequency > Self::MAXIMUM_PUZZLE_REQUESTS_PER_INTERVAL {
+                    bail!("Peer '{peer_ip}' is not following the protocol (excessive epoch challenge requests)")
+                }
+                // Process the epoch challenge request.
+                match self.epoch_challenge_request(peer_ip, message.epoch_number) {
+                    true => Ok(()),
+                    false => bail!("Peer '{peer_ip}' sent an invalid epoch challenge request"),
+                }
+            }
+            Message::EpochChallengeResponse(message) => {
+                // Process the epoch challenge response.
+                match self.epoch_challenge_response(peer_ip, message.epoch_number, message.epoch_challenge) {
+                    true => Ok(()),
+                    false => bail!("Peer '{peer_ip}' sent an invalid epoch challenge response"),
+                }
+            }
         }
     }
 
@@ -257,10 +524,8 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
 
     /// Handles a `PeerRequest` message.
     fn peer_request(&self, peer_ip: SocketAddr) -> bool {
-        // Retrieve the connected peers.
-        let peers = self.router().connected_peers();
-        // Filter out bogon addresses.
-        let peers = peers.into_iter().filter(|addr| !is_bogon_address(addr.ip())).collect();
+        // Retrieve the connected peers, without bogon addresses, in a deterministic order.
+        let peers = self.router().shareable_peers();
         // Send a `PeerResponse` message to the peer.
         self.send(peer_ip, Message::PeerResponse(PeerResponse { peers }));
         true
@@ -282,12 +547,33 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
     fn pong(&self, peer_ip: SocketAddr, _message: Pong) -> bool;
 
     /// Handles a `PuzzleRequest` message.
-    fn puzzle_request(&self, peer_ip: SocketAddr) -> bool;
+    async fn puzzle_request(&self, peer_ip: SocketAddr) -> bool;
 
     /// Handles a `PuzzleResponse` message.
     fn puzzle_response(&self, peer_ip: SocketAddr, _challenge: EpochChallenge<N>, _header: Header<N>) -> bool;
 
+    /// Handles an `EpochChallengeRequest` message.
+    /// By default, the request is declined with a response without an epoch challenge.
+    fn epoch_challenge_request(&self, peer_ip: SocketAddr, epoch_number: u32) -> bool {
+        trace!("Declining 'EpochChallengeRequest' from '{peer_ip}' for epoch {epoch_number}");
+        let response = EpochChallengeResponse { epoch_number, epoch_challenge: None };
+        self.send(peer_ip, Message::EpochChallengeResponse(response));
+        true
+    }
+
+    /// Handles an `EpochChallengeResponse` message.
+    fn epoch_challenge_response(
+        &self,
+        peer_ip: SocketAddr,
+        epoch_number: u32,
+        _epoch_challenge: Option<EpochChallenge<N>>,
+    ) -> bool {
+        trace!("Ignoring 'EpochChallengeResponse' from '{peer_ip}' for epoch {epoch_number}");
+        true
+    }
+
     /// Handles an `UnconfirmedSolution` message.
+    /// Once the solution is accepted, the handler must call `Router::insert_accepted_solution`.
     async fn unconfirmed_solution(
         &self,
         peer_ip: SocketAddr,
@@ -296,6 +582,7 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
     ) -> bool;
 
     /// Handles an `UnconfirmedTransaction` message.
+    /// Once the transaction is accepted, the handler must call `Router::insert_accepted_transaction`.
     async fn unconfirmed_transaction(
         &self,
         peer_ip: SocketAddr,
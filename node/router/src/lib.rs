@@ -24,6 +24,9 @@ pub use snarkos_node_router_messages as messages;
 mod helpers;
 pub use helpers::*;
 
+mod connection_manager;
+pub use connection_manager::*;
+
 mod handshake;
 pub use handshake::*;
 
@@ -39,16 +42,44 @@ pub use outbound::*;
 mod routing;
 pub use routing::*;
 
-use crate::messages::NodeType;
+use crate::messages::{CapabilitySet, DisconnectReason, Feature, Message, MessageTraffic, NodeType};
 use snarkos_account::Account;
-use snarkos_node_tcp::{Config, Tcp};
-use snarkvm::prelude::{Address, Network, PrivateKey, ViewKey};
+use snarkos_node_tcp::{is_bogon_address, Config, ConnectionSide, Tcp};
+use snarkvm::prelude::{coinbase::PuzzleCommitment, Address, Network, PrivateKey, ViewKey};
 
 use anyhow::{bail, Result};
 use indexmap::{IndexMap, IndexSet};
 use parking_lot::{Mutex, RwLock};
-use std::{collections::HashSet, future::Future, net::SocketAddr, ops::Deref, str::FromStr, sync::Arc, time::Instant};
-use tokio::task::JoinHandle;
+use rand::{
+    prelude::IteratorRandom,
+    rngs::{OsRng, StdRng},
+    SeedableRng,
+};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
+    net::SocketAddr,
+    ops::Deref,
+    path::Path,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use time::OffsetDateTime;
+use tokio::{
+    sync::{mpsc, oneshot, Notify, OwnedSemaphorePermit},
+    task::JoinHandle,
+};
+use tokio_util::sync::CancellationToken;
+
+/// A callback invoked with the peer IP and the connection side of the peer, after a successful handshake.
+pub type HandshakeHook = Box<dyn FnMut(SocketAddr, ConnectionSide) + Send>;
+
+/// A callback invoked with each message that is propagated under a propagation permit.
+pub type PropagationHook<N> = Arc<dyn Fn(&Message<N>) + Send + Sync>;
 
 #[derive(Clone)]
 pub struct Router<N: Network>(Arc<InnerRouter<N>>);
@@ -74,6 +105,8 @@ pub struct InnerRouter<N: Network> {
     resolver: Resolver,
     /// The set of trusted peers.
     trusted_peers: IndexSet<SocketAddr>,
+    /// The set of pinned peers, which are never evicted and may connect beyond the maximum number of peers.
+    pinned_peers: RwLock<IndexSet<SocketAddr>>,
     /// The map of connected peer IPs to their peer handlers.
     connected_peers: RwLock<IndexMap<SocketAddr, Peer<N>>>,
     /// The set of handshaking peers. While `Tcp` already recognizes the connecting IP addresses
@@ -85,6 +118,73 @@ pub struct InnerRouter<N: Network> {
     candidate_peers: RwLock<IndexSet<SocketAddr>>,
     /// The set of restricted peer IPs.
     restricted_peers: RwLock<IndexMap<SocketAddr, Instant>>,
+    /// The peers on probation, after a protocol violation.
+    probation: ProbationList,
+    /// The recent connection attempts of each peer, and the peers soft-banned for reconnecting too often.
+    churn_limiter: ChurnLimiter,
+    /// The limiter on the number of concurrent handshakes.
+    handshake_limiter: ConcurrencyLimiter,
+    /// The limiter on the number of concurrent block serializations for puzzle responses.
+    puzzle_serialization_limiter: ConcurrencyLimiter,
+    /// The limiter on the number of concurrent propagations of solutions and transactions.
+    propagation_limiter: ConcurrencyLimiter,
+    /// The callbacks invoked with each message propagated under a propagation permit.
+    propagation_hooks: RwLock<Vec<PropagationHook<N>>>,
+    /// The callbacks invoked after a successful handshake.
+    handshake_hooks: Mutex<Vec<HandshakeHook>>,
+    /// The nonces of the challenge requests sent by this node, for handshakes in progress.
+    /// A challenge request carrying one of these nonces was sent by this node to itself.
+    handshake_nonces: Mutex<HashSet<u64>>,
+    /// The load shedder for inbound messages.
+    load_shedder: LoadShedder,
+    /// The number of puzzle requests that were coalesced with one already in flight.
+    num_coalesced_puzzle_requests: AtomicU64,
+    /// The message types this node advertises to its peers during the handshake.
+    capabilities: RwLock<CapabilitySet>,
+    /// The flag indicating whether the node is catching up with the ledger, during which puzzle requests are declined.
+    is_syncing: AtomicBool,
+    /// The number of puzzle requests that were declined, as the node was syncing or too busy.
+    num_declined_puzzle_requests: AtomicU64,
+    /// The number of puzzle requests that were declined, as reading the puzzle state from the ledger timed out.
+    num_slow_ledger_reads: AtomicU64,
+    /// The token that is cancelled when the node shuts down, aborting the messages still being processed.
+    shutdown_token: CancellationToken,
+    /// The number of bytes sent and received, per message type.
+    traffic: Arc<MessageTraffic>,
+    /// The log of the most recent disconnects.
+    disconnect_log: DisconnectLog,
+    /// The histogram of the durations of successful handshakes.
+    handshake_durations: DurationHistogram,
+    /// The times of the inbound connections in the last second.
+    recent_inbound_connections: Mutex<VecDeque<Instant>>,
+    /// The number of admission challenges sent to connecting peers.
+    num_admission_challenges: AtomicU64,
+    /// The live configuration.
+    config: RwLock<RouterConfig>,
+    /// The classifier that assigns peers to groups.
+    peer_classifier: RwLock<Arc<dyn PeerClassifier>>,
+    /// The strategy for choosing the connected peer to evict, when a new peer connects at capacity.
+    eviction_strategy: RwLock<Arc<dyn EvictionStrategy<N>>>,
+    /// The governor that reports whether the host is too loaded to accept new inbound connections.
+    load_governor: RwLock<Arc<dyn LoadGovernor>>,
+    /// The flag indicating whether new inbound connections are refused, e.g. during a maintenance window.
+    is_accepting_paused: AtomicBool,
+    /// The map of additional listening addresses to their admission policies.
+    listener_policies: RwLock<HashMap<SocketAddr, ListenerPolicy>>,
+    /// The sink for dropped and rejected inbound messages, if one is set.
+    dead_letter_sink: RwLock<Option<mpsc::Sender<DeadLetter>>>,
+    /// The sink for changes in the state of connected peers, if one is set.
+    peer_event_sink: RwLock<Option<mpsc::Sender<PeerEvent>>>,
+    /// The recorder of inbound messages, if recording is enabled.
+    recorder: Mutex<Option<MessageRecorder>>,
+    /// The notification of a connected peer being removed.
+    disconnect_notify: Notify,
+    /// The codec upgrades awaiting an acknowledgement from the peer.
+    pending_codec_upgrades: Mutex<HashMap<SocketAddr, oneshot::Sender<()>>>,
+    /// The clock used to measure timeouts and round-trip times.
+    clock: RwLock<MonotonicClock>,
+    /// The seeded RNG used to select the peers to propagate to, if one is set; otherwise, `OsRng` is used.
+    propagation_rng: Mutex<Option<StdRng>>,
     /// The spawned handles.
     handles: Mutex<Vec<JoinHandle<()>>>,
     /// The boolean flag for the development mode.
@@ -99,6 +199,26 @@ impl<N: Network> Router<N> {
     /// The duration in seconds after which a connected peer is considered inactive or
     /// disconnected if no message has been received in the meantime.
     const RADIO_SILENCE_IN_SECS: u64 = 150; // 2.5 minutes
+    /// The duration in seconds over which the strikes of a peer are counted.
+    const STRIKE_INTERVAL_IN_SECS: i64 = 300; // 5 minutes
+    /// The maximum number of concurrent handshakes.
+    const MAXIMUM_CONCURRENT_HANDSHAKES: usize = 32;
+    /// The duration in milliseconds to wait for a handshake permit, before the connection is rejected.
+    const HANDSHAKE_QUEUE_TIMEOUT_IN_MS: u64 = 1_000;
+    /// The duration in milliseconds to wait for a puzzle serialization permit, before the puzzle request is declined.
+    const PUZZLE_SERIALIZATION_QUEUE_TIMEOUT_IN_MS: u64 = 100;
+    /// The maximum number of concurrent propagations of solutions and transactions.
+    const MAXIMUM_CONCURRENT_PROPAGATIONS: usize = 16;
+    /// The duration in milliseconds to wait for a propagation permit, before the message is not propagated.
+    const PROPAGATION_QUEUE_TIMEOUT_IN_MS: u64 = 5_000;
+    /// The maximum number of consecutive liveness pings a peer may miss, before it is disconnected.
+    const MAXIMUM_MISSED_PINGS: u32 = 3;
+    /// The maximum number of records in the disconnect log.
+    const MAXIMUM_DISCONNECT_RECORDS: usize = 256;
+    /// The maximum duration in milliseconds to wait for a `Disconnect` message to be written, before disconnecting.
+    const DISCONNECT_FLUSH_TIMEOUT_IN_MS: u64 = 500;
+    /// The maximum number of pinned peers, which are connected in addition to the maximum number of peers.
+    const MAXIMUM_PINNED_PEERS: u16 = 8;
 }
 
 impl<N: Network> Router<N> {
@@ -111,8 +231,8 @@ impl<N: Network> Router<N> {
         max_peers: u16,
         is_dev: bool,
     ) -> Result<Self> {
-        // Initialize the TCP stack.
-        let tcp = Tcp::new(Config::new(node_ip, max_peers));
+        // Initialize the TCP stack, reserving connections for the pinned peers.
+        let tcp = Tcp::new(Config::new(node_ip, max_peers.saturating_add(Self::MAXIMUM_PINNED_PEERS)));
         // Initialize the router.
         Ok(Self(Arc::new(InnerRouter {
             tcp,
@@ -121,10 +241,53 @@ impl<N: Network> Router<N> {
             cache: Default::default(),
             resolver: Default::default(),
             trusted_peers: trusted_peers.iter().copied().collect(),
+            pinned_peers: Default::default(),
             connected_peers: Default::default(),
             connecting_peers: Default::default(),
             candidate_peers: Default::default(),
             restricted_peers: Default::default(),
+            probation: Default::default(),
+            churn_limiter: Default::default(),
+            handshake_limiter: ConcurrencyLimiter::new(
+                Self::MAXIMUM_CONCURRENT_HANDSHAKES,
+                Duration::from_millis(Self::HANDSHAKE_QUEUE_TIMEOUT_IN_MS),
+            ),
+            puzzle_serialization_limiter: ConcurrencyLimiter::new(
+                std::thread::available_parallelism().map_or(1, |num_cpus| num_cpus.get()),
+                Duration::from_millis(Self::PUZZLE_SERIALIZATION_QUEUE_TIMEOUT_IN_MS),
+            ),
+            propagation_limiter: ConcurrencyLimiter::new(
+                Self::MAXIMUM_CONCURRENT_PROPAGATIONS,
+                Duration::from_millis(Self::PROPAGATION_QUEUE_TIMEOUT_IN_MS),
+            ),
+            propagation_hooks: Default::default(),
+            handshake_hooks: Default::default(),
+            handshake_nonces: Default::default(),
+            load_shedder: Default::default(),
+            num_coalesced_puzzle_requests: Default::default(),
+            capabilities: RwLock::new(Message::<N>::capabilities().with(CapabilitySet::COMPRESSION)),
+            is_syncing: Default::default(),
+            num_declined_puzzle_requests: Default::default(),
+            num_slow_ledger_reads: Default::default(),
+            shutdown_token: Default::default(),
+            traffic: Default::default(),
+            disconnect_log: DisconnectLog::new(Self::MAXIMUM_DISCONNECT_RECORDS),
+            handshake_durations: Default::default(),
+            recent_inbound_connections: Default::default(),
+            num_admission_challenges: Default::default(),
+            config: RwLock::new(RouterConfig::new(max_peers as usize)),
+            peer_classifier: RwLock::new(Arc::new(SingleGroupClassifier)),
+            eviction_strategy: RwLock::new(Arc::new(RejectNewcomer)),
+            load_governor: RwLock::new(Arc::new(UnlimitedGovernor)),
+            is_accepting_paused: Default::default(),
+            listener_policies: Default::default(),
+            dead_letter_sink: Default::default(),
+            peer_event_sink: Default::default(),
+            recorder: Default::default(),
+            disconnect_notify: Default::default(),
+            pending_codec_upgrades: Default::default(),
+            clock: Default::default(),
+            propagation_rng: Default::default(),
             handles: Default::default(),
             is_dev,
         })))
@@ -134,52 +297,77 @@ impl<N: Network> Router<N> {
 impl<N: Network> Router<N> {
     /// Attempts to connect to the given peer IP.
     pub fn connect(&self, peer_ip: SocketAddr) -> Option<JoinHandle<bool>> {
-        // Return early if the attempt is against the protocol rules.
-        if let Err(forbidden_message) = self.check_connection_attempt(peer_ip) {
-            warn!("{forbidden_message}");
-            return None;
+        match self.try_connect(peer_ip) {
+            Ok(handle) => Some(handle),
+            Err(error) => {
+                warn!("{error}");
+                None
+            }
         }
+    }
+
+    /// Attempts to connect to the given peer IP, or returns the reason the attempt was not made.
+    /// A concurrent attempt to a peer that is already being dialed returns `ConnectError::AlreadyConnecting`.
+    pub fn try_connect(&self, peer_ip: SocketAddr) -> Result<JoinHandle<bool>, ConnectError> {
+        let handle = self.dial(peer_ip)?;
+        Ok(tokio::spawn(async move { matches!(handle.await, Ok(Ok(()))) }))
+    }
+
+    /// Attempts to connect to the given peer IP, returning an error if the attempt is against the protocol rules.
+    /// The returned handle resolves to the handshake error if the connection fails.
+    pub fn dial(&self, peer_ip: SocketAddr) -> Result<JoinHandle<Result<(), HandshakeError>>, ConnectError> {
+        // Return early if the attempt is against the protocol rules.
+        self.check_connection_attempt(peer_ip)?;
 
         let router = self.clone();
-        Some(tokio::spawn(async move {
+        Ok(tokio::spawn(async move {
             // Attempt to connect to the candidate peer.
             match router.tcp.connect(peer_ip).await {
-                // Remove the peer from the candidate peers.
+                // Remove the peer from the candidate peers, and from the connecting peers.
                 Ok(()) => {
+                    router.connecting_peers.lock().remove(&peer_ip);
                     router.remove_candidate_peer(peer_ip);
-                    true
+                    Ok(())
                 }
                 // If the connection was not allowed, log the error.
                 Err(error) => {
                     router.connecting_peers.lock().remove(&peer_ip);
                     warn!("Unable to connect to '{peer_ip}' - {error}");
-                    false
+                    Err(HandshakeError::from_io(peer_ip, error))
                 }
             }
         }))
     }
 
     /// Ensure we are allowed to connect to the given peer.
-    fn check_connection_attempt(&self, peer_ip: SocketAddr) -> Result<()> {
+    fn check_connection_attempt(&self, peer_ip: SocketAddr) -> Result<(), ConnectError> {
         // Ensure the peer IP is not this node.
         if self.is_local_ip(&peer_ip) {
-            bail!("Dropping connection attempt to '{peer_ip}' (attempted to self-connect)")
+            return Err(ConnectError::SelfConnect(peer_ip));
         }
-        // Ensure the node does not surpass the maximum number of peer connections.
-        if self.number_of_connected_peers() >= self.max_connected_peers() {
-            bail!("Dropping connection attempt to '{peer_ip}' (maximum peers reached)")
+        // Ensure the node does not surpass the maximum number of peer connections, unless the peer is pinned.
+        if !self.is_pinned(&peer_ip) && self.number_of_connected_peers() >= self.max_connected_peers() {
+            return Err(ConnectError::MaximumPeersReached(peer_ip));
         }
         // Ensure the node is not already connected to this peer.
         if self.is_connected(&peer_ip) {
-            bail!("Dropping connection attempt to '{peer_ip}' (already connected)")
+            return Err(ConnectError::AlreadyConnected(peer_ip));
         }
         // Ensure the peer is not restricted.
         if self.is_restricted(&peer_ip) {
-            bail!("Dropping connection attempt to '{peer_ip}' (restricted)")
+            return Err(ConnectError::Restricted(peer_ip));
+        }
+        // Ensure the peer is not cooling down after a protocol violation.
+        if self.is_cooling_down(&peer_ip) {
+            return Err(ConnectError::OnProbation(peer_ip));
+        }
+        // Ensure the peer is not soft-banned for reconnecting too often.
+        if self.is_soft_banned(&peer_ip) {
+            return Err(ConnectError::SoftBanned(peer_ip));
         }
         // Ensure the node is not already connecting to this peer.
         if !self.connecting_peers.lock().insert(peer_ip) {
-            bail!("Dropping connection attempt to '{peer_ip}' (already shaking hands as the initiator)")
+            return Err(ConnectError::AlreadyConnecting(peer_ip));
         }
         Ok(())
     }
@@ -204,6 +392,31 @@ impl<N: Network> Router<N> {
         })
     }
 
+    /// Starts listening for connections on the given address, in addition to the listening address.
+    /// The peers connecting through it are admitted under the given policy. Returns the bound address.
+    pub async fn add_listener(&self, addr: SocketAddr, policy: ListenerPolicy) -> std::io::Result<SocketAddr> {
+        let listening_addr = self.tcp.enable_additional_listener(addr).await?;
+        self.listener_policies.write().insert(listening_addr, policy);
+        Ok(listening_addr)
+    }
+
+    /// Returns the admission policy of the listener that accepted a connection on the given local address.
+    pub fn listener_policy(&self, local_addr: SocketAddr) -> ListenerPolicy {
+        self.listener_policies
+            .read()
+            .iter()
+            .find(|(addr, _)| {
+                addr.port() == local_addr.port() && (addr.ip() == local_addr.ip() || addr.ip().is_unspecified())
+            })
+            .map(|(_, policy)| *policy)
+            .unwrap_or_default()
+    }
+
+    /// Returns the listening addresses of this node, starting with the main listening address.
+    pub fn listening_addrs(&self) -> Vec<SocketAddr> {
+        self.tcp.listening_addrs()
+    }
+
     /// Returns the IP address of this node.
     pub fn local_ip(&self) -> SocketAddr {
         self.tcp.listening_addr().expect("The TCP listener is not enabled")
@@ -240,6 +453,607 @@ impl<N: Network> Router<N> {
         self.is_dev
     }
 
+    /// Returns the maximum number of concurrent handshakes.
+    pub fn max_concurrent_handshakes(&self) -> usize {
+        self.handshake_limiter.limit()
+    }
+
+    /// Sets the maximum number of concurrent handshakes.
+    pub fn set_max_concurrent_handshakes(&self, limit: usize) {
+        self.handshake_limiter.set_limit(limit)
+    }
+
+    /// Returns the number of handshakes in progress.
+    pub fn number_of_handshakes_in_flight(&self) -> usize {
+        self.handshake_limiter.num_in_flight()
+    }
+
+    /// Returns the maximum number of concurrent block serializations for puzzle responses.
+    pub fn max_puzzle_serializations(&self) -> usize {
+        self.puzzle_serialization_limiter.limit()
+    }
+
+    /// Sets the maximum number of concurrent block serializations for puzzle responses.
+    pub fn set_max_puzzle_serializations(&self, limit: usize) {
+        self.puzzle_serialization_limiter.set_limit(limit)
+    }
+
+    /// Returns the number of block serializations for puzzle responses in progress.
+    pub fn number_of_puzzle_serializations_in_flight(&self) -> usize {
+        self.puzzle_serialization_limiter.num_in_flight()
+    }
+
+    /// Waits briefly for a permit to serialize a puzzle response, returning `None` if none became available.
+    pub async fn acquire_puzzle_serialization(&self) -> Option<OwnedSemaphorePermit> {
+        self.puzzle_serialization_limiter.acquire().await
+    }
+
+    /// Returns the maximum number of concurrent propagations.
+    pub fn max_concurrent_propagations(&self) -> usize {
+        self.propagation_limiter.limit()
+    }
+
+    /// Sets the maximum number of concurrent propagations.
+    pub fn set_max_concurrent_propagations(&self, limit: usize) {
+        self.propagation_limiter.set_limit(limit)
+    }
+
+    /// Returns the number of propagations in progress.
+    pub fn number_of_propagations_in_flight(&self) -> usize {
+        self.propagation_limiter.num_in_flight()
+    }
+
+    /// Waits for a permit to propagate the given message, and invokes the propagation callbacks with it.
+    /// The permit is to be held for the duration of the propagation. Returns `None` if none became available,
+    /// in which case the message should not be propagated.
+    pub async fn acquire_propagation(&self, message: &Message<N>) -> Option<OwnedSemaphorePermit> {
+        let permit = self.propagation_limiter.acquire().await?;
+        // Invoke the propagation callbacks, without holding the lock, so that they may run concurrently.
+        let hooks = self.propagation_hooks.read().clone();
+        for hook in hooks {
+            hook(message);
+        }
+        Some(permit)
+    }
+
+    /// Registers a callback to be invoked with each message that is propagated under a propagation permit,
+    /// while the permit is held.
+    pub fn on_propagate<F: Fn(&Message<N>) + Send + Sync + 'static>(&self, hook: F) {
+        self.propagation_hooks.write().push(Arc::new(hook));
+    }
+
+    /// Registers a callback to be invoked with the peer IP and the connection side of the peer,
+    /// after every successful handshake. Note: the callback must not register further callbacks.
+    pub fn on_handshake_complete<F: FnMut(SocketAddr, ConnectionSide) + Send + 'static>(&self, hook: F) {
+        self.handshake_hooks.lock().push(Box::new(hook));
+    }
+
+    /// Invokes the handshake callbacks for the given peer.
+    /// A callback that panics does not prevent the remaining callbacks, nor the connection.
+    fn run_handshake_hooks(&self, peer_ip: SocketAddr, peer_side: ConnectionSide) {
+        for hook in self.handshake_hooks.lock().iter_mut() {
+            if std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| hook(peer_ip, peer_side))).is_err() {
+                warn!("A handshake callback for '{peer_ip}' has failed");
+            }
+        }
+    }
+
+    /// Returns the load shedder for inbound messages.
+    pub fn load_shedder(&self) -> &LoadShedder {
+        &self.load_shedder
+    }
+
+    /// Returns the number of inbound messages that were shed under load.
+    pub fn number_of_shed_messages(&self) -> u64 {
+        self.load_shedder.num_shed()
+    }
+
+    /// Returns the number of puzzle requests that were coalesced with one already in flight.
+    pub fn number_of_coalesced_puzzle_requests(&self) -> u64 {
+        self.num_coalesced_puzzle_requests.load(Ordering::Relaxed)
+    }
+
+    /// Returns the message types (and support for compressed frames) this node advertises to its peers.
+    pub fn capabilities(&self) -> CapabilitySet {
+        *self.capabilities.read()
+    }
+
+    /// Sets the message types this node advertises to the peers it connects to from now on.
+    pub fn set_capabilities(&self, capabilities: CapabilitySet) {
+        *self.capabilities.write() = capabilities;
+    }
+
+    /// Returns `true` if the message with the given ID was (probably) seen, from any peer.
+    pub fn has_seen_message(&self, id: MessageId<N>) -> bool {
+        self.cache.contains_message(&id, &self.connected_peers())
+    }
+
+    /// Returns `true` if the node is catching up with the ledger.
+    pub fn is_syncing(&self) -> bool {
+        self.is_syncing.load(Ordering::Relaxed)
+    }
+
+    /// Sets whether the node is catching up with the ledger. While syncing, puzzle requests are declined.
+    pub fn set_syncing(&self, is_syncing: bool) {
+        self.is_syncing.store(is_syncing, Ordering::Relaxed);
+    }
+
+    /// Records a puzzle request that was declined, as the node was syncing or too busy.
+    pub fn decline_puzzle_request(&self) {
+        self.num_declined_puzzle_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of puzzle requests that were declined, as the node was syncing or too busy.
+    pub fn number_of_declined_puzzle_requests(&self) -> u64 {
+        self.num_declined_puzzle_requests.load(Ordering::Relaxed)
+    }
+
+    /// Returns the maximum duration of reading the puzzle state from the ledger.
+    pub fn ledger_read_timeout(&self) -> Duration {
+        self.config.read().ledger_read_timeout
+    }
+
+    /// Returns `true` if puzzle requests received while the ledger has no epoch challenge are answered,
+    /// with a puzzle response without one.
+    pub fn sends_unavailable_puzzle_responses(&self) -> bool {
+        self.config.read().send_unavailable_puzzle_responses
+    }
+
+    /// Records a puzzle request that was declined, as reading the puzzle state from the ledger timed out.
+    pub fn record_slow_ledger_read(&self) {
+        self.num_slow_ledger_reads.fetch_add(1, Ordering::Relaxed);
+        self.decline_puzzle_request();
+    }
+
+    /// Returns the number of puzzle requests that were declined, as reading the puzzle state from the ledger timed out.
+    pub fn number_of_slow_ledger_reads(&self) -> u64 {
+        self.num_slow_ledger_reads.load(Ordering::Relaxed)
+    }
+
+    /// Returns the token that is cancelled when the node shuts down.
+    pub fn shutdown_token(&self) -> &CancellationToken {
+        &self.shutdown_token
+    }
+
+    /// Returns `true` if the node is shutting down.
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown_token.is_cancelled()
+    }
+
+    /// Returns the live configuration.
+    pub fn config(&self) -> RouterConfig {
+        self.config.read().clone()
+    }
+
+    /// Replaces the live configuration. The new values apply to subsequent checks on all connections.
+    pub fn set_config(&self, config: RouterConfig) {
+        *self.config.write() = config;
+    }
+
+    /// Returns the maximum number of inbound messages handled at once, before non-critical messages are shed.
+    pub fn max_in_flight_messages(&self) -> usize {
+        self.config.read().max_in_flight_messages
+    }
+
+    /// Returns the maximum number of blocks served for a single block request.
+    pub fn max_blocks_per_request(&self) -> u32 {
+        self.config.read().max_blocks_per_request
+    }
+
+    /// Returns the maximum number of blocks served to a peer within the block request interval.
+    pub fn max_blocks_per_interval(&self) -> usize {
+        self.config.read().max_blocks_per_interval
+    }
+
+    /// Returns the maximum number of messages a peer may send, and the interval in seconds they are counted over.
+    pub fn message_rate_limit(&self) -> (usize, i64) {
+        let config = self.config.read();
+        (config.max_messages_per_interval, config.message_interval_in_secs)
+    }
+
+    /// Returns the duration after which a connected peer that has not sent any message is disconnected.
+    pub fn idle_timeout(&self) -> Duration {
+        self.config.read().idle_timeout
+    }
+
+    /// Returns the maximum duration of a connection from the completion of its handshake, if one is set.
+    pub fn max_connection_lifetime(&self) -> Option<Duration> {
+        self.config.read().max_connection_lifetime
+    }
+
+    /// Returns the maximum duration of a handshake.
+    pub fn handshake_timeout(&self) -> Duration {
+        self.config.read().handshake_timeout
+    }
+
+    /// Returns the maximum duration of writing a single message to a peer.
+    pub fn write_timeout(&self) -> Duration {
+        self.config.read().write_timeout
+    }
+
+    /// Returns the window over which the messages queued for a peer are coalesced into a single write, if any.
+    pub fn write_coalescing_window(&self) -> Option<Duration> {
+        self.config.read().write_coalescing_window
+    }
+
+    /// Marks the peer with the given address as a slow consumer, as writing a message to it timed out.
+    /// The connection is dropped right after.
+    pub fn handle_write_timeout(&self, peer_addr: SocketAddr) {
+        if let Some(peer_ip) = self.resolve_to_listener(&peer_addr) {
+            warn!("Disconnecting from '{peer_ip}' - writing a message timed out");
+            self.set_disconnect_reason(peer_ip, DisconnectReason::SlowConsumer);
+        }
+    }
+
+    /// Returns the maximum number of bytes buffered across all peers, awaiting the rest of an inbound message.
+    pub fn max_inbound_buffer_bytes(&self) -> usize {
+        self.config.read().max_inbound_buffer_bytes
+    }
+
+    /// Marks the peer with the given address as having exceeded the rate limit, as it holds the largest
+    /// inbound buffer once the buffers of all peers exceed their limit. The connection is dropped right after.
+    pub fn handle_inbound_buffer_overflow(&self, peer_addr: SocketAddr) {
+        if let Some(peer_ip) = self.resolve_to_listener(&peer_addr) {
+            warn!("Disconnecting from '{peer_ip}' - its inbound buffer is the largest, and the buffers are full");
+            self.set_disconnect_reason(peer_ip, DisconnectReason::RateLimitExceeded);
+        }
+    }
+
+    /// Returns the maximum number of puzzle requests a peer may have pending, before it is disconnected.
+    pub fn max_pending_puzzle_requests(&self) -> usize {
+        self.config.read().max_pending_puzzle_requests
+    }
+
+    /// Records the number of peers the given peer reported in a peer response.
+    pub fn update_reported_peers(&self, peer_ip: SocketAddr, num_peers: usize) {
+        if let Some(peer) = self.connected_peers.write().get_mut(&peer_ip) {
+            peer.set_num_reported_peers(num_peers);
+        }
+    }
+
+    /// Returns the maximum number of peers each propagated message is sent to.
+    pub fn propagation_fanout(&self) -> usize {
+        self.config.read().propagation_fanout
+    }
+
+    /// Returns the maximum number of outbound messages queued for a peer, before it is disconnected.
+    pub fn max_outbound_backlog(&self) -> usize {
+        self.config.read().max_outbound_backlog
+    }
+
+    /// Sets the maximum number of outbound messages queued for a peer, before it is disconnected.
+    pub fn set_max_outbound_backlog(&self, max_outbound_backlog: usize) {
+        self.config.write().max_outbound_backlog = max_outbound_backlog;
+    }
+
+    /// Sets the maximum number of peers each propagated message is sent to.
+    pub fn set_propagation_fanout(&self, fanout: usize) {
+        self.config.write().propagation_fanout = fanout;
+    }
+
+    /// Seeds the selection of the peers to propagate to, making it reproducible across nodes with the same seed.
+    pub fn set_propagation_seed(&self, seed: u64) {
+        *self.propagation_rng.lock() = Some(StdRng::seed_from_u64(seed));
+    }
+
+    /// Selects up to the fanout limit of the given peers at random, to propagate a message to.
+    pub fn select_propagation_peers(&self, mut peers: Vec<SocketAddr>) -> Vec<SocketAddr> {
+        let fanout = self.propagation_fanout();
+        match &mut *self.propagation_rng.lock() {
+            Some(rng) => {
+                // Sort the peers, so that the selection does not depend on the order of the connections.
+                peers.sort_unstable();
+                peers.into_iter().choose_multiple(rng, fanout)
+            }
+            None => peers.into_iter().choose_multiple(&mut OsRng, fanout),
+        }
+    }
+
+    /// Sets the classifier that assigns peers to groups.
+    pub fn set_peer_classifier<C: PeerClassifier + 'static>(&self, classifier: C) {
+        *self.peer_classifier.write() = Arc::new(classifier);
+    }
+
+    /// Sets the maximum number of connected peers in each group of the peer classifier.
+    pub fn set_max_peers_per_group(&self, max_peers_per_group: usize) {
+        self.config.write().max_peers_per_group = max_peers_per_group;
+    }
+
+    /// Returns the group of the given peer.
+    pub fn peer_group(&self, peer_ip: SocketAddr) -> u64 {
+        self.peer_classifier.read().classify(peer_ip)
+    }
+
+    /// Returns the number of connected peers in the given group.
+    pub fn number_of_connected_peers_in_group(&self, group: u64) -> usize {
+        let classifier = self.peer_classifier.read().clone();
+        self.connected_peers().into_iter().filter(|peer_ip| classifier.classify(*peer_ip) == group).count()
+    }
+
+    /// Returns `true` if the group of the given peer has reached the maximum number of connected peers.
+    pub fn is_peer_group_full(&self, peer_ip: SocketAddr) -> bool {
+        let max_peers_per_group = self.config.read().max_peers_per_group;
+        self.number_of_connected_peers_in_group(self.peer_group(peer_ip)) >= max_peers_per_group
+    }
+
+    /// Sets the strategy for choosing the connected peer to evict, when a new peer connects at capacity.
+    pub fn set_eviction_strategy<S: EvictionStrategy<N> + 'static>(&self, strategy: S) {
+        *self.eviction_strategy.write() = Arc::new(strategy);
+    }
+
+    /// Returns the connected peer to evict in favour of the given newcomer, as chosen by the eviction strategy.
+    /// The pinned, trusted, and bootstrap peers are never evicted.
+    pub fn select_eviction(&self, newcomer: &Peer<N>) -> Option<SocketAddr> {
+        // Retrieve the bootstrap peers.
+        let bootstrap = self.bootstrap_peers();
+        // Retrieve the connected peers that may be evicted.
+        let peers = self
+            .connected_peers
+            .read()
+            .values()
+            .filter(|peer| !self.trusted_peers.contains(&peer.ip()) && !bootstrap.contains(&peer.ip()))
+            .filter(|peer| !self.is_pinned(&peer.ip()))
+            .cloned()
+            .collect::<Vec<_>>();
+        // Select the peer to evict.
+        let strategy = self.eviction_strategy.read().clone();
+        strategy.select_eviction(newcomer, &peers)
+    }
+
+    /// Sets the governor that reports whether the host is too loaded to accept new inbound connections.
+    pub fn set_load_governor<G: LoadGovernor + 'static>(&self, governor: G) {
+        *self.load_governor.write() = Arc::new(governor);
+    }
+
+    /// Returns `true` if the host is too loaded to accept new inbound connections.
+    pub fn is_overloaded(&self) -> bool {
+        self.load_governor.read().is_overloaded()
+    }
+
+    /// Pauses accepting new inbound connections, e.g. during a maintenance window.
+    /// The connected peers are kept, and pinned peers may still connect.
+    pub fn pause_accepting(&self) {
+        self.is_accepting_paused.store(true, Ordering::Relaxed);
+    }
+
+    /// Resumes accepting new inbound connections.
+    pub fn resume_accepting(&self) {
+        self.is_accepting_paused.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if accepting new inbound connections is paused.
+    pub fn is_accepting_paused(&self) -> bool {
+        self.is_accepting_paused.load(Ordering::Relaxed)
+    }
+
+    /// Starts recording the inbound messages to the message log at the given path, which is rotated
+    /// once it exceeds the given number of bytes. An existing recording is replaced.
+    pub fn start_recording(&self, path: &Path, max_file_bytes: u64) -> Result<()> {
+        *self.recorder.lock() = Some(MessageRecorder::new(path, max_file_bytes)?);
+        Ok(())
+    }
+
+    /// Stops recording the inbound messages.
+    pub fn stop_recording(&self) {
+        *self.recorder.lock() = None;
+    }
+
+    /// Returns `true` if the inbound messages are being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recorder.lock().is_some()
+    }
+
+    /// Records the given inbound message from the given peer, if recording is enabled.
+    pub fn record_inbound_message(&self, peer_addr: SocketAddr, message: &Message<N>) {
+        if let Some(recorder) = self.recorder.lock().as_mut() {
+            if let Err(error) = recorder.record(peer_addr, message) {
+                warn!("Failed to record a '{}' message from '{peer_addr}' - {error}", message.name());
+            }
+        }
+    }
+
+    /// Returns `true` if the given message type is dropped on receipt.
+    pub fn is_dropped_message(&self, name: &str) -> bool {
+        self.config.read().dropped_messages.contains(name)
+    }
+
+    /// Sets the sink for dropped and rejected inbound messages.
+    pub fn set_dead_letter_sink(&self, sink: mpsc::Sender<DeadLetter>) {
+        *self.dead_letter_sink.write() = Some(sink);
+    }
+
+    /// Sends a dead letter for the given message to the sink, if one is set.
+    /// The dead letter is dropped if the sink is full.
+    pub fn insert_dead_letter(&self, peer_ip: SocketAddr, message_kind: &str, reason: DeadLetterReason) {
+        if let Some(sink) = &*self.dead_letter_sink.read() {
+            let _ = sink.try_send(DeadLetter { peer_ip, message_kind: message_kind.to_string(), reason });
+        }
+    }
+
+    /// Marks the given solution as accepted, so that its copies from any peer are dropped early.
+    /// This must only be called once the solution was checked, as its commitment does not bind its proof.
+    pub fn insert_accepted_solution(&self, solution_id: PuzzleCommitment<N>) {
+        self.cache.insert_global_solution(&solution_id);
+    }
+
+    /// Marks the given transaction as accepted, so that its copies from any peer are dropped early.
+    /// This must only be called once the transaction was accepted, so that a temporary rejection does not
+    /// drop the later copies.
+    pub fn insert_accepted_transaction(&self, transaction_id: N::TransactionID) {
+        self.cache.insert_global_transaction(&transaction_id);
+    }
+
+    /// Sets the sink for changes in the state of connected peers.
+    pub fn set_peer_event_sink(&self, sink: mpsc::Sender<PeerEvent>) {
+        *self.peer_event_sink.write() = Some(sink);
+    }
+
+    /// Sends the given peer event to the sink, if one is set.
+    /// The event is dropped if the sink is full.
+    fn insert_peer_event(&self, event: PeerEvent) {
+        if let Some(sink) = &*self.peer_event_sink.read() {
+            let _ = sink.try_send(event);
+        }
+    }
+
+    /// Updates the latest block height of the given peer IP, emitting a `PeerEvent::HeightUpdated` if it changed.
+    pub fn update_peer_height(&self, peer_ip: SocketAddr, height: u32) {
+        let is_updated = match self.connected_peers.write().get_mut(&peer_ip) {
+            Some(peer) => peer.set_height(height),
+            None => false,
+        };
+        if is_updated {
+            self.insert_peer_event(PeerEvent::HeightUpdated { addr: peer_ip, height });
+        }
+    }
+
+    /// Resolves once a connected peer is removed. A removal since the last call resolves immediately.
+    pub async fn disconnected(&self) {
+        self.disconnect_notify.notified().await
+    }
+
+    /// Returns the connected peers that are more than `threshold` blocks behind the given height.
+    /// Peers that have not reported a height yet are excluded.
+    pub fn peers_behind(&self, our_height: u32, threshold: u32) -> Vec<SocketAddr> {
+        self.connected_peers_where(|peer| match peer.height() {
+            Some(height) => our_height.saturating_sub(height) > threshold,
+            None => false,
+        })
+    }
+
+    /// Returns the 50th, 95th, and 99th percentiles of the durations of successful handshakes.
+    pub fn handshake_duration_percentiles(&self) -> (Duration, Duration, Duration) {
+        let histogram = &self.handshake_durations;
+        (histogram.percentile(50.0), histogram.percentile(95.0), histogram.percentile(99.0))
+    }
+
+    /// Returns the clock used to measure timeouts and round-trip times.
+    pub fn clock(&self) -> MonotonicClock {
+        self.clock.read().clone()
+    }
+
+    /// Sets the clock used to measure timeouts and round-trip times, e.g. to a fake clock in tests.
+    pub fn set_clock(&self, clock: MonotonicClock) {
+        *self.clock.write() = clock;
+    }
+
+    /// Records the duration of a successful handshake.
+    pub fn record_handshake_duration(&self, duration: Duration) {
+        self.handshake_durations.record(duration)
+    }
+
+    /// Records an inbound connection, to track the rate of inbound connections.
+    pub fn record_inbound_connection(&self) {
+        let now = self.clock().now();
+        let mut recent = self.recent_inbound_connections.lock();
+        recent.push_back(now);
+        // Forget the connections older than a second.
+        let is_expired = |time: &Instant| MonotonicClock::duration_between(*time, now) > Duration::from_secs(1);
+        while matches!(recent.front(), Some(time) if is_expired(time)) {
+            recent.pop_front();
+        }
+    }
+
+    /// Returns `true` if the rate of inbound connections exceeds the admission threshold,
+    /// in which case connecting peers must solve an admission challenge.
+    pub fn is_under_connection_load(&self) -> bool {
+        let now = self.clock().now();
+        let recent = self.recent_inbound_connections.lock();
+        let is_recent = |time: &&Instant| MonotonicClock::duration_between(**time, now) <= Duration::from_secs(1);
+        let rate = recent.iter().filter(is_recent).count();
+        rate > self.config.read().admission_rate_threshold
+    }
+
+    /// Records an admission challenge sent to a connecting peer.
+    pub fn record_admission_challenge(&self) {
+        self.num_admission_challenges.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of admission challenges sent to connecting peers.
+    pub fn number_of_admission_challenges(&self) -> u64 {
+        self.num_admission_challenges.load(Ordering::Relaxed)
+    }
+
+    /// Returns up to the given number of the most recent disconnects, from oldest to newest.
+    pub fn recent_disconnects(&self, limit: usize) -> Vec<DisconnectRecord> {
+        self.disconnect_log.recent(limit)
+    }
+
+    /// Sets the maximum number of records in the disconnect log.
+    pub fn set_disconnect_log_capacity(&self, capacity: usize) {
+        self.disconnect_log.set_capacity(capacity)
+    }
+
+    /// Returns the codec version of the connection with the given (ambiguous) peer address.
+    pub fn codec_version(&self, peer_addr: &SocketAddr) -> u8 {
+        self.resolve_to_listener(peer_addr)
+            .and_then(|peer_ip| self.get_connected_peer(&peer_ip))
+            .map_or(0, |peer| peer.codec_version())
+    }
+
+    /// Returns the compression flag shared by the codecs of the connection with the given (ambiguous) peer address,
+    /// if both this node and the peer advertised support for compressed frames during the handshake.
+    pub fn compression(&self, peer_addr: &SocketAddr) -> Option<Arc<AtomicBool>> {
+        if !self.capabilities().supports_compression() {
+            return None;
+        }
+        self.resolve_to_listener(peer_addr)
+            .and_then(|peer_ip| self.get_connected_peer(&peer_ip))
+            .filter(|peer| peer.capabilities().supports_compression())
+            .map(|peer| peer.compression().clone())
+    }
+
+    /// Returns `true` if compression was negotiated with the given peer IP, and has not fallen back to uncompressed.
+    pub fn peer_supports_compression(&self, peer_ip: &SocketAddr) -> bool {
+        self.capabilities().supports_compression()
+            && matches!(self.connected_peers.read().get(peer_ip), Some(peer) if peer.supports_compression())
+    }
+
+    /// Returns the side to orient the codecs of the connection with the given peer address for, if the peer
+    /// is on a version of the network protocol with oriented codecs. The side is from the perspective of this node.
+    pub fn codec_side(&self, peer_addr: &SocketAddr, side: ConnectionSide) -> Option<ConnectionSide> {
+        self.resolve_to_listener(peer_addr)
+            .and_then(|peer_ip| self.get_connected_peer(&peer_ip))
+            .filter(|peer| peer.features().contains(Feature::OrientedCodecs))
+            .map(|_| side)
+    }
+
+    /// Registers a codec upgrade with the given peer, returning a receiver for the acknowledgement.
+    /// A previous upgrade with the peer that is still pending is superseded.
+    pub fn insert_codec_upgrade(&self, peer_ip: SocketAddr) -> oneshot::Receiver<()> {
+        let (sender, receiver) = oneshot::channel();
+        self.pending_codec_upgrades.lock().insert(peer_ip, sender);
+        receiver
+    }
+
+    /// Removes the pending codec upgrade with the given peer, returning `true` if one was pending.
+    pub fn remove_codec_upgrade(&self, peer_ip: SocketAddr) -> bool {
+        self.pending_codec_upgrades.lock().remove(&peer_ip).is_some()
+    }
+
+    /// Acknowledges the pending codec upgrade with the given peer, returning `true` if one was pending.
+    pub fn acknowledge_codec_upgrade(&self, peer_ip: SocketAddr) -> bool {
+        match self.pending_codec_upgrades.lock().remove(&peer_ip) {
+            Some(sender) => sender.send(()).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Returns the counters of bytes sent and received, per message type.
+    pub fn traffic(&self) -> &Arc<MessageTraffic> {
+        &self.traffic
+    }
+
+    /// Returns the number of received messages that arrived over multiple reads.
+    pub fn num_reassembled_messages(&self) -> u64 {
+        self.traffic.num_reassembled_messages()
+    }
+
+    /// Returns the number of bytes (sent, received), for each message type.
+    pub fn bytes_by_type(&self) -> HashMap<&'static str, (u64, u64)> {
+        self.traffic.bytes_by_type()
+    }
+
     /// Returns the listener IP address from the (ambiguous) peer address.
     pub fn resolve_to_listener(&self, peer_addr: &SocketAddr) -> Option<SocketAddr> {
         self.resolver.get_listener(peer_addr)
@@ -280,13 +1094,43 @@ impl<N: Network> Router<N> {
         self.restricted_peers
             .read()
             .get(ip)
-            .map(|time| time.elapsed().as_secs() < Self::RADIO_SILENCE_IN_SECS)
+            .map(|time| self.clock().elapsed(*time).as_secs() < Self::RADIO_SILENCE_IN_SECS)
             .unwrap_or(false)
     }
 
+    /// Returns `true` if the given IP violated the protocol within the probation period.
+    pub fn is_on_probation(&self, ip: &SocketAddr) -> bool {
+        self.probation.contains(ip, self.config.read().probation_period)
+    }
+
+    /// Returns `true` if the given IP violated the protocol within the probation cooldown, and may not reconnect.
+    pub fn is_cooling_down(&self, ip: &SocketAddr) -> bool {
+        self.probation.is_cooling_down(ip, self.config.read().probation_cooldown)
+    }
+
+    /// Returns `true` if the given IP is soft-banned for reconnecting too often, and may not reconnect yet.
+    pub fn is_soft_banned(&self, ip: &SocketAddr) -> bool {
+        self.churn_limiter.is_banned(ip, self.config.read().churn_ban_duration)
+    }
+
+    /// Records a connection attempt by the given peer, and returns `true` if the peer is soft-banned as a result,
+    /// for making more than the maximum number of connection attempts within the churn window.
+    pub fn record_connection_attempt(&self, peer_ip: SocketAddr) -> bool {
+        let (max_attempts, window) = {
+            let config = self.config.read();
+            (config.max_connection_attempts, config.churn_window)
+        };
+        self.churn_limiter.record_attempt(peer_ip, max_attempts, window)
+    }
+
+    /// Lifts the soft-ban on the given peer, and forgets its recent connection attempts.
+    pub fn remove_soft_ban(&self, peer_ip: &SocketAddr) {
+        self.churn_limiter.remove(peer_ip);
+    }
+
     /// Returns the maximum number of connected peers.
     pub fn max_connected_peers(&self) -> usize {
-        self.tcp.config().max_connections as usize
+        self.config.read().max_peers.min(self.tcp.config().max_connections as usize)
     }
 
     /// Returns the number of connected peers.
@@ -324,6 +1168,27 @@ impl<N: Network> Router<N> {
         self.connected_peers.read().get(ip).cloned()
     }
 
+    /// Returns `true` if the given peer is connected, and its message version supports the given feature.
+    /// Messages of a feature are only sent to the peers that support it, as older peers cannot decode them.
+    pub fn peer_supports(&self, peer_ip: &SocketAddr, feature: Feature) -> bool {
+        self.connected_peers.read().get(peer_ip).map_or(false, |peer| peer.features().contains(feature))
+    }
+
+    /// Returns the connection metadata of the given connected peer IP, if it is connected.
+    pub fn peer_context(&self, peer_ip: &SocketAddr) -> Option<PeerContext> {
+        let peer = self.get_connected_peer(peer_ip)?;
+        Some(PeerContext {
+            peer_ip: peer.ip(),
+            side: peer.side(),
+            node_type: peer.node_type(),
+            version: peer.version(),
+            codec_version: peer.codec_version(),
+            capabilities: peer.capabilities(),
+            is_pinned: self.is_pinned(peer_ip),
+            is_on_probation: self.is_on_probation(peer_ip),
+        })
+    }
+
     /// Returns the connected peers.
     pub fn get_connected_peers(&self) -> Vec<Peer<N>> {
         self.connected_peers.read().values().cloned().collect()
@@ -334,6 +1199,23 @@ impl<N: Network> Router<N> {
         self.connected_peers.read().keys().copied().collect()
     }
 
+    /// Returns the connected peers to share in a `PeerResponse`, excluding bogon addresses.
+    /// The peers are sorted by address, so that the response is stable and does not reveal the connection order.
+    pub fn shareable_peers(&self) -> Vec<SocketAddr> {
+        let mut peers = self.connected_peers();
+        peers.retain(|addr| !is_bogon_address(addr.ip()));
+        peers.sort_unstable();
+        peers
+    }
+
+    /// Returns the list of connected peers that match the given predicate.
+    ///
+    /// The predicate is evaluated against a snapshot of the connected peers,
+    /// so the connected peers may be safely mutated while acting on the result.
+    pub fn connected_peers_where<F: Fn(&Peer<N>) -> bool>(&self, predicate: F) -> Vec<SocketAddr> {
+        self.connected_peers.read().iter().filter(|(_, peer)| predicate(peer)).map(|(ip, _)| *ip).collect()
+    }
+
     /// Returns the list of connected validators.
     pub fn connected_validators(&self) -> Vec<SocketAddr> {
         self.connected_peers.read().iter().filter(|(_, peer)| peer.is_validator()).map(|(ip, _)| *ip).collect()
@@ -359,11 +1241,76 @@ impl<N: Network> Router<N> {
         self.restricted_peers.read().keys().copied().collect()
     }
 
+    /// Returns the set of peer IPs whose restrictions have not expired.
+    pub fn restricted_peers_snapshot(&self) -> HashSet<SocketAddr> {
+        self.restricted_peers
+            .read()
+            .iter()
+            .filter(|(_, time)| self.clock().elapsed(**time).as_secs() < Self::RADIO_SILENCE_IN_SECS)
+            .map(|(peer_ip, _)| *peer_ip)
+            .collect()
+    }
+
     /// Returns the list of trusted peers.
     pub fn trusted_peers(&self) -> &IndexSet<SocketAddr> {
         &self.trusted_peers
     }
 
+    /// Returns the list of pinned peers.
+    pub fn pinned_peers(&self) -> Vec<SocketAddr> {
+        self.pinned_peers.read().iter().copied().collect()
+    }
+
+    /// Returns `true` if the given peer IP is pinned.
+    pub fn is_pinned(&self, peer_ip: &SocketAddr) -> bool {
+        self.pinned_peers.read().contains(peer_ip)
+    }
+
+    /// Returns the number of connected peers that are pinned.
+    pub fn number_of_connected_pinned_peers(&self) -> usize {
+        self.pinned_peers.read().iter().filter(|peer_ip| self.is_connected(peer_ip)).count()
+    }
+
+    /// Pins the given peer IP, so that it is never evicted and may connect beyond the maximum number of peers.
+    pub fn pin_peer(&self, peer_ip: SocketAddr) -> Result<()> {
+        let mut pinned_peers = self.pinned_peers.write();
+        // Ensure the number of pinned peers does not exceed the reserved connections.
+        if !pinned_peers.contains(&peer_ip) && pinned_peers.len() >= Self::MAXIMUM_PINNED_PEERS as usize {
+            bail!("Unable to pin '{peer_ip}' (at most {} peers may be pinned)", Self::MAXIMUM_PINNED_PEERS)
+        }
+        pinned_peers.insert(peer_ip);
+        Ok(())
+    }
+
+    /// Unpins the given peer IP, returning `true` if the peer was pinned.
+    pub fn unpin_peer(&self, peer_ip: &SocketAddr) -> bool {
+        self.pinned_peers.write().remove(peer_ip)
+    }
+
+    /// Reconciles the connections of this node with the given topology, by dialing the required peers that are
+    /// not connected, and disconnecting from the connected peers that the topology disallows.
+    pub fn apply_topology(&self, topology: &TopologyDesc) -> TopologyChanges {
+        let mut changes = TopologyChanges::default();
+        // Disconnect from the connected peers that are disallowed.
+        for peer_ip in self.connected_peers() {
+            if topology.is_disallowed(&peer_ip) {
+                self.disconnect(peer_ip);
+                changes.disconnected.push(peer_ip);
+            }
+        }
+        // Drop the avoided peers from the candidate peers, so that they are not dialed.
+        for peer_ip in &topology.avoided {
+            self.remove_candidate_peer(*peer_ip);
+        }
+        // Dial the required peers that are neither connected nor being connected to.
+        for peer_ip in topology.required.iter().filter(|peer_ip| !topology.avoided.contains(*peer_ip)) {
+            if !self.is_connected(peer_ip) && !self.is_connecting(peer_ip) && self.connect(*peer_ip).is_some() {
+                changes.dialed.push(*peer_ip);
+            }
+        }
+        changes
+    }
+
     /// Returns the list of bootstrap peers.
     pub fn bootstrap_peers(&self) -> Vec<SocketAddr> {
         if cfg!(feature = "test") || self.is_dev {
@@ -378,6 +1325,29 @@ impl<N: Network> Router<N> {
         }
     }
 
+    /// Returns a consistent snapshot of the connected peers, taken under a single lock acquisition.
+    /// The snapshot does not change when the connected peers are updated afterwards.
+    pub fn peers_snapshot(&self) -> Arc<PeerTableSnapshot<N>> {
+        Arc::new(PeerTableSnapshot::new(self.connected_peers.read().clone()))
+    }
+
+    /// Returns a snapshot of the connections of this node, read under a single lock of the connected peers.
+    pub fn topology_snapshot(&self) -> TopologySnapshot<N> {
+        let peers = self
+            .connected_peers
+            .read()
+            .values()
+            .map(|peer| TopologyPeer {
+                peer_ip: peer.ip(),
+                side: peer.side(),
+                node_type: peer.node_type(),
+                version: peer.version(),
+                num_peers: peer.num_reported_peers(),
+            })
+            .collect();
+        TopologySnapshot { node_ip: self.local_ip(), address: self.address(), peers }
+    }
+
     /// Returns the list of metrics for the connected peers.
     pub fn connected_metrics(&self) -> Vec<(SocketAddr, NodeType)> {
         self.connected_peers.read().iter().map(|(ip, peer)| (*ip, peer.node_type())).collect()
@@ -396,6 +1366,76 @@ impl<N: Network> Router<N> {
         self.restricted_peers.write().remove(&peer_ip);
     }
 
+    /// Marks a puzzle request from the given peer IP as in flight. Returns `false` if a puzzle request
+    /// from the peer is already in flight (or the peer is not connected), in which case the request is coalesced.
+    pub fn insert_puzzle_request_in_flight(&self, peer_ip: SocketAddr) -> bool {
+        // Set the in-flight flag, if it is not already set.
+        let is_new = match self.connected_peers.write().get_mut(&peer_ip) {
+            Some(peer) => {
+                let is_new = !peer.is_puzzle_request_in_flight();
+                peer.set_puzzle_request_in_flight(true);
+                peer.insert_pending_puzzle_request();
+                is_new
+            }
+            None => false,
+        };
+        if !is_new {
+            self.num_coalesced_puzzle_requests.fetch_add(1, Ordering::Relaxed);
+        }
+        is_new
+    }
+
+    /// Returns the number of puzzle requests from the given peer IP, awaiting the delivery of the response in flight.
+    pub fn num_pending_puzzle_requests(&self, peer_ip: &SocketAddr) -> usize {
+        self.connected_peers.read().get(peer_ip).map_or(0, |peer| peer.num_pending_puzzle_requests())
+    }
+
+    /// Clears the in-flight puzzle request from the given peer IP.
+    pub fn remove_puzzle_request_in_flight(&self, peer_ip: SocketAddr) {
+        if let Some(peer) = self.connected_peers.write().get_mut(&peer_ip) {
+            peer.set_puzzle_request_in_flight(false);
+        }
+    }
+
+    /// Sets the reason for disconnecting from the given peer IP, unless a reason was already set.
+    pub fn set_disconnect_reason(&self, peer_ip: SocketAddr, reason: DisconnectReason) {
+        if let Some(peer) = self.connected_peers.write().get_mut(&peer_ip) {
+            peer.set_disconnect_reason(reason);
+        }
+    }
+
+    /// Updates the codec version of the connection with the given peer.
+    pub fn set_codec_version(&self, peer_ip: SocketAddr, codec_version: u8) {
+        if let Some(peer) = self.connected_peers.write().get_mut(&peer_ip) {
+            peer.set_codec_version(codec_version);
+        }
+    }
+
+    /// Records a liveness ping sent to the given peer IP, returning the number of consecutive missed pings.
+    pub fn insert_ping(&self, peer_ip: SocketAddr) -> u32 {
+        match self.connected_peers.write().get_mut(&peer_ip) {
+            Some(peer) => peer.insert_ping(self.clock().now()),
+            None => 0,
+        }
+    }
+
+    /// Records a pong received from the given peer IP, updating its round-trip time.
+    pub fn insert_pong(&self, peer_ip: SocketAddr) {
+        if let Some(peer) = self.connected_peers.write().get_mut(&peer_ip) {
+            peer.insert_pong(self.clock().now());
+        }
+    }
+
+    /// Inserts a strike for the given peer IP, returning the number of recent strikes.
+    pub fn insert_strike(&self, peer_ip: SocketAddr) -> usize {
+        self.cache.insert_inbound_strike(peer_ip, Self::STRIKE_INTERVAL_IN_SECS)
+    }
+
+    /// Returns the number of recent strikes for the given peer IP.
+    pub fn number_of_strikes(&self, peer_ip: SocketAddr) -> usize {
+        self.cache.num_inbound_strikes(peer_ip, Self::STRIKE_INTERVAL_IN_SECS)
+    }
+
     /// Inserts the given peer IPs to the set of candidate peers.
     ///
     /// This method skips adding any given peers if the combined size exceeds the threshold,
@@ -416,12 +1456,44 @@ impl<N: Network> Router<N> {
         self.candidate_peers.write().extend(eligible_peers);
     }
 
+    /// Records a protocol violation by the given peer, and returns the resulting tier.
+    /// Violations up to the configured tolerance place the peer on probation; a further one within the
+    /// probation period restricts it.
+    pub fn record_violation(&self, peer_ip: SocketAddr) -> ViolationTier {
+        let (probation_period, tolerated_violations) = {
+            let config = self.config.read();
+            (config.probation_period, config.tolerated_violations)
+        };
+        let tier = self.probation.record_violation(peer_ip, probation_period, tolerated_violations);
+        if tier == ViolationTier::Restricted {
+            self.insert_restricted_peer(peer_ip);
+        }
+        tier
+    }
+
     /// Inserts the given peer into the restricted peers.
     pub fn insert_restricted_peer(&self, peer_ip: SocketAddr) {
         // Remove this peer from the candidate peers, if it exists.
         self.candidate_peers.write().remove(&peer_ip);
         // Add the peer to the restricted peers.
-        self.restricted_peers.write().insert(peer_ip, Instant::now());
+        self.restricted_peers.write().insert(peer_ip, self.clock().now());
+    }
+
+    /// Atomically replaces the restricted peers with the given set, e.g. when a ban list is pushed
+    /// from a central controller. Connected peers in the new set are disconnected.
+    pub fn replace_restricted_peers(&self, peers: HashSet<SocketAddr>) {
+        // Remove the newly restricted peers from the candidate peers.
+        self.candidate_peers.write().retain(|peer_ip| !peers.contains(peer_ip));
+        // Swap in the new restricted peers.
+        let now = self.clock().now();
+        *self.restricted_peers.write() = peers.iter().map(|peer_ip| (*peer_ip, now)).collect();
+        // Disconnect from the newly restricted peers.
+        for peer_ip in peers {
+            if self.is_connected(&peer_ip) {
+                debug!("Disconnecting from '{peer_ip}' (restricted)");
+                self.disconnect(peer_ip);
+            }
+        }
     }
 
     /// Updates the connected peer with the given function.
@@ -448,7 +1520,13 @@ impl<N: Network> Router<N> {
         // Removes the bidirectional map between the listener address and (ambiguous) peer address.
         self.resolver.remove_peer(&peer_ip);
         // Remove this peer from the connected peers, if it exists.
-        self.connected_peers.write().remove(&peer_ip);
+        if let Some(peer) = self.connected_peers.write().remove(&peer_ip) {
+            // Record the disconnect. If no reason was given, the connection was dropped.
+            let reason = peer.disconnect_reason().unwrap_or(DisconnectReason::PeerHasDisconnected);
+            self.disconnect_log.insert(peer_ip, reason);
+            // Notify the waiter on disconnects, if any.
+            self.disconnect_notify.notify_one();
+        }
         // Add the peer to the candidate peers.
         self.candidate_peers.write().insert(peer_ip);
     }
@@ -463,6 +1541,97 @@ impl<N: Network> Router<N> {
         self.candidate_peers.write().remove(&peer_ip);
     }
 
+    /// Saves the addresses of the connected peers to the given file.
+    pub fn save_peers(&self, path: &Path) -> Result<()> {
+        let peers = self.connected_peers();
+        std::fs::write(path, bincode::serialize(&peers)?)?;
+        debug!("Saved {} peers to '{}'", peers.len(), path.display());
+        Ok(())
+    }
+
+    /// Loads the peer addresses from the given file, and inserts them into the candidate peers.
+    /// Returns the loaded peer addresses, or an empty list if the file is missing or corrupt.
+    pub fn load_peers(&self, path: &Path) -> Vec<SocketAddr> {
+        // Ensure the file exists.
+        if !path.exists() {
+            return vec![];
+        }
+        // Read and deserialize the peer addresses.
+        let result = std::fs::read(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(bincode::deserialize::<Vec<SocketAddr>>(&bytes)?));
+        let peers = match result {
+            Ok(peers) => peers,
+            Err(error) => {
+                warn!("Ignoring the saved peers in '{}' - {error}", path.display());
+                return vec![];
+            }
+        };
+        debug!("Loaded {} peers from '{}'", peers.len(), path.display());
+        // Insert the peers into the candidate peers.
+        self.insert_candidate_peers(&peers);
+        peers
+    }
+
+    /// Saves the restricted peers to the given file, with the UNIX timestamps at which their restrictions expire.
+    pub fn save_restricted_peers(&self, path: &Path) -> Result<()> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        // Retain the restrictions that have not expired, and convert them to expiry timestamps.
+        let restricted_peers = self
+            .restricted_peers
+            .read()
+            .iter()
+            .filter_map(|(peer_ip, time)| {
+                let remaining = Self::RADIO_SILENCE_IN_SECS.checked_sub(self.clock().elapsed(*time).as_secs())?;
+                (remaining > 0).then(|| (*peer_ip, now.saturating_add(remaining as i64)))
+            })
+            .collect::<Vec<(SocketAddr, i64)>>();
+        std::fs::write(path, bincode::serialize(&restricted_peers)?)?;
+        debug!("Saved {} restricted peers to '{}'", restricted_peers.len(), path.display());
+        Ok(())
+    }
+
+    /// Loads the restricted peers from the given file, and restricts them until their saved expiry.
+    /// Expired restrictions are purged. Returns the restricted peer addresses,
+    /// or an empty list if the file is missing or corrupt.
+    pub fn load_restricted_peers(&self, path: &Path) -> Vec<SocketAddr> {
+        // Ensure the file exists.
+        if !path.exists() {
+            return vec![];
+        }
+        // Read and deserialize the restricted peers.
+        let result = std::fs::read(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(bincode::deserialize::<Vec<(SocketAddr, i64)>>(&bytes)?));
+        let restricted_peers = match result {
+            Ok(restricted_peers) => restricted_peers,
+            Err(error) => {
+                warn!("Ignoring the saved restricted peers in '{}' - {error}", path.display());
+                return vec![];
+            }
+        };
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let mut loaded = Vec::with_capacity(restricted_peers.len());
+        for (peer_ip, expiry) in restricted_peers {
+            // Purge the expired restrictions.
+            let remaining = expiry.saturating_sub(now);
+            if remaining <= 0 {
+                continue;
+            }
+            // Backdate the restriction, so that it expires at the saved expiry.
+            let remaining = (remaining as u64).min(Self::RADIO_SILENCE_IN_SECS);
+            let elapsed = Duration::from_secs(Self::RADIO_SILENCE_IN_SECS - remaining);
+            let now = self.clock().now();
+            let time = now.checked_sub(elapsed).unwrap_or(now);
+            // Remove this peer from the candidate peers, and add it to the restricted peers.
+            self.candidate_peers.write().remove(&peer_ip);
+            self.restricted_peers.write().insert(peer_ip, time);
+            loaded.push(peer_ip);
+        }
+        debug!("Loaded {} restricted peers from '{}'", loaded.len(), path.display());
+        loaded
+    }
+
     /// Spawns a task with the given future; it should only be used for long-running tasks.
     pub fn spawn<T: Future<Output = ()> + Send + 'static>(&self, future: T) {
         self.handles.lock().push(tokio::spawn(future));
@@ -471,6 +1640,8 @@ impl<N: Network> Router<N> {
     /// Shuts down the router.
     pub async fn shut_down(&self) {
         info!("Shutting down the router...");
+        // Cancel the processing of inbound messages.
+        self.shutdown_token.cancel();
         // Abort the tasks.
         self.handles.lock().iter().for_each(|handle| handle.abort());
         // Close the listener.
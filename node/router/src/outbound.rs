@@ -13,16 +13,21 @@
 // limitations under the License.
 
 use crate::{
-    messages::{Message, Ping},
+    messages::{BlockRequest, BlockResponse, DataBlocks, DisconnectReason, Message, Ping},
+    Peer,
     Router,
 };
 use snarkos_node_sync_locators::BlockLocators;
 use snarkos_node_tcp::protocols::Writing;
-use snarkvm::prelude::Network;
+use snarkvm::{
+    ledger::narwhal::Data,
+    prelude::{block::Block, Network},
+};
+
 use std::io;
 
-use std::net::SocketAddr;
-use tokio::sync::oneshot;
+use std::{net::SocketAddr, time::Duration};
+use tokio::{sync::oneshot, task::JoinHandle};
 
 pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
     /// Returns a reference to the router.
@@ -33,6 +38,51 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
         self.send(peer_ip, Message::Ping(Ping::new(self.router().node_type(), block_locators)));
     }
 
+    /// Sends a `Ping` message to every connected peer, to measure its liveness and round-trip time.
+    /// Disconnects from the peers that missed too many consecutive pings.
+    fn ping_all(&self, block_locators: Option<BlockLocators<N>>) {
+        for peer_ip in self.router().connected_peers() {
+            // Record the ping, and retrieve the number of consecutive pings the peer has missed.
+            let missed_pings = self.router().insert_ping(peer_ip);
+            // If the peer missed too many pings, disconnect from it.
+            if missed_pings >= Router::<N>::MAXIMUM_MISSED_PINGS {
+                debug!("Disconnecting from '{peer_ip}' (missed {missed_pings} pings)");
+                self.send_disconnect(peer_ip, DisconnectReason::PeerHasDisconnected);
+                continue;
+            }
+            // Send the ping.
+            self.send_ping(peer_ip, block_locators.clone());
+        }
+    }
+
+    /// Sends the given blocks for the block request to the peer, in as many `BlockResponse` messages as needed.
+    fn send_block_responses(&self, peer_ip: SocketAddr, request: BlockRequest, blocks: Vec<Block<N>>) {
+        // Determine the maximum number of blocks per message.
+        let chunk_size = DataBlocks::<N>::MAXIMUM_NUMBER_OF_BLOCKS;
+        // Send each chunk of blocks, alongside the part of the block request it covers.
+        for (request, blocks) in request.chunks(chunk_size as u32).zip(blocks.chunks(chunk_size as usize)) {
+            let blocks = Data::Object(DataBlocks(blocks.to_vec()));
+            self.send(peer_ip, Message::BlockResponse(BlockResponse { request, blocks }));
+        }
+    }
+
+    /// Sends a `Disconnect` message with the given reason to the peer, and disconnects from it once the message
+    /// is written, or once a short timeout elapses, so that the peer reliably learns the reason.
+    /// The message is sent ahead of the messages queued for the peer, even if its outbound queue is full.
+    fn send_disconnect(&self, peer_ip: SocketAddr, reason: DisconnectReason) -> JoinHandle<bool> {
+        let delivery = self.send_priority(peer_ip, Message::Disconnect(reason.into()));
+        let router = self.router().clone();
+        tokio::spawn(async move {
+            // Wait for the message to be written, before closing the connection.
+            if let Some(delivery) = delivery {
+                let timeout = Duration::from_millis(Router::<N>::DISCONNECT_FLUSH_TIMEOUT_IN_MS);
+                let _ = tokio::time::timeout(timeout, delivery).await;
+            }
+            // Disconnect from this peer.
+            router.disconnect(peer_ip).await.unwrap_or(false)
+        })
+    }
+
     /// Sends the given message to specified peer.
     ///
     /// This function returns as soon as the message is queued to be sent,
@@ -51,16 +101,29 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
                 return None;
             }
         };
+        // If the peer is not reading its messages, disconnect instead of buffering more of them.
+        if self.outbound_backlog(peer_addr).unwrap_or(0) >= self.router().max_outbound_backlog() {
+            warn!("Disconnecting from '{peer_ip}' (outbound backlog exceeded, dropped '{}')", message.name());
+            self.router().set_disconnect_reason(peer_ip, DisconnectReason::SlowConsumer);
+            self.router().disconnect(peer_ip);
+            return None;
+        }
         // If the message type is a block request, add it to the cache.
         if let Message::BlockRequest(request) = message {
             self.router().cache.insert_outbound_block_request(peer_ip, request);
         }
+        // If the message type is a disconnect, record the reason for the disconnect.
+        if let Message::Disconnect(disconnect) = &message {
+            self.router().set_disconnect_reason(peer_ip, disconnect.reason);
+        }
         // If the message type is a puzzle request, increment the cache.
         if matches!(message, Message::PuzzleRequest(_)) {
             self.router().cache.increment_outbound_puzzle_requests(peer_ip);
         }
         // Retrieve the message name.
         let name = message.name();
+        // Determine whether the message is a puzzle response.
+        let is_puzzle_response = matches!(message, Message::PuzzleResponse(_));
         // Send the message to the peer.
         trace!("Sending '{name}' to '{peer_ip}'");
         let result = self.unicast(peer_addr, message);
@@ -70,9 +133,67 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
             debug!("Disconnecting from '{peer_ip}' (unable to send)");
             self.router().disconnect(peer_ip);
         }
+        // If the message is a puzzle response, clear the in-flight puzzle request once the response is delivered.
+        if is_puzzle_response {
+            if let Ok(delivery) = result {
+                let (sender, receiver) = oneshot::channel();
+                let router = self.router().clone();
+                tokio::spawn(async move {
+                    let outcome = delivery.await;
+                    router.remove_puzzle_request_in_flight(peer_ip);
+                    if let Ok(outcome) = outcome {
+                        let _ = sender.send(outcome);
+                    }
+                });
+                return Some(receiver);
+            }
+            self.router().remove_puzzle_request_in_flight(peer_ip);
+        }
         result.ok()
     }
 
+    /// Sends the given message to the specified peer, ahead of the messages queued for it. The queued messages
+    /// are dropped, so it is only meant for a message that precedes closing the connection, such as a `Disconnect`.
+    fn send_priority(&self, peer_ip: SocketAddr, message: Message<N>) -> Option<oneshot::Receiver<io::Result<()>>> {
+        // Determine whether to send the message.
+        if !self.can_send(peer_ip, &message) {
+            return None;
+        }
+        // Resolve the listener IP to the (ambiguous) peer address.
+        let Some(peer_addr) = self.router().resolve_to_ambiguous(&peer_ip) else {
+            warn!("Unable to resolve the listener IP address '{peer_ip}'");
+            return None;
+        };
+        // If the message type is a disconnect, record the reason for the disconnect.
+        if let Message::Disconnect(disconnect) = &message {
+            self.router().set_disconnect_reason(peer_ip, disconnect.reason);
+        }
+        // Retrieve the message name.
+        let name = message.name();
+        // Send the message to the peer, ahead of the queued messages.
+        trace!("Sending '{name}' to '{peer_ip}' (priority)");
+        match self.unicast_priority(peer_addr, message) {
+            Ok(delivery) => Some(delivery),
+            Err(e) => {
+                warn!("Failed to send '{name}' to '{peer_ip}': {e}");
+                None
+            }
+        }
+    }
+
+    /// Disconnects from every connected peer that matches the given predicate, with the given reason.
+    /// Returns the list of disconnected peer IPs.
+    fn disconnect_where<F: Fn(&Peer<N>) -> bool>(&self, predicate: F, reason: DisconnectReason) -> Vec<SocketAddr> {
+        // Collect the matching peers, before disconnecting from any of them.
+        let peer_ips = self.router().connected_peers_where(predicate);
+        for peer_ip in &peer_ips {
+            debug!("Disconnecting from '{peer_ip}' ({reason:?})");
+            // Inform the peer of the reason for the disconnect, and disconnect from it.
+            self.send_disconnect(*peer_ip, reason);
+        }
+        peer_ips
+    }
+
     /// Sends the given message to every connected peer, excluding the sender and any specified peer IPs.
     fn propagate(&self, message: Message<N>, excluded_peers: &[SocketAddr]) {
         // TODO (howardwu): Serialize large messages once only.
@@ -93,11 +214,13 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
 
         // Prepare the peers to send to.
         let connected_peers = self.router().connected_peers();
-        let peers = connected_peers.iter().filter(|peer_ip| !excluded_peers.contains(peer_ip));
+        let peers = connected_peers.into_iter().filter(|peer_ip| !excluded_peers.contains(peer_ip)).collect();
+        // Select up to the fanout limit of peers, leaving the remainder to their own gossip.
+        let peers = self.router().select_propagation_peers(peers);
 
-        // Iterate through all peers that are not the sender and excluded peers.
+        // Iterate through the selected peers.
         for peer_ip in peers {
-            self.send(*peer_ip, message.clone());
+            self.send(peer_ip, message.clone());
         }
     }
 
@@ -121,11 +244,13 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
 
         // Prepare the peers to send to.
         let connected_validators = self.router().connected_validators();
-        let peers = connected_validators.iter().filter(|peer_ip| !excluded_peers.contains(peer_ip));
+        let peers = connected_validators.into_iter().filter(|peer_ip| !excluded_peers.contains(peer_ip)).collect();
+        // Select up to the fanout limit of validators, leaving the remainder to their own gossip.
+        let peers = self.router().select_propagation_peers(peers);
 
-        // Iterate through all validators that are not the sender and excluded validators.
+        // Iterate through the selected validators.
         for peer_ip in peers {
-            self.send(*peer_ip, message.clone());
+            self.send(peer_ip, message.clone());
         }
     }
 
@@ -136,6 +261,11 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
             warn!("Attempted to send to a non-connected peer {peer_ip}");
             return false;
         }
+        // Ensure the peer understands the message type.
+        if !self.router().get_connected_peer(&peer_ip).map_or(true, |peer| peer.supports(message)) {
+            trace!("Skipping '{}' to '{peer_ip}' (unsupported by the peer)", message.name());
+            return false;
+        }
         // Determine whether to send the message.
         match message {
             Message::UnconfirmedSolution(message) => {
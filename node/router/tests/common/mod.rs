@@ -20,11 +20,34 @@ use std::{
     env,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
+use futures_util::{SinkExt, StreamExt};
+use rand::rngs::OsRng;
 use snarkos_account::Account;
-use snarkos_node_router::{messages::NodeType, Router};
-use snarkvm::prelude::{block::Block, FromBytes, Network, Testnet3 as CurrentNetwork};
+use snarkos_node_router::{
+    messages::{
+        CapabilitySet,
+        ChallengeRequest,
+        ChallengeResponse,
+        DisconnectReason,
+        Message,
+        MessageCodec,
+        NodeType,
+    },
+    Router,
+};
+use snarkos_node_tcp::ConnectionSide;
+use snarkvm::{
+    ledger::narwhal::Data,
+    prelude::{block::Block, FromBytes, Network, Testnet3 as CurrentNetwork},
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::codec::Framed;
 
 /// A helper macro to print the TCP listening address, along with the connected and connecting peers.
 #[macro_export]
@@ -115,3 +138,77 @@ pub async fn validator(listening_port: u16, max_peers: u16) -> TestRouter<Curren
     .expect("couldn't create validator router")
     .into()
 }
+
+/// Starts a mock peer that answers each challenge request with a disconnect for the given reason,
+/// or never answers if no reason is given. Returns the address of the peer, and its number of dials.
+#[allow(dead_code)]
+pub async fn mock_handshake_listener(reason: Option<DisconnectReason>) -> (SocketAddr, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let peer_ip = listener.local_addr().unwrap();
+    let num_dials = Arc::new(AtomicUsize::new(0));
+    let num_dials_clone = num_dials.clone();
+    tokio::spawn(async move {
+        let mut streams = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            num_dials_clone.fetch_add(1, Ordering::SeqCst);
+            let mut framed = Framed::new(stream, MessageCodec::<CurrentNetwork>::handshake());
+            if let Some(reason) = reason {
+                if let Some(Ok(Message::ChallengeRequest(..))) = framed.next().await {
+                    let _ = framed.send(reason.into()).await;
+                }
+            }
+            streams.push(framed);
+        }
+    });
+    (peer_ip, num_dials)
+}
+
+/// Connects a mock peer to the given router, and completes the handshake as a client.
+/// The returned stream is left unread, for the test to use as it sees fit.
+#[allow(dead_code)]
+pub async fn mock_connected_peer(
+    node: &TestRouter<CurrentNetwork>,
+    listener_port: u16,
+) -> (SocketAddr, Framed<TcpStream, MessageCodec<CurrentNetwork>>) {
+    mock_connected_peer_with_capabilities(node, listener_port, Message::capabilities()).await
+}
+
+/// Connects a mock peer advertising the given capabilities to the given router, as in `mock_connected_peer`.
+#[allow(dead_code)]
+pub async fn mock_connected_peer_with_capabilities(
+    node: &TestRouter<CurrentNetwork>,
+    listener_port: u16,
+    capabilities: CapabilitySet,
+) -> (SocketAddr, Framed<TcpStream, MessageCodec<CurrentNetwork>>) {
+    let request = ChallengeRequest::new(listener_port, NodeType::Client, sample_account().address(), 0);
+    mock_connected_peer_with_request(node, request.with_capabilities(capabilities)).await
+}
+
+/// Connects a mock peer sending the given challenge request to the given router, as in `mock_connected_peer`.
+#[allow(dead_code)]
+pub async fn mock_connected_peer_with_request(
+    node: &TestRouter<CurrentNetwork>,
+    request: ChallengeRequest<CurrentNetwork>,
+) -> (SocketAddr, Framed<TcpStream, MessageCodec<CurrentNetwork>>) {
+    let account = sample_account();
+    let stream = TcpStream::connect(node.local_ip()).await.unwrap();
+    let peer_ip = SocketAddr::new(stream.local_addr().unwrap().ip(), request.listener_port);
+    let mut framed = Framed::new(stream, MessageCodec::<CurrentNetwork>::handshake());
+
+    // Send the challenge request.
+    framed.send(Message::ChallengeRequest(request)).await.unwrap();
+    // Receive the challenge response, followed by the challenge request.
+    assert!(matches!(framed.next().await, Some(Ok(Message::ChallengeResponse(..)))));
+    let Some(Ok(Message::ChallengeRequest(request))) = framed.next().await else {
+        panic!("Expected a challenge request");
+    };
+    // Send the challenge response.
+    let signature = account.sign_bytes(&request.nonce.to_le_bytes(), &mut OsRng).unwrap();
+    let genesis_header = *sample_genesis_block::<CurrentNetwork>().header();
+    let response = ChallengeResponse { genesis_header, signature: Data::Object(signature) };
+    framed.send(Message::ChallengeResponse(response)).await.unwrap();
+    // Switch to the codec of an established connection, as the initiator.
+    *framed.codec_mut() = MessageCodec::default().with_version(2).with_side(Some(ConnectionSide::Initiator));
+
+    (peer_ip, framed)
+}
@@ -44,15 +44,41 @@ use snarkvm::prelude::{
 };
 
 use async_trait::async_trait;
-use std::{io, net::SocketAddr};
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use tracing::*;
 
 #[derive(Clone)]
-pub struct TestRouter<N: Network>(Router<N>);
+pub struct TestRouter<N: Network> {
+    router: Router<N>,
+    /// The number of unconfirmed solutions passed to the handler.
+    num_unconfirmed_solutions: Arc<AtomicUsize>,
+    /// Whether the handler rejects the unconfirmed solutions, as if their proofs were invalid.
+    rejects_solutions: Arc<AtomicBool>,
+}
 
 impl<N: Network> From<Router<N>> for TestRouter<N> {
     fn from(router: Router<N>) -> Self {
-        Self(router)
+        Self { router, num_unconfirmed_solutions: Default::default(), rejects_solutions: Default::default() }
+    }
+}
+
+impl<N: Network> TestRouter<N> {
+    /// Returns the number of unconfirmed solutions passed to the handler.
+    pub fn number_of_unconfirmed_solutions(&self) -> usize {
+        self.num_unconfirmed_solutions.load(Ordering::SeqCst)
+    }
+
+    /// Sets whether the handler rejects the unconfirmed solutions, as if their proofs were invalid.
+    pub fn set_rejects_solutions(&self, rejects_solutions: bool) {
+        self.rejects_solutions.store(rejects_solutions, Ordering::SeqCst);
     }
 }
 
@@ -60,7 +86,7 @@ impl<N: Network> core::ops::Deref for TestRouter<N> {
     type Target = Router<N>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.router
     }
 }
 
@@ -110,8 +136,27 @@ impl<N: Network> Writing for TestRouter<N> {
 
     /// Creates an [`Encoder`] used to write the outbound messages to the target stream.
     /// The `side` parameter indicates the connection side **from the node's perspective**.
-    fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, addr: SocketAddr, side: ConnectionSide) -> Self::Codec {
+        MessageCodec::default()
+            .with_version(self.router().codec_version(&addr))
+            .with_side(self.router().codec_side(&addr, side))
+            .with_compression(self.router().compression(&addr))
+            .with_traffic(self.router().traffic().clone())
+    }
+
+    /// Returns the maximum time allowed to write a single message to a peer.
+    fn write_timeout(&self) -> Option<Duration> {
+        Some(self.router().write_timeout())
+    }
+
+    /// Marks the peer as a slow consumer, as writing a message to it timed out.
+    fn on_write_timeout(&self, peer_addr: SocketAddr) {
+        self.router().handle_write_timeout(peer_addr)
+    }
+
+    /// Returns the window over which the messages queued for a peer are coalesced into a single write, if any.
+    fn coalescing_window(&self) -> Option<Duration> {
+        self.router().write_coalescing_window()
     }
 }
 
@@ -122,18 +167,35 @@ impl<N: Network> Reading for TestRouter<N> {
 
     /// Creates a [`Decoder`] used to interpret messages from the network.
     /// The `side` param indicates the connection side **from the node's perspective**.
-    fn codec(&self, _peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, side: ConnectionSide) -> Self::Codec {
+        let node = self.clone();
+        MessageCodec::default()
+            .with_version(self.router().codec_version(&peer_addr))
+            .with_side(self.router().codec_side(&peer_addr, side))
+            .with_compression(self.router().compression(&peer_addr))
+            .with_traffic(self.router().traffic().clone())
+            .with_malformed_frame_handler(move || node.malformed_frame(peer_addr))
+    }
+
+    /// Returns the maximum number of bytes buffered across all peers, awaiting the rest of an inbound message.
+    fn max_inbound_buffer_bytes(&self) -> Option<usize> {
+        Some(self.router().max_inbound_buffer_bytes())
+    }
+
+    /// Marks the peer holding the largest inbound buffer, once the buffers exceed their limit.
+    fn on_inbound_buffer_overflow(&self, peer_addr: SocketAddr) {
+        self.router().handle_inbound_buffer_overflow(peer_addr)
     }
 
     /// Processes a message received from the network.
     async fn process_message(&self, peer_ip: SocketAddr, message: Self::Message) -> io::Result<()> {
-        // Process the message. Disconnect if the peer violated the protocol.
-        if let Err(error) = self.inbound(peer_ip, message).await {
+        // Process the message, unless the node is shutting down. Disconnect if the peer violated the protocol.
+        if let Some(Err(error)) = self.inbound_until_shutdown(peer_ip, message).await {
             warn!("Disconnecting from '{peer_ip}' - {error}");
-            self.send(peer_ip, Message::Disconnect(DisconnectReason::ProtocolViolation.into()));
-            // Disconnect from this peer.
-            self.router().disconnect(peer_ip);
+            // Place the peer on probation, or restrict it if it is a repeat offender.
+            self.router().record_violation(peer_ip);
+            // Inform the peer of the violation, and disconnect from it.
+            self.send_disconnect(peer_ip, DisconnectReason::ProtocolViolation);
         }
         Ok(())
     }
@@ -147,14 +209,16 @@ impl<N: Network> Heartbeat<N> for TestRouter<N> {}
 impl<N: Network> Outbound<N> for TestRouter<N> {
     /// Returns a reference to the router.
     fn router(&self) -> &Router<N> {
-        &self.0
+        &self.router
     }
 }
 
 #[async_trait]
 impl<N: Network> Inbound<N> for TestRouter<N> {
-    /// Handles a `BlockRequest` message.
-    fn block_request(&self, _peer_ip: SocketAddr, _message: BlockRequest) -> bool {
+    /// Handles a `BlockRequest` message, serving the genesis block in place of each requested block.
+    fn block_request(&self, peer_ip: SocketAddr, message: BlockRequest) -> bool {
+        let num_blocks = message.end_height.saturating_sub(message.start_height) as usize;
+        self.send_block_responses(peer_ip, message, vec![sample_genesis_block(); num_blocks]);
         true
     }
 
@@ -164,7 +228,9 @@ impl<N: Network> Inbound<N> for TestRouter<N> {
     }
 
     /// Handles an `Ping` message.
-    fn ping(&self, _peer_ip: SocketAddr, _message: Ping<N>) -> bool {
+    fn ping(&self, peer_ip: SocketAddr, _message: Ping<N>) -> bool {
+        // Send a `Pong` message to the peer.
+        self.send(peer_ip, Message::Pong(Pong { is_fork: Some(false) }));
         true
     }
 
@@ -174,7 +240,7 @@ impl<N: Network> Inbound<N> for TestRouter<N> {
     }
 
     /// Handles an `PuzzleRequest` message.
-    fn puzzle_request(&self, _peer_ip: SocketAddr) -> bool {
+    async fn puzzle_request(&self, _peer_ip: SocketAddr) -> bool {
         true
     }
 
@@ -187,9 +253,13 @@ impl<N: Network> Inbound<N> for TestRouter<N> {
     async fn unconfirmed_solution(
         &self,
         _peer_ip: SocketAddr,
-        _serialized: UnconfirmedSolution<N>,
+        serialized: UnconfirmedSolution<N>,
         _solution: ProverSolution<N>,
     ) -> bool {
+        self.num_unconfirmed_solutions.fetch_add(1, Ordering::SeqCst);
+        if !self.rejects_solutions.load(Ordering::SeqCst) {
+            self.router().insert_accepted_solution(serialized.solution_id);
+        }
         true
     }
 
@@ -197,9 +267,10 @@ impl<N: Network> Inbound<N> for TestRouter<N> {
     async fn unconfirmed_transaction(
         &self,
         _peer_ip: SocketAddr,
-        _serialized: UnconfirmedTransaction<N>,
+        serialized: UnconfirmedTransaction<N>,
         _transaction: Transaction<N>,
     ) -> bool {
+        self.router().insert_accepted_transaction(serialized.transaction_id);
         true
     }
 }
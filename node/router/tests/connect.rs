@@ -15,9 +15,47 @@
 mod common;
 use common::*;
 
-use snarkos_node_tcp::{protocols::Handshake, P2P};
+use snarkos_node_router::{
+    messages::{
+        AdmissionSolution,
+        ChallengeRequest,
+        DisconnectReason,
+        Message,
+        MessageCodec,
+        NodeType,
+        PeerRequest,
+        PeerResponse,
+    },
+    ConnectError,
+    EvictOldest,
+    HandshakeError,
+    Inbound,
+    ListenerPolicy,
+    LoadGovernor,
+    Outbound,
+    PeerClassifier,
+    Router,
+};
+use snarkos_node_tcp::{
+    protocols::{Handshake, Reading, Writing},
+    ConnectionSide,
+    P2P,
+};
+use snarkvm::prelude::{Testnet3 as CurrentNetwork, ToBytes};
 
 use core::time::Duration;
+use deadline::deadline;
+use futures_util::{future::join_all, SinkExt, StreamExt};
+use parking_lot::Mutex;
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::codec::Framed;
 
 #[tokio::test]
 async fn test_connect_without_handshake() {
@@ -185,3 +223,792 @@ async fn test_connect_simultaneously_with_handshake() {
         assert_eq!(node1.number_of_connected_peers(), 1);
     }
 }
+
+#[tokio::test]
+async fn test_handshake_hooks() {
+    // Create 2 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 2).await;
+
+    // Register the handshake callbacks.
+    let events0 = Arc::new(Mutex::new(Vec::new()));
+    let events1 = Arc::new(Mutex::new(Vec::new()));
+    {
+        let events0 = events0.clone();
+        node0.on_handshake_complete(move |peer_ip, peer_side| events0.lock().push((peer_ip, peer_side)));
+        // Register a failing callback, which must not prevent the others nor the connection.
+        node0.on_handshake_complete(|_, _| panic!("This callback fails"));
+        let events1 = events1.clone();
+        node1.on_handshake_complete(move |peer_ip, peer_side| events1.lock().push((peer_ip, peer_side)));
+    }
+
+    // Enable handshake protocol.
+    node0.enable_handshake().await;
+    node1.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check the connection was established.
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert_eq!(node1.number_of_connected_peers(), 1);
+
+    // Check the callbacks fired with the correct address and side.
+    assert_eq!(*events0.lock(), vec![(node1.local_ip(), ConnectionSide::Responder)]);
+    assert_eq!(events1.lock().len(), 1);
+    assert_eq!(events1.lock()[0].0, node0.local_ip());
+    assert_eq!(events1.lock()[0].1, ConnectionSide::Initiator);
+}
+
+#[tokio::test]
+async fn test_self_connect_is_refused() {
+    // Create a router listening on all interfaces, so that its loopback address is not recognized as itself by TCP.
+    let node0: TestRouter<CurrentNetwork> = Router::new(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+        NodeType::Validator,
+        sample_account(),
+        &[],
+        2,
+        true,
+    )
+    .await
+    .unwrap()
+    .into();
+
+    // Enable handshake protocol.
+    node0.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to itself, through the loopback address.
+    let loopback_ip = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), node0.local_ip().port());
+    let result = node0.tcp().connect(loopback_ip).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    print_tcp!(node0);
+
+    // Check the self-connection was refused.
+    assert!(result.is_err());
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert_eq!(node0.tcp().num_connected(), 0);
+}
+
+#[tokio::test]
+async fn test_codec_version_negotiation() {
+    // Create 2 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 2).await;
+
+    // Enable handshake protocol, and start listening.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that the upgraded peer negotiated the latest codec version.
+    let node1_ip = node1.local_ip();
+    assert_eq!(node0.codec_version(&node1_ip), 2);
+
+    // Check that a peer on the minimum protocol version falls back to codec version 0.
+    let node_type = node0.get_connected_peer(&node1_ip).unwrap().node_type();
+    node0.update_connected_peer(node1_ip, node_type, |peer| peer.set_version(11)).unwrap();
+    assert_eq!(node0.codec_version(&node1_ip), 0);
+
+    // Check that an unknown peer uses codec version 0.
+    assert_eq!(node0.codec_version(&node0.local_ip()), 0);
+}
+
+#[tokio::test]
+async fn test_peer_context_reflects_handshake() {
+    // Create 2 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 2).await;
+
+    // Enable handshake protocol, and start listening.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let (node0_ip, node1_ip) = (node0.local_ip(), node1.local_ip());
+
+    // Check that node0 sees node1 as the responder of an outbound connection.
+    let context = node0.peer_context(&node1_ip).unwrap();
+    assert_eq!(context.peer_ip, node1_ip);
+    assert_eq!(context.side, ConnectionSide::Responder);
+    assert!(!context.is_inbound());
+    assert_eq!(context.node_type, NodeType::Client);
+    assert_eq!(context.version, Message::<CurrentNetwork>::VERSION);
+    assert_eq!(context.codec_version, 2);
+    assert!(!context.is_pinned);
+
+    // Check that node1 sees node0 as the initiator of an inbound connection.
+    let context = node1.peer_context(&node0_ip).unwrap();
+    assert_eq!(context.side, ConnectionSide::Initiator);
+    assert!(context.is_inbound());
+    assert_eq!(context.node_type, NodeType::Validator);
+
+    // Check that the context reflects the pinned status, and that unknown peers have no context.
+    node0.pin_peer(node1_ip).unwrap();
+    assert!(node0.peer_context(&node1_ip).unwrap().is_pinned);
+    assert!(node0.peer_context(&node0_ip).is_none());
+}
+
+#[tokio::test]
+async fn test_upgrade_peer_codec() {
+    // Create 2 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 2).await;
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node1 to node0.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let (node0_ip, node1_ip) = (node0.local_ip(), node1.local_ip());
+    assert_eq!(node0.get_connected_peer(&node1_ip).unwrap().codec_version(), 2);
+
+    // Switch the connection to codec version 0, in place.
+    node0.upgrade_peer_codec(node1_ip, 0).await.unwrap();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Check that both sides recorded the new codec version, without reconnecting.
+    assert_eq!(node0.get_connected_peer(&node1_ip).unwrap().codec_version(), 0);
+    assert_eq!(node1.get_connected_peer(&node0_ip).unwrap().codec_version(), 0);
+
+    // Request the peers of node0, and check that the response is encoded and decoded with codec version 0.
+    node1.send(node0_ip, Message::PeerRequest(PeerRequest));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let response = Message::<CurrentNetwork>::PeerResponse(PeerResponse { peers: vec![] });
+    let num_bytes = response.to_bytes_le().unwrap().len() as u64;
+    assert_eq!(node0.traffic().bytes_sent(&response), num_bytes);
+    assert_eq!(node1.traffic().bytes_received(&response), num_bytes);
+    assert!(node0.is_connected(&node1_ip));
+    assert!(node1.is_connected(&node0_ip));
+
+    // Check that an unsupported codec version is refused.
+    assert!(node0.upgrade_peer_codec(node1_ip, u8::MAX).await.is_err());
+    assert!(node0.is_connected(&node1_ip));
+}
+
+#[tokio::test]
+async fn test_peer_group_cap() {
+    /// A classifier that assigns every peer to the same group.
+    struct SameGroupClassifier;
+
+    impl PeerClassifier for SameGroupClassifier {
+        fn classify(&self, _peer_ip: SocketAddr) -> u64 {
+            7
+        }
+    }
+
+    // Create 3 routers.
+    let node0 = validator(0, 5).await;
+    let node1 = client(0, 5).await;
+    let node2 = client(0, 5).await;
+
+    // Allow a single peer per group in node0.
+    node0.set_peer_classifier(SameGroupClassifier);
+    node0.set_max_peers_per_group(1);
+
+    // Enable handshake protocol, and start listening.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node1 to node0.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert_eq!(node0.number_of_connected_peers_in_group(7), 1);
+
+    // Connect node2 to node0, and check that it is rejected, as its group is full.
+    node2.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert!(node0.is_connected(&node1.local_ip()));
+    assert!(!node0.is_connected(&node2.local_ip()));
+    assert_eq!(node2.number_of_connected_peers(), 0);
+}
+
+#[tokio::test]
+async fn test_eviction_at_capacity() {
+    // Create 4 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 5).await;
+    let node2 = client(0, 5).await;
+    let node3 = client(0, 5).await;
+
+    // Enable handshake protocol, and start listening.
+    for node in [&node0, &node1, &node2, &node3] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node1 to node0.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&node1.local_ip()));
+
+    // Connect node2 to node0, and check that it is rejected under the default strategy.
+    node2.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.connected_peers(), vec![node1.local_ip()]);
+
+    // Connect node3 to node0 under the evict-oldest strategy, and check that it replaces node1.
+    node0.set_eviction_strategy(EvictOldest);
+    node3.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.connected_peers(), vec![node3.local_ip()]);
+    assert_eq!(node1.number_of_connected_peers(), 0);
+
+    // Pin node3, and check that it is not evicted in favour of node2.
+    node0.pin_peer(node3.local_ip()).unwrap();
+    node2.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.connected_peers(), vec![node3.local_ip()]);
+}
+
+#[tokio::test]
+async fn test_overloaded_host_rejects_unpinned_peers() {
+    /// A governor that always reports a high load.
+    struct OverloadedGovernor;
+
+    impl LoadGovernor for OverloadedGovernor {
+        fn is_overloaded(&self) -> bool {
+            true
+        }
+    }
+
+    // Create 3 routers.
+    let node0 = validator(0, 5).await;
+    let node1 = client(0, 5).await;
+    let node2 = client(0, 5).await;
+
+    // Report a high load in node0.
+    node0.set_load_governor(OverloadedGovernor);
+
+    // Enable handshake protocol, and start listening.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node1 to node0, and check that it is rejected, as the host is overloaded.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert_eq!(node1.number_of_connected_peers(), 0);
+
+    // Pin node2 in node0, and check that it connects regardless of the load.
+    node0.pin_peer(node2.local_ip()).unwrap();
+    node2.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert!(node0.is_connected(&node2.local_ip()));
+
+    // Check that node0 may still connect to other peers itself.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&node1.local_ip()));
+}
+
+#[tokio::test]
+async fn test_paused_accepting_keeps_existing_peers() {
+    // Create 4 routers.
+    let node0 = validator(0, 5).await;
+    let node1 = client(0, 5).await;
+    let node2 = client(0, 5).await;
+    let node3 = client(0, 5).await;
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1, &node2, &node3] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node1 to node0, and pause accepting new connections in node0.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&node1.local_ip()));
+    node0.pause_accepting();
+    assert!(node0.is_accepting_paused());
+
+    // Connect node2 to node0, and check that it is refused.
+    node2.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!node0.is_connected(&node2.local_ip()));
+    assert_eq!(node2.number_of_connected_peers(), 0);
+
+    // Check that the traffic from node1 continues.
+    let request = Message::<CurrentNetwork>::PeerRequest(PeerRequest);
+    node1.send(node0.local_ip(), request.clone());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.traffic().bytes_received(&request), request.to_bytes_le().unwrap().len() as u64);
+    assert!(node0.is_connected(&node1.local_ip()));
+
+    // Pin node3 in node0, and check that it connects regardless of the pause.
+    node0.pin_peer(node3.local_ip()).unwrap();
+    node3.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&node3.local_ip()));
+
+    // Resume accepting new connections, and check that node2 connects.
+    node0.resume_accepting();
+    node2.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&node2.local_ip()));
+    assert_eq!(node0.number_of_connected_peers(), 3);
+}
+
+/// Connects a mock peer to the given router, and sends a challenge request for the given listener port.
+async fn mock_handshake_peer(
+    node: &TestRouter<CurrentNetwork>,
+    listener_port: u16,
+) -> (SocketAddr, Framed<TcpStream, MessageCodec<CurrentNetwork>>) {
+    let stream = TcpStream::connect(node.local_ip()).await.unwrap();
+    let peer_ip = SocketAddr::new(stream.local_addr().unwrap().ip(), listener_port);
+    let mut framed = Framed::new(stream, MessageCodec::<CurrentNetwork>::handshake());
+    let request = ChallengeRequest::new(listener_port, NodeType::Client, sample_account().address(), 0);
+    framed.send(Message::ChallengeRequest(request)).await.unwrap();
+    (peer_ip, framed)
+}
+
+#[tokio::test]
+async fn test_handshake_hang_up_is_not_restricted() {
+    // Create a router.
+    let node0 = validator(0, 1).await;
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Start a handshake from a mock peer, and close the socket mid-handshake.
+    let (peer_ip, framed) = mock_handshake_peer(&node0, 4140).await;
+    drop(framed);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that the peer was not connected, and not restricted.
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert!(!node0.is_restricted(&peer_ip));
+    assert!(node0.restricted_peers().is_empty());
+}
+
+/// Starts a handshake from a mock peer on the given port, and violates the handshake protocol.
+async fn fail_handshake(node: &TestRouter<CurrentNetwork>, listener_port: u16) -> SocketAddr {
+    let (peer_ip, mut framed) = mock_handshake_peer(node, listener_port).await;
+    // Receive the challenge response and the challenge request.
+    assert!(matches!(framed.next().await, Some(Ok(Message::ChallengeResponse(..)))));
+    assert!(matches!(framed.next().await, Some(Ok(Message::ChallengeRequest(..)))));
+    // Send an unexpected message, instead of the challenge response.
+    framed.send(Message::PeerRequest(PeerRequest)).await.unwrap();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    peer_ip
+}
+
+#[tokio::test]
+async fn test_handshake_protocol_failure_is_probated() {
+    // Create a router.
+    let node0 = validator(0, 1).await;
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Violate the handshake protocol once.
+    let peer_ip = fail_handshake(&node0, 4141).await;
+
+    // Check that the peer was not connected, and was placed on probation instead of being restricted.
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert!(node0.is_on_probation(&peer_ip));
+    assert!(!node0.is_restricted(&peer_ip));
+}
+
+#[tokio::test]
+async fn test_repeated_handshake_protocol_failure_is_restricted() {
+    // Create a router, without a probation cooldown so that the peer may retry immediately.
+    let node0 = validator(0, 1).await;
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+    let mut config = node0.config();
+    config.probation_cooldown = Duration::ZERO;
+    node0.set_config(config);
+
+    // Violate the handshake protocol once, and check that the peer is not restricted.
+    let peer_ip = fail_handshake(&node0, 4142).await;
+    assert!(node0.is_on_probation(&peer_ip));
+    assert!(!node0.is_restricted(&peer_ip));
+
+    // Violate the handshake protocol again from the same address, and check that the peer is restricted.
+    assert_eq!(fail_handshake(&node0, 4142).await, peer_ip);
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert!(node0.is_restricted(&peer_ip));
+    assert!(!node0.is_on_probation(&peer_ip));
+}
+
+#[tokio::test]
+async fn test_handshake_protocol_failure_without_tolerance_is_restricted() {
+    // Create a router that tolerates no violations.
+    let node0 = validator(0, 1).await;
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+    let mut config = node0.config();
+    config.tolerated_violations = 0;
+    node0.set_config(config);
+
+    // Violate the handshake protocol once.
+    let peer_ip = fail_handshake(&node0, 4143).await;
+
+    // Check that the peer was restricted immediately.
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert!(node0.is_restricted(&peer_ip));
+}
+
+#[tokio::test]
+async fn test_concurrent_connects_dial_once() {
+    // Create a router, with a short handshake timeout.
+    let node0 = validator(0, 2).await;
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+    let mut config = node0.config();
+    config.handshake_timeout = Duration::from_millis(300);
+    node0.set_config(config);
+
+    // Start a listener that counts the dials, and never responds to the handshake.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let peer_ip = listener.local_addr().unwrap();
+    let num_dials = Arc::new(AtomicUsize::new(0));
+    let num_dials_clone = num_dials.clone();
+    tokio::spawn(async move {
+        let mut streams = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            num_dials_clone.fetch_add(1, Ordering::SeqCst);
+            streams.push(stream);
+        }
+    });
+
+    // Connect to the listener twice, concurrently.
+    let first = node0.try_connect(peer_ip);
+    let second = node0.try_connect(peer_ip);
+    assert!(first.is_ok());
+    assert_eq!(second.unwrap_err(), ConnectError::AlreadyConnecting(peer_ip));
+    assert!(node0.is_connecting(&peer_ip));
+
+    // Check that the first attempt fails once the handshake times out.
+    assert!(!first.unwrap().await.unwrap());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Check that only one dial occurred, and that the in-flight entry was cleaned up.
+    assert_eq!(num_dials.load(Ordering::SeqCst), 1);
+    assert!(!node0.is_connecting(&peer_ip));
+    assert!(node0.try_connect(peer_ip).is_ok());
+}
+
+#[tokio::test]
+async fn test_handshake_errors_are_classified() {
+    // Create a router, with a short handshake timeout.
+    let node0 = validator(0, 2).await;
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+    let mut config = node0.config();
+    config.handshake_timeout = Duration::from_millis(300);
+    node0.set_config(config);
+
+    // Check that a peer that never responds times out, which is transient.
+    let (silent_ip, _) = mock_handshake_listener(None).await;
+    let error = node0.dial(silent_ip).unwrap().await.unwrap().unwrap_err();
+    assert_eq!(error, HandshakeError::TimedOut(silent_ip));
+    assert!(error.is_transient());
+
+    // Check that a peer that rejects this node's version is a permanent failure.
+    let (outdated_ip, _) = mock_handshake_listener(Some(DisconnectReason::OutdatedClientVersion)).await;
+    let error = node0.dial(outdated_ip).unwrap().await.unwrap().unwrap_err();
+    assert_eq!(error, HandshakeError::Disconnected(outdated_ip, DisconnectReason::OutdatedClientVersion));
+    assert!(!error.is_transient());
+}
+
+#[tokio::test]
+async fn test_multiple_listeners_share_connected_peers() {
+    // Create 3 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+    // Start a second listener on node0.
+    let second_ip = node0.add_listener("127.0.0.1:0".parse().unwrap(), ListenerPolicy::Standard).await.unwrap();
+    assert_eq!(node0.listening_addrs(), vec![node0.local_ip(), second_ip]);
+
+    // Connect node1 to the main listener, and node2 to the second listener.
+    node1.connect(node0.local_ip());
+    node2.connect(second_ip);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that both peers are counted in the same connected peers.
+    assert_eq!(node0.number_of_connected_peers(), 2);
+    assert!(node0.is_connected(&node1.local_ip()));
+    assert!(node0.is_connected(&node2.local_ip()));
+    assert!(node1.is_connected(&node0.local_ip()));
+    assert!(node2.is_connected(&second_ip));
+}
+
+#[tokio::test]
+async fn test_always_allow_listener_admits_restricted_peers() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+    // Start an admin listener on node0, and restrict node1.
+    let admin_ip = node0.add_listener("127.0.0.1:0".parse().unwrap(), ListenerPolicy::AlwaysAllow).await.unwrap();
+    node0.insert_restricted_peer(node1.local_ip());
+
+    // Check that node1 is rejected on the main listener.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 0);
+
+    // Check that node1 is admitted on the admin listener.
+    node1.connect(admin_ip);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&node1.local_ip()));
+}
+
+#[tokio::test]
+async fn test_rapid_reconnects_are_soft_banned() {
+    const MAX_CONNECTION_ATTEMPTS: usize = 3;
+
+    // Create a router, which soft-bans peers after a few connection attempts.
+    let node0 = validator(0, 1).await;
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+    let mut config = node0.config();
+    config.max_connection_attempts = MAX_CONNECTION_ATTEMPTS;
+    node0.set_config(config);
+
+    // Reconnect from a mock peer up to the limit, hanging up cleanly after the challenge response each time.
+    for _ in 0..MAX_CONNECTION_ATTEMPTS {
+        let (peer_ip, mut framed) = mock_handshake_peer(&node0, 4143).await;
+        assert!(matches!(framed.next().await, Some(Ok(Message::ChallengeResponse(..)))));
+        drop(framed);
+        assert!(!node0.is_soft_banned(&peer_ip));
+    }
+
+    // Check that the next attempt is refused, and the peer is soft-banned, but not restricted.
+    let (peer_ip, mut framed) = mock_handshake_peer(&node0, 4143).await;
+    assert!(!matches!(framed.next().await, Some(Ok(Message::ChallengeResponse(..)))));
+    assert!(node0.is_soft_banned(&peer_ip));
+    assert!(!node0.is_restricted(&peer_ip));
+    assert_eq!(node0.try_connect(peer_ip).unwrap_err(), ConnectError::SoftBanned(peer_ip));
+    // Check that the soft-banned peer is refused again, without extending the attempts of other peers.
+    let (_, mut framed) = mock_handshake_peer(&node0, 4143).await;
+    assert!(!matches!(framed.next().await, Some(Ok(Message::ChallengeResponse(..)))));
+    let (other_ip, mut framed) = mock_handshake_peer(&node0, 4144).await;
+    assert!(matches!(framed.next().await, Some(Ok(Message::ChallengeResponse(..)))));
+    assert!(!node0.is_soft_banned(&other_ip));
+
+    // Check that lifting the soft-ban admits the peer again.
+    node0.remove_soft_ban(&peer_ip);
+    let (_, mut framed) = mock_handshake_peer(&node0, 4143).await;
+    assert!(matches!(framed.next().await, Some(Ok(Message::ChallengeResponse(..)))));
+}
+
+/// Returns 2 routers listening for connections, where the first demands an admission challenge
+/// from its connecting peers if more than the given number of peers connect to it per second.
+async fn admission_pair(admission_rate_threshold: usize) -> [TestRouter<CurrentNetwork>; 2] {
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 2).await;
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+    let mut config = node0.config();
+    config.admission_rate_threshold = admission_rate_threshold;
+    config.admission_difficulty = 8;
+    node0.set_config(config);
+    [node0, node1]
+}
+
+#[tokio::test]
+async fn test_admission_challenge_is_solved_under_load() {
+    // Simulate a high connection rate, so that every connection exceeds the threshold.
+    let [node0, node1] = admission_pair(0).await;
+
+    // Connect node1 to node0.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that node1 solved the admission challenge, and connected.
+    assert!(node0.is_under_connection_load());
+    assert_eq!(node0.number_of_admission_challenges(), 1);
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert_eq!(node1.number_of_connected_peers(), 1);
+}
+
+#[tokio::test]
+async fn test_admission_challenge_is_bypassed_under_low_load() {
+    let [node0, node1] = admission_pair(64).await;
+
+    // Connect node1 to node0.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that node1 connected without an admission challenge.
+    assert!(!node0.is_under_connection_load());
+    assert_eq!(node0.number_of_admission_challenges(), 0);
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert_eq!(node1.number_of_connected_peers(), 1);
+}
+
+#[tokio::test]
+async fn test_unsolved_admission_challenge_is_rejected() {
+    let [node0, _node1] = admission_pair(0).await;
+
+    // Start a handshake from a mock peer, and receive the admission challenge.
+    let (peer_ip, mut framed) = mock_handshake_peer(&node0, 4142).await;
+    let Some(Ok(Message::AdmissionChallenge(challenge))) = framed.next().await else {
+        panic!("Expected an admission challenge");
+    };
+    // Send a nonce that does not solve the challenge.
+    let nonce = (0..).find(|nonce| !challenge.is_solved_by(*nonce)).unwrap();
+    framed.send(Message::AdmissionSolution(AdmissionSolution { nonce })).await.unwrap();
+
+    // Check that the peer is disconnected for the unsolved challenge.
+    let Some(Ok(Message::Disconnect(disconnect))) = framed.next().await else {
+        panic!("Expected a disconnect");
+    };
+    assert_eq!(disconnect.reason, DisconnectReason::UnsolvedAdmissionChallenge);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert!(!node0.is_restricted(&peer_ip));
+}
+
+/// Opens the given number of simultaneous connections to the given router, which never send a challenge request,
+/// so that each of them holds a handshake permit until it hangs up.
+async fn stall_handshakes(node: &TestRouter<CurrentNetwork>, num_connections: usize) -> Vec<TcpStream> {
+    let connections = (0..num_connections).map(|_| TcpStream::connect(node.local_ip()));
+    join_all(connections).await.into_iter().map(|stream| stream.unwrap()).collect()
+}
+
+/// Waits until the given router has the given number of handshakes in progress.
+fn wait_for_handshakes_in_flight(node: &TestRouter<CurrentNetwork>, num_handshakes: usize) {
+    let node = node.clone();
+    deadline!(Duration::from_secs(5), move || node.number_of_handshakes_in_flight() == num_handshakes);
+}
+
+/// Opens a connection to the given router, and checks that it is refused for having too many handshakes in progress.
+async fn expect_too_many_handshakes(node: &TestRouter<CurrentNetwork>) {
+    let stream = TcpStream::connect(node.local_ip()).await.unwrap();
+    let mut framed = Framed::new(stream, MessageCodec::<CurrentNetwork>::handshake());
+    let Some(Ok(Message::Disconnect(disconnect))) = framed.next().await else {
+        panic!("Expected a disconnect");
+    };
+    assert_eq!(disconnect.reason, DisconnectReason::TooManyPeers);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_concurrent_handshakes_are_bounded() {
+    // Create 2 routers, where node0 has room for more connections than the concurrent handshakes.
+    let node0 = validator(0, 100).await;
+    let node1 = client(0, 2).await;
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+    // Ensure the stalled handshakes outlast the test.
+    let mut config = node0.config();
+    config.handshake_timeout = Duration::from_secs(60);
+    node0.set_config(config);
+
+    // Fire as many simultaneous handshakes as the default limit, and stall them.
+    let limit = node0.max_concurrent_handshakes();
+    let streams = stall_handshakes(&node0, limit).await;
+    wait_for_handshakes_in_flight(&node0, limit);
+
+    // Check that the handshakes over the limit are refused, once they waited for a permit.
+    join_all((0..2).map(|_| expect_too_many_handshakes(&node0))).await;
+    assert_eq!(node0.number_of_handshakes_in_flight(), limit);
+
+    // Lower the limit in place while the handshakes are in progress, and check that their permits are released.
+    node0.set_max_concurrent_handshakes(1);
+    drop(streams);
+    wait_for_handshakes_in_flight(&node0, 0);
+
+    // Check that the released permits do not exceed the lowered limit.
+    let stalled = stall_handshakes(&node0, 1).await;
+    wait_for_handshakes_in_flight(&node0, 1);
+    expect_too_many_handshakes(&node0).await;
+
+    // Raise the limit in place, and check that a real handshake completes alongside the stalled one.
+    node0.set_max_concurrent_handshakes(2);
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&node1.local_ip()));
+    assert!(node1.is_connected(&node0.local_ip()));
+
+    // Check that every permit is released once the stalled handshake hangs up.
+    drop(stalled);
+    wait_for_handshakes_in_flight(&node0, 0);
+}
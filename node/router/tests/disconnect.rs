@@ -15,9 +15,45 @@
 mod common;
 use common::*;
 
-use snarkos_node_tcp::{protocols::Handshake, P2P};
+use snarkos_node_router::{
+    messages::{
+        BlockRequest,
+        BlockResponse,
+        CapabilitySet,
+        ChallengeRequest,
+        DisconnectReason,
+        EpochChallengeRequest,
+        Feature,
+        Message,
+        MessageCodec,
+        NodeType,
+        PeerRequest,
+        PuzzleRequest,
+    },
+    Clock,
+    ConnectError,
+    Heartbeat,
+    Inbound,
+    MonotonicClock,
+    Outbound,
+};
+use snarkos_node_tcp::{
+    protocols::{Handshake, Reading, Writing},
+    ConnectionSide,
+    P2P,
+};
+use snarkvm::{ledger::narwhal::Data, prelude::Testnet3 as CurrentNetwork};
 
 use core::time::Duration;
+use futures_util::{SinkExt, StreamExt};
+use parking_lot::Mutex;
+use std::{
+    net::SocketAddr,
+    sync::{atomic::AtomicBool, Arc},
+    time::Instant,
+};
+use tokio::{io::AsyncWriteExt, net::TcpStream};
+use tokio_util::codec::Framed;
 
 #[tokio::test]
 async fn test_disconnect_without_handshake() {
@@ -108,3 +144,892 @@ async fn test_disconnect_with_handshake() {
     assert_eq!(node1.tcp().num_connected(), 1); // Router 1 has no way of knowing that Router 0 disconnected.
     assert_eq!(node1.tcp().num_connecting(), 0);
 }
+
+#[tokio::test]
+async fn test_disconnect_where() {
+    // Create 4 routers.
+    let node0 = validator(0, 3).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+    let node3 = client(0, 1).await;
+
+    // Enable handshake protocol, and start listening.
+    for node in [&node0, &node1, &node2, &node3] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to the other nodes.
+    for node in [&node1, &node2, &node3] {
+        node0.connect(node.local_ip());
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    print_tcp!(node0);
+
+    // Check the router level.
+    assert_eq!(node0.number_of_connected_peers(), 3);
+
+    // Disconnect node0 from node1 and node3.
+    let (node1_ip, node3_ip) = (node1.local_ip(), node3.local_ip());
+    let disconnected =
+        node0.disconnect_where(|peer| peer.ip() == node1_ip || peer.ip() == node3_ip, DisconnectReason::PeerRefresh);
+    assert_eq!(disconnected.len(), 2);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    print_tcp!(node0);
+
+    // Check that only node2 remains connected.
+    assert_eq!(node0.tcp().num_connected(), 1);
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert!(node0.is_connected(&node2.local_ip()));
+}
+
+#[tokio::test]
+async fn test_disconnect_on_malformed_frames() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable handshake protocol, and start listening.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Report malformed frames from node1, up to the limit.
+    for _ in 0..<TestRouter<CurrentNetwork> as Inbound<CurrentNetwork>>::MAXIMUM_MALFORMED_FRAMES_PER_INTERVAL {
+        node0.malformed_frame(node1.local_ip());
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    // Check that node1 is still connected.
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Report one more malformed frame from node1.
+    node0.malformed_frame(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    print_tcp!(node0);
+
+    // Check that node1 was disconnected.
+    assert_eq!(node0.tcp().num_connected(), 0);
+    assert_eq!(node0.number_of_connected_peers(), 0);
+}
+
+#[tokio::test]
+async fn test_ping_all_liveness() {
+    // Create 3 routers.
+    let node0 = prover(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable the protocols, and start listening. Note: node2 does not read, so it never answers a ping.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+    node0.enable_reading().await;
+    node1.enable_reading().await;
+
+    // Connect node0 to the other nodes.
+    node0.connect(node1.local_ip());
+    node0.connect(node2.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Ping every peer, until the unresponsive peer has missed too many pings.
+    for _ in 0..=3 {
+        node0.ping_all(None);
+        // Sleep briefly.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    print_tcp!(node0);
+
+    // Check that the round-trip time of the responsive peer was recorded.
+    let peer = node0.get_connected_peer(&node1.local_ip()).unwrap();
+    assert!(peer.rtt().is_some());
+    assert_eq!(peer.missed_pings(), 0);
+
+    // Check that the unresponsive peer was disconnected.
+    assert!(!node0.is_connected(&node2.local_ip()));
+    assert_eq!(node0.number_of_connected_peers(), 1);
+}
+
+#[tokio::test]
+async fn test_disconnect_log() {
+    // Create 3 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable handshake protocol, and start listening.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to the other nodes.
+    node0.connect(node1.local_ip());
+    node0.connect(node2.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+    assert!(node0.recent_disconnects(10).is_empty());
+
+    // Disconnect from node1, and then from node2, for different reasons.
+    let (node1_ip, node2_ip) = (node1.local_ip(), node2.local_ip());
+    node0.disconnect_where(|peer| peer.ip() == node1_ip, DisconnectReason::PeerRefresh);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    node0.disconnect_where(|peer| peer.ip() == node2_ip, DisconnectReason::TooManyPeers);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Check that the log contains the reasons, in order.
+    let records = node0.recent_disconnects(10);
+    let records = records.iter().map(|record| (record.addr, record.reason)).collect::<Vec<_>>();
+    assert_eq!(records, vec![(node1_ip, DisconnectReason::PeerRefresh), (node2_ip, DisconnectReason::TooManyPeers)]);
+    // Check that the limit returns the most recent disconnect.
+    assert_eq!(node0.recent_disconnects(1)[0].reason, DisconnectReason::TooManyPeers);
+}
+
+#[tokio::test]
+async fn test_reload_config_evicts_surplus_peers() {
+    // Create 5 routers.
+    let node0 = validator(0, 5).await;
+    let peers = [client(0, 1).await, client(0, 1).await, client(0, 1).await, client(0, 1).await];
+
+    // Enable handshake protocol, and start listening.
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+    for peer in &peers {
+        peer.enable_handshake().await;
+        peer.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to the other nodes.
+    for peer in &peers {
+        node0.connect(peer.local_ip());
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 4);
+
+    // Lower the maximum number of peers.
+    let mut config = node0.config();
+    config.max_peers = 2;
+    node0.reload_config(config);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that the surplus peers were disconnected.
+    assert_eq!(node0.max_connected_peers(), 2);
+    assert_eq!(node0.number_of_connected_peers(), 2);
+}
+
+#[tokio::test]
+async fn test_peers_snapshot_is_immutable() {
+    // Create 3 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable handshake protocol, and start listening.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to the other nodes.
+    for node in [&node1, &node2] {
+        node0.connect(node.local_ip());
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Take a snapshot of the connected peers.
+    let snapshot = node0.peers_snapshot();
+    assert_eq!(snapshot.len(), 2);
+    let last_seen = snapshot.get(&node1.local_ip()).unwrap().last_seen();
+
+    // Mutate the live peer table.
+    node0.disconnect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Check that the snapshot is unchanged.
+    assert_eq!(snapshot.len(), 2);
+    assert!(snapshot.contains(&node1.local_ip()));
+    assert!(snapshot.contains(&node2.local_ip()));
+    assert_eq!(snapshot.get(&node1.local_ip()).unwrap().last_seen(), last_seen);
+
+    // Check that a new snapshot reflects the live peer table.
+    let snapshot = node0.peers_snapshot();
+    assert_eq!(snapshot.len(), 1);
+    assert!(!snapshot.contains(&node1.local_ip()));
+}
+
+#[tokio::test]
+async fn test_pinned_peer_beyond_limit_is_not_evicted() {
+    // Create 4 routers.
+    let node0 = validator(0, 2).await;
+    let peers = [client(0, 1).await, client(0, 1).await, client(0, 1).await];
+
+    // Enable handshake protocol, and start listening.
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+    for peer in &peers {
+        peer.enable_handshake().await;
+        peer.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to the first two peers, reaching the maximum number of peers.
+    for peer in &peers[..2] {
+        node0.connect(peer.local_ip());
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Check that an unpinned peer may not connect beyond the limit.
+    let pinned_ip = peers[2].local_ip();
+    assert!(node0.connect(pinned_ip).is_none());
+
+    // Pin the last peer, and check that it connects beyond the limit.
+    node0.pin_peer(pinned_ip).unwrap();
+    assert!(node0.connect(pinned_ip).is_some());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 3);
+    assert!(node0.is_connected(&pinned_ip));
+
+    // Lower the maximum number of peers, to evict the surplus peers.
+    let mut config = node0.config();
+    config.max_peers = 1;
+    node0.reload_config(config);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that the pinned peer was not evicted, and does not count towards the maximum.
+    assert_eq!(node0.number_of_connected_peers(), 2);
+    assert!(node0.is_connected(&pinned_ip));
+}
+
+#[tokio::test]
+async fn test_slow_consumer_is_disconnected() {
+    const MAX_OUTBOUND_BACKLOG: usize = 8;
+
+    // Create a router.
+    let node0 = validator(0, 1).await;
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+    node0.set_max_outbound_backlog(MAX_OUTBOUND_BACKLOG);
+
+    // Connect a mock peer, which never reads its messages.
+    let (peer_ip, _framed) = mock_connected_peer(&node0, 4150).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&peer_ip));
+
+    // Send large messages, until the socket buffers fill up and the outbound backlog crosses the threshold.
+    let request = BlockRequest { start_height: 1, end_height: 2 };
+    let blocks = Data::Buffer(vec![0u8; 64 * 1024].into());
+    let mut num_sent = 0;
+    while node0.is_connected(&peer_ip) && num_sent < 10_000 {
+        let message = Message::BlockResponse(BlockResponse { request, blocks: blocks.clone() });
+        if node0.send(peer_ip, message).is_some() {
+            num_sent += 1;
+        }
+        tokio::task::yield_now().await;
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that the peer was disconnected as a slow consumer, once the backlog crossed the threshold.
+    assert!(num_sent >= MAX_OUTBOUND_BACKLOG);
+    assert!(!node0.is_connected(&peer_ip));
+    assert_eq!(node0.recent_disconnects(1)[0].reason, DisconnectReason::SlowConsumer);
+}
+
+#[tokio::test]
+async fn test_stuck_write_times_out() {
+    // Create a router, which only disconnects on a timed out write.
+    let node0 = validator(0, 1).await;
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+    node0.set_max_outbound_backlog(usize::MAX);
+    let mut config = node0.config();
+    config.write_timeout = Duration::from_millis(300);
+    node0.set_config(config);
+
+    // Connect a mock peer, which never drains its socket.
+    let (peer_ip, _framed) = mock_connected_peer(&node0, 4151).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&peer_ip));
+
+    // Send more than the socket buffers can hold, keeping the delivery notification of the last message.
+    let request = BlockRequest { start_height: 1, end_height: 2 };
+    let blocks = Data::Buffer(vec![0u8; 64 * 1024].into());
+    let mut last_delivery = None;
+    for _ in 0..512 {
+        let message = Message::BlockResponse(BlockResponse { request, blocks: blocks.clone() });
+        if let Some(delivery) = node0.send(peer_ip, message) {
+            last_delivery = Some(delivery);
+        }
+    }
+
+    // Check that the pending write resolves after the timeout, instead of hanging.
+    let delivery = tokio::time::timeout(Duration::from_secs(5), last_delivery.unwrap()).await;
+    assert!(delivery.is_ok());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that the peer was disconnected as a slow consumer.
+    assert!(!node0.is_connected(&peer_ip));
+    assert_eq!(node0.recent_disconnects(1)[0].reason, DisconnectReason::SlowConsumer);
+}
+
+#[tokio::test]
+async fn test_disconnect_message_is_flushed_before_closing() {
+    // Create a router.
+    let node0 = validator(0, 1).await;
+    node0.enable_handshake().await;
+    node0.enable_reading().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Connect a mock peer.
+    let (peer_ip, mut framed) = mock_connected_peer(&node0, 4171).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&peer_ip));
+
+    // Violate the protocol.
+    framed.send(sample_violation()).await.unwrap();
+
+    // Check that the peer receives the full `Disconnect` message, with the reason, before the connection closes.
+    let mut reason = None;
+    while let Some(Ok(message)) = framed.next().await {
+        if let Message::Disconnect(disconnect) = message {
+            reason = Some(disconnect.reason);
+            break;
+        }
+    }
+    assert_eq!(reason, Some(DisconnectReason::ProtocolViolation));
+    // Check that the connection is closed right after.
+    let next = tokio::time::timeout(Duration::from_secs(5), framed.next()).await.unwrap();
+    assert!(!matches!(next, Some(Ok(_))));
+    assert!(!node0.is_connected(&peer_ip));
+}
+
+#[tokio::test]
+async fn test_disconnect_bypasses_full_outbound_queue() {
+    const MAX_OUTBOUND_BACKLOG: usize = 8;
+
+    // Create a router.
+    let node0 = validator(0, 1).await;
+    node0.enable_handshake().await;
+    node0.enable_reading().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+    node0.set_max_outbound_backlog(MAX_OUTBOUND_BACKLOG);
+
+    // Connect a mock peer, which does not read its messages yet.
+    let (peer_ip, mut framed) = mock_connected_peer(&node0, 4172).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&peer_ip));
+    let peer_addr = node0.resolve_to_ambiguous(&peer_ip).unwrap();
+
+    // Send large messages, until the socket buffers fill up and the outbound queue reaches the backlog limit.
+    let request = BlockRequest { start_height: 1, end_height: 2 };
+    let blocks = Data::Buffer(vec![0u8; 64 * 1024].into());
+    let mut deliveries = Vec::new();
+    while node0.outbound_backlog(peer_addr).unwrap() < MAX_OUTBOUND_BACKLOG {
+        let message = Message::BlockResponse(BlockResponse { request, blocks: blocks.clone() });
+        deliveries.extend(node0.send(peer_ip, message));
+        tokio::task::yield_now().await;
+    }
+    assert!(node0.is_connected(&peer_ip));
+
+    // Disconnect from the peer, while its outbound queue is full.
+    let disconnect = node0.send_disconnect(peer_ip, DisconnectReason::ProtocolViolation);
+
+    // Drain the socket, and check that the `Disconnect` message is delivered.
+    let mut reason = None;
+    while let Some(Ok(message)) = framed.next().await {
+        if let Message::Disconnect(disconnect) = message {
+            reason = Some(disconnect.reason);
+            break;
+        }
+    }
+    assert_eq!(reason, Some(DisconnectReason::ProtocolViolation));
+    assert!(disconnect.await.unwrap());
+    assert_eq!(node0.recent_disconnects(1)[0].reason, DisconnectReason::ProtocolViolation);
+
+    // Check that the messages still queued behind the `Disconnect` message were dropped.
+    let mut num_dropped = 0;
+    for delivery in deliveries {
+        if !matches!(delivery.await, Ok(Ok(()))) {
+            num_dropped += 1;
+        }
+    }
+    assert!(num_dropped > 0);
+}
+
+#[tokio::test]
+async fn test_excessive_pending_puzzle_requests_disconnect() {
+    const MAX_PENDING_PUZZLE_REQUESTS: usize = 2;
+
+    // Create a router. As this test router does not respond to puzzle requests,
+    // the first request from a peer remains pending for the remainder of the test.
+    let node0 = validator(0, 1).await;
+    node0.enable_handshake().await;
+    node0.enable_reading().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+    let mut config = node0.config();
+    config.max_pending_puzzle_requests = MAX_PENDING_PUZZLE_REQUESTS;
+    node0.set_config(config);
+
+    // Connect a mock peer, which never drains its socket.
+    let (peer_ip, mut framed) = mock_connected_peer(&node0, 4170).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&peer_ip));
+
+    // Send puzzle requests, up to the limit.
+    for _ in 0..MAX_PENDING_PUZZLE_REQUESTS {
+        framed.send(Message::PuzzleRequest(PuzzleRequest)).await.unwrap();
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&peer_ip));
+    assert_eq!(node0.num_pending_puzzle_requests(&peer_ip), MAX_PENDING_PUZZLE_REQUESTS);
+
+    // Send one more puzzle request.
+    framed.send(Message::PuzzleRequest(PuzzleRequest)).await.unwrap();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that the peer was disconnected, once the limit was exceeded.
+    assert!(!node0.is_connected(&peer_ip));
+    assert_eq!(node0.recent_disconnects(1)[0].reason, DisconnectReason::RateLimitExceeded);
+}
+
+#[tokio::test]
+async fn test_largest_inbound_buffer_is_evicted() {
+    const MAX_INBOUND_BUFFER_BYTES: usize = 256 * 1024;
+
+    // Create a router.
+    let node0 = validator(0, 3).await;
+    node0.enable_handshake().await;
+    node0.enable_reading().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+    let mut config = node0.config();
+    config.max_inbound_buffer_bytes = MAX_INBOUND_BUFFER_BYTES;
+    node0.set_config(config);
+
+    // Connect mock peers, each of which announces a large message, and only sends a part of it.
+    let mut peers = Vec::new();
+    for (i, num_bytes) in [128 * 1024, 64 * 1024, 96 * 1024].into_iter().enumerate() {
+        let (peer_ip, mut framed) = mock_connected_peer(&node0, 4160 + i as u16).await;
+        // Send a valid message first, which also carries the connection preamble.
+        framed.send(Message::PeerRequest(PeerRequest)).await.unwrap();
+        // Announce a 1MiB message, and send a part of it.
+        let mut partial_frame = (1024u32 * 1024).to_le_bytes().to_vec();
+        partial_frame.extend(vec![0u8; num_bytes]);
+        framed.get_mut().write_all(&partial_frame).await.unwrap();
+        // Sleep briefly.
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        peers.push((peer_ip, framed));
+    }
+
+    // Check that the peer with the largest buffer was disconnected, once the buffers exceeded the limit.
+    assert!(!node0.is_connected(&peers[0].0));
+    assert!(node0.is_connected(&peers[1].0));
+    assert!(node0.is_connected(&peers[2].0));
+    assert_eq!(node0.recent_disconnects(1)[0].reason, DisconnectReason::RateLimitExceeded);
+    assert!(node0.tcp().inbound_buffers().total() <= MAX_INBOUND_BUFFER_BYTES);
+}
+
+#[tokio::test]
+async fn test_connection_lifetime_cycles_unpinned_peers() {
+    // Create 3 routers.
+    let node0 = validator(0, 5).await;
+    let node1 = client(0, 5).await;
+    let node2 = client(0, 5).await;
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+    // Set a connection lifetime in node0, measured with a fake clock, and pin node2.
+    let time = Arc::new(Mutex::new(Instant::now()));
+    node0.set_clock(MonotonicClock::new(FakeClock(time.clone())));
+    let mut config = node0.config();
+    config.max_connection_lifetime = Some(Duration::from_secs(60));
+    node0.set_config(config);
+    node0.pin_peer(node2.local_ip()).unwrap();
+
+    // Connect node1 and node2 to node0.
+    node1.connect(node0.local_ip());
+    node2.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Check that the connections are kept before the lifetime elapses.
+    *time.lock() += Duration::from_secs(59);
+    node0.remove_expired_connected_peers();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Check that node1 is cycled once the lifetime elapses, while the pinned node2 persists.
+    *time.lock() += Duration::from_secs(2);
+    node0.remove_expired_connected_peers();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!node0.is_connected(&node1.local_ip()));
+    assert!(node0.is_connected(&node2.local_ip()));
+    assert_eq!(node0.recent_disconnects(1)[0].reason, DisconnectReason::ConnectionLifetimeExpired);
+
+    // Check that node1 may reconnect cleanly.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&node1.local_ip()));
+    assert!(!node0.is_restricted(&node1.local_ip()));
+}
+
+/// A clock that returns the instants it is set to.
+struct FakeClock(Arc<Mutex<Instant>>);
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        *self.0.lock()
+    }
+}
+
+/// Returns a message that violates the protocol after the handshake.
+fn sample_violation() -> Message<CurrentNetwork> {
+    Message::ChallengeRequest(ChallengeRequest::new(0, NodeType::Client, sample_account().address(), 0))
+}
+
+/// Returns a validator connected to a client, with the given probation cooldown and period on the validator.
+async fn connected_pair(probation_cooldown: Duration, probation_period: Duration) -> [TestRouter<CurrentNetwork>; 2] {
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+    let mut config = node0.config();
+    config.probation_cooldown = probation_cooldown;
+    config.probation_period = probation_period;
+    node0.set_config(config);
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&node1.local_ip()));
+    [node0, node1]
+}
+
+#[tokio::test]
+async fn test_violations_escalate_from_probation_to_restriction() {
+    let [node0, node1] = connected_pair(Duration::from_millis(300), Duration::from_secs(60)).await;
+    let peer_ip = node1.local_ip();
+
+    // Violate the protocol once.
+    node0.process_message(peer_ip, sample_violation()).await.unwrap();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Check that the peer was disconnected and placed on probation, but not restricted.
+    assert!(!node0.is_connected(&peer_ip));
+    assert!(node0.is_on_probation(&peer_ip));
+    assert!(!node0.is_restricted(&peer_ip));
+    // Check that the peer may not reconnect during the cooldown.
+    assert_eq!(node0.try_connect(peer_ip).unwrap_err(), ConnectError::OnProbation(peer_ip));
+
+    // Reconnect after the cooldown.
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    node0.connect(peer_ip);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&peer_ip));
+
+    // Violate the protocol again, while on probation.
+    node0.process_message(peer_ip, sample_violation()).await.unwrap();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Check that the peer was escalated to restriction.
+    assert!(!node0.is_connected(&peer_ip));
+    assert!(node0.is_restricted(&peer_ip));
+    assert!(!node0.is_on_probation(&peer_ip));
+    assert_eq!(node0.try_connect(peer_ip).unwrap_err(), ConnectError::Restricted(peer_ip));
+}
+
+#[tokio::test]
+async fn test_probation_is_cleared_after_good_behavior() {
+    let [node0, node1] = connected_pair(Duration::from_millis(50), Duration::from_millis(400)).await;
+    let peer_ip = node1.local_ip();
+
+    // Violate the protocol once.
+    node0.process_message(peer_ip, sample_violation()).await.unwrap();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(node0.is_on_probation(&peer_ip));
+
+    // Reconnect, and behave for the rest of the probation period.
+    node0.connect(peer_ip);
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert!(node0.is_connected(&peer_ip));
+    assert!(!node0.is_on_probation(&peer_ip));
+
+    // Check that another violation places the peer on probation again, instead of restricting it.
+    node0.process_message(peer_ip, sample_violation()).await.unwrap();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!node0.is_connected(&peer_ip));
+    assert!(node0.is_on_probation(&peer_ip));
+    assert!(!node0.is_restricted(&peer_ip));
+}
+
+#[tokio::test]
+async fn test_shutdown_cancels_processing_without_punishing_the_peer() {
+    let [node0, node1] = connected_pair(Duration::from_millis(300), Duration::from_secs(60)).await;
+    let peer_ip = node1.local_ip();
+
+    // Cancel the processing of messages, as happens when the node shuts down.
+    node0.shutdown_token().cancel();
+    assert!(node0.is_shutting_down());
+
+    // Violate the protocol while the node is shutting down.
+    node0.process_message(peer_ip, sample_violation()).await.unwrap();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Check that the message was aborted, without disconnecting, probating or restricting the peer.
+    assert!(node0.is_connected(&peer_ip));
+    assert!(!node0.is_on_probation(&peer_ip));
+    assert!(!node0.is_restricted(&peer_ip));
+}
+
+#[tokio::test]
+async fn test_replaced_restricted_peers_are_disconnected() {
+    let [node0, node1] = connected_pair(Duration::from_secs(1), Duration::from_secs(60)).await;
+    let peer_ip = node1.local_ip();
+    let other_ip = "1.2.3.4:4130".parse().unwrap();
+
+    // Restrict an unrelated peer, which is dropped by the swap.
+    node0.insert_restricted_peer(other_ip);
+    assert!(node0.is_restricted(&other_ip));
+
+    // Swap in a restricted set with the connected peer.
+    node0.replace_restricted_peers([peer_ip].into_iter().collect());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that the connected peer was disconnected, and that the new set replaced the old one.
+    assert!(!node0.is_connected(&peer_ip));
+    assert!(node0.is_restricted(&peer_ip));
+    assert!(!node0.is_restricted(&other_ip));
+    assert_eq!(node0.restricted_peers_snapshot(), [peer_ip].into_iter().collect());
+}
+
+/// Waits for a `PeerResponse` from the router, skipping any other messages it sends in the meantime.
+async fn expect_peer_response(framed: &mut Framed<TcpStream, MessageCodec<CurrentNetwork>>) {
+    loop {
+        match tokio::time::timeout(Duration::from_secs(1), framed.next()).await {
+            Ok(Some(Ok(Message::PeerResponse(..)))) => return,
+            Ok(Some(Ok(_))) => continue,
+            result => panic!("Expected a peer response, got {result:?}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_corrupt_compressed_frame_falls_back_to_uncompressed() {
+    // Create a router.
+    let node0 = validator(0, 2).await;
+    node0.enable_handshake().await;
+    node0.enable_reading().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Check that compression is not used with a peer that does not advertise it.
+    let (plain_ip, _plain) = mock_connected_peer(&node0, 4170).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&plain_ip));
+    assert!(!node0.peer_supports_compression(&plain_ip));
+
+    // Connect a mock peer that claims to support compression.
+    let capabilities = Message::<CurrentNetwork>::capabilities().with(CapabilitySet::COMPRESSION);
+    let (peer_ip, mut framed) = mock_connected_peer_with_capabilities(&node0, 4171, capabilities).await;
+    *framed.codec_mut() = MessageCodec::default()
+        .with_version(2)
+        .with_side(Some(ConnectionSide::Initiator))
+        .with_compression(Some(Arc::new(AtomicBool::new(true))));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.peer_supports_compression(&peer_ip));
+
+    // Send a valid message first, which also carries the connection preamble.
+    framed.send(Message::PeerRequest(PeerRequest)).await.unwrap();
+    expect_peer_response(&mut framed).await;
+
+    // Send a compressed frame that cannot be decompressed, followed by a valid message.
+    let mut corrupt_frame = 5u32.to_le_bytes().to_vec();
+    corrupt_frame.extend([u8::MAX, u8::MAX, 1, 2, 3]);
+    framed.get_mut().write_all(&corrupt_frame).await.unwrap();
+    framed.send(Message::PeerRequest(PeerRequest)).await.unwrap();
+
+    // Check that the router skipped the frame, fell back to uncompressed, and kept the peer connected.
+    expect_peer_response(&mut framed).await;
+    assert!(node0.is_connected(&peer_ip));
+    assert!(!node0.peer_supports_compression(&peer_ip));
+    assert!(!node0.is_on_probation(&peer_ip));
+}
+
+#[tokio::test]
+async fn test_disconnect_on_corrupt_compressed_frames() {
+    const MAXIMUM_MALFORMED_FRAMES: usize =
+        <TestRouter<CurrentNetwork> as Inbound<CurrentNetwork>>::MAXIMUM_MALFORMED_FRAMES_PER_INTERVAL;
+
+    // Create a router.
+    let node0 = validator(0, 1).await;
+    node0.enable_handshake().await;
+    node0.enable_reading().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Connect a mock peer that claims to support compression.
+    let capabilities = Message::<CurrentNetwork>::capabilities().with(CapabilitySet::COMPRESSION);
+    let (peer_ip, mut framed) = mock_connected_peer_with_capabilities(&node0, 4173, capabilities).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.peer_supports_compression(&peer_ip));
+
+    // Send a valid message first, which also carries the connection preamble.
+    framed.send(Message::PeerRequest(PeerRequest)).await.unwrap();
+    expect_peer_response(&mut framed).await;
+
+    // Send corrupt compressed frames up to the limit, followed by a valid message.
+    let mut corrupt_frame = 5u32.to_le_bytes().to_vec();
+    corrupt_frame.extend([u8::MAX, u8::MAX, 1, 2, 3]);
+    for _ in 0..MAXIMUM_MALFORMED_FRAMES {
+        framed.get_mut().write_all(&corrupt_frame).await.unwrap();
+    }
+    framed.send(Message::PeerRequest(PeerRequest)).await.unwrap();
+    // Check that the peer is still connected.
+    expect_peer_response(&mut framed).await;
+    assert!(node0.is_connected(&peer_ip));
+
+    // Send one more corrupt compressed frame.
+    framed.get_mut().write_all(&corrupt_frame).await.unwrap();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that the peer was disconnected.
+    assert!(!node0.is_connected(&peer_ip));
+    assert_eq!(node0.recent_disconnects(1)[0].reason, DisconnectReason::ProtocolViolation);
+}
+
+#[tokio::test]
+async fn test_inflight_sends_to_stalled_peer_are_cancelled() {
+    // Create a router.
+    let node0 = validator(0, 1).await;
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+    node0.set_max_outbound_backlog(usize::MAX);
+
+    // Connect a mock peer, which never reads its messages.
+    let (peer_ip, _framed) = mock_connected_peer(&node0, 4172).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&peer_ip));
+    assert_eq!(node0.inflight_sends(), 0);
+
+    // Send large messages, until the socket buffers fill up and the messages start queueing.
+    let request = BlockRequest { start_height: 1, end_height: 2 };
+    let blocks = Data::Buffer(vec![0u8; 64 * 1024].into());
+    let mut num_sent = 0;
+    while node0.inflight_sends() < 8 && num_sent < 10_000 {
+        let message = Message::BlockResponse(BlockResponse { request, blocks: blocks.clone() });
+        if node0.send(peer_ip, message).is_some() {
+            num_sent += 1;
+        }
+        tokio::task::yield_now().await;
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let num_inflight = node0.inflight_sends();
+    assert!(num_inflight >= 8);
+
+    // Check that the queued messages are cancelled, and that the peer stays connected.
+    let num_cancelled = node0.cancel_inflight_sends();
+    assert!(num_cancelled > 0 && num_cancelled <= num_inflight);
+    assert_eq!(node0.inflight_sends(), 0);
+    assert!(node0.is_connected(&peer_ip));
+}
+
+#[tokio::test]
+async fn test_pre_feature_peer_is_not_sent_gated_messages() {
+    // Create a router.
+    let node0 = validator(0, 2).await;
+    node0.enable_handshake().await;
+    node0.enable_reading().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Connect a mock peer on the version before epoch challenge requests, which advertises every message type.
+    let mut request = ChallengeRequest::new(4180, NodeType::Client, sample_account().address(), 0);
+    request.version = Feature::EpochChallenges.min_version() - 1;
+    let (peer_ip, mut framed) = mock_connected_peer_with_request(&node0, request).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&peer_ip));
+    assert!(!node0.peer_supports(&peer_ip, Feature::EpochChallenges));
+    assert!(node0.peer_supports(&peer_ip, Feature::CodecUpgrade));
+
+    // Check that the gated message is not sent, while the others still are.
+    assert!(node0.send(peer_ip, Message::EpochChallengeRequest(EpochChallengeRequest { epoch_number: 0 })).is_none());
+    assert!(node0.send(peer_ip, Message::PeerRequest(PeerRequest)).is_some());
+
+    // Check that the peer only receives the ungated message, and remains connected.
+    let mut received = Vec::new();
+    while let Ok(Some(Ok(message))) = tokio::time::timeout(Duration::from_millis(500), framed.next()).await {
+        received.push(message.name());
+    }
+    assert!(received.iter().any(|name| name == "PeerRequest"));
+    assert!(!received.iter().any(|name| name == "EpochChallengeRequest"));
+    assert!(node0.is_connected(&peer_ip));
+}
@@ -0,0 +1,450 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod common;
+use common::*;
+
+use snarkos_node_router::{
+    messages::{
+        BlockRequest,
+        BlockResponse,
+        DataBlocks,
+        DisconnectReason,
+        Message,
+        MessageCodec,
+        PeerRequest,
+        PeerResponse,
+        UnconfirmedSolution,
+    },
+    DeadLetterReason,
+    Inbound,
+    Outbound,
+};
+use snarkos_node_tcp::{
+    protocols::{Handshake, Reading, Writing},
+    P2P,
+};
+use snarkvm::{
+    algorithms::polycommit::kzg10::{KZGCommitment, KZGProof},
+    ledger::{coinbase::PartialSolution, narwhal::Data},
+    prelude::{coinbase::ProverSolution, Address, PrivateKey, Rng, TestRng, Testnet3 as CurrentNetwork},
+};
+
+use core::time::Duration;
+use futures_util::{SinkExt, StreamExt};
+use tokio::{net::TcpStream, sync::mpsc};
+use tokio_util::codec::Framed;
+
+#[tokio::test]
+async fn test_filtered_message_is_dead_lettered() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Filter out peer requests in node0, and set a dead letter sink.
+    let mut config = node0.config();
+    config.dropped_messages.insert("PeerRequest".to_string());
+    node0.set_config(config);
+    let (sender, mut receiver) = mpsc::channel(8);
+    node0.set_dead_letter_sink(sender);
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node1 to node0.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Send a peer request from node1.
+    node1.send(node0.local_ip(), Message::PeerRequest(PeerRequest));
+
+    // Check that the filtered message produced a dead letter.
+    let dead_letter = tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await.unwrap().unwrap();
+    assert_eq!(dead_letter.peer_ip, node1.local_ip());
+    assert_eq!(dead_letter.message_kind, "PeerRequest");
+    assert_eq!(dead_letter.reason, DeadLetterReason::Filtered);
+
+    // Check that the filtered message did not disconnect the peer.
+    assert_eq!(node0.number_of_connected_peers(), 1);
+}
+
+/// Samples an unconfirmed solution message.
+fn sample_solution_message(rng: &mut TestRng) -> Message<CurrentNetwork> {
+    let address = Address::try_from(PrivateKey::<CurrentNetwork>::new(rng).unwrap()).unwrap();
+    let partial_solution = PartialSolution::new(address, rng.gen(), KZGCommitment(rng.gen()));
+    let solution = ProverSolution::new(partial_solution, KZGProof { w: rng.gen(), random_v: None });
+    solution_message(solution)
+}
+
+/// Returns the unconfirmed solution message for the given solution.
+fn solution_message(solution: ProverSolution<CurrentNetwork>) -> Message<CurrentNetwork> {
+    let solution_id = solution.commitment();
+    Message::UnconfirmedSolution(UnconfirmedSolution { solution_id, solution: Data::Object(solution) })
+}
+
+#[tokio::test]
+async fn test_duplicate_solutions_from_different_peers() {
+    const NUM_SOLUTIONS: usize = 20;
+
+    // Create 3 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to the other nodes.
+    node0.connect(node1.local_ip());
+    node0.connect(node2.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Set a dead letter sink in node0.
+    let (sender, mut receiver) = mpsc::channel(NUM_SOLUTIONS);
+    node0.set_dead_letter_sink(sender);
+
+    // Receive the same solutions from both peers, as happens with regular gossip.
+    let rng = &mut TestRng::default();
+    for _ in 0..NUM_SOLUTIONS {
+        let message = sample_solution_message(rng);
+        node0.inbound(node1.local_ip(), message.clone()).await.unwrap();
+        node0.inbound(node2.local_ip(), message).await.unwrap();
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Check that each solution reached the handler exactly once.
+    assert_eq!(node0.number_of_unconfirmed_solutions(), NUM_SOLUTIONS);
+    // Check that each drop of a solution seen from the other peer produced a dead letter.
+    for _ in 0..NUM_SOLUTIONS {
+        let dead_letter = receiver.try_recv().unwrap();
+        assert_eq!(dead_letter.peer_ip, node2.local_ip());
+        assert_eq!(dead_letter.message_kind, "UnconfirmedSolution");
+        assert_eq!(dead_letter.reason, DeadLetterReason::AlreadySeen);
+    }
+    assert!(receiver.try_recv().is_err());
+
+    // Check that the cross-peer duplicates did not count against either peer.
+    assert_eq!(node0.number_of_connected_peers(), 2);
+}
+
+#[tokio::test]
+async fn test_invalid_solution_does_not_hide_honest_copies() {
+    // Create 3 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to the other nodes.
+    node0.connect(node1.local_ip());
+    node0.connect(node2.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Sample a solution, and a copy of it with a garbage proof under the same commitment.
+    let rng = &mut TestRng::default();
+    let address = Address::try_from(PrivateKey::<CurrentNetwork>::new(rng).unwrap()).unwrap();
+    let partial_solution = PartialSolution::new(address, rng.gen(), KZGCommitment(rng.gen()));
+    let solution = ProverSolution::new(partial_solution, KZGProof { w: rng.gen(), random_v: None });
+    let garbage = ProverSolution::new(partial_solution, KZGProof { w: rng.gen(), random_v: None });
+    assert_eq!(solution.commitment(), garbage.commitment());
+
+    // Receive the garbage copy from node1, which the handler rejects.
+    node0.set_rejects_solutions(true);
+    node0.inbound(node1.local_ip(), solution_message(garbage)).await.unwrap();
+    assert_eq!(node0.number_of_unconfirmed_solutions(), 1);
+
+    // Check that the honest copy from node2 is still handled, and accepted.
+    node0.set_rejects_solutions(false);
+    node0.inbound(node2.local_ip(), solution_message(solution)).await.unwrap();
+    assert_eq!(node0.number_of_unconfirmed_solutions(), 2);
+    assert_eq!(node0.number_of_connected_peers(), 2);
+}
+
+#[tokio::test]
+async fn test_duplicate_solutions_from_one_peer() {
+    const MAXIMUM_DUPLICATES: usize =
+        <TestRouter<CurrentNetwork> as Inbound<CurrentNetwork>>::MAXIMUM_DUPLICATE_SOLUTIONS_PER_INTERVAL;
+
+    // Create 3 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to the other nodes.
+    node0.connect(node1.local_ip());
+    node0.connect(node2.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Receive a solution from node1, followed by the maximum number of duplicates.
+    let message = sample_solution_message(&mut TestRng::default());
+    for _ in 0..=MAXIMUM_DUPLICATES {
+        node0.inbound(node1.local_ip(), message.clone()).await.unwrap();
+    }
+    // Check that the peer is still connected.
+    assert!(node0.is_connected(&node1.local_ip()));
+
+    // Receive one more duplicate, and check that node1 is disconnected for exceeding the rate limit.
+    node0.inbound(node1.local_ip(), message).await.unwrap();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!node0.is_connected(&node1.local_ip()));
+    assert_eq!(node0.recent_disconnects(1)[0].reason, DisconnectReason::RateLimitExceeded);
+
+    // Check that the other peer is unaffected.
+    assert!(node0.is_connected(&node2.local_ip()));
+}
+
+#[tokio::test]
+async fn test_recorded_messages_replay_identically() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Record a few messages from node1, including a malformed one.
+    let path = std::env::temp_dir().join(format!("snarkos-replay-{}.log", std::process::id()));
+    node0.start_recording(&path, 1 << 20).unwrap();
+    let messages = vec![
+        Message::PeerRequest(PeerRequest),
+        Message::BlockRequest(BlockRequest { start_height: 5, end_height: 1 }),
+        Message::PeerResponse(PeerResponse { peers: vec![] }),
+    ];
+    let mut outcomes = Vec::new();
+    for message in messages {
+        outcomes.push(node0.inbound(node1.local_ip(), message).await);
+    }
+    node0.stop_recording();
+    assert!(outcomes[1].is_err());
+
+    // Replay the recorded messages, and check that they have the same outcomes.
+    let replayed = node0.replay(&path).await.unwrap();
+    assert_eq!(replayed.len(), outcomes.len());
+    for (outcome, replayed) in outcomes.iter().zip(replayed.iter()) {
+        assert_eq!(
+            outcome.as_ref().map_err(|error| error.to_string()),
+            replayed.as_ref().map_err(|error| error.to_string())
+        );
+    }
+
+    std::fs::remove_file(path).unwrap();
+}
+
+/// Sends the given block request from the mock peer, and returns the heights covered by each block response,
+/// alongside the number of blocks in each, until the router stops sending block responses.
+async fn request_blocks(
+    framed: &mut Framed<TcpStream, MessageCodec<CurrentNetwork>>,
+    request: BlockRequest,
+) -> Vec<(BlockRequest, usize)> {
+    framed.send(Message::BlockRequest(request)).await.unwrap();
+    let mut responses = Vec::new();
+    loop {
+        match tokio::time::timeout(Duration::from_millis(500), framed.next()).await {
+            Ok(Some(Ok(Message::BlockResponse(BlockResponse { request, blocks })))) => {
+                responses.push((request, blocks.deserialize().await.unwrap().len()));
+            }
+            Ok(Some(Ok(_))) => continue,
+            Ok(result) => panic!("Expected a block response, got {result:?}"),
+            Err(_) => return responses,
+        }
+    }
+}
+
+/// Returns the block responses expected for the given block request, as in `request_blocks`.
+fn expected_block_responses(request: BlockRequest) -> Vec<(BlockRequest, usize)> {
+    let chunk_size = DataBlocks::<CurrentNetwork>::MAXIMUM_NUMBER_OF_BLOCKS as u32;
+    request.chunks(chunk_size).map(|chunk| (chunk, (chunk.end_height - chunk.start_height) as usize)).collect()
+}
+
+#[tokio::test]
+async fn test_block_request_is_served() {
+    // Create a router.
+    let node0 = validator(0, 1).await;
+    node0.enable_handshake().await;
+    node0.enable_reading().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Connect a syncing mock peer.
+    let (peer_ip, mut framed) = mock_connected_peer(&node0, 4190).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&peer_ip));
+
+    // Request the blocks 5 to 10, and check that exactly those blocks are received.
+    let request = BlockRequest { start_height: 5, end_height: 10 };
+    let responses = request_blocks(&mut framed, request).await;
+    assert_eq!(responses, expected_block_responses(request));
+    assert_eq!(responses.iter().map(|(_, num_blocks)| num_blocks).sum::<usize>(), MAXIMUM_BLOCKS_PER_REQUEST as usize);
+    assert!(node0.is_connected(&peer_ip));
+}
+
+#[tokio::test]
+async fn test_oversized_block_request_is_clamped() {
+    // Create a router.
+    let node0 = validator(0, 1).await;
+    let maximum_blocks = node0.max_blocks_per_request();
+    node0.enable_handshake().await;
+    node0.enable_reading().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Connect a syncing mock peer.
+    let (peer_ip, mut framed) = mock_connected_peer(&node0, 4191).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&peer_ip));
+
+    // Request more blocks than allowed, and check that only the allowed number of blocks is received.
+    let request = BlockRequest { start_height: 5, end_height: 5 + 10 * maximum_blocks };
+    let responses = request_blocks(&mut framed, request).await;
+    let clamped = BlockRequest { start_height: 5, end_height: 5 + maximum_blocks };
+    assert_eq!(responses, expected_block_responses(clamped));
+    assert_eq!(responses.iter().map(|(_, num_blocks)| num_blocks).sum::<usize>(), maximum_blocks as usize);
+    assert!(node0.is_connected(&peer_ip));
+}
+
+#[tokio::test]
+async fn test_disconnect_on_block_request_rate() {
+    const MAXIMUM_BLOCKS_PER_REQUEST: u32 = 5;
+    const NUM_FULL_REQUESTS: u32 = 3;
+
+    // Create a router, which serves a limited number of blocks to each peer.
+    let node0 = validator(0, 1).await;
+    let mut config = node0.config();
+    config.max_blocks_per_request = MAXIMUM_BLOCKS_PER_REQUEST;
+    config.max_blocks_per_interval = (NUM_FULL_REQUESTS * MAXIMUM_BLOCKS_PER_REQUEST) as usize;
+    node0.set_config(config);
+    node0.enable_handshake().await;
+    node0.enable_reading().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Connect a syncing mock peer.
+    let (peer_ip, mut framed) = mock_connected_peer(&node0, 4193).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&peer_ip));
+
+    // Request oversized ranges up to the limit, and check that the clamped blocks count against it.
+    for i in 0..NUM_FULL_REQUESTS {
+        let start_height = 1 + i * MAXIMUM_BLOCKS_PER_REQUEST;
+        let request = BlockRequest { start_height, end_height: start_height + 10 * MAXIMUM_BLOCKS_PER_REQUEST };
+        let responses = request_blocks(&mut framed, request).await;
+        let num_blocks = responses.iter().map(|(_, num_blocks)| num_blocks).sum::<usize>();
+        assert_eq!(num_blocks, MAXIMUM_BLOCKS_PER_REQUEST as usize);
+    }
+    assert!(node0.is_connected(&peer_ip));
+
+    // Request one more block, and check that the peer is disconnected for exceeding the rate limit.
+    framed.send(Message::BlockRequest(BlockRequest { start_height: 100, end_height: 101 })).await.unwrap();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!node0.is_connected(&peer_ip));
+    assert_eq!(node0.recent_disconnects(1)[0].reason, DisconnectReason::RateLimitExceeded);
+}
+
+#[tokio::test]
+async fn test_sheddable_messages_are_shed_under_load() {
+    // Create a router, which sheds messages beyond a single in-flight message.
+    let node0 = validator(0, 1).await;
+    let mut config = node0.config();
+    config.max_in_flight_messages = 1;
+    node0.set_config(config);
+    let (sender, mut receiver) = mpsc::channel(8);
+    node0.set_dead_letter_sink(sender);
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Connect a mock peer.
+    let (peer_ip, mut framed) = mock_connected_peer(&node0, 4192).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&peer_ip));
+
+    // Saturate node0, as if a message from another peer was being handled.
+    let in_flight = node0.load_shedder().try_admit(false, node0.max_in_flight_messages()).unwrap();
+
+    // Receive a solution, and check that it is shed before it reaches the handler.
+    let rng = &mut TestRng::default();
+    node0.inbound(peer_ip, sample_solution_message(rng)).await.unwrap();
+    assert_eq!(node0.number_of_unconfirmed_solutions(), 0);
+    assert_eq!(node0.number_of_shed_messages(), 1);
+    let dead_letter = receiver.try_recv().unwrap();
+    assert_eq!(dead_letter.peer_ip, peer_ip);
+    assert_eq!(dead_letter.message_kind, "UnconfirmedSolution");
+    assert_eq!(dead_letter.reason, DeadLetterReason::Shed);
+
+    // Receive a peer request, and check that it is still handled.
+    node0.inbound(peer_ip, Message::PeerRequest(PeerRequest)).await.unwrap();
+    loop {
+        match tokio::time::timeout(Duration::from_secs(1), framed.next()).await.unwrap() {
+            Some(Ok(Message::PeerResponse(..))) => break,
+            Some(Ok(_)) => continue,
+            result => panic!("Expected a peer response, got {result:?}"),
+        }
+    }
+    assert_eq!(node0.number_of_shed_messages(), 1);
+
+    // Release the load, and check that solutions are handled again.
+    drop(in_flight);
+    node0.inbound(peer_ip, sample_solution_message(rng)).await.unwrap();
+    assert_eq!(node0.number_of_unconfirmed_solutions(), 1);
+    assert_eq!(node0.number_of_shed_messages(), 1);
+    assert!(receiver.try_recv().is_err());
+    assert!(node0.is_connected(&peer_ip));
+}
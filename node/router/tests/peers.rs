@@ -0,0 +1,402 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod common;
+use common::*;
+
+use snarkos_node_router::{
+    messages::{ChallengeRequest, DisconnectReason, Message, NodeType, PeerResponse, Ping},
+    ConnectionManager,
+    Outbound,
+    Peer,
+    PeerEvent,
+    TopologyDesc,
+};
+use snarkos_node_sync_locators::BlockLocators;
+use snarkos_node_tcp::{
+    protocols::{Handshake, Reading, Writing},
+    ConnectionSide,
+    P2P,
+};
+use snarkvm::prelude::{Testnet3 as CurrentNetwork, ToBytes};
+
+use core::time::Duration;
+use deadline::deadline;
+use std::{
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{atomic::Ordering, Arc},
+};
+use tokio::sync::mpsc;
+
+/// Returns a unique path for a peers file in the temporary directory.
+fn sample_peers_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("snarkos-{name}-{}.peers", std::process::id()))
+}
+
+#[tokio::test]
+async fn test_save_and_load_peers() {
+    // Create 3 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable handshake protocol, and start listening.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to the other nodes.
+    node0.connect(node1.local_ip());
+    node0.connect(node2.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Save the peers of node0.
+    let path = sample_peers_path("roundtrip");
+    node0.save_peers(&path).unwrap();
+
+    // Load the peers into a fresh router.
+    let node3 = validator(0, 2).await;
+    let mut peers = node3.load_peers(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    // Check that the peers were restored as candidate peers.
+    peers.sort();
+    let mut expected = vec![node1.local_ip(), node2.local_ip()];
+    expected.sort();
+    assert_eq!(peers, expected);
+    assert_eq!(node3.number_of_candidate_peers(), 2);
+    assert!(expected.iter().all(|peer_ip| node3.candidate_peers().contains(peer_ip)));
+}
+
+#[tokio::test]
+async fn test_load_truncated_peers() {
+    let node = validator(0, 2).await;
+
+    // Write a peers file, and truncate it.
+    let path = sample_peers_path("truncated");
+    let peers = vec!["127.0.0.1:4130".parse().unwrap(), "127.0.0.1:4131".parse().unwrap()];
+    let bytes = bincode::serialize::<Vec<std::net::SocketAddr>>(&peers).unwrap();
+    std::fs::write(&path, &bytes[..bytes.len() - 3]).unwrap();
+
+    // Check that the truncated file is ignored.
+    assert!(node.load_peers(&path).is_empty());
+    assert_eq!(node.number_of_candidate_peers(), 0);
+    std::fs::remove_file(&path).unwrap();
+
+    // Check that a missing file is ignored.
+    assert!(node.load_peers(&path).is_empty());
+}
+
+#[tokio::test]
+async fn test_save_and_load_restricted_peers() {
+    let node0 = validator(0, 2).await;
+
+    // Restrict a peer.
+    let peer_ip = "127.0.0.1:4130".parse().unwrap();
+    node0.insert_restricted_peer(peer_ip);
+    assert!(node0.is_restricted(&peer_ip));
+
+    // Save the restricted peers of node0.
+    let path = sample_peers_path("restricted");
+    node0.save_restricted_peers(&path).unwrap();
+
+    // Load the restricted peers into a fresh router, as on a restart.
+    let node1 = validator(0, 2).await;
+    assert!(!node1.is_restricted(&peer_ip));
+    let restricted_peers = node1.load_restricted_peers(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    // Check that the restriction survived.
+    assert_eq!(restricted_peers, vec![peer_ip]);
+    assert!(node1.is_restricted(&peer_ip));
+    assert_eq!(node1.restricted_peers(), vec![peer_ip]);
+}
+
+#[tokio::test]
+async fn test_load_expired_restricted_peers() {
+    let node = validator(0, 2).await;
+
+    // Write a restricted peers file, with one expired and one unexpired restriction.
+    let path = sample_peers_path("expired");
+    let expired_ip: std::net::SocketAddr = "127.0.0.1:4130".parse().unwrap();
+    let restricted_ip: std::net::SocketAddr = "127.0.0.1:4131".parse().unwrap();
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64;
+    let restricted_peers = vec![(expired_ip, now - 1), (restricted_ip, now + 60)];
+    std::fs::write(&path, bincode::serialize(&restricted_peers).unwrap()).unwrap();
+
+    // Check that the expired restriction is purged on load.
+    assert_eq!(node.load_restricted_peers(&path), vec![restricted_ip]);
+    assert!(!node.is_restricted(&expired_ip));
+    assert!(node.is_restricted(&restricted_ip));
+    assert_eq!(node.number_of_restricted_peers(), 1);
+    std::fs::remove_file(&path).unwrap();
+
+    // Check that a missing file is ignored.
+    assert!(node.load_restricted_peers(&path).is_empty());
+}
+
+#[tokio::test]
+async fn test_peer_height_updates() {
+    // Create 3 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to the other nodes.
+    node0.connect(node1.local_ip());
+    node0.connect(node2.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Observe the peer events of node0.
+    let (sender, mut receiver) = mpsc::channel(16);
+    node0.set_peer_event_sink(sender);
+
+    // Report the genesis height of node1 in a ping.
+    let block_locators = BlockLocators::new_genesis(sample_genesis_block::<CurrentNetwork>().hash());
+    let version = Message::<CurrentNetwork>::VERSION;
+    let ping = Ping { version, node_type: NodeType::Client, block_locators: Some(block_locators) };
+    node0.process_message(node1.local_ip(), Message::Ping(ping.clone())).await.unwrap();
+
+    // Check that the height was stored, and the event was emitted.
+    assert_eq!(node0.get_connected_peer(&node1.local_ip()).unwrap().height(), Some(0));
+    assert_eq!(receiver.try_recv().unwrap(), PeerEvent::HeightUpdated { addr: node1.local_ip(), height: 0 });
+    // Check that reporting the same height does not emit an event.
+    node0.process_message(node1.local_ip(), Message::Ping(ping)).await.unwrap();
+    assert!(receiver.try_recv().is_err());
+
+    // Update the height of node2.
+    node0.update_peer_height(node2.local_ip(), 100);
+    assert_eq!(receiver.try_recv().unwrap(), PeerEvent::HeightUpdated { addr: node2.local_ip(), height: 100 });
+
+    // Check that the peers behind are filtered by the threshold.
+    assert_eq!(node0.peers_behind(100, 10), vec![node1.local_ip()]);
+    assert!(node0.peers_behind(100, 100).is_empty());
+    let mut behind = node0.peers_behind(200, 50);
+    behind.sort();
+    let mut expected = vec![node1.local_ip(), node2.local_ip()];
+    expected.sort();
+    assert_eq!(behind, expected);
+}
+
+#[tokio::test]
+async fn test_connection_manager_redials_dropped_peer() {
+    // Create 3 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable handshake protocol, and start listening.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Maintain 2 outbound connections from node0, to the candidate peers.
+    node0.insert_candidate_peers(&[node1.local_ip(), node2.local_ip()]);
+    let manager = Arc::new(ConnectionManager::new(node0.router().clone(), 2));
+    let manager_ = manager.clone();
+    tokio::spawn(async move { manager_.run().await });
+
+    // Check that the target is reached.
+    let manager_ = manager.clone();
+    deadline!(Duration::from_secs(3), move || manager_.number_of_outbound_peers() == 2);
+
+    // Disconnect node0 from node1.
+    assert!(node0.disconnect(node1.local_ip()).await.unwrap());
+
+    // Check that the manager redials node1, restoring the target.
+    let (manager_, node1_ip) = (manager.clone(), node1.local_ip());
+    deadline!(Duration::from_secs(10), move || manager_.number_of_outbound_peers() == 2);
+    assert!(node0.is_connected(&node1_ip));
+
+    // Shut down the manager.
+    node0.shut_down().await;
+}
+
+#[tokio::test]
+async fn test_connection_manager_retries_only_transient_failures() {
+    // Create a router, with a short handshake timeout.
+    let node0 = validator(0, 2).await;
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+    let mut config = node0.config();
+    config.handshake_timeout = Duration::from_millis(300);
+    node0.set_config(config);
+
+    // Start a peer that times out, and a peer that rejects this node's version.
+    let (silent_ip, silent_dials) = mock_handshake_listener(None).await;
+    let (outdated_ip, outdated_dials) = mock_handshake_listener(Some(DisconnectReason::OutdatedClientVersion)).await;
+    node0.insert_candidate_peers(&[silent_ip, outdated_ip]);
+
+    // Dial both candidates, and wait out the backoff before dialing again.
+    let manager = ConnectionManager::new(node0.router().clone(), 2);
+    manager.maintain().await;
+    tokio::time::sleep(Duration::from_millis(700)).await;
+    manager.maintain().await;
+
+    // Check that only the transient failure was retried.
+    assert_eq!(silent_dials.load(Ordering::SeqCst), 2);
+    assert_eq!(outdated_dials.load(Ordering::SeqCst), 1);
+    assert_eq!(manager.excluded_peers(), vec![outdated_ip]);
+}
+
+#[tokio::test]
+async fn test_apply_topology() {
+    // Create 4 routers.
+    let node0 = validator(0, 3).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+    let node3 = client(0, 1).await;
+
+    // Enable handshake protocol, and start listening.
+    for node in [&node0, &node1, &node2, &node3] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1 and node3.
+    node0.connect(node1.local_ip());
+    node0.connect(node3.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Apply a topology that requires exactly node1 and node2.
+    let topology = TopologyDesc::new([node1.local_ip(), node2.local_ip()]).exclusive();
+    let changes = node0.apply_topology(&topology);
+    assert_eq!(changes.dialed, vec![node2.local_ip()]);
+    assert_eq!(changes.disconnected, vec![node3.local_ip()]);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that node0 is connected to exactly the required peers.
+    let mut connected_peers = node0.connected_peers();
+    connected_peers.sort();
+    let mut required = vec![node1.local_ip(), node2.local_ip()];
+    required.sort();
+    assert_eq!(connected_peers, required);
+
+    // Check that applying the topology again makes no changes.
+    assert_eq!(node0.apply_topology(&topology), Default::default());
+
+    // Check that an avoided peer is disconnected, even if it is required.
+    let changes = node0.apply_topology(&topology.with_avoided([node2.local_ip()]));
+    assert_eq!(changes.disconnected, vec![node2.local_ip()]);
+    assert!(changes.dialed.is_empty());
+}
+
+#[tokio::test]
+async fn test_topology_snapshot() {
+    // Create 3 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1, and node2 to node0.
+    node0.connect(node1.local_ip());
+    node2.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Check that the snapshot describes node0 and both of its peers.
+    let snapshot = node0.topology_snapshot();
+    assert_eq!(snapshot.node_ip, node0.local_ip());
+    assert_eq!(snapshot.address, node0.address());
+    assert_eq!(snapshot.peers.len(), 2);
+
+    // Check that the direction of each connection is recorded.
+    let outbound = snapshot.peers.iter().find(|peer| peer.peer_ip == node1.local_ip()).unwrap();
+    assert_eq!(outbound.side, ConnectionSide::Responder);
+    assert!(!outbound.is_inbound());
+    let inbound = snapshot.peers.iter().find(|peer| peer.peer_ip == node2.local_ip()).unwrap();
+    assert_eq!(inbound.side, ConnectionSide::Initiator);
+    assert!(inbound.is_inbound());
+    for peer in &snapshot.peers {
+        assert_eq!(peer.node_type, NodeType::Client);
+        assert_eq!(peer.version, Message::<CurrentNetwork>::VERSION);
+        assert_eq!(peer.num_peers, None);
+    }
+
+    // Report the peers of node1 in a peer response.
+    let peers = vec!["1.2.3.4:4130".parse().unwrap(), "5.6.7.8:4130".parse().unwrap()];
+    node0.process_message(node1.local_ip(), Message::PeerResponse(PeerResponse { peers })).await.unwrap();
+
+    // Check that the reported number of peers is in the next snapshot only.
+    let next = node0.topology_snapshot();
+    assert_eq!(next.peers.iter().find(|peer| peer.peer_ip == node1.local_ip()).unwrap().num_peers, Some(2));
+    assert_eq!(next.peers.iter().find(|peer| peer.peer_ip == node2.local_ip()).unwrap().num_peers, None);
+    assert!(snapshot.peers.iter().all(|peer| peer.num_peers.is_none()));
+
+    // Disconnect node2, and check that an earlier snapshot is unaffected.
+    node0.disconnect(node2.local_ip());
+    let node0_ = node0.clone();
+    deadline!(Duration::from_secs(3), move || node0_.number_of_connected_peers() == 1);
+    assert_eq!(snapshot.peers.len(), 2);
+}
+
+#[tokio::test]
+async fn test_peer_response_is_sorted() {
+    // Create 2 routers.
+    let node0 = validator(0, 10).await;
+    let node1 = validator(0, 10).await;
+
+    // Register the same peers as connected in both routers, in opposite orders, alongside a bogon address.
+    let peers: Vec<SocketAddr> = ["8.8.8.8:4130", "1.2.3.4:4131", "5.6.7.8:4130", "1.2.3.4:4130", "127.0.0.1:4130"]
+        .iter()
+        .map(|addr| addr.parse().unwrap())
+        .collect();
+    let request = ChallengeRequest::new(4130, NodeType::Client, sample_account().address(), 0);
+    for (node, peers) in [(&node0, peers.clone()), (&node1, peers.iter().rev().copied().collect())] {
+        for peer_ip in peers {
+            let peer = Peer::new(peer_ip, &request, ConnectionSide::Initiator, node.clock().now());
+            node.insert_connected_peer(peer, peer_ip);
+        }
+    }
+
+    // Check that the shared peers are sorted by address, without the bogon address.
+    let shared = node0.shareable_peers();
+    let expected: Vec<SocketAddr> = ["1.2.3.4:4130", "1.2.3.4:4131", "5.6.7.8:4130", "8.8.8.8:4130"]
+        .iter()
+        .map(|addr| addr.parse().unwrap())
+        .collect();
+    assert_eq!(shared, expected);
+
+    // Check that the responses over the same peers are byte-identical.
+    let response0 = Message::<CurrentNetwork>::PeerResponse(PeerResponse { peers: shared });
+    let response1 = Message::<CurrentNetwork>::PeerResponse(PeerResponse { peers: node1.shareable_peers() });
+    assert_eq!(response0.to_bytes_le().unwrap(), response1.to_bytes_le().unwrap());
+}
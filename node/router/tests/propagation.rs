@@ -0,0 +1,188 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod common;
+use common::*;
+
+use snarkos_node_router::{
+    messages::{Message, UnconfirmedSolution, UnconfirmedTransaction},
+    MessageId,
+    Outbound,
+};
+use snarkos_node_tcp::{
+    protocols::{Handshake, Reading, Writing},
+    P2P,
+};
+use snarkvm::{
+    algorithms::polycommit::kzg10::KZGCommitment,
+    ledger::narwhal::Data,
+    prelude::{coinbase::PuzzleCommitment, Field, FromBytes, Network, Rng, TestRng, Testnet3 as CurrentNetwork, ToBytes},
+};
+
+use core::time::Duration;
+use std::net::SocketAddr;
+
+#[tokio::test]
+async fn test_propagation_fanout() {
+    const NUM_PEERS: usize = 20;
+    const FANOUT: usize = 8;
+
+    // Create the routers.
+    let node0 = validator(0, NUM_PEERS as u16).await;
+    let mut peers = Vec::with_capacity(NUM_PEERS);
+    for _ in 0..NUM_PEERS {
+        peers.push(client(0, 1).await);
+    }
+
+    // Enable the protocols, and start listening.
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+    for peer in &peers {
+        peer.enable_handshake().await;
+        peer.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to the other nodes.
+    for peer in &peers {
+        node0.connect(peer.local_ip());
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+    assert_eq!(node0.number_of_connected_peers(), NUM_PEERS);
+
+    // Propagate a solution.
+    let rng = &mut TestRng::default();
+    let message = Message::UnconfirmedSolution(UnconfirmedSolution {
+        solution_id: PuzzleCommitment::new(KZGCommitment(rng.gen())),
+        solution: Data::Buffer((0..64).map(|_| rng.gen::<u8>()).collect::<Vec<_>>().into()),
+    });
+    assert_eq!(node0.propagation_fanout(), FANOUT);
+    node0.propagate(message.clone(), &[]);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that the solution was sent to exactly the fanout limit of peers.
+    let num_bytes = message.to_bytes_le().unwrap().len() as u64;
+    assert_eq!(node0.traffic().bytes_sent(&message), FANOUT as u64 * num_bytes);
+}
+
+#[tokio::test]
+async fn test_seeded_propagation_is_reproducible() {
+    const SEED: u64 = 4;
+
+    // Create 2 routers, seeded identically.
+    let node0 = validator(0, 1).await;
+    let node1 = validator(0, 1).await;
+    node0.set_propagation_seed(SEED);
+    node1.set_propagation_seed(SEED);
+
+    // Prepare the peers, in a different order for each router.
+    let peers = (0..20).map(|i| SocketAddr::from(([127, 0, 0, 1], 5000 + i))).collect::<Vec<_>>();
+    let reversed_peers = peers.iter().rev().copied().collect::<Vec<_>>();
+
+    // Check that both routers select the same peers, repeatedly.
+    for _ in 0..10 {
+        let selected = node0.select_propagation_peers(peers.clone());
+        assert_eq!(selected.len(), node0.propagation_fanout());
+        assert_eq!(selected, node1.select_propagation_peers(reversed_peers.clone()));
+    }
+}
+
+#[tokio::test]
+async fn test_has_seen_message() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(node0.is_connected(&node1.local_ip()));
+
+    // Prepare a transaction, and an unrelated transaction ID.
+    let rng = &mut TestRng::default();
+    let transaction_id = sample_transaction_id(1);
+    let unrelated_id = sample_transaction_id(2);
+    let message = Message::<CurrentNetwork>::UnconfirmedTransaction(UnconfirmedTransaction {
+        transaction_id,
+        transaction: Data::Buffer((0..64).map(|_| rng.gen::<u8>()).collect::<Vec<_>>().into()),
+    });
+    assert!(!node1.has_seen_message(MessageId::Transaction(transaction_id)));
+
+    // Send the transaction from node0 to node1.
+    assert!(node0.send(node1.local_ip(), message).is_some());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that node1 has seen the transaction, and only the transaction.
+    assert!(node1.has_seen_message(MessageId::Transaction(transaction_id)));
+    assert!(!node1.has_seen_message(MessageId::Transaction(unrelated_id)));
+}
+
+#[tokio::test]
+async fn test_unsupported_messages_are_not_sent() {
+    // Create 3 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Let node1 advertise that it does not understand unconfirmed transactions.
+    let rng = &mut TestRng::default();
+    let transaction_id = sample_transaction_id(1);
+    let message = Message::<CurrentNetwork>::UnconfirmedTransaction(UnconfirmedTransaction {
+        transaction_id,
+        transaction: Data::Buffer((0..64).map(|_| rng.gen::<u8>()).collect::<Vec<_>>().into()),
+    });
+    node1.set_capabilities(node1.capabilities().without(message.id()));
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to the other nodes.
+    node0.connect(node1.local_ip());
+    node0.connect(node2.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+    assert!(!node0.get_connected_peer(&node1.local_ip()).unwrap().supports(&message));
+
+    // Check that the transaction is never sent to node1, neither directly nor through propagation.
+    assert!(node0.send(node1.local_ip(), message.clone()).is_none());
+    node0.propagate(message.clone(), &[]);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that the transaction was only sent to node2.
+    let num_bytes = message.to_bytes_le().unwrap().len() as u64;
+    assert_eq!(node0.traffic().bytes_sent(&message), num_bytes);
+    assert_eq!(node0.number_of_connected_peers(), 2);
+}
+
+/// Returns the transaction ID derived from the given number.
+fn sample_transaction_id(n: u64) -> <CurrentNetwork as Network>::TransactionID {
+    let id_bytes = Field::<CurrentNetwork>::from_u64(n).to_bytes_le().unwrap();
+    <CurrentNetwork as Network>::TransactionID::from_bytes_le(&id_bytes).unwrap()
+}
@@ -0,0 +1,165 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod common;
+use common::*;
+
+use snarkos_node_router::{
+    messages::{Message, PuzzleRequest},
+    Outbound,
+};
+use snarkos_node_tcp::{
+    protocols::{Handshake, Reading, Writing},
+    P2P,
+};
+
+use core::time::Duration;
+
+#[tokio::test]
+async fn test_concurrent_puzzle_requests_are_coalesced() {
+    const NUM_REQUESTS: usize = 5;
+
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node1 to node0.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Fire several concurrent puzzle requests from node1. As this test router does not respond
+    // to puzzle requests, the first request remains in flight for the remainder of the test.
+    for _ in 0..NUM_REQUESTS {
+        node1.send(node0.local_ip(), Message::PuzzleRequest(PuzzleRequest));
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that only the first puzzle request was processed, and the others were coalesced.
+    let peer = node0.get_connected_peer(&node1.local_ip()).unwrap();
+    assert!(peer.is_puzzle_request_in_flight());
+    assert_eq!(node0.number_of_coalesced_puzzle_requests(), NUM_REQUESTS as u64 - 1);
+
+    // Check that the coalesced requests are not treated as a protocol violation.
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert_eq!(node1.number_of_connected_peers(), 1);
+
+    // Clear the in-flight puzzle request, and check that the next puzzle request is processed.
+    node0.remove_puzzle_request_in_flight(node1.local_ip());
+    assert!(node0.insert_puzzle_request_in_flight(node1.local_ip()));
+    assert_eq!(node0.number_of_coalesced_puzzle_requests(), NUM_REQUESTS as u64 - 1);
+}
+
+#[tokio::test]
+async fn test_puzzle_requests_are_declined_while_syncing() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node1 to node0.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Send a puzzle request while node0 is syncing.
+    node0.set_syncing(true);
+    node1.send(node0.local_ip(), Message::PuzzleRequest(PuzzleRequest));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that the puzzle request was declined, without disconnecting the peer.
+    let peer = node0.get_connected_peer(&node1.local_ip()).unwrap();
+    assert!(!peer.is_puzzle_request_in_flight());
+    assert_eq!(node0.number_of_declined_puzzle_requests(), 1);
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Send another puzzle request once node0 has synced.
+    node0.set_syncing(false);
+    node1.send(node0.local_ip(), Message::PuzzleRequest(PuzzleRequest));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Check that the puzzle request was served.
+    let peer = node0.get_connected_peer(&node1.local_ip()).unwrap();
+    assert!(peer.is_puzzle_request_in_flight());
+    assert_eq!(node0.number_of_declined_puzzle_requests(), 1);
+    assert_eq!(node0.number_of_connected_peers(), 1);
+}
+
+#[tokio::test]
+async fn test_puzzle_requests_are_declined_when_serializations_are_saturated() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the protocols, and start listening.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node1 to node0.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Saturate the puzzle serializations of node0.
+    node0.set_max_puzzle_serializations(1);
+    let permit = node0.acquire_puzzle_serialization().await;
+    assert!(permit.is_some());
+    assert_eq!(node0.number_of_puzzle_serializations_in_flight(), 1);
+
+    // Send a puzzle request while no serialization permit is available.
+    node1.send(node0.local_ip(), Message::PuzzleRequest(PuzzleRequest));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // Check that the puzzle request was declined, without disconnecting the peer.
+    let peer = node0.get_connected_peer(&node1.local_ip()).unwrap();
+    assert!(!peer.is_puzzle_request_in_flight());
+    assert_eq!(node0.number_of_declined_puzzle_requests(), 1);
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Release the permit, and check that the next puzzle request is served.
+    drop(permit);
+    node1.send(node0.local_ip(), Message::PuzzleRequest(PuzzleRequest));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let peer = node0.get_connected_peer(&node1.local_ip()).unwrap();
+    assert!(peer.is_puzzle_request_in_flight());
+    assert_eq!(node0.number_of_declined_puzzle_requests(), 1);
+    assert_eq!(node0.number_of_puzzle_serializations_in_flight(), 0);
+}
@@ -82,8 +82,27 @@ impl<N: Network, C: ConsensusStorage<N>> Writing for Prover<N, C> {
 
     /// Creates an [`Encoder`] used to write the outbound messages to the target stream.
     /// The `side` parameter indicates the connection side **from the node's perspective**.
-    fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, addr: SocketAddr, side: ConnectionSide) -> Self::Codec {
+        MessageCodec::default()
+            .with_version(self.router().codec_version(&addr))
+            .with_side(self.router().codec_side(&addr, side))
+            .with_compression(self.router().compression(&addr))
+            .with_traffic(self.router().traffic().clone())
+    }
+
+    /// Returns the maximum time allowed to write a single message to a peer.
+    fn write_timeout(&self) -> Option<Duration> {
+        Some(self.router().write_timeout())
+    }
+
+    /// Marks the peer as a slow consumer, as writing a message to it timed out.
+    fn on_write_timeout(&self, peer_addr: SocketAddr) {
+        self.router().handle_write_timeout(peer_addr)
+    }
+
+    /// Returns the window over which the messages queued for a peer are coalesced into a single write, if any.
+    fn coalescing_window(&self) -> Option<Duration> {
+        self.router().write_coalescing_window()
     }
 }
 
@@ -94,19 +113,36 @@ impl<N: Network, C: ConsensusStorage<N>> Reading for Prover<N, C> {
 
     /// Creates a [`Decoder`] used to interpret messages from the network.
     /// The `side` param indicates the connection side **from the node's perspective**.
-    fn codec(&self, _peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, side: ConnectionSide) -> Self::Codec {
+        let node = self.clone();
+        MessageCodec::default()
+            .with_version(self.router().codec_version(&peer_addr))
+            .with_side(self.router().codec_side(&peer_addr, side))
+            .with_compression(self.router().compression(&peer_addr))
+            .with_traffic(self.router().traffic().clone())
+            .with_malformed_frame_handler(move || node.malformed_frame(peer_addr))
+    }
+
+    /// Returns the maximum number of bytes buffered across all peers, awaiting the rest of an inbound message.
+    fn max_inbound_buffer_bytes(&self) -> Option<usize> {
+        Some(self.router().max_inbound_buffer_bytes())
+    }
+
+    /// Marks the peer holding the largest inbound buffer, once the buffers exceed their limit.
+    fn on_inbound_buffer_overflow(&self, peer_addr: SocketAddr) {
+        self.router().handle_inbound_buffer_overflow(peer_addr)
     }
 
     /// Processes a message received from the network.
     async fn process_message(&self, peer_addr: SocketAddr, message: Self::Message) -> io::Result<()> {
-        // Process the message. Disconnect if the peer violated the protocol.
-        if let Err(error) = self.inbound(peer_addr, message).await {
+        // Process the message, unless the node is shutting down. Disconnect if the peer violated the protocol.
+        if let Some(Err(error)) = self.inbound_until_shutdown(peer_addr, message).await {
             if let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) {
                 warn!("Disconnecting from '{peer_addr}' - {error}");
-                Outbound::send(self, peer_ip, Message::Disconnect(DisconnectReason::ProtocolViolation.into()));
-                // Disconnect from this peer.
-                self.router().disconnect(peer_ip);
+                // Place the peer on probation, or restrict it if it is a repeat offender.
+                self.router().record_violation(peer_ip);
+                // Inform the peer of the violation, and disconnect from it.
+                self.send_disconnect(peer_ip, DisconnectReason::ProtocolViolation);
             }
         }
         Ok(())
@@ -187,7 +223,7 @@ impl<N: Network, C: ConsensusStorage<N>> Inbound<N> for Prover<N, C> {
     }
 
     /// Disconnects on receipt of a `PuzzleRequest` message.
-    fn puzzle_request(&self, peer_ip: SocketAddr) -> bool {
+    async fn puzzle_request(&self, peer_ip: SocketAddr) -> bool {
         debug!("Disconnecting '{peer_ip}' for the following reason - {:?}", DisconnectReason::ProtocolViolation);
         false
     }
@@ -237,6 +273,8 @@ impl<N: Network, C: ConsensusStorage<N>> Inbound<N> for Prover<N, C> {
             match is_valid {
                 // If the solution is valid, propagate the `UnconfirmedSolution`.
                 Ok(Ok(true)) => {
+                    // Mark the solution as seen across all peers.
+                    self.router().insert_accepted_solution(serialized.solution_id);
                     let message = Message::UnconfirmedSolution(serialized);
                     // Propagate the "UnconfirmedSolution".
                     self.propagate(message, &[peer_ip]);
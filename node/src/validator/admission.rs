@@ -0,0 +1,221 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use super::*;
+use snarkvm::prelude::ToBytes;
+
+/// A rule that an unconfirmed transaction from a peer must pass, before it is queued for the memory pool.
+pub trait TxAdmissionRule<N: Network>: Send + Sync {
+    /// Returns the reason for rejecting the given transaction, if it must not be admitted.
+    fn check(&self, transaction: &Transaction<N>) -> Result<(), MempoolRejectReason>;
+}
+
+/// The rule rejecting the transactions with a fee below the minimum fee of the validator.
+#[derive(Clone, Debug, Default)]
+pub struct MinFeeRule {
+    /// The minimum fee, in microcredits, shared with the validator so that it can be changed at runtime.
+    min_fee: Arc<AtomicU64>,
+}
+
+impl MinFeeRule {
+    /// Initializes the rule with the given minimum fee, in microcredits.
+    pub fn new(min_fee: u64) -> Self {
+        Self { min_fee: Arc::new(AtomicU64::new(min_fee)) }
+    }
+
+    /// Initializes the rule with the given shared minimum fee, in microcredits.
+    pub(super) fn shared(min_fee: Arc<AtomicU64>) -> Self {
+        Self { min_fee }
+    }
+}
+
+impl<N: Network> TxAdmissionRule<N> for MinFeeRule {
+    fn check(&self, transaction: &Transaction<N>) -> Result<(), MempoolRejectReason> {
+        let fee = transaction.fee_amount().map_or(0, |fee| *fee);
+        let min_fee = self.min_fee.load(Ordering::Relaxed);
+        match fee < min_fee {
+            true => Err(MempoolRejectReason::FeeTooLow { fee, min_fee }),
+            false => Ok(()),
+        }
+    }
+}
+
+/// The rule rejecting the transactions larger than the given number of bytes, once serialized.
+#[derive(Copy, Clone, Debug)]
+pub struct MaxSizeRule(pub usize);
+
+impl<N: Network> TxAdmissionRule<N> for MaxSizeRule {
+    fn check(&self, transaction: &Transaction<N>) -> Result<(), MempoolRejectReason> {
+        let size = transaction.to_bytes_le().map_or(usize::MAX, |bytes| bytes.len());
+        match size > self.0 {
+            true => Err(MempoolRejectReason::TooLarge { size, max_size: self.0 }),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Returns the admission rules of a new validator, which only enforce the given shared minimum fee.
+pub(super) fn default_admission_rules<N: Network>(min_fee: &Arc<AtomicU64>) -> Vec<Box<dyn TxAdmissionRule<N>>> {
+    vec![Box::new(MinFeeRule::shared(min_fee.clone()))]
+}
+
+impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
+    /// Appends the given rule to the admission rules, which run in order on each unconfirmed transaction from a peer.
+    pub fn add_admission_rule<R: TxAdmissionRule<N> + 'static>(&self, rule: R) {
+        self.admission_rules.write().push(Box::new(rule));
+    }
+
+    /// Replaces the admission rules. By default, only the minimum fee set by `set_min_fee` is enforced,
+    /// which no longer applies once the rules are replaced, unless a `MinFeeRule` is included.
+    pub fn set_admission_rules(&self, rules: Vec<Box<dyn TxAdmissionRule<N>>>) {
+        *self.admission_rules.write() = rules;
+    }
+
+    /// Runs the admission rules on the given transaction, in order.
+    /// The first rule to reject the transaction short-circuits the others, and its reason is returned.
+    pub(super) fn check_admission_rules(&self, transaction: &Transaction<N>) -> Result<(), MempoolRejectReason> {
+        self.admission_rules.read().iter().try_for_each(|rule| rule.check(transaction))
+    }
+
+    /// Runs the admission rules on the given transaction, and records its rejection, if any.
+    /// This is shared by the transactions from peers and the locally-submitted transactions.
+    pub(super) fn admit_transaction(&self, transaction: &Transaction<N>) -> Result<(), MempoolRejectReason> {
+        let reason = match self.check_admission_rules(transaction) {
+            Ok(()) => return Ok(()),
+            Err(reason) => reason,
+        };
+        if matches!(reason, MempoolRejectReason::FeeTooLow { .. }) {
+            self.num_low_fee_rejections.fetch_add(1, Ordering::Relaxed);
+        }
+        let id = MempoolId::Transaction(transaction.id());
+        self.emit_mempool_event(MempoolEvent::Rejected { id, reason: reason.clone() });
+        Err(reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::test_helpers::*;
+    use snarkvm::prelude::TestRng;
+
+    #[test]
+    fn test_min_fee_rule() {
+        let transaction = sample_transaction();
+        let fee = *transaction.fee_amount().unwrap();
+
+        // Check that the transaction is admitted at the minimum fee, and rejected below it.
+        assert_eq!(MinFeeRule::new(fee).check(&transaction), Ok(()));
+        let reason = MempoolRejectReason::FeeTooLow { fee, min_fee: fee + 1 };
+        assert_eq!(MinFeeRule::new(fee + 1).check(&transaction), Err(reason));
+    }
+
+    #[test]
+    fn test_max_size_rule() {
+        let transaction = sample_transaction();
+        let size = transaction.to_bytes_le().unwrap().len();
+
+        // Check that the transaction is admitted at the maximum size, and rejected above it.
+        assert_eq!(MaxSizeRule(size).check(&transaction), Ok(()));
+        let reason = MempoolRejectReason::TooLarge { size, max_size: size - 1 };
+        assert_eq!(MaxSizeRule(size - 1).check(&transaction), Err(reason));
+    }
+
+    #[tokio::test]
+    async fn test_unconfirmed_transaction_below_min_fee_is_dropped() {
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4130".parse().unwrap();
+
+        // Initialize a validator with a mock consensus and ledger.
+        let consensus = Arc::new(MockConsensus::default());
+        let ledger_api = Arc::new(MockLedger::new(sample_ledger(), Duration::ZERO, false));
+        let validator = sample_validator(consensus.clone(), ledger_api, rng).await;
+
+        // Prepare a transaction, and read its fee.
+        let transaction = sample_transaction();
+        let transaction_id = transaction.id();
+        let fee = *transaction.fee_amount().unwrap();
+        let message = transaction_message(&transaction);
+
+        // Check that a transaction below the minimum fee is dropped, and the peer is kept.
+        validator.set_min_fee(fee + 1);
+        assert!(validator.unconfirmed_transaction(peer_ip, message.clone(), transaction.clone()).await);
+        assert!(consensus.transactions.lock().is_empty());
+        assert_eq!(validator.number_of_low_fee_rejections(), 1);
+
+        // Check that a transaction at the minimum fee reaches the consensus.
+        validator.set_min_fee(fee);
+        assert!(validator.unconfirmed_transaction(peer_ip, message, transaction).await);
+        wait_for_transactions(&consensus, 1).await;
+        assert_eq!(*consensus.transactions.lock(), vec![transaction_id]);
+        assert_eq!(validator.number_of_low_fee_rejections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_admission_rules_short_circuit() {
+        /// A rule that counts its checks, and rejects every transaction with the given reason, if any.
+        struct CountingRule {
+            num_checks: Arc<AtomicUsize>,
+            rejection: Option<&'static str>,
+        }
+
+        impl TxAdmissionRule<CurrentNetwork> for CountingRule {
+            fn check(&self, _transaction: &Transaction<CurrentNetwork>) -> Result<(), MempoolRejectReason> {
+                self.num_checks.fetch_add(1, Ordering::SeqCst);
+                self.rejection.map_or(Ok(()), |reason| Err(MempoolRejectReason::Rule(reason.to_string())))
+            }
+        }
+
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4130".parse().unwrap();
+
+        // Initialize a validator with a mock consensus and ledger, and subscribe to its memory pool events.
+        let consensus = Arc::new(MockConsensus::default());
+        let ledger_api = Arc::new(MockLedger::new(sample_ledger(), Duration::ZERO, false));
+        let validator = sample_validator(consensus.clone(), ledger_api, rng).await;
+        let mut events = validator.subscribe_mempool();
+
+        // Compose two rules that reject every transaction, after the default minimum fee rule.
+        let (first, second) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        validator.add_admission_rule(CountingRule { num_checks: first.clone(), rejection: Some("first") });
+        validator.add_admission_rule(CountingRule { num_checks: second.clone(), rejection: Some("second") });
+
+        // Prepare a transaction.
+        let transaction = sample_transaction();
+        let id = transaction.id();
+        let message = transaction_message(&transaction);
+
+        // Check that the first rejection wins, and the peer is kept.
+        assert!(validator.unconfirmed_transaction(peer_ip, message.clone(), transaction.clone()).await);
+        let reason = MempoolRejectReason::Rule("first".to_string());
+        assert_eq!(events.try_recv().unwrap(), MempoolEvent::Rejected { id: MempoolId::Transaction(id), reason });
+        assert_eq!((first.load(Ordering::SeqCst), second.load(Ordering::SeqCst)), (1, 0));
+
+        // Replace the rules with a passing rule followed by a rejecting one, and check that both are consulted.
+        validator.set_admission_rules(vec![
+            Box::new(CountingRule { num_checks: first.clone(), rejection: None }),
+            Box::new(CountingRule { num_checks: second.clone(), rejection: Some("second") }),
+        ]);
+        assert!(validator.unconfirmed_transaction(peer_ip, message, transaction).await);
+        let reason = MempoolRejectReason::Rule("second".to_string());
+        assert_eq!(events.try_recv().unwrap(), MempoolEvent::Rejected { id: MempoolId::Transaction(id), reason });
+        assert_eq!((first.load(Ordering::SeqCst), second.load(Ordering::SeqCst)), (2, 1));
+
+        // Check that no transaction reached the consensus.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(consensus.transactions.lock().is_empty());
+        assert_eq!(validator.number_of_low_fee_rejections(), 0);
+    }
+}
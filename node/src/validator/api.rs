@@ -0,0 +1,168 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use snarkvm::prelude::coinbase::{EpochChallenge, PuzzleCommitment};
+
+/// The consensus operations the validator depends on, which are implemented by [`Consensus`].
+#[async_trait]
+pub trait ConsensusApi<N: Network>: Send + Sync {
+    /// Adds the given unconfirmed solution to the memory pool.
+    async fn add_unconfirmed_solution(&self, solution: ProverSolution<N>) -> Result<()>;
+
+    /// Adds the given unconfirmed transactions to the memory pool, and returns one result per transaction.
+    async fn add_unconfirmed_transactions(&self, transactions: Vec<Transaction<N>>) -> Vec<Result<()>>;
+
+    /// Returns a snapshot of the pending entries in the memory pool.
+    fn mempool_stats(&self) -> MempoolStats;
+
+    /// Shuts down the consensus.
+    async fn shut_down(&self);
+}
+
+#[async_trait]
+impl<N: Network> ConsensusApi<N> for Consensus<N> {
+    async fn add_unconfirmed_solution(&self, solution: ProverSolution<N>) -> Result<()> {
+        Consensus::add_unconfirmed_solution(self, solution).await
+    }
+
+    async fn add_unconfirmed_transactions(&self, transactions: Vec<Transaction<N>>) -> Vec<Result<()>> {
+        Consensus::add_unconfirmed_transactions(self, transactions).await
+    }
+
+    fn mempool_stats(&self) -> MempoolStats {
+        Consensus::mempool_stats(self)
+    }
+
+    async fn shut_down(&self) {
+        Consensus::shut_down(self).await
+    }
+}
+
+/// The ledger reads the validator depends on to handle messages, which are implemented by [`Ledger`].
+pub trait LedgerApi<N: Network>: Send + Sync {
+    /// Returns the latest block height.
+    fn latest_height(&self) -> u32;
+
+    /// Returns the latest proof target.
+    fn latest_proof_target(&self) -> u64;
+
+    /// Returns `true` if the given puzzle commitment exists in the ledger.
+    fn contains_puzzle_commitment(&self, commitment: &PuzzleCommitment<N>) -> Result<bool>;
+
+    /// Returns the latest epoch challenge and the latest height, from a single consistent view of the ledger.
+    fn latest_puzzle_state(&self) -> Result<(EpochChallenge<N>, u32), PuzzleStateError>;
+
+    /// Returns the block header at the given height.
+    fn get_header(&self, height: u32) -> Result<Header<N>>;
+
+    /// Returns the epoch challenge of the given epoch.
+    fn epoch_challenge(&self, epoch_number: u32) -> Result<EpochChallenge<N>>;
+}
+
+impl<N: Network, C: ConsensusStorage<N>> LedgerApi<N> for Ledger<N, C> {
+    fn latest_height(&self) -> u32 {
+        Ledger::latest_height(self)
+    }
+
+    fn latest_proof_target(&self) -> u64 {
+        Ledger::latest_proof_target(self)
+    }
+
+    fn contains_puzzle_commitment(&self, commitment: &PuzzleCommitment<N>) -> Result<bool> {
+        Ledger::contains_puzzle_commitment(self, commitment)
+    }
+
+    fn latest_puzzle_state(&self) -> Result<(EpochChallenge<N>, u32), PuzzleStateError> {
+        super::puzzle::latest_puzzle_state(self)
+    }
+
+    fn get_header(&self, height: u32) -> Result<Header<N>> {
+        Ledger::get_header(self, height)
+    }
+
+    fn epoch_challenge(&self, epoch_number: u32) -> Result<EpochChallenge<N>> {
+        Ledger::get_epoch_challenge(self, epoch_number.saturating_mul(N::NUM_BLOCKS_PER_EPOCH))
+    }
+}
+
+impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
+    /// Returns the consensus used by the message handlers.
+    pub(crate) fn consensus(&self) -> Arc<dyn ConsensusApi<N>> {
+        self.consensus.read().clone()
+    }
+
+    /// Sets the consensus used by the message handlers.
+    pub fn set_consensus(&self, consensus: Arc<dyn ConsensusApi<N>>) {
+        *self.consensus.write() = consensus;
+    }
+
+    /// Returns the ledger reads used by the message handlers.
+    pub(crate) fn ledger_api(&self) -> Arc<dyn LedgerApi<N>> {
+        self.ledger_api.read().clone()
+    }
+
+    /// Sets the ledger reads used by the message handlers.
+    pub fn set_ledger_api(&self, ledger_api: Arc<dyn LedgerApi<N>>) {
+        *self.ledger_api.write() = ledger_api;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::test_helpers::*;
+    use snarkvm::prelude::TestRng;
+
+    #[tokio::test]
+    async fn test_message_handlers_use_injected_consensus() {
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4130".parse().unwrap();
+
+        // Initialize a validator with a mock consensus and ledger.
+        let consensus = Arc::new(MockConsensus::default());
+        let ledger_api = Arc::new(MockLedger::new(sample_ledger(), Duration::ZERO, false));
+        let validator = sample_validator(consensus.clone(), ledger_api.clone(), rng).await;
+
+        // Handle an unconfirmed solution.
+        let address = sample_address(rng);
+        let (message, solution) = sample_solution(address, rng);
+        assert!(validator.unconfirmed_solution(peer_ip, message, solution).await);
+        // Check that the solution reached the mock consensus.
+        assert_eq!(*consensus.solutions.lock(), vec![solution.commitment()]);
+
+        // Handle an unconfirmed transaction.
+        let transaction = sample_transaction();
+        let transaction_id = transaction.id();
+        assert!(validator.unconfirmed_transaction(peer_ip, transaction_message(&transaction), transaction).await);
+        // Check that the transaction reached the mock consensus.
+        wait_for_transactions(&consensus, 1).await;
+        assert_eq!(*consensus.transactions.lock(), vec![transaction_id]);
+        assert_eq!(validator.mempool_stats().num_transactions, 1);
+
+        // Handle two puzzle requests.
+        assert!(validator.puzzle_request(peer_ip).await);
+        assert!(validator.puzzle_request(peer_ip).await);
+        // Check that the puzzle state was read from the mock ledger, and the block header only once.
+        assert_eq!(ledger_api.num_puzzle_state_reads.load(Ordering::SeqCst), 2);
+        assert_eq!(ledger_api.num_header_reads.load(Ordering::SeqCst), 1);
+        assert_eq!(validator.block_cache.len(), 1);
+
+        // Check that a relay-only validator does not pass solutions to the consensus.
+        validator.set_relay_only(true);
+        let (message, solution) = sample_solution(address, rng);
+        assert!(validator.unconfirmed_solution(peer_ip, message, solution).await);
+        assert_eq!(consensus.solutions.lock().len(), 1);
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+use tokio::sync::oneshot;
+
+/// The default window over which unconfirmed transactions are collected into a batch.
+pub const DEFAULT_TRANSACTION_BATCH_WINDOW: Duration = Duration::from_millis(10);
+/// The default maximum number of unconfirmed transactions in a batch.
+pub const DEFAULT_MAX_TRANSACTION_BATCH_SIZE: usize = 128;
+
+/// A buffer that collects items submitted over a short window, and processes them in a single batch.
+/// The first submitter of a batch waits for the window to elapse, and then processes the whole batch
+/// on behalf of the other submitters, unless the batch fills up first and is processed by its last submitter.
+pub struct MicroBatcher<T, R> {
+    /// The batch of items waiting to be processed.
+    pending: Mutex<PendingBatch<T, R>>,
+    /// The window over which items are collected.
+    window: Mutex<Duration>,
+    /// The maximum number of items in a batch.
+    max_batch_size: Mutex<usize>,
+}
+
+/// A batch of items waiting to be processed.
+struct PendingBatch<T, R> {
+    /// The sequence number of the batch.
+    id: u64,
+    /// The items, with the channels to return their results on.
+    items: Vec<(T, oneshot::Sender<R>)>,
+}
+
+impl<T: Send, R: Send> MicroBatcher<T, R> {
+    /// Initializes a new batcher with the given window and maximum batch size.
+    pub fn new(window: Duration, max_batch_size: usize) -> Self {
+        Self {
+            pending: Mutex::new(PendingBatch { id: 0, items: Vec::new() }),
+            window: Mutex::new(window),
+            max_batch_size: Mutex::new(max_batch_size.max(1)),
+        }
+    }
+
+    /// Submits the given item, and returns its result once its batch has been processed with the given function.
+    /// The function must return one result per item, in the given order.
+    /// Returns `None` if the batch was dropped before the item was processed.
+    pub async fn submit<F, Fut>(&self, item: T, process: F) -> Option<R>
+    where
+        F: FnOnce(Vec<T>) -> Fut,
+        Fut: Future<Output = Vec<R>>,
+    {
+        let (sender, mut receiver) = oneshot::channel();
+        // Add the item to the pending batch, and take the batch if it is full.
+        let (batch_id, is_first, full_batch) = {
+            let mut pending = self.pending.lock();
+            pending.items.push((item, sender));
+            let is_first = pending.items.len() == 1;
+            let full_batch = match pending.items.len() >= self.max_batch_size() {
+                true => Some(Self::take_batch(&mut pending)),
+                false => None,
+            };
+            (pending.id, is_first, full_batch)
+        };
+        // If this item filled up the batch, process the batch.
+        if let Some(batch) = full_batch {
+            Self::process_batch(batch, process).await;
+            return receiver.await.ok();
+        }
+        // If this item started the batch, wait for the window to elapse, unless the batch is processed sooner.
+        if is_first {
+            tokio::select! {
+                result = &mut receiver => return result.ok(),
+                _ = tokio::time::sleep(self.window()) => {}
+            }
+            // Take the batch, unless it has already been taken.
+            let batch = {
+                let mut pending = self.pending.lock();
+                match pending.id == batch_id {
+                    true => Some(Self::take_batch(&mut pending)),
+                    false => None,
+                }
+            };
+            if let Some(batch) = batch {
+                Self::process_batch(batch, process).await;
+            }
+        }
+        receiver.await.ok()
+    }
+
+    /// Takes the items of the pending batch, and starts a new batch.
+    fn take_batch(pending: &mut PendingBatch<T, R>) -> Vec<(T, oneshot::Sender<R>)> {
+        pending.id = pending.id.wrapping_add(1);
+        std::mem::take(&mut pending.items)
+    }
+
+    /// Processes the given batch with the given function, and returns the results to the submitters.
+    async fn process_batch<F, Fut>(batch: Vec<(T, oneshot::Sender<R>)>, process: F)
+    where
+        F: FnOnce(Vec<T>) -> Fut,
+        Fut: Future<Output = Vec<R>>,
+    {
+        let (items, senders): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+        // Process the batch, and return the results.
+        for (sender, result) in senders.into_iter().zip(process(items).await) {
+            let _ = sender.send(result);
+        }
+    }
+
+    /// Returns the window over which items are collected.
+    pub fn window(&self) -> Duration {
+        *self.window.lock()
+    }
+
+    /// Sets the window over which items are collected.
+    pub fn set_window(&self, window: Duration) {
+        *self.window.lock() = window;
+    }
+
+    /// Returns the maximum number of items in a batch.
+    pub fn max_batch_size(&self) -> usize {
+        *self.max_batch_size.lock()
+    }
+
+    /// Sets the maximum number of items in a batch.
+    pub fn set_max_batch_size(&self, max_batch_size: usize) {
+        *self.max_batch_size.lock() = max_batch_size.max(1);
+    }
+
+    /// Returns the number of items waiting to be processed.
+    pub fn num_pending(&self) -> usize {
+        self.pending.lock().items.len()
+    }
+}
+
+impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
+    /// Sets the window over which unconfirmed transactions are collected before being added to the memory pool.
+    pub fn set_transaction_batch_window(&self, window: Duration) {
+        self.transaction_batcher.set_window(window)
+    }
+
+    /// Sets the maximum number of unconfirmed transactions added to the memory pool in a single batch.
+    pub fn set_max_transaction_batch_size(&self, max_batch_size: usize) {
+        self.transaction_batcher.set_max_batch_size(max_batch_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Submits the given number of items concurrently, and returns their results.
+    async fn submit_burst(
+        batcher: &Arc<MicroBatcher<usize, usize>>,
+        num_items: usize,
+        batch_sizes: &Arc<Mutex<Vec<usize>>>,
+    ) -> Vec<Option<usize>> {
+        let handles = (0..num_items)
+            .map(|item| {
+                let batcher = batcher.clone();
+                let batch_sizes = batch_sizes.clone();
+                tokio::spawn(async move {
+                    batcher
+                        .submit(item, |items| async move {
+                            batch_sizes.lock().push(items.len());
+                            items.into_iter().map(|item| item * 2).collect()
+                        })
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut results = Vec::with_capacity(num_items);
+        for handle in handles {
+            results.push(handle.await.unwrap());
+        }
+        results
+    }
+
+    #[tokio::test]
+    async fn test_burst_is_processed_in_a_single_batch() {
+        let batcher = Arc::new(MicroBatcher::new(Duration::from_millis(100), 128));
+        let batch_sizes = Arc::new(Mutex::new(Vec::new()));
+
+        // Submit a burst of items.
+        let results = submit_burst(&batcher, 50, &batch_sizes).await;
+
+        // Check that the burst was processed in a single call, and each submitter received its own result.
+        assert_eq!(*batch_sizes.lock(), vec![50]);
+        assert_eq!(results, (0..50).map(|item| Some(item * 2)).collect::<Vec<_>>());
+        assert_eq!(batcher.num_pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_full_batch_is_processed_early() {
+        let batcher = Arc::new(MicroBatcher::new(Duration::from_secs(60), 10));
+        let batch_sizes = Arc::new(Mutex::new(Vec::new()));
+
+        // Submit a burst of items, which fills up the batch well before the window elapses.
+        let results = tokio::time::timeout(Duration::from_secs(5), submit_burst(&batcher, 10, &batch_sizes)).await;
+
+        // Check that the batch was processed once it was full.
+        assert!(results.unwrap().iter().all(Option::is_some));
+        assert_eq!(*batch_sizes.lock(), vec![10]);
+    }
+
+    #[tokio::test]
+    async fn test_single_item_is_processed_after_the_window() {
+        let batcher = MicroBatcher::new(Duration::from_millis(10), 128);
+        let num_calls = AtomicUsize::new(0);
+
+        // Submit a single item.
+        let result = batcher
+            .submit(1usize, |items| {
+                num_calls.fetch_add(1, Ordering::SeqCst);
+                async move { items }
+            })
+            .await;
+        assert_eq!(result, Some(1));
+        assert_eq!(num_calls.load(Ordering::SeqCst), 1);
+    }
+}
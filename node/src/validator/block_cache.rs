@@ -0,0 +1,158 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use snarkvm::{ledger::narwhal::Data, prelude::ToBytes};
+
+use indexmap::IndexMap;
+
+/// The default number of serialized block headers kept in the block cache.
+pub const DEFAULT_BLOCK_CACHE_CAPACITY: usize = 16;
+
+/// A size-bounded cache of recently-served block headers, pre-serialized and keyed by height.
+/// When the cache is full, the least recently used entry is evicted.
+pub struct BlockCache<N: Network> {
+    /// The maximum number of entries.
+    capacity: Mutex<usize>,
+    /// The map of block heights to serialized block headers, from least to most recently used.
+    headers: Mutex<IndexMap<u32, Data<Header<N>>>>,
+}
+
+impl<N: Network> BlockCache<N> {
+    /// Initializes a new block cache with the given capacity.
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: Mutex::new(capacity), headers: Default::default() }
+    }
+
+    /// Returns the serialized block header at the given height, loading it with the given function on a cache miss.
+    pub fn get_or_load<F: FnOnce(u32) -> Result<Header<N>>>(&self, height: u32, load: F) -> Result<Data<Header<N>>> {
+        // Return the cached header, marking it as the most recently used.
+        {
+            let mut headers = self.headers.lock();
+            if let Some(header) = headers.shift_remove(&height) {
+                headers.insert(height, header.clone());
+                return Ok(header);
+            }
+        }
+        // Load and serialize the header.
+        let header = Data::Buffer(load(height)?.to_bytes_le()?.into());
+        // Insert the header, evicting the least recently used entries if the cache is full.
+        let capacity = self.capacity();
+        let mut headers = self.headers.lock();
+        headers.insert(height, header.clone());
+        while headers.len() > capacity {
+            headers.shift_remove_index(0);
+        }
+        Ok(header)
+    }
+
+    /// Returns the maximum number of entries.
+    pub fn capacity(&self) -> usize {
+        *self.capacity.lock()
+    }
+
+    /// Sets the maximum number of entries, evicting the least recently used entries if needed.
+    pub fn set_capacity(&self, capacity: usize) {
+        *self.capacity.lock() = capacity;
+        let mut headers = self.headers.lock();
+        while headers.len() > capacity {
+            headers.shift_remove_index(0);
+        }
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.headers.lock().len()
+    }
+
+    /// Returns `true` if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.headers.lock().is_empty()
+    }
+}
+
+impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
+    /// Returns the serialized block header at the given height, consulting the block cache before the ledger.
+    pub fn block_header(&self, height: u32) -> Result<Data<Header<N>>> {
+        self.block_cache.get_or_load(height, |height| self.ledger_api().get_header(height))
+    }
+
+    /// Sets the maximum number of serialized block headers kept in the block cache.
+    pub fn set_block_cache_capacity(&self, capacity: usize) {
+        self.block_cache.set_capacity(capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::prelude::{FromBytes, Testnet3};
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    type CurrentNetwork = Testnet3;
+
+    /// Returns the genesis block header.
+    fn sample_header() -> Header<CurrentNetwork> {
+        *Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap().header()
+    }
+
+    #[test]
+    fn test_block_cache_hit() {
+        let cache = BlockCache::<CurrentNetwork>::new(2);
+        let num_loads = AtomicUsize::new(0);
+        let load = |_| {
+            num_loads.fetch_add(1, Ordering::SeqCst);
+            Ok(sample_header())
+        };
+
+        // Request the same height twice.
+        let first = cache.get_or_load(0, load).unwrap();
+        let second = cache.get_or_load(0, load).unwrap();
+
+        // Check that the ledger was queried only once, and the header is served pre-serialized.
+        assert_eq!(num_loads.load(Ordering::SeqCst), 1);
+        assert!(matches!(second, Data::Buffer(_)));
+        assert_eq!(first.to_bytes_le().unwrap(), second.to_bytes_le().unwrap());
+        assert_eq!(first.to_bytes_le().unwrap(), Data::Object(sample_header()).to_bytes_le().unwrap());
+    }
+
+    #[test]
+    fn test_block_cache_eviction() {
+        let cache = BlockCache::<CurrentNetwork>::new(2);
+        let num_loads = AtomicUsize::new(0);
+        let load = |_| {
+            num_loads.fetch_add(1, Ordering::SeqCst);
+            Ok(sample_header())
+        };
+
+        // Fill the cache, and mark height 0 as the most recently used.
+        cache.get_or_load(0, load).unwrap();
+        cache.get_or_load(1, load).unwrap();
+        cache.get_or_load(0, load).unwrap();
+        assert_eq!(num_loads.load(Ordering::SeqCst), 2);
+
+        // Insert a third height, which evicts height 1.
+        cache.get_or_load(2, load).unwrap();
+        assert_eq!(cache.len(), 2);
+        cache.get_or_load(0, load).unwrap();
+        assert_eq!(num_loads.load(Ordering::SeqCst), 3);
+        cache.get_or_load(1, load).unwrap();
+        assert_eq!(num_loads.load(Ordering::SeqCst), 4);
+
+        // Shrink the cache.
+        cache.set_capacity(1);
+        assert_eq!(cache.len(), 1);
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// The default minimum number of connected peers for a validator to be ready.
+pub const DEFAULT_MIN_HEALTHY_PEERS: usize = 1;
+
+/// The health of a validator, as reported to orchestration platforms.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthStatus {
+    /// Whether the node is listening for connections.
+    pub is_listening: bool,
+    /// The number of connected peers.
+    pub num_connected_peers: usize,
+    /// The minimum number of connected peers for the node to be ready.
+    pub min_peers: usize,
+    /// The latest block height, or `None` if the ledger did not respond within the ledger read timeout.
+    pub latest_height: Option<u32>,
+    /// Whether the node is shutting down.
+    pub is_shutting_down: bool,
+}
+
+impl HealthStatus {
+    /// Returns `true` if the node is alive, i.e. it is not shutting down.
+    pub const fn is_live(&self) -> bool {
+        !self.is_shutting_down
+    }
+
+    /// Returns `true` if the node is ready to serve, i.e. it is alive, listening, connected to enough peers,
+    /// and its ledger is responsive.
+    pub const fn is_ready(&self) -> bool {
+        let has_enough_peers = self.num_connected_peers >= self.min_peers;
+        self.is_live() && self.is_listening && has_enough_peers && self.latest_height.is_some()
+    }
+}
+
+impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
+    /// Returns the health of the node, reading the latest block height within the ledger read timeout.
+    pub async fn health(&self) -> HealthStatus {
+        HealthStatus {
+            is_listening: self.router.tcp().listening_addr().is_ok(),
+            num_connected_peers: self.router.number_of_connected_peers(),
+            min_peers: self.min_healthy_peers(),
+            latest_height: self.latest_height_with_timeout(self.router.ledger_read_timeout()).await,
+            is_shutting_down: self.shutdown.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns the minimum number of connected peers for the node to be ready.
+    pub fn min_healthy_peers(&self) -> usize {
+        self.min_healthy_peers.load(Ordering::Relaxed)
+    }
+
+    /// Sets the minimum number of connected peers for the node to be ready.
+    pub fn set_min_healthy_peers(&self, min_peers: usize) {
+        self.min_healthy_peers.store(min_peers, Ordering::Relaxed)
+    }
+
+    /// Returns the latest block height, or `None` if reading it from the ledger takes longer than the given timeout.
+    async fn latest_height_with_timeout(&self, timeout: Duration) -> Option<u32> {
+        // Read the height on the blocking thread pool, so that a stalled read does not hold up the caller.
+        let ledger_api = self.ledger_api();
+        let read = tokio::task::spawn_blocking(move || ledger_api.latest_height());
+        tokio::time::timeout(timeout, read).await.ok()?.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::test_helpers::*;
+    use snarkvm::prelude::TestRng;
+
+    #[tokio::test]
+    async fn test_health_requires_min_peers() {
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4130".parse().unwrap();
+
+        // Initialize a listening validator, without peers.
+        let ledger_api = Arc::new(MockLedger::new(sample_ledger(), Duration::ZERO, false));
+        let validator = sample_validator(Arc::new(MockConsensus::default()), ledger_api, rng).await;
+        validator.router.tcp().enable_listener().await.unwrap();
+        validator.set_min_healthy_peers(1);
+
+        // Check that the validator is live, but not ready without a peer.
+        let health = validator.health().await;
+        assert!(health.is_live());
+        assert!(health.is_listening);
+        assert_eq!(health.latest_height, Some(0));
+        assert_eq!(health.num_connected_peers, 0);
+        assert!(!health.is_ready());
+
+        // Check that the validator is ready once a peer connects.
+        connect_peer(&validator, peer_ip, &sample_challenge_request(peer_ip, NodeType::Client, rng));
+        assert!(validator.health().await.is_ready());
+
+        // Check that a shutting down validator is neither live nor ready.
+        validator.shutdown.store(true, Ordering::Relaxed);
+        let health = validator.health().await;
+        assert!(!health.is_live());
+        assert!(!health.is_ready());
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use super::*;
+use snarkos_node_consensus::TransactionRejectReason;
+use snarkvm::prelude::coinbase::PuzzleCommitment;
+
+use core::fmt;
+use tokio::sync::broadcast;
+
+/// The number of memory pool events buffered for each subscriber, before a lagging subscriber misses events.
+pub const MEMPOOL_EVENT_CAPACITY: usize = 1024;
+
+/// The ID of an unconfirmed solution or transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MempoolId<N: Network> {
+    /// The commitment of an unconfirmed solution.
+    Solution(PuzzleCommitment<N>),
+    /// The ID of an unconfirmed transaction.
+    Transaction(N::TransactionID),
+}
+
+/// The reason an unconfirmed solution or transaction was not admitted to the memory pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MempoolRejectReason {
+    /// The solution failed the inexpensive checks.
+    Solution(SolutionRejectReason),
+    /// The transaction was rejected by the memory pool.
+    Transaction(TransactionRejectReason),
+    /// The transaction fee is below the minimum fee.
+    FeeTooLow { fee: u64, min_fee: u64 },
+    /// The transaction is larger than the maximum size, in bytes.
+    TooLarge { size: usize, max_size: usize },
+    /// The transaction was rejected by an admission rule, for the given reason.
+    Rule(String),
+    /// The queue of unconfirmed transactions to the memory pool was full.
+    QueueFull,
+    /// The solution or transaction could not be added to the memory pool.
+    Failed(String),
+}
+
+impl fmt::Display for MempoolRejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Solution(reason) => write!(f, "{reason}"),
+            Self::Transaction(reason) => write!(f, "{reason}"),
+            Self::FeeTooLow { fee, min_fee } => write!(f, "the fee ({fee}) is below the minimum fee ({min_fee})"),
+            Self::TooLarge { size, max_size } => {
+                write!(f, "the transaction size ({size} bytes) exceeds the maximum size ({max_size} bytes)")
+            }
+            Self::Rule(reason) => write!(f, "{reason}"),
+            Self::QueueFull => write!(f, "the queue to the memory pool is full"),
+            Self::Failed(error) => write!(f, "failed to add to the memory pool - {error}"),
+        }
+    }
+}
+
+impl MempoolRejectReason {
+    /// Returns the rejection reason for the given error from adding a transaction to the memory pool.
+    pub(super) fn from_transaction_error(error: &anyhow::Error) -> Self {
+        match error.downcast_ref::<TransactionRejectReason>() {
+            Some(reason) => Self::Transaction(reason.clone()),
+            None => Self::Failed(error.to_string()),
+        }
+    }
+}
+
+/// The outcome of an unconfirmed solution or transaction, for observation by applications.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MempoolEvent<N: Network> {
+    /// The solution was admitted to the memory pool.
+    SolutionAdmitted {
+        /// The commitment of the solution.
+        id: PuzzleCommitment<N>,
+    },
+    /// The transaction was admitted to the memory pool.
+    TransactionAdmitted {
+        /// The ID of the transaction.
+        id: N::TransactionID,
+    },
+    /// The solution or transaction was not admitted to the memory pool.
+    Rejected {
+        /// The ID of the solution or transaction.
+        id: MempoolId<N>,
+        /// The reason for the rejection.
+        reason: MempoolRejectReason,
+    },
+}
+
+impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
+    /// Subscribes to the outcomes of the unconfirmed solutions and transactions, whether they were received
+    /// from peers or submitted locally. Relayed solutions and transactions do not emit events.
+    pub fn subscribe_mempool(&self) -> broadcast::Receiver<MempoolEvent<N>> {
+        self.mempool_events.subscribe()
+    }
+
+    /// Sends the given memory pool event to the subscribers.
+    /// The event is dropped if there are no subscribers.
+    pub(super) fn emit_mempool_event(&self, event: MempoolEvent<N>) {
+        let _ = self.mempool_events.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::test_helpers::*;
+    use snarkvm::prelude::TestRng;
+
+    #[tokio::test]
+    async fn test_mempool_events() {
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4130".parse().unwrap();
+
+        // Initialize a validator with a mock consensus and ledger, and subscribe to its memory pool events.
+        let consensus = Arc::new(MockConsensus::default());
+        let ledger_api = Arc::new(MockLedger::new(sample_ledger(), Duration::ZERO, false));
+        let validator = sample_validator(consensus.clone(), ledger_api, rng).await;
+        let mut events = validator.subscribe_mempool();
+
+        // Check that submitting a valid transaction emits an admission event.
+        let transaction = sample_transaction();
+        let id = transaction.id();
+        validator.submit_transaction(transaction.clone()).await.unwrap();
+        assert_eq!(events.try_recv().unwrap(), MempoolEvent::TransactionAdmitted { id });
+
+        // Check that submitting the transaction again emits a rejection event, with the reason.
+        consensus.rejections.lock().insert(id, TransactionRejectReason::AlreadyInMemoryPool);
+        let error = validator.submit_transaction(transaction.clone()).await.unwrap_err();
+        assert_eq!(error, SubmitError::Rejected(TransactionRejectReason::AlreadyInMemoryPool));
+        let reason = MempoolRejectReason::Transaction(TransactionRejectReason::AlreadyInMemoryPool);
+        assert_eq!(events.try_recv().unwrap(), MempoolEvent::Rejected { id: MempoolId::Transaction(id), reason });
+
+        // Check that a transaction from a peer below the minimum fee emits a rejection event.
+        let fee = *transaction.fee_amount().unwrap();
+        validator.set_min_fee(fee + 1);
+        assert!(validator.unconfirmed_transaction(peer_ip, transaction_message(&transaction), transaction).await);
+        let reason = MempoolRejectReason::FeeTooLow { fee, min_fee: fee + 1 };
+        assert_eq!(events.try_recv().unwrap(), MempoolEvent::Rejected { id: MempoolId::Transaction(id), reason });
+
+        // Check that a solution from a peer emits an admission event.
+        let (message, solution) = sample_solution(sample_address(rng), rng);
+        assert!(validator.unconfirmed_solution(peer_ip, message, solution).await);
+        assert_eq!(events.try_recv().unwrap(), MempoolEvent::SolutionAdmitted { id: solution.commitment() });
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -12,28 +12,74 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod admission;
+pub use admission::*;
+
+mod api;
+pub use api::*;
+
+mod batcher;
+pub use batcher::*;
+
+mod block_cache;
+pub use block_cache::*;
+
+mod health;
+pub use health::*;
+
+mod mempool_events;
+pub use mempool_events::*;
+
+mod precheck;
+pub use precheck::*;
+
+mod puzzle;
+pub use puzzle::*;
+
 mod router;
 
+mod submit;
+pub use submit::*;
+
+mod writer;
+pub use writer::*;
+
+#[cfg(test)]
+mod test_helpers;
+
 use crate::traits::NodeInterface;
 use snarkos_account::Account;
 use snarkos_node_bft::{helpers::init_primary_channels, ledger_service::CoreLedgerService};
-use snarkos_node_consensus::Consensus;
+use snarkos_node_consensus::{Consensus, MempoolStats};
 use snarkos_node_rest::Rest;
 use snarkos_node_router::{
-    messages::{NodeType, PuzzleResponse, UnconfirmedSolution, UnconfirmedTransaction},
+    messages::{Feature, Message, NodeType, PuzzleResponse, UnconfirmedSolution, UnconfirmedTransaction},
+    ConnectionManager,
+    DisconnectRecord,
+    EvictionStrategy,
     Heartbeat,
     Inbound,
+    ListenerPolicy,
+    LoadGovernor,
+    MessageId,
     Outbound,
+    PeerClassifier,
     Router,
+    RouterConfig,
     Routing,
+    TopologyChanges,
+    TopologyDesc,
+    TopologySnapshot,
+    DEFAULT_TARGET_OUTBOUND_PEERS,
 };
 use snarkos_node_sync::{BlockSync, BlockSyncMode};
 use snarkos_node_tcp::{
     protocols::{Disconnect, Handshake, OnConnect, Reading, Writing},
+    ConnectionSide,
     P2P,
 };
 use snarkvm::prelude::{
-    block::{Block, Header},
+    block::{Block, Header, Transaction},
     coinbase::ProverSolution,
     store::ConsensusStorage,
     Ledger,
@@ -42,10 +88,14 @@ use snarkvm::prelude::{
 
 use anyhow::Result;
 use core::future::Future;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use std::{
     net::SocketAddr,
-    sync::{atomic::AtomicBool, Arc},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
     time::Duration,
 };
 use tokio::task::JoinHandle;
@@ -55,14 +105,46 @@ use tokio::task::JoinHandle;
 pub struct Validator<N: Network, C: ConsensusStorage<N>> {
     /// The ledger of the node.
     ledger: Ledger<N, C>,
-    /// The consensus module of the node.
-    consensus: Consensus<N>,
+    /// The consensus module of the node, as used by the message handlers.
+    consensus: Arc<RwLock<Arc<dyn ConsensusApi<N>>>>,
+    /// The ledger reads used by the message handlers.
+    ledger_api: Arc<RwLock<Arc<dyn LedgerApi<N>>>>,
     /// The router of the node.
     router: Router<N>,
     /// The REST server of the node.
     rest: Option<Rest<N, C, Self>>,
     /// The sync module.
     sync: BlockSync<N>,
+    /// The cache of recently-served block headers.
+    block_cache: Arc<BlockCache<N>>,
+    /// The buffer that collects unconfirmed transactions received in bursts, to add them to the memory pool in batches.
+    transaction_batcher: Arc<MicroBatcher<Transaction<N>, Result<()>>>,
+    /// The sender of the queue of unconfirmed transactions, handed off from the message handlers to the memory pool.
+    transaction_sender: tokio::sync::mpsc::Sender<QueuedTransaction<N>>,
+    /// The number of unconfirmed transactions dropped, as the queue to the memory pool was full.
+    num_transaction_queue_overflows: Arc<AtomicU64>,
+    /// The sender of the outcomes of the unconfirmed solutions and transactions, to the subscribers.
+    mempool_events: tokio::sync::broadcast::Sender<MempoolEvent<N>>,
+    /// The manager of the outbound connections.
+    connection_manager: Arc<ConnectionManager<N>>,
+    /// The strategy for selecting the block served in puzzle responses.
+    puzzle_block_selector: Arc<RwLock<Arc<dyn PuzzleBlockSelector<N, C>>>>,
+    /// The number of puzzle state reads that timed out, and are still in progress.
+    num_stalled_puzzle_state_reads: Arc<AtomicUsize>,
+    /// The path to the file of saved peers.
+    peers_path: PathBuf,
+    /// The path to the file of saved restricted peers.
+    restricted_peers_path: PathBuf,
+    /// The flag indicating whether the node only relays unconfirmed solutions and transactions.
+    relay_only: Arc<AtomicBool>,
+    /// The minimum fee of the unconfirmed transactions accepted from peers, in microcredits.
+    min_fee: Arc<AtomicU64>,
+    /// The rules an unconfirmed transaction from a peer must pass, in order, before it is queued for the memory pool.
+    admission_rules: Arc<RwLock<Vec<Box<dyn TxAdmissionRule<N>>>>>,
+    /// The number of unconfirmed transactions dropped for a fee below the minimum.
+    num_low_fee_rejections: Arc<AtomicU64>,
+    /// The minimum number of connected peers for the node to be ready.
+    min_healthy_peers: Arc<AtomicUsize>,
     /// The spawned handles.
     handles: Arc<Mutex<Vec<JoinHandle<()>>>>,
     /// The shutdown signal.
@@ -122,15 +204,8 @@ impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
         .await?;
 
         // Initialize the node.
-        let mut node = Self {
-            ledger: ledger.clone(),
-            consensus: consensus.clone(),
-            router,
-            rest: None,
-            sync,
-            handles: Default::default(),
-            shutdown: Default::default(),
-        };
+        let consensus_api = Arc::new(consensus.clone());
+        let mut node = Self::from_parts(ledger.clone(), consensus_api, Arc::new(ledger.clone()), router, sync, dev);
         // Initialize the transaction pool.
         node.initialize_transaction_pool(dev)?;
 
@@ -138,8 +213,17 @@ impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
         if let Some(rest_ip) = rest_ip {
             node.rest = Some(Rest::start(rest_ip, Some(consensus), ledger.clone(), Arc::new(node.clone()))?);
         }
+        // Restore the restrictions saved on the last shutdown, which have not yet expired.
+        node.router.load_restricted_peers(&node.restricted_peers_path);
         // Initialize the routing.
         node.initialize_routing().await;
+        // Reconnect to the peers saved on the last shutdown.
+        for peer_ip in node.router.load_peers(&node.peers_path) {
+            node.router.connect(peer_ip);
+        }
+        // Maintain the target number of outbound connections.
+        let connection_manager = node.connection_manager.clone();
+        node.spawn(async move { connection_manager.run().await });
         // Initialize the notification message loop.
         node.handles.lock().push(crate::start_notification_message_loop());
         // Pass the node to the signal handler.
@@ -148,6 +232,65 @@ impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
         Ok(node)
     }
 
+    /// Initializes the node from its ledger, consensus, router and sync module, without starting it,
+    /// except for adding the queued unconfirmed transactions to the memory pool.
+    fn from_parts(
+        ledger: Ledger<N, C>,
+        consensus: Arc<dyn ConsensusApi<N>>,
+        ledger_api: Arc<dyn LedgerApi<N>>,
+        router: Router<N>,
+        sync: BlockSync<N>,
+        dev: Option<u16>,
+    ) -> Self {
+        // Initialize the connection manager.
+        let connection_manager = Arc::new(ConnectionManager::new(router.clone(), DEFAULT_TARGET_OUTBOUND_PEERS));
+        // Initialize the queue of unconfirmed transactions for the memory pool.
+        let (transaction_sender, transaction_receiver) = tokio::sync::mpsc::channel(DEFAULT_TRANSACTION_QUEUE_CAPACITY);
+
+        // Initialize the minimum fee, which is enforced by the default admission rules.
+        let min_fee = Arc::new(AtomicU64::new(0));
+
+        // Initialize the node.
+        let node = Self {
+            ledger,
+            consensus: Arc::new(RwLock::new(consensus)),
+            ledger_api: Arc::new(RwLock::new(ledger_api)),
+            router,
+            connection_manager,
+            rest: None,
+            sync,
+            block_cache: Arc::new(BlockCache::new(DEFAULT_BLOCK_CACHE_CAPACITY)),
+            transaction_batcher: Arc::new(MicroBatcher::new(
+                DEFAULT_TRANSACTION_BATCH_WINDOW,
+                DEFAULT_MAX_TRANSACTION_BATCH_SIZE,
+            )),
+            transaction_sender,
+            num_transaction_queue_overflows: Default::default(),
+            mempool_events: tokio::sync::broadcast::channel(MEMPOOL_EVENT_CAPACITY).0,
+            puzzle_block_selector: Arc::new(RwLock::new(Arc::new(LatestBlockSelector))),
+            num_stalled_puzzle_state_reads: Default::default(),
+            peers_path: Self::saved_peers_path(dev, "peers"),
+            restricted_peers_path: Self::saved_peers_path(dev, "restricted"),
+            relay_only: Default::default(),
+            admission_rules: Arc::new(RwLock::new(default_admission_rules(&min_fee))),
+            min_fee,
+            num_low_fee_rejections: Default::default(),
+            min_healthy_peers: Arc::new(AtomicUsize::new(DEFAULT_MIN_HEALTHY_PEERS)),
+            handles: Default::default(),
+            shutdown: Default::default(),
+        };
+        // Start adding the queued unconfirmed transactions to the memory pool.
+        node.initialize_transaction_writer(transaction_receiver);
+        node
+    }
+
+    /// Returns the path to the file of saved peers with the given extension, next to the ledger in storage.
+    fn saved_peers_path(dev: Option<u16>, extension: &str) -> PathBuf {
+        let mut path = aleo_std::aleo_ledger_dir(N::ID, dev);
+        path.set_extension(extension);
+        path
+    }
+
     /// Returns the ledger.
     pub fn ledger(&self) -> &Ledger<N, C> {
         &self.ledger
@@ -157,6 +300,222 @@ impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
     pub fn rest(&self) -> &Option<Rest<N, C, Self>> {
         &self.rest
     }
+
+    /// Registers a callback to be invoked with the peer IP and the connection side of the peer,
+    /// after every successful handshake.
+    pub fn on_handshake_complete<F: FnMut(SocketAddr, ConnectionSide) + Send + 'static>(&self, hook: F) {
+        self.router.on_handshake_complete(hook)
+    }
+
+    /// Sets the maximum number of concurrent handshakes.
+    pub fn set_max_concurrent_handshakes(&self, limit: usize) {
+        self.router.set_max_concurrent_handshakes(limit)
+    }
+
+    /// Replaces the live router configuration, without restarting the node.
+    /// If the maximum number of peers was lowered, the surplus peers are disconnected.
+    pub fn reload_config(&self, config: RouterConfig) {
+        Heartbeat::reload_config(self, config)
+    }
+
+    /// Replays the messages from the message log at the given path through the inbound handler,
+    /// and returns the outcome of each message. This reproduces a recorded exchange for debugging.
+    pub async fn replay(&self, path: &Path) -> Result<Vec<Result<()>>> {
+        Inbound::replay(self, path).await
+    }
+
+    /// Sets the classifier that assigns peers to groups, and the maximum number of connected peers per group.
+    pub fn set_peer_classifier<P: PeerClassifier + 'static>(&self, classifier: P, max_peers_per_group: usize) {
+        self.router.set_peer_classifier(classifier);
+        self.router.set_max_peers_per_group(max_peers_per_group);
+    }
+
+    /// Starts listening for connections on the given address, in addition to the node IP.
+    /// The peers connecting through it are admitted under the given policy. Returns the bound address.
+    pub async fn add_listener(&self, addr: SocketAddr, policy: ListenerPolicy) -> std::io::Result<SocketAddr> {
+        self.router.add_listener(addr, policy).await
+    }
+
+    /// Sets the strategy for choosing the connected peer to evict, when a new peer connects at capacity.
+    /// By default, the new peer is rejected. Pinned peers are never evicted.
+    pub fn set_eviction_strategy<S: EvictionStrategy<N> + 'static>(&self, strategy: S) {
+        self.router.set_eviction_strategy(strategy);
+    }
+
+    /// Sets the governor that reports whether the host is too loaded to accept new inbound connections.
+    /// Pinned peers are accepted regardless.
+    pub fn set_load_governor<G: LoadGovernor + 'static>(&self, governor: G) {
+        self.router.set_load_governor(governor);
+    }
+
+    /// Pauses accepting new inbound connections, e.g. during a maintenance window.
+    /// The connected peers are kept, and pinned peers may still connect.
+    pub fn pause_accepting(&self) {
+        self.router.pause_accepting();
+    }
+
+    /// Resumes accepting new inbound connections.
+    pub fn resume_accepting(&self) {
+        self.router.resume_accepting();
+    }
+
+    /// Pins the given peer, so that it is never evicted and may connect beyond the maximum number of peers.
+    /// If the peer is not connected, the node attempts to connect to it.
+    pub fn pin_peer(&self, peer_ip: SocketAddr) -> Result<()> {
+        self.router.pin_peer(peer_ip)?;
+        if !self.router.is_connected(&peer_ip) {
+            self.router.connect(peer_ip);
+        }
+        Ok(())
+    }
+
+    /// Unpins the given peer, returning `true` if the peer was pinned.
+    pub fn unpin_peer(&self, peer_ip: &SocketAddr) -> bool {
+        self.router.unpin_peer(peer_ip)
+    }
+
+    /// Reconciles the connections of the node with the given topology, dialing the missing required peers,
+    /// and disconnecting from the peers the topology disallows.
+    pub fn apply_topology(&self, topology: &TopologyDesc) -> TopologyChanges {
+        self.router.apply_topology(topology)
+    }
+
+    /// Returns `true` if the given peer is connected, and its message version supports the given feature.
+    pub fn peer_supports(&self, peer_ip: &SocketAddr, feature: Feature) -> bool {
+        self.router.peer_supports(peer_ip, feature)
+    }
+
+    /// Returns a snapshot of the connections of the validator, for tools assembling a graph of the network.
+    pub fn topology_snapshot(&self) -> TopologySnapshot<N> {
+        self.router.topology_snapshot()
+    }
+
+    /// Sets the maximum number of peers each propagated solution and transaction is sent to.
+    pub fn set_propagation_fanout(&self, fanout: usize) {
+        self.router.set_propagation_fanout(fanout)
+    }
+
+    /// Sets the maximum number of solutions and transactions propagated concurrently.
+    /// Under load, further propagations wait for one in progress to complete.
+    pub fn set_max_concurrent_propagations(&self, limit: usize) {
+        self.router.set_max_concurrent_propagations(limit)
+    }
+
+    /// Propagates the given message to the connected validators, once a propagation permit is available,
+    /// so that a burst of solutions and transactions is propagated a few at a time.
+    /// Returns `false` if no permit became available, in which case the message is not propagated.
+    pub(crate) async fn propagate_to_validators_bounded(
+        &self,
+        message: Message<N>,
+        excluded_peers: &[SocketAddr],
+    ) -> bool {
+        let Some(_permit) = self.router.acquire_propagation(&message).await else {
+            debug!("Skipping the propagation of '{}' (too many concurrent propagations)", message.name());
+            return false;
+        };
+        self.propagate_to_validators(message, excluded_peers);
+        true
+    }
+
+    /// Returns a snapshot of the solutions and transactions admitted to the memory pool by this node.
+    pub fn mempool_stats(&self) -> MempoolStats {
+        self.consensus().mempool_stats()
+    }
+
+    /// Sets the target number of outbound connections kept by the connection manager.
+    pub fn set_target_outbound_peers(&self, target: usize) {
+        self.connection_manager.set_target(target)
+    }
+
+    /// Returns the number of connected peers that were dialed by the connection manager.
+    pub fn number_of_outbound_peers(&self) -> usize {
+        self.connection_manager.number_of_outbound_peers()
+    }
+
+    /// Returns `true` if the node relays unconfirmed solutions and transactions, without adding them to its mempool.
+    pub fn is_relay_only(&self) -> bool {
+        self.relay_only.load(Ordering::Relaxed)
+    }
+
+    /// Sets whether the node relays unconfirmed solutions and transactions, without adding them to its mempool.
+    pub fn set_relay_only(&self, relay_only: bool) {
+        self.relay_only.store(relay_only, Ordering::Relaxed)
+    }
+
+    /// Returns the minimum fee of the unconfirmed transactions accepted from peers, in microcredits.
+    pub fn min_fee(&self) -> u64 {
+        self.min_fee.load(Ordering::Relaxed)
+    }
+
+    /// Sets the minimum fee of the unconfirmed transactions accepted from peers, in microcredits.
+    /// Transactions with a lower fee are dropped by the default `MinFeeRule`, without adding them to the memory pool
+    /// or propagating them.
+    pub fn set_min_fee(&self, min_fee: u64) {
+        self.min_fee.store(min_fee, Ordering::Relaxed)
+    }
+
+    /// Returns the number of unconfirmed transactions dropped for a fee below the minimum.
+    pub fn number_of_low_fee_rejections(&self) -> u64 {
+        self.num_low_fee_rejections.load(Ordering::Relaxed)
+    }
+
+    /// Seeds the selection of the peers to propagate to, making it reproducible, e.g. in tests.
+    pub fn with_propagation_seed(self, seed: u64) -> Self {
+        self.router.set_propagation_seed(seed);
+        self
+    }
+
+    /// Returns `true` if the message with the given ID was (probably) seen, from any peer.
+    pub fn has_seen_message(&self, id: MessageId<N>) -> bool {
+        self.router.has_seen_message(id)
+    }
+
+    /// Returns `true` if the node is catching up with the ledger.
+    pub fn is_syncing(&self) -> bool {
+        self.router.is_syncing()
+    }
+
+    /// Sets whether the node is catching up with the ledger. While syncing, puzzle requests are declined
+    /// without disconnecting, and are served normally once the flag is cleared.
+    pub fn set_syncing(&self, is_syncing: bool) {
+        self.router.set_syncing(is_syncing)
+    }
+
+    /// Upgrades the codec of the connection with the given peer to the given version, without reconnecting.
+    /// Disconnects from the peer if it does not acknowledge the upgrade in time.
+    pub async fn upgrade_peer_codec(&self, peer_ip: SocketAddr, version: u8) -> Result<()> {
+        Inbound::upgrade_peer_codec(self, peer_ip, version).await
+    }
+
+    /// Returns `true` if compressed frames are in use with the given peer, as negotiated during the handshake.
+    pub fn peer_supports_compression(&self, peer_ip: &SocketAddr) -> bool {
+        self.router.peer_supports_compression(peer_ip)
+    }
+
+    /// Returns the number of outbound messages queued for the connected peers, that have not yet been written.
+    pub fn inflight_sends(&self) -> usize {
+        Writing::inflight_sends(self)
+    }
+
+    /// Cancels the outbound messages queued for the connected peers, returning their number.
+    /// The peers stay connected, and a message that is being written is left to complete.
+    pub fn cancel_inflight_sends(&self) -> usize {
+        Writing::cancel_inflight_sends(self)
+    }
+
+    /// Returns up to the given number of the most recent disconnects, from oldest to newest.
+    pub fn recent_disconnects(&self, limit: usize) -> Vec<DisconnectRecord> {
+        self.router.recent_disconnects(limit)
+    }
+
+    /// Sends a `Ping` message to every connected peer, to measure its liveness and round-trip time.
+    /// Disconnects from the peers that missed too many consecutive pings.
+    pub fn ping_all(&self) {
+        match self.sync.get_block_locators() {
+            Ok(block_locators) => Outbound::ping_all(self, Some(block_locators)),
+            Err(e) => error!("Failed to get block locators - {e}"),
+        }
+    }
 }
 
 impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
@@ -424,17 +783,28 @@ impl<N: Network, C: ConsensusStorage<N>> NodeInterface<N> for Validator<N, C> {
         // Shut down the node.
         trace!("Shutting down the node...");
         self.shutdown.store(true, std::sync::atomic::Ordering::Relaxed);
+        // Cancel the messages being processed, so that peers are not punished for failures caused by the shutdown.
+        self.router.shutdown_token().cancel();
 
         // Abort the tasks.
         trace!("Shutting down the validator...");
         self.handles.lock().iter().for_each(|handle| handle.abort());
 
+        // Save the connected peers, to reconnect to them on the next startup.
+        if let Err(error) = self.router.save_peers(&self.peers_path) {
+            warn!("Failed to save the peers - {error}");
+        }
+        // Save the restricted peers, so that their restrictions survive a restart.
+        if let Err(error) = self.router.save_restricted_peers(&self.restricted_peers_path) {
+            warn!("Failed to save the restricted peers - {error}");
+        }
+
         // Shut down the router.
         self.router.shut_down().await;
 
         // Shut down consensus.
         trace!("Shutting down consensus...");
-        self.consensus.shut_down().await;
+        self.consensus().shut_down().await;
 
         info!("Node has shut down.");
     }
@@ -0,0 +1,172 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use snarkos_node_consensus::TransactionRejectReason;
+use snarkvm::prelude::Address;
+
+use core::fmt;
+
+/// The reason an unconfirmed solution was rejected, before reaching consensus.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SolutionRejectReason {
+    /// The solution is for the zero address.
+    InvalidAddress,
+    /// The solution commitment could not be converted into a proof target.
+    InvalidCommitment,
+    /// The solution does not meet the latest proof target.
+    InsufficientProofTarget { solution_target: u64, proof_target: u64 },
+    /// The solution already exists in the ledger.
+    AlreadyInLedger,
+}
+
+impl SolutionRejectReason {
+    /// Returns `true` if the sender of the solution is at fault for the rejection.
+    /// The proof target may have changed while an honest peer was relaying the solution,
+    /// and the solution may have reached the ledger in the meantime, so neither is a fault of the sender.
+    pub const fn is_sender_fault(&self) -> bool {
+        matches!(self, Self::InvalidAddress | Self::InvalidCommitment)
+    }
+}
+
+impl fmt::Display for SolutionRejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidAddress => write!(f, "the solution is for the zero address"),
+            Self::InvalidCommitment => write!(f, "the solution commitment is malformed"),
+            Self::InsufficientProofTarget { solution_target, proof_target } => {
+                write!(f, "the solution target ({solution_target}) is below the proof target ({proof_target})")
+            }
+            Self::AlreadyInLedger => write!(f, "the solution already exists in the ledger"),
+        }
+    }
+}
+
+impl std::error::Error for SolutionRejectReason {}
+
+impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
+    /// Performs the inexpensive checks on the given solution, before it is added to the memory pool.
+    pub fn precheck_solution(&self, solution: &ProverSolution<N>) -> Result<(), SolutionRejectReason> {
+        // Ensure the solution is well-formed, and meets the latest proof target.
+        let ledger = self.ledger_api();
+        precheck_solution(solution, ledger.latest_proof_target())?;
+        // Ensure the solution does not already exist in the ledger.
+        if ledger.contains_puzzle_commitment(&solution.commitment()).unwrap_or(false) {
+            return Err(SolutionRejectReason::AlreadyInLedger);
+        }
+        Ok(())
+    }
+}
+
+/// Ensures the given solution is well-formed, and meets the given proof target.
+fn precheck_solution<N: Network>(solution: &ProverSolution<N>, proof_target: u64) -> Result<(), SolutionRejectReason> {
+    // Ensure the solution is not for the zero address.
+    if solution.address() == Address::zero() {
+        return Err(SolutionRejectReason::InvalidAddress);
+    }
+    // Ensure the solution target is at least the proof target.
+    match solution.to_target() {
+        Ok(solution_target) if solution_target >= proof_target => Ok(()),
+        Ok(solution_target) => Err(SolutionRejectReason::InsufficientProofTarget { solution_target, proof_target }),
+        Err(_) => Err(SolutionRejectReason::InvalidCommitment),
+    }
+}
+
+/// Returns `true` if the given error from adding an unconfirmed transaction means the transaction is invalid.
+/// Any other error (e.g. a duplicate transaction) is not a fault of the peer that sent the transaction.
+pub(super) fn is_invalid_transaction(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<TransactionRejectReason>().map_or(false, |reason| reason.is_sender_fault())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::{
+        algorithms::polycommit::kzg10::{KZGCommitment, KZGProof},
+        ledger::coinbase::PartialSolution,
+        prelude::{PrivateKey, Rng, TestRng, Testnet3},
+    };
+
+    type CurrentNetwork = Testnet3;
+
+    /// Samples a prover solution for the given address.
+    fn sample_solution(address: Address<CurrentNetwork>, rng: &mut TestRng) -> ProverSolution<CurrentNetwork> {
+        let partial_solution = PartialSolution::new(address, rng.gen(), KZGCommitment(rng.gen()));
+        ProverSolution::new(partial_solution, KZGProof { w: rng.gen(), random_v: None })
+    }
+
+    #[test]
+    fn test_precheck_rejects_malformed_solution() {
+        let rng = &mut TestRng::default();
+
+        // Sample a solution for the zero address.
+        let solution = sample_solution(Address::zero(), rng);
+        // Check that the solution is rejected.
+        assert_eq!(precheck_solution(&solution, 1), Err(SolutionRejectReason::InvalidAddress));
+    }
+
+    #[test]
+    fn test_precheck_rejects_insufficient_target() {
+        let rng = &mut TestRng::default();
+
+        // Sample a solution.
+        let address = Address::try_from(PrivateKey::<CurrentNetwork>::new(rng).unwrap()).unwrap();
+        let solution = sample_solution(address, rng);
+        let solution_target = solution.to_target().unwrap();
+        // Check that the solution is rejected, if the proof target is above the solution target.
+        assert_eq!(
+            precheck_solution(&solution, solution_target + 1),
+            Err(SolutionRejectReason::InsufficientProofTarget { solution_target, proof_target: solution_target + 1 })
+        );
+    }
+
+    #[test]
+    fn test_precheck_accepts_valid_solution() {
+        let rng = &mut TestRng::default();
+
+        // Sample a solution.
+        let address = Address::try_from(PrivateKey::<CurrentNetwork>::new(rng).unwrap()).unwrap();
+        let solution = sample_solution(address, rng);
+        let solution_target = solution.to_target().unwrap();
+        // Check that the solution passes, if the proof target is met.
+        assert!(precheck_solution(&solution, solution_target).is_ok());
+        assert!(precheck_solution(&solution, 1).is_ok());
+    }
+
+    #[test]
+    fn test_solution_reject_reason_is_sender_fault() {
+        // Check that a malformed solution is the fault of the peer.
+        assert!(SolutionRejectReason::InvalidAddress.is_sender_fault());
+        assert!(SolutionRejectReason::InvalidCommitment.is_sender_fault());
+
+        // Check that a solution below a changed proof target, or already in the ledger, is not the fault of the peer.
+        let reason = SolutionRejectReason::InsufficientProofTarget { solution_target: 1, proof_target: 2 };
+        assert!(!reason.is_sender_fault());
+        assert!(!SolutionRejectReason::AlreadyInLedger.is_sender_fault());
+    }
+
+    #[test]
+    fn test_is_invalid_transaction() {
+        // Check that a malformed transaction is the fault of the peer.
+        let error = anyhow::Error::from(TransactionRejectReason::Invalid("bad proof".to_string()));
+        assert!(is_invalid_transaction(&error));
+
+        // Check that a duplicate transaction is not the fault of the peer.
+        assert!(!is_invalid_transaction(&TransactionRejectReason::AlreadyInLedger.into()));
+        assert!(!is_invalid_transaction(&TransactionRejectReason::AlreadyInMemoryPool.into()));
+
+        // Check that an internal error is not the fault of the peer.
+        assert!(!is_invalid_transaction(&anyhow::anyhow!("storage is unavailable")));
+    }
+}
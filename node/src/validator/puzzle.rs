@@ -0,0 +1,378 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use snarkos_node_router::messages::Message;
+use snarkvm::{ledger::narwhal::Data, prelude::coinbase::EpochChallenge};
+
+use anyhow::ensure;
+use core::fmt;
+
+/// A strategy for selecting the block whose header is served in puzzle responses.
+pub trait PuzzleBlockSelector<N: Network, C: ConsensusStorage<N>>: Send + Sync {
+    /// Returns the height of the block to serve in a puzzle response, given the latest height of the ledger.
+    /// The block is selected by height, so that its header may be served from the block cache.
+    /// Note that the latest epoch challenge is served alongside the block, whichever block is selected.
+    fn select_height(&self, ledger: &Ledger<N, C>, latest_height: u32) -> Result<u32>;
+}
+
+/// The default puzzle block selector, which serves the latest block.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LatestBlockSelector;
+
+impl<N: Network, C: ConsensusStorage<N>> PuzzleBlockSelector<N, C> for LatestBlockSelector {
+    fn select_height(&self, _ledger: &Ledger<N, C>, latest_height: u32) -> Result<u32> {
+        Ok(latest_height)
+    }
+}
+
+/// The number of attempts to read a consistent puzzle state, before giving up.
+const MAXIMUM_PUZZLE_STATE_ATTEMPTS: usize = 3;
+
+/// The reason the latest puzzle state could not be retrieved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PuzzleStateError {
+    /// The ledger does not have an epoch challenge.
+    MissingEpochChallenge(String),
+    /// The ledger advanced during every attempt to read the puzzle state.
+    Inconsistent,
+}
+
+impl fmt::Display for PuzzleStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingEpochChallenge(error) => write!(f, "the epoch challenge is unavailable - {error}"),
+            Self::Inconsistent => write!(f, "the ledger advanced while reading the puzzle state"),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleStateError {}
+
+impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
+    /// Returns the latest epoch challenge and the latest height, from a single consistent view of the ledger.
+    pub fn latest_puzzle_state(&self) -> Result<(EpochChallenge<N>, u32), PuzzleStateError> {
+        self.ledger_api().latest_puzzle_state()
+    }
+
+    /// Returns the latest epoch challenge and the serialized header of the block selected for puzzle responses.
+    /// The header is served from the block cache, and only read from the ledger on a cache miss.
+    pub fn puzzle_state(&self) -> Result<(EpochChallenge<N>, Data<Header<N>>)> {
+        let (epoch_challenge, height) =
+            select_puzzle_state(self.ledger_api().as_ref(), &self.ledger, self.puzzle_block_selector.read().as_ref())?;
+        Ok((epoch_challenge, self.block_header(height)?))
+    }
+
+    /// Returns the latest epoch challenge and the serialized header of the block selected for puzzle responses,
+    /// or `None` if reading them from the ledger takes longer than the given timeout. In that case, the read
+    /// completes in the background, and its result is discarded. While such a read is still in progress,
+    /// `None` is returned without reading.
+    pub async fn puzzle_state_with_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<Option<(EpochChallenge<N>, Data<Header<N>>)>> {
+        // Ensure an earlier read is not stalled, so that reads do not pile up on the blocking thread pool.
+        if self.num_stalled_puzzle_state_reads.load(Ordering::SeqCst) > 0 {
+            return Ok(None);
+        }
+        // Read the puzzle state on the blocking thread pool, so that a stalled read does not hold up the caller.
+        let self_ = self.clone();
+        let mut read = tokio::task::spawn_blocking(move || self_.puzzle_state());
+        match tokio::time::timeout(timeout, &mut read).await {
+            Ok(puzzle_state) => puzzle_state?.map(Some),
+            Err(_) => {
+                // Track the stalled read, until it completes in the background.
+                let num_stalled_reads = self.num_stalled_puzzle_state_reads.clone();
+                num_stalled_reads.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let _ = read.await;
+                    num_stalled_reads.fetch_sub(1, Ordering::SeqCst);
+                });
+                Ok(None)
+            }
+        }
+    }
+
+    /// Answers a puzzle request received while the ledger has no epoch challenge, with a puzzle response
+    /// without one, or declines it if the peer cannot decode such a response or if the node is configured to.
+    /// Returns `true`, as the peer remains connected.
+    pub(super) fn respond_without_epoch_challenge(&self, peer_ip: SocketAddr) -> bool {
+        // Ensure the peer supports puzzle responses without an epoch challenge.
+        if !self.peer_supports(&peer_ip, Feature::UnavailablePuzzleResponse)
+            || !self.router.sends_unavailable_puzzle_responses()
+        {
+            debug!("Declining 'PuzzleRequest' from '{peer_ip}' (the epoch challenge is unavailable)");
+            self.router.decline_puzzle_request();
+            self.router.remove_puzzle_request_in_flight(peer_ip);
+            return true;
+        }
+        // Retrieve the latest block header, pre-serialized from the block cache.
+        let block_header = match self.block_header(self.ledger_api().latest_height()) {
+            Ok(block_header) => block_header,
+            Err(error) => {
+                error!("Failed to serialize the block header for '{peer_ip}': {error}");
+                self.router.remove_puzzle_request_in_flight(peer_ip);
+                return true;
+            }
+        };
+        // Send the `PuzzleResponse` message without an epoch challenge to the peer.
+        debug!("Sending a 'PuzzleResponse' without an epoch challenge to '{peer_ip}'");
+        let response = PuzzleResponse { epoch_challenge: None, block_header };
+        Outbound::send(self, peer_ip, Message::PuzzleResponse(response));
+        true
+    }
+
+    /// Returns the epoch challenge of the given epoch, if it is one of the recent epochs of the ledger,
+    /// i.e. no later than the latest epoch, and at most `MAXIMUM_EPOCH_CHALLENGE_AGE` epochs before it.
+    pub fn recent_epoch_challenge(&self, epoch_number: u32) -> Option<EpochChallenge<N>> {
+        let ledger_api = self.ledger_api();
+        // Ensure the epoch is one of the recent epochs, before reading from the ledger.
+        let latest_epoch = ledger_api.latest_height() / N::NUM_BLOCKS_PER_EPOCH;
+        let oldest_epoch = latest_epoch.saturating_sub(<Self as Inbound<N>>::MAXIMUM_EPOCH_CHALLENGE_AGE);
+        if !(oldest_epoch..=latest_epoch).contains(&epoch_number) {
+            return None;
+        }
+        // Retrieve the epoch challenge from the ledger.
+        match ledger_api.epoch_challenge(epoch_number) {
+            Ok(epoch_challenge) => Some(epoch_challenge),
+            Err(error) => {
+                debug!("Failed to retrieve the epoch challenge of epoch {epoch_number}: {error}");
+                None
+            }
+        }
+    }
+
+    /// Sets the strategy for selecting the block whose header is served in puzzle responses.
+    pub fn set_puzzle_block_selector(&self, selector: Arc<dyn PuzzleBlockSelector<N, C>>) {
+        *self.puzzle_block_selector.write() = selector;
+    }
+}
+
+/// Returns `true` if the given error is due to the ledger not having an epoch challenge yet.
+pub(super) fn is_missing_epoch_challenge(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<PuzzleStateError>(), Some(PuzzleStateError::MissingEpochChallenge(_)))
+}
+
+/// Returns the latest epoch challenge from the given ledger reads, and the block height chosen by the given selector.
+fn select_puzzle_state<N: Network, C: ConsensusStorage<N>>(
+    ledger_api: &dyn LedgerApi<N>,
+    ledger: &Ledger<N, C>,
+    selector: &dyn PuzzleBlockSelector<N, C>,
+) -> Result<(EpochChallenge<N>, u32)> {
+    // Retrieve the latest epoch challenge and latest height.
+    let (epoch_challenge, latest_height) = ledger_api.latest_puzzle_state()?;
+    // Select the block to serve.
+    let height = selector.select_height(ledger, latest_height)?;
+    // Ensure the selected block exists.
+    ensure!(height <= latest_height, "The selected block {height} is ahead of the latest block {latest_height}");
+    Ok((epoch_challenge, height))
+}
+
+/// Returns the latest epoch challenge and the latest height, from a single consistent view of the given ledger.
+pub(super) fn latest_puzzle_state<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+) -> Result<(EpochChallenge<N>, u32), PuzzleStateError> {
+    for _ in 0..MAXIMUM_PUZZLE_STATE_ATTEMPTS {
+        // Retrieve the latest height.
+        let height = ledger.latest_height();
+        // Retrieve the latest epoch challenge.
+        let epoch_challenge = ledger
+            .latest_epoch_challenge()
+            .map_err(|error| PuzzleStateError::MissingEpochChallenge(error.to_string()))?;
+        // Ensure the ledger did not advance in between the two reads.
+        let is_same_height = ledger.latest_height() == height;
+        let is_same_epoch = epoch_challenge.epoch_number() == height / N::NUM_BLOCKS_PER_EPOCH;
+        if is_same_height && is_same_epoch {
+            return Ok((epoch_challenge, height));
+        }
+    }
+    Err(PuzzleStateError::Inconsistent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::test_helpers::*;
+    use snarkvm::prelude::{store::helpers::memory::ConsensusMemory, FromBytes, TestRng};
+
+    use std::time::Instant;
+
+    #[test]
+    fn test_latest_puzzle_state_is_consistent() {
+        // Load the genesis block.
+        let genesis = Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap();
+        // Initialize the ledger.
+        let ledger = Ledger::<CurrentNetwork, ConsensusMemory<CurrentNetwork>>::load(genesis, None).unwrap();
+
+        // Retrieve the latest puzzle state.
+        let (epoch_challenge, height) = latest_puzzle_state(&ledger).unwrap();
+
+        // Check that the height and the epoch challenge are mutually consistent.
+        assert_eq!(height, ledger.latest_height());
+        assert_eq!(epoch_challenge, ledger.latest_epoch_challenge().unwrap());
+        assert_eq!(epoch_challenge.epoch_number(), height / CurrentNetwork::NUM_BLOCKS_PER_EPOCH);
+    }
+
+    /// A selector that serves the block at a fixed height, and records the latest height it was given.
+    struct FixedHeightSelector {
+        height: u32,
+        latest_height: Mutex<Option<u32>>,
+    }
+
+    impl PuzzleBlockSelector<CurrentNetwork, ConsensusMemory<CurrentNetwork>> for FixedHeightSelector {
+        fn select_height(
+            &self,
+            _ledger: &Ledger<CurrentNetwork, ConsensusMemory<CurrentNetwork>>,
+            latest_height: u32,
+        ) -> Result<u32> {
+            *self.latest_height.lock() = Some(latest_height);
+            Ok(self.height)
+        }
+    }
+
+    #[test]
+    fn test_puzzle_block_selector() {
+        // Load the genesis block.
+        let genesis = Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap();
+        // Initialize the ledger.
+        let ledger = Ledger::<CurrentNetwork, ConsensusMemory<CurrentNetwork>>::load(genesis.clone(), None).unwrap();
+
+        // Check that the default selector serves the latest block.
+        let (_, height) = select_puzzle_state(&ledger, &ledger, &LatestBlockSelector).unwrap();
+        assert_eq!(height, ledger.latest_height());
+
+        // Check that a custom selector is consulted, and its block is served.
+        let selector = FixedHeightSelector { height: 0, latest_height: Default::default() };
+        let (epoch_challenge, height) = select_puzzle_state(&ledger, &ledger, &selector).unwrap();
+        assert_eq!(*selector.latest_height.lock(), Some(ledger.latest_height()));
+        assert_eq!(height, genesis.height());
+        assert_eq!(epoch_challenge, ledger.latest_epoch_challenge().unwrap());
+
+        // Check that the puzzle state fails if the selected block does not exist.
+        let selector = FixedHeightSelector { height: 100, latest_height: Default::default() };
+        assert!(select_puzzle_state(&ledger, &ledger, &selector).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_puzzle_request_declined_on_slow_ledger() {
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4130".parse().unwrap();
+
+        // Initialize a validator with a ledger that is slower than the ledger read timeout.
+        let consensus = Arc::new(MockConsensus::default());
+        let delay = Duration::from_millis(1_000);
+        let ledger_api = Arc::new(MockLedger::new(sample_ledger(), delay, false));
+        let validator = sample_validator(consensus, ledger_api.clone(), rng).await;
+        let mut config = validator.router.config();
+        config.ledger_read_timeout = Duration::from_millis(100);
+        validator.router.set_config(config);
+
+        // Check that the puzzle request is declined promptly, and the peer is kept.
+        let start = Instant::now();
+        assert!(validator.puzzle_request(peer_ip).await);
+        assert!(start.elapsed() < delay / 2);
+        assert_eq!(validator.router.number_of_slow_ledger_reads(), 1);
+        assert_eq!(validator.router.number_of_declined_puzzle_requests(), 1);
+
+        // Check that another puzzle request is declined without reading, while the stalled read is in progress.
+        assert!(validator.puzzle_request(peer_ip).await);
+        assert_eq!(validator.router.number_of_slow_ledger_reads(), 2);
+
+        // Check that the stalled read completes in the background, and is no longer tracked.
+        tokio::time::sleep(delay).await;
+        assert_eq!(ledger_api.num_puzzle_state_reads.load(Ordering::SeqCst), 1);
+        assert_eq!(validator.num_stalled_puzzle_state_reads.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_puzzle_request_without_epoch_challenge() {
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4130".parse().unwrap();
+
+        // Initialize a validator with a fresh ledger, which has no epoch challenge yet.
+        let ledger_api = Arc::new(MockLedger::new(sample_ledger(), Duration::ZERO, true));
+        let validator = sample_validator(Arc::new(MockConsensus::default()), ledger_api, rng).await;
+
+        // Register a prover that supports puzzle responses without an epoch challenge.
+        connect_peer(&validator, peer_ip, &sample_challenge_request(peer_ip, NodeType::Prover, rng));
+
+        // Check that the puzzle request is declined, and the peer is kept, if the node is configured to.
+        let mut config = validator.router.config();
+        config.send_unavailable_puzzle_responses = false;
+        validator.router.set_config(config.clone());
+        assert!(validator.puzzle_request(peer_ip).await);
+        assert_eq!(validator.router.number_of_declined_puzzle_requests(), 1);
+        assert!(validator.block_cache.is_empty());
+
+        // Check that the puzzle request is otherwise answered without an epoch challenge, and the peer is kept.
+        config.send_unavailable_puzzle_responses = true;
+        validator.router.set_config(config);
+        assert!(validator.puzzle_request(peer_ip).await);
+        assert_eq!(validator.router.number_of_declined_puzzle_requests(), 1);
+        assert!(!validator.block_cache.is_empty());
+
+        // Check that the puzzle request of a peer that predates such responses is declined, and the peer is kept.
+        let old_ip = "127.0.0.1:4131".parse().unwrap();
+        let mut request = sample_challenge_request(old_ip, NodeType::Prover, rng);
+        request.version = Feature::UnavailablePuzzleResponse.min_version() - 1;
+        connect_peer(&validator, old_ip, &request);
+        assert!(validator.puzzle_request(old_ip).await);
+        assert_eq!(validator.router.number_of_declined_puzzle_requests(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_epoch_challenge_request_is_bounded_to_recent_epochs() {
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4130".parse().unwrap();
+
+        // Initialize a validator at genesis, in the first epoch.
+        let ledger = sample_ledger();
+        let ledger_api = Arc::new(MockLedger::new(ledger.clone(), Duration::ZERO, false));
+        let validator = sample_validator(Arc::new(MockConsensus::default()), ledger_api.clone(), rng).await;
+
+        // Register a peer that supports epoch challenge requests.
+        connect_peer(&validator, peer_ip, &sample_challenge_request(peer_ip, NodeType::Prover, rng));
+
+        // Check that the epoch challenge of the latest epoch is served, and the peer is kept.
+        let (latest_epoch_challenge, _) = ledger.latest_puzzle_state().unwrap();
+        assert_eq!(validator.recent_epoch_challenge(0), Some(latest_epoch_challenge));
+        assert!(validator.epoch_challenge_request(peer_ip, 0));
+        assert_eq!(ledger_api.num_epoch_challenge_reads.load(Ordering::SeqCst), 2);
+
+        // Check that a far-future epoch is declined without reading from the ledger, and the peer is kept.
+        assert_eq!(validator.recent_epoch_challenge(u32::MAX), None);
+        assert!(validator.epoch_challenge_request(peer_ip, u32::MAX));
+        assert_eq!(ledger_api.num_epoch_challenge_reads.load(Ordering::SeqCst), 2);
+
+        // Advance the ledger past the recent epochs.
+        let max_age = <CurrentValidator as Inbound<CurrentNetwork>>::MAXIMUM_EPOCH_CHALLENGE_AGE;
+        let latest_height = (max_age + 1) * CurrentNetwork::NUM_BLOCKS_PER_EPOCH;
+        let ledger_api = Arc::new(MockLedger::new(ledger, Duration::ZERO, false).with_latest_height(latest_height));
+        validator.set_ledger_api(ledger_api.clone());
+
+        // Check that a too-old epoch is declined without reading from the ledger, and the peer is kept.
+        assert_eq!(validator.recent_epoch_challenge(0), None);
+        assert!(validator.epoch_challenge_request(peer_ip, 0));
+        assert_eq!(ledger_api.num_epoch_challenge_reads.load(Ordering::SeqCst), 0);
+
+        // Check that a request from a peer that predates epoch challenges is ignored, and the peer is kept.
+        let old_ip = "127.0.0.1:4131".parse().unwrap();
+        let mut request = sample_challenge_request(old_ip, NodeType::Prover, rng);
+        request.version = Feature::EpochChallenges.min_version() - 1;
+        connect_peer(&validator, old_ip, &request);
+        assert!(!validator.peer_supports(&old_ip, Feature::EpochChallenges));
+        assert!(validator.epoch_challenge_request(old_ip, u32::MAX));
+        assert!(validator.peer_supports(&peer_ip, Feature::EpochChallenges));
+    }
+}
@@ -15,9 +15,8 @@
 use super::*;
 use snarkos_node_router::messages::{
     BlockRequest,
-    BlockResponse,
-    DataBlocks,
     DisconnectReason,
+    EpochChallengeResponse,
     Message,
     MessageCodec,
     Ping,
@@ -25,10 +24,7 @@ use snarkos_node_router::messages::{
     UnconfirmedTransaction,
 };
 use snarkos_node_tcp::{Connection, ConnectionSide, Tcp};
-use snarkvm::{
-    ledger::narwhal::Data,
-    prelude::{block::Transaction, coinbase::EpochChallenge, error, Network},
-};
+use snarkvm::prelude::{block::Transaction, coinbase::EpochChallenge, error, Network};
 
 use std::{io, net::SocketAddr, time::Duration};
 
@@ -93,8 +89,27 @@ impl<N: Network, C: ConsensusStorage<N>> Writing for Validator<N, C> {
 
     /// Creates an [`Encoder`] used to write the outbound messages to the target stream.
     /// The `side` parameter indicates the connection side **from the node's perspective**.
-    fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, addr: SocketAddr, side: ConnectionSide) -> Self::Codec {
+        MessageCodec::default()
+            .with_version(self.router().codec_version(&addr))
+            .with_side(self.router().codec_side(&addr, side))
+            .with_compression(self.router().compression(&addr))
+            .with_traffic(self.router().traffic().clone())
+    }
+
+    /// Returns the maximum time allowed to write a single message to a peer.
+    fn write_timeout(&self) -> Option<Duration> {
+        Some(self.router().write_timeout())
+    }
+
+    /// Marks the peer as a slow consumer, as writing a message to it timed out.
+    fn on_write_timeout(&self, peer_addr: SocketAddr) {
+        self.router().handle_write_timeout(peer_addr)
+    }
+
+    /// Returns the window over which the messages queued for a peer are coalesced into a single write, if any.
+    fn coalescing_window(&self) -> Option<Duration> {
+        self.router().write_coalescing_window()
     }
 }
 
@@ -105,19 +120,36 @@ impl<N: Network, C: ConsensusStorage<N>> Reading for Validator<N, C> {
 
     /// Creates a [`Decoder`] used to interpret messages from the network.
     /// The `side` param indicates the connection side **from the node's perspective**.
-    fn codec(&self, _peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, side: ConnectionSide) -> Self::Codec {
+        let node = self.clone();
+        MessageCodec::default()
+            .with_version(self.router().codec_version(&peer_addr))
+            .with_side(self.router().codec_side(&peer_addr, side))
+            .with_compression(self.router().compression(&peer_addr))
+            .with_traffic(self.router().traffic().clone())
+            .with_malformed_frame_handler(move || node.malformed_frame(peer_addr))
+    }
+
+    /// Returns the maximum number of bytes buffered across all peers, awaiting the rest of an inbound message.
+    fn max_inbound_buffer_bytes(&self) -> Option<usize> {
+        Some(self.router().max_inbound_buffer_bytes())
+    }
+
+    /// Marks the peer holding the largest inbound buffer, once the buffers exceed their limit.
+    fn on_inbound_buffer_overflow(&self, peer_addr: SocketAddr) {
+        self.router().handle_inbound_buffer_overflow(peer_addr)
     }
 
     /// Processes a message received from the network.
     async fn process_message(&self, peer_addr: SocketAddr, message: Self::Message) -> io::Result<()> {
-        // Process the message. Disconnect if the peer violated the protocol.
-        if let Err(error) = self.inbound(peer_addr, message).await {
+        // Process the message, unless the node is shutting down. Disconnect if the peer violated the protocol.
+        if let Some(Err(error)) = self.inbound_until_shutdown(peer_addr, message).await {
             if let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) {
                 warn!("Disconnecting from '{peer_ip}' - {error}");
-                Outbound::send(self, peer_ip, Message::Disconnect(DisconnectReason::ProtocolViolation.into()));
-                // Disconnect from this peer.
-                self.router().disconnect(peer_ip);
+                // Place the peer on probation, or restrict it if it is a repeat offender.
+                self.router().record_violation(peer_ip);
+                // Inform the peer of the violation, and disconnect from it.
+                self.send_disconnect(peer_ip, DisconnectReason::ProtocolViolation);
             }
         }
         Ok(())
@@ -147,14 +179,14 @@ impl<N: Network, C: ConsensusStorage<N>> Inbound<N> for Validator<N, C> {
 
         // Retrieve the blocks within the requested range.
         let blocks = match self.ledger.get_blocks(*start_height..*end_height) {
-            Ok(blocks) => Data::Object(DataBlocks(blocks)),
+            Ok(blocks) => blocks,
             Err(error) => {
                 error!("Failed to retrieve blocks {start_height} to {end_height} from the ledger - {error}");
                 return false;
             }
         };
-        // Send the `BlockResponse` message to the peer.
-        Outbound::send(self, peer_ip, Message::BlockResponse(BlockResponse { request: message, blocks }));
+        // Send the `BlockResponse` messages to the peer.
+        self.send_block_responses(peer_ip, message, blocks);
         true
     }
 
@@ -209,20 +241,49 @@ impl<N: Network, C: ConsensusStorage<N>> Inbound<N> for Validator<N, C> {
         true
     }
 
-    /// Retrieves the latest epoch challenge and latest block header, and returns the puzzle response to the peer.
-    fn puzzle_request(&self, peer_ip: SocketAddr) -> bool {
-        // Retrieve the latest epoch challenge.
-        let epoch_challenge = match self.ledger.latest_epoch_challenge() {
-            Ok(epoch_challenge) => epoch_challenge,
+    /// Retrieves the latest epoch challenge and the selected block header, and returns the puzzle response to the peer.
+    async fn puzzle_request(&self, peer_ip: SocketAddr) -> bool {
+        // Retrieve the latest epoch challenge, and the header of the block selected for puzzle responses.
+        let timeout = self.router().ledger_read_timeout();
+        let (epoch_challenge, block_header) = match self.puzzle_state_with_timeout(timeout).await {
+            Ok(Some(puzzle_state)) => puzzle_state,
+            // Decline the puzzle request if the ledger is slow, as it is not the fault of the peer.
+            Ok(None) => {
+                warn!("Declining 'PuzzleRequest' from '{peer_ip}' (reading from the ledger timed out)");
+                self.router().record_slow_ledger_read();
+                self.router().remove_puzzle_request_in_flight(peer_ip);
+                return true;
+            }
+            // Respond without an epoch challenge if the ledger does not have one yet, e.g. at genesis.
+            Err(error) if is_missing_epoch_challenge(&error) => {
+                return self.respond_without_epoch_challenge(peer_ip);
+            }
             Err(error) => {
                 error!("Failed to prepare a puzzle request for '{peer_ip}': {error}");
                 return false;
             }
         };
-        // Retrieve the latest block header.
-        let block_header = Data::Object(self.ledger.latest_header());
         // Send the `PuzzleResponse` message to the peer.
-        Outbound::send(self, peer_ip, Message::PuzzleResponse(PuzzleResponse { epoch_challenge, block_header }));
+        let response = PuzzleResponse { epoch_challenge: Some(epoch_challenge), block_header };
+        Outbound::send(self, peer_ip, Message::PuzzleResponse(response));
+        true
+    }
+
+    /// Serves the epoch challenge of the requested epoch, if it is one of the recent epochs of the ledger,
+    /// or declines the request otherwise. Returns `true`, as the peer remains connected.
+    fn epoch_challenge_request(&self, peer_ip: SocketAddr, epoch_number: u32) -> bool {
+        // Ignore the request if the peer predates epoch challenge requests, as it cannot decode the response.
+        if !self.peer_supports(&peer_ip, Feature::EpochChallenges) {
+            debug!("Ignoring 'EpochChallengeRequest' from '{peer_ip}' (unsupported by its version)");
+            return true;
+        }
+        let epoch_challenge = self.recent_epoch_challenge(epoch_number);
+        if epoch_challenge.is_none() {
+            debug!("Declining 'EpochChallengeRequest' from '{peer_ip}' for epoch {epoch_number}");
+        }
+        // Send the `EpochChallengeResponse` message to the peer.
+        let response = EpochChallengeResponse { epoch_number, epoch_challenge };
+        Outbound::send(self, peer_ip, Message::EpochChallengeResponse(response));
         true
     }
 
@@ -239,14 +300,36 @@ impl<N: Network, C: ConsensusStorage<N>> Inbound<N> for Validator<N, C> {
         serialized: UnconfirmedSolution<N>,
         solution: ProverSolution<N>,
     ) -> bool {
-        // Add the unconfirmed solution to the memory pool.
-        if let Err(error) = self.consensus.add_unconfirmed_solution(solution).await {
-            trace!("[UnconfirmedSolution] {error}");
-            return true; // Maintain the connection.
+        // Perform the inexpensive checks on the unconfirmed solution.
+        if let Err(reason) = self.precheck_solution(&solution) {
+            trace!("[UnconfirmedSolution] Rejected solution from '{peer_ip}' - {reason}");
+            let id = MempoolId::Solution(solution.commitment());
+            self.emit_mempool_event(MempoolEvent::Rejected { id, reason: MempoolRejectReason::Solution(reason) });
+            // A solution below the latest proof target, or already in the ledger, is not a fault of the peer.
+            if !reason.is_sender_fault() {
+                return true; // Maintain the connection.
+            }
+            // Disconnect from the peer, if they have submitted too many invalid solutions.
+            return self.router().insert_strike(peer_ip) < Self::MAXIMUM_STRIKES_PER_INTERVAL;
         }
+        // Add the unconfirmed solution to the memory pool, unless the node only relays solutions.
+        if self.is_relay_only() {
+            trace!("[UnconfirmedSolution] Relaying the solution from '{peer_ip}'");
+        } else {
+            let id = solution.commitment();
+            if let Err(error) = self.consensus().add_unconfirmed_solution(solution).await {
+                trace!("[UnconfirmedSolution] {error}");
+                let reason = MempoolRejectReason::Failed(error.to_string());
+                self.emit_mempool_event(MempoolEvent::Rejected { id: MempoolId::Solution(id), reason });
+                return true; // Maintain the connection.
+            }
+            self.emit_mempool_event(MempoolEvent::SolutionAdmitted { id });
+        }
+        // Mark the solution as seen across all peers, now that it was accepted.
+        self.router().insert_accepted_solution(serialized.solution_id);
         let message = Message::UnconfirmedSolution(serialized);
         // Propagate the "UnconfirmedSolution" to the connected validators.
-        self.propagate_to_validators(message, &[peer_ip]);
+        self.propagate_to_validators_bounded(message, &[peer_ip]).await;
         true
     }
 
@@ -257,14 +340,162 @@ impl<N: Network, C: ConsensusStorage<N>> Inbound<N> for Validator<N, C> {
         serialized: UnconfirmedTransaction<N>,
         transaction: Transaction<N>,
     ) -> bool {
-        // Add the unconfirmed transaction to the memory pool.
-        if let Err(error) = self.consensus.add_unconfirmed_transaction(transaction).await {
-            trace!("[UnconfirmedTransaction] {error}");
-            return true; // Maintain the connection.
+        // Drop the transaction if an admission rule rejects it, without penalizing the peer.
+        if let Err(reason) = self.admit_transaction(&transaction) {
+            trace!("[UnconfirmedTransaction] Dropping a transaction from '{peer_ip}' - {reason}");
+            return true;
+        }
+        // Queue the unconfirmed transaction for the memory pool, unless the node only relays transactions.
+        // The transaction is propagated once it is accepted, without blocking the reading of messages.
+        if !self.is_relay_only() {
+            self.enqueue_unconfirmed_transaction(peer_ip, serialized, transaction, None);
+            return true;
         }
+        trace!("[UnconfirmedTransaction] Relaying the transaction from '{peer_ip}'");
+        self.router().insert_accepted_transaction(serialized.transaction_id);
         let message = Message::UnconfirmedTransaction(serialized);
         // Propagate the "UnconfirmedTransaction" to the connected validators.
-        self.propagate_to_validators(message, &[peer_ip]);
+        self.propagate_to_validators_bounded(message, &[peer_ip]).await;
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::test_helpers::*;
+    use snarkvm::prelude::{Address, TestRng};
+
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_relay_only() {
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4130".parse().unwrap();
+
+        // Initialize a validator with a mock consensus and ledger, and record the propagated messages.
+        let consensus = Arc::new(MockConsensus::default());
+        let ledger_api = Arc::new(MockLedger::new(sample_ledger(), Duration::ZERO, false));
+        let validator = sample_validator(consensus.clone(), ledger_api, rng).await;
+        let propagated = Arc::new(Mutex::new(Vec::new()));
+        let propagated_ = propagated.clone();
+        validator.router.on_propagate(move |message| propagated_.lock().push(message.name()));
+
+        // Waits until the given number of messages were propagated.
+        let wait_for_propagations = |num_propagations: usize| {
+            let propagated = propagated.clone();
+            async move {
+                let deadline = Instant::now() + Duration::from_secs(5);
+                while propagated.lock().len() < num_propagations {
+                    assert!(Instant::now() < deadline, "the message was not propagated");
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            }
+        };
+
+        let address = sample_address(rng);
+        let transaction = sample_transaction();
+        let transaction_id = transaction.id();
+        let serialized = transaction_message(&transaction);
+
+        // Check that a solution reaches the consensus, and is propagated.
+        let (message, solution) = sample_solution(address, rng);
+        assert!(validator.unconfirmed_solution(peer_ip, message, solution).await);
+        assert_eq!(*consensus.solutions.lock(), vec![solution.commitment()]);
+        assert_eq!(*propagated.lock(), vec!["UnconfirmedSolution"]);
+
+        // Check that a transaction reaches the consensus, and is propagated.
+        assert!(validator.unconfirmed_transaction(peer_ip, serialized.clone(), transaction.clone()).await);
+        wait_for_propagations(2).await;
+        assert_eq!(*consensus.transactions.lock(), vec![transaction_id]);
+        assert_eq!(propagated.lock()[1], "UnconfirmedTransaction");
+
+        // Check that a relay-only validator propagates a solution, without passing it to the consensus.
+        validator.set_relay_only(true);
+        let (message, solution) = sample_solution(address, rng);
+        assert!(validator.unconfirmed_solution(peer_ip, message, solution).await);
+        assert_eq!(consensus.solutions.lock().len(), 1);
+        assert_eq!(propagated.lock()[2], "UnconfirmedSolution");
+
+        // Check that a relay-only validator propagates a transaction, without passing it to the consensus.
+        assert!(validator.unconfirmed_transaction(peer_ip, serialized, transaction).await);
+        wait_for_propagations(4).await;
+        assert_eq!(consensus.transactions.lock().len(), 1);
+        assert_eq!(propagated.lock()[3], "UnconfirmedTransaction");
+    }
+
+    #[tokio::test]
+    async fn test_insufficient_proof_target_is_not_a_strike() {
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4132".parse().unwrap();
+
+        // Initialize a validator, whose proof target was raised above any solution target.
+        let consensus = Arc::new(MockConsensus::default());
+        let ledger_api = Arc::new(MockLedger::new(sample_ledger(), Duration::ZERO, false).with_proof_target(u64::MAX));
+        let validator = sample_validator(consensus.clone(), ledger_api, rng).await;
+        let request = sample_challenge_request(peer_ip, NodeType::Validator, rng);
+        connect_peer(&validator, peer_ip, &request);
+
+        // Check that solutions below the proof target are rejected, without striking the peer.
+        let address = sample_address(rng);
+        for _ in 0..CurrentValidator::MAXIMUM_STRIKES_PER_INTERVAL * 2 {
+            let (message, solution) = sample_solution(address, rng);
+            assert!(validator.unconfirmed_solution(peer_ip, message, solution).await);
+        }
+        assert!(consensus.solutions.lock().is_empty());
+        assert_eq!(validator.router.number_of_strikes(peer_ip), 0);
+        assert!(validator.router.is_connected(&peer_ip));
+
+        // Check that a malformed solution still strikes the peer.
+        let (message, solution) = sample_solution(Address::zero(), rng);
+        assert!(validator.unconfirmed_solution(peer_ip, message, solution).await);
+        assert_eq!(validator.router.number_of_strikes(peer_ip), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrent_propagations_are_bounded() {
+        const LIMIT: usize = 2;
+        const NUM_SOLUTIONS: usize = 16;
+
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4130".parse().unwrap();
+
+        // Initialize a validator, which propagates a few solutions at a time.
+        let consensus = Arc::new(MockConsensus::default());
+        let ledger_api = Arc::new(MockLedger::new(sample_ledger(), Duration::ZERO, false));
+        let validator = sample_validator(consensus.clone(), ledger_api, rng).await;
+        validator.set_max_concurrent_propagations(LIMIT);
+
+        // Instrument the propagations, simulating a slow broadcast.
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let num_propagations = Arc::new(AtomicUsize::new(0));
+        let (in_flight_, peak_, num_propagations_) = (in_flight.clone(), peak.clone(), num_propagations.clone());
+        validator.router.on_propagate(move |_| {
+            let current = in_flight_.fetch_add(1, Ordering::SeqCst) + 1;
+            peak_.fetch_max(current, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(20));
+            in_flight_.fetch_sub(1, Ordering::SeqCst);
+            num_propagations_.fetch_add(1, Ordering::SeqCst);
+        });
+
+        // Receive a burst of solutions at once.
+        let address = sample_address(rng);
+        let handles = (0..NUM_SOLUTIONS)
+            .map(|_| {
+                let (message, solution) = sample_solution(address, rng);
+                let validator = validator.clone();
+                tokio::spawn(async move { validator.unconfirmed_solution(peer_ip, message, solution).await })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert!(handle.await.unwrap());
+        }
+
+        // Check that every solution was propagated, but no more than the limit at once.
+        assert_eq!(num_propagations.load(Ordering::SeqCst), NUM_SOLUTIONS);
+        assert!(peak.load(Ordering::SeqCst) <= LIMIT);
+        assert_eq!(validator.router.number_of_propagations_in_flight(), 0);
+        assert_eq!(consensus.solutions.lock().len(), NUM_SOLUTIONS);
+    }
+}
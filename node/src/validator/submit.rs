@@ -0,0 +1,120 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use snarkos_node_consensus::TransactionRejectReason;
+use snarkos_node_router::messages::Message;
+use snarkvm::{ledger::narwhal::Data, prelude::block::Transaction};
+
+use core::fmt;
+use tokio::sync::oneshot;
+
+/// The reason a locally-submitted transaction was not accepted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SubmitError {
+    /// The transaction was dropped before the memory pool, e.g. by an admission rule.
+    Dropped(MempoolRejectReason),
+    /// The transaction was rejected by the memory pool.
+    Rejected(TransactionRejectReason),
+    /// The transaction could not be added to the memory pool.
+    Failed(String),
+}
+
+impl fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dropped(reason) => write!(f, "{reason}"),
+            Self::Rejected(reason) => write!(f, "{reason}"),
+            Self::Failed(error) => write!(f, "failed to add the transaction to the memory pool - {error}"),
+        }
+    }
+}
+
+impl std::error::Error for SubmitError {}
+
+impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
+    /// Submits the given locally-built transaction to the memory pool, and propagates it to the connected validators.
+    /// This performs the same handling as an `UnconfirmedTransaction` message, without a sending peer.
+    pub async fn submit_transaction(&self, transaction: Transaction<N>) -> Result<(), SubmitError> {
+        // Drop the transaction if an admission rule rejects it.
+        self.admit_transaction(&transaction).map_err(SubmitError::Dropped)?;
+        let transaction_id = transaction.id();
+        let serialized = UnconfirmedTransaction { transaction_id, transaction: Data::Object(transaction.clone()) };
+        // Propagate the transaction directly, if the node only relays transactions.
+        if self.is_relay_only() {
+            self.propagate_to_validators_bounded(Message::UnconfirmedTransaction(serialized), &[]).await;
+            return Ok(());
+        }
+        // Queue the transaction for the memory pool, which propagates it once it is accepted.
+        let (callback, result) = oneshot::channel();
+        if !self.enqueue_unconfirmed_transaction(self.router.local_ip(), serialized, transaction, Some(callback)) {
+            return Err(SubmitError::Dropped(MempoolRejectReason::QueueFull));
+        }
+        match result.await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(error)) => Err(match error.downcast::<TransactionRejectReason>() {
+                Ok(reason) => SubmitError::Rejected(reason),
+                Err(error) => SubmitError::Failed(error.to_string()),
+            }),
+            Err(_) => Err(SubmitError::Failed("the transaction was dropped before reaching the memory pool".into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::test_helpers::*;
+    use snarkvm::prelude::TestRng;
+
+    #[tokio::test]
+    async fn test_submit_transaction() {
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4130".parse().unwrap();
+
+        // Initialize a validator with a mock consensus and ledger.
+        let consensus = Arc::new(MockConsensus::default());
+        let ledger_api = Arc::new(MockLedger::new(sample_ledger(), Duration::ZERO, false));
+        let validator = sample_validator(consensus.clone(), ledger_api, rng).await;
+
+        // Connect a validator peer, and record the propagated transactions.
+        connect_peer(&validator, peer_ip, &sample_challenge_request(peer_ip, NodeType::Validator, rng));
+        let propagated = Arc::new(Mutex::new(Vec::new()));
+        let propagated_ = propagated.clone();
+        validator.router.on_propagate(move |message| {
+            if let Message::UnconfirmedTransaction(message) = message {
+                propagated_.lock().push(message.transaction_id);
+            }
+        });
+
+        let transaction = sample_transaction();
+        let id = transaction.id();
+
+        // Check that a transaction below the minimum fee is dropped by the admission rules.
+        let fee = *transaction.fee_amount().unwrap();
+        validator.set_min_fee(fee + 1);
+        let error = validator.submit_transaction(transaction.clone()).await.unwrap_err();
+        assert_eq!(error, SubmitError::Dropped(MempoolRejectReason::FeeTooLow { fee, min_fee: fee + 1 }));
+        assert_eq!(validator.number_of_low_fee_rejections(), 1);
+        assert!(consensus.transactions.lock().is_empty());
+        assert!(propagated.lock().is_empty());
+
+        // Check that a submitted transaction reaches the memory pool, and is propagated to the connected peer.
+        validator.set_min_fee(fee);
+        validator.submit_transaction(transaction).await.unwrap();
+        assert_eq!(*consensus.transactions.lock(), vec![id]);
+        assert_eq!(*propagated.lock(), vec![id]);
+        assert_eq!(validator.router.connected_validators(), vec![peer_ip]);
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use snarkos_node_consensus::TransactionRejectReason;
+use snarkos_node_router::{messages::ChallengeRequest, Peer};
+use snarkvm::{
+    algorithms::polycommit::kzg10::{KZGCommitment, KZGProof},
+    ledger::{
+        coinbase::{EpochChallenge, PartialSolution, PuzzleCommitment},
+        narwhal::Data,
+    },
+    prelude::{store::helpers::memory::ConsensusMemory, Address, FromBytes, PrivateKey, Rng, TestRng, Testnet3},
+};
+
+use std::{collections::HashMap, time::Instant};
+
+pub(crate) type CurrentNetwork = Testnet3;
+pub(crate) type CurrentLedger = Ledger<CurrentNetwork, ConsensusMemory<CurrentNetwork>>;
+pub(crate) type CurrentValidator = Validator<CurrentNetwork, ConsensusMemory<CurrentNetwork>>;
+
+/// A consensus that records the solutions and transactions it was given, and accepts them.
+/// Each batch of transactions takes at least the given delay, and rejects the transactions with a set reason.
+#[derive(Default)]
+pub(crate) struct MockConsensus {
+    pub(crate) solutions: Mutex<Vec<PuzzleCommitment<CurrentNetwork>>>,
+    pub(crate) transactions: Mutex<Vec<<CurrentNetwork as Network>::TransactionID>>,
+    pub(crate) rejections: Mutex<HashMap<<CurrentNetwork as Network>::TransactionID, TransactionRejectReason>>,
+    pub(crate) delay: Duration,
+}
+
+#[async_trait]
+impl ConsensusApi<CurrentNetwork> for MockConsensus {
+    async fn add_unconfirmed_solution(&self, solution: ProverSolution<CurrentNetwork>) -> Result<()> {
+        self.solutions.lock().push(solution.commitment());
+        Ok(())
+    }
+
+    async fn add_unconfirmed_transactions(&self, transactions: Vec<Transaction<CurrentNetwork>>) -> Vec<Result<()>> {
+        tokio::time::sleep(self.delay).await;
+        let rejections = self.rejections.lock().clone();
+        transactions
+            .iter()
+            .map(|transaction| match rejections.get(&transaction.id()) {
+                Some(reason) => Err(reason.clone().into()),
+                None => {
+                    self.transactions.lock().push(transaction.id());
+                    Ok(())
+                }
+            })
+            .collect()
+    }
+
+    fn mempool_stats(&self) -> MempoolStats {
+        MempoolStats {
+            num_solutions: self.solutions.lock().len(),
+            num_transactions: self.transactions.lock().len(),
+            ..Default::default()
+        }
+    }
+
+    async fn shut_down(&self) {}
+}
+
+/// A ledger that serves the given ledger with the lowest proof target, and counts the puzzle state reads,
+/// which take at least the given delay, and the block header reads. A fresh ledger reports that it has no
+/// epoch challenge yet. The latest height and proof target may be overridden, to simulate a ledger that advanced.
+pub(crate) struct MockLedger {
+    pub(crate) ledger: CurrentLedger,
+    pub(crate) num_puzzle_state_reads: AtomicUsize,
+    pub(crate) num_header_reads: AtomicUsize,
+    pub(crate) num_epoch_challenge_reads: AtomicUsize,
+    delay: Duration,
+    is_fresh: bool,
+    latest_height: Option<u32>,
+    proof_target: u64,
+}
+
+impl MockLedger {
+    pub(crate) fn new(ledger: CurrentLedger, delay: Duration, is_fresh: bool) -> Self {
+        Self {
+            ledger,
+            num_puzzle_state_reads: Default::default(),
+            num_header_reads: Default::default(),
+            num_epoch_challenge_reads: Default::default(),
+            delay,
+            is_fresh,
+            latest_height: None,
+            proof_target: 1,
+        }
+    }
+
+    pub(crate) fn with_latest_height(mut self, latest_height: u32) -> Self {
+        self.latest_height = Some(latest_height);
+        self
+    }
+
+    pub(crate) fn with_proof_target(mut self, proof_target: u64) -> Self {
+        self.proof_target = proof_target;
+        self
+    }
+}
+
+impl LedgerApi<CurrentNetwork> for MockLedger {
+    fn latest_height(&self) -> u32 {
+        self.latest_height.unwrap_or_else(|| self.ledger.latest_height())
+    }
+
+    fn latest_proof_target(&self) -> u64 {
+        self.proof_target
+    }
+
+    fn contains_puzzle_commitment(&self, _commitment: &PuzzleCommitment<CurrentNetwork>) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn latest_puzzle_state(&self) -> Result<(EpochChallenge<CurrentNetwork>, u32), PuzzleStateError> {
+        self.num_puzzle_state_reads.fetch_add(1, Ordering::SeqCst);
+        std::thread::sleep(self.delay);
+        if self.is_fresh {
+            return Err(PuzzleStateError::MissingEpochChallenge("the ledger is fresh".to_string()));
+        }
+        self.ledger.latest_puzzle_state()
+    }
+
+    fn get_header(&self, height: u32) -> Result<Header<CurrentNetwork>> {
+        self.num_header_reads.fetch_add(1, Ordering::SeqCst);
+        self.ledger.get_header(height)
+    }
+
+    fn epoch_challenge(&self, epoch_number: u32) -> Result<EpochChallenge<CurrentNetwork>> {
+        self.num_epoch_challenge_reads.fetch_add(1, Ordering::SeqCst);
+        self.ledger.epoch_challenge(epoch_number)
+    }
+}
+
+/// Returns the genesis block.
+pub(crate) fn sample_genesis_block() -> Block<CurrentNetwork> {
+    Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap()
+}
+
+/// Returns a ledger at genesis.
+pub(crate) fn sample_ledger() -> CurrentLedger {
+    CurrentLedger::load(sample_genesis_block(), None).unwrap()
+}
+
+/// Returns a transaction from the genesis block.
+pub(crate) fn sample_transaction() -> Transaction<CurrentNetwork> {
+    sample_genesis_block().transactions().iter().next().unwrap().transaction().clone()
+}
+
+/// Returns the unconfirmed transaction message for the given transaction.
+pub(crate) fn transaction_message(transaction: &Transaction<CurrentNetwork>) -> UnconfirmedTransaction<CurrentNetwork> {
+    UnconfirmedTransaction { transaction_id: transaction.id(), transaction: Data::Object(transaction.clone()) }
+}
+
+/// Samples a prover solution for the given address, and returns it with its unconfirmed solution message.
+pub(crate) fn sample_solution(
+    address: Address<CurrentNetwork>,
+    rng: &mut TestRng,
+) -> (UnconfirmedSolution<CurrentNetwork>, ProverSolution<CurrentNetwork>) {
+    let partial_solution = PartialSolution::new(address, rng.gen(), KZGCommitment(rng.gen()));
+    let solution = ProverSolution::new(partial_solution, KZGProof { w: rng.gen(), random_v: None });
+    (UnconfirmedSolution { solution_id: solution.commitment(), solution: Data::Object(solution) }, solution)
+}
+
+/// Samples a random address.
+pub(crate) fn sample_address(rng: &mut TestRng) -> Address<CurrentNetwork> {
+    Address::try_from(PrivateKey::<CurrentNetwork>::new(rng).unwrap()).unwrap()
+}
+
+/// Initializes a validator, without starting it, that handles messages with the given consensus and ledger.
+pub(crate) async fn sample_validator(
+    consensus: Arc<MockConsensus>,
+    ledger_api: Arc<MockLedger>,
+    rng: &mut TestRng,
+) -> CurrentValidator {
+    let ledger = ledger_api.ledger.clone();
+    let account = Account::new(rng).unwrap();
+    let router =
+        Router::new("127.0.0.1:0".parse().unwrap(), NodeType::Validator, account, &[], 10, true).await.unwrap();
+    let sync = BlockSync::new(BlockSyncMode::Gateway, Arc::new(CoreLedgerService::new(ledger.clone())));
+    Validator::from_parts(ledger, consensus, ledger_api, router, sync, Some(0))
+}
+
+/// Returns the challenge request of a peer of the given type, listening on the port of the given peer IP.
+pub(crate) fn sample_challenge_request(
+    peer_ip: SocketAddr,
+    node_type: NodeType,
+    rng: &mut TestRng,
+) -> ChallengeRequest<CurrentNetwork> {
+    ChallengeRequest::new(peer_ip.port(), node_type, sample_address(rng), rng.gen())
+}
+
+/// Registers a connected peer with the given challenge request, as if it completed the handshake.
+pub(crate) fn connect_peer(
+    validator: &CurrentValidator,
+    peer_ip: SocketAddr,
+    request: &ChallengeRequest<CurrentNetwork>,
+) {
+    let peer = Peer::new(peer_ip, request, ConnectionSide::Initiator, validator.router.clock().now());
+    validator.router.insert_connected_peer(peer, peer_ip);
+}
+
+/// Waits for the given consensus to receive the given number of transactions.
+pub(crate) async fn wait_for_transactions(consensus: &MockConsensus, num_transactions: usize) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while consensus.transactions.lock().len() < num_transactions {
+        assert!(Instant::now() < deadline, "the transactions did not reach the consensus");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use super::*;
+use snarkos_node_router::messages::{DisconnectReason, UnconfirmedTransaction};
+
+use futures_util::future::join_all;
+use tokio::sync::{mpsc, oneshot};
+
+/// The default maximum number of unconfirmed transactions queued for the memory pool, before new ones are dropped.
+pub const DEFAULT_TRANSACTION_QUEUE_CAPACITY: usize = 1024;
+
+/// The sender notified of the result of adding a locally-submitted transaction to the memory pool.
+pub(super) type TransactionCallback = oneshot::Sender<Result<()>>;
+
+/// An unconfirmed transaction queued for the memory pool, with the peer it was received from,
+/// and the callback of the local submitter, if any.
+pub(super) type QueuedTransaction<N> =
+    (SocketAddr, UnconfirmedTransaction<N>, Transaction<N>, Option<TransactionCallback>);
+
+impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
+    /// Returns the number of unconfirmed transactions dropped, as the queue to the memory pool was full.
+    pub fn number_of_transaction_queue_overflows(&self) -> u64 {
+        self.num_transaction_queue_overflows.load(Ordering::Relaxed)
+    }
+
+    /// Queues the given unconfirmed transaction for the memory pool, without waiting for the consensus.
+    /// If the queue is full, the transaction is dropped, so that reading messages never blocks on the consensus.
+    /// Returns `true` if the transaction was queued.
+    pub(super) fn enqueue_unconfirmed_transaction(
+        &self,
+        peer_ip: SocketAddr,
+        serialized: UnconfirmedTransaction<N>,
+        transaction: Transaction<N>,
+        callback: Option<TransactionCallback>,
+    ) -> bool {
+        match self.transaction_sender.try_send((peer_ip, serialized, transaction, callback)) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full((_, _, transaction, _))) => {
+                trace!("[UnconfirmedTransaction] Dropping a transaction from '{peer_ip}' (the queue is full)");
+                self.num_transaction_queue_overflows.fetch_add(1, Ordering::Relaxed);
+                let id = MempoolId::Transaction(transaction.id());
+                self.emit_mempool_event(MempoolEvent::Rejected { id, reason: MempoolRejectReason::QueueFull });
+                false
+            }
+            // The writer has stopped, which only happens on shutdown.
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        }
+    }
+
+    /// Starts the task that adds the queued unconfirmed transactions to the memory pool.
+    pub(super) fn initialize_transaction_writer(&self, mut receiver: mpsc::Receiver<QueuedTransaction<N>>) {
+        let self_ = self.clone();
+        self.spawn(async move {
+            while let Some(first) = receiver.recv().await {
+                // Take the transactions queued behind the first one, up to the maximum batch size.
+                let mut queued = vec![first];
+                while queued.len() < self_.transaction_batcher.max_batch_size() {
+                    match receiver.try_recv() {
+                        Ok(transaction) => queued.push(transaction),
+                        Err(_) => break,
+                    }
+                }
+                // Add the transactions to the memory pool, in a single batch.
+                join_all(queued.into_iter().map(|(peer_ip, serialized, transaction, callback)| {
+                    self_.write_unconfirmed_transaction(peer_ip, serialized, transaction, callback)
+                }))
+                .await;
+            }
+        });
+    }
+
+    /// Adds the given unconfirmed transaction to the memory pool, and propagates it if it was accepted.
+    /// The peer that sent the transaction is disconnected, if the transaction is invalid.
+    /// For a locally-submitted transaction, the result is sent to the callback instead.
+    async fn write_unconfirmed_transaction(
+        &self,
+        peer_ip: SocketAddr,
+        serialized: UnconfirmedTransaction<N>,
+        transaction: Transaction<N>,
+        callback: Option<TransactionCallback>,
+    ) {
+        // Add the transaction to the memory pool in a batch, with the other transactions received in the window.
+        let id = transaction.id();
+        let consensus = self.consensus();
+        let result = self
+            .transaction_batcher
+            .submit(transaction, |transactions| consensus.add_unconfirmed_transactions(transactions))
+            .await;
+        match result {
+            Some(Ok(())) => {
+                // Mark the transaction as seen across all peers, now that it was accepted.
+                self.router.insert_accepted_transaction(id);
+                self.emit_mempool_event(MempoolEvent::TransactionAdmitted { id });
+            }
+            Some(Err(error)) => {
+                trace!("[UnconfirmedTransaction] {error}");
+                let reason = MempoolRejectReason::from_transaction_error(&error);
+                self.emit_mempool_event(MempoolEvent::Rejected { id: MempoolId::Transaction(id), reason });
+                // Return the error to the local submitter, if any.
+                if let Some(callback) = callback {
+                    let _ = callback.send(Err(error));
+                    return;
+                }
+                // Disconnect from the peer, if it sent an invalid transaction.
+                // Otherwise, the transaction was rejected by the memory pool (e.g. as a duplicate).
+                if is_invalid_transaction(&error) {
+                    warn!("Peer '{peer_ip}' is not following the 'UnconfirmedTransaction' protocol");
+                    self.send_disconnect(peer_ip, DisconnectReason::ProtocolViolation);
+                }
+                return;
+            }
+            // The batch was dropped before the transaction was processed, which is not the fault of the peer.
+            None => return,
+        }
+        // Propagate the "UnconfirmedTransaction" to the connected validators.
+        self.propagate_to_validators_bounded(Message::UnconfirmedTransaction(serialized), &[peer_ip]).await;
+        // Notify the local submitter, if any, once the transaction is propagated.
+        if let Some(callback) = callback {
+            let _ = callback.send(Ok(()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::test_helpers::*;
+    use snarkos_node_consensus::TransactionRejectReason;
+    use snarkvm::prelude::TestRng;
+
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_unconfirmed_transaction_rejections() {
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4130".parse().unwrap();
+
+        // Initialize a validator with a mock consensus and ledger, and subscribe to its memory pool events.
+        let consensus = Arc::new(MockConsensus::default());
+        let ledger_api = Arc::new(MockLedger::new(sample_ledger(), Duration::ZERO, false));
+        let validator = sample_validator(consensus.clone(), ledger_api, rng).await;
+        let mut events = validator.subscribe_mempool();
+
+        // Connect a peer.
+        connect_peer(&validator, peer_ip, &sample_challenge_request(peer_ip, NodeType::Validator, rng));
+
+        let transaction = sample_transaction();
+        let id = transaction.id();
+        let message = transaction_message(&transaction);
+
+        // Check that a transaction rejected by the memory pool, e.g. as a duplicate, keeps the peer.
+        let reason = TransactionRejectReason::AlreadyInMemoryPool;
+        consensus.rejections.lock().insert(id, reason.clone());
+        assert!(validator.unconfirmed_transaction(peer_ip, message.clone(), transaction.clone()).await);
+        let reason = MempoolRejectReason::Transaction(reason);
+        assert_eq!(events.recv().await.unwrap(), MempoolEvent::Rejected { id: MempoolId::Transaction(id), reason });
+        assert!(validator.router.is_connected(&peer_ip));
+
+        // Check that an invalid transaction disconnects the peer for a protocol violation.
+        let reason = TransactionRejectReason::Invalid("the proof is invalid".to_string());
+        consensus.rejections.lock().insert(id, reason.clone());
+        assert!(validator.unconfirmed_transaction(peer_ip, message, transaction).await);
+        let reason = MempoolRejectReason::Transaction(reason);
+        assert_eq!(events.recv().await.unwrap(), MempoolEvent::Rejected { id: MempoolId::Transaction(id), reason });
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while validator.router.is_connected(&peer_ip) {
+            assert!(Instant::now() < deadline, "the peer was not disconnected");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let disconnects = validator.router.recent_disconnects(1);
+        assert_eq!(disconnects[0].addr, peer_ip);
+        assert_eq!(disconnects[0].reason, DisconnectReason::ProtocolViolation);
+        assert!(consensus.transactions.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_slow_consensus_does_not_stall_transactions() {
+        const CAPACITY: usize = 2;
+
+        let rng = &mut TestRng::default();
+        let peer_ip = "127.0.0.1:4130".parse().unwrap();
+
+        // Initialize a validator with a slow consensus, and a short queue to the memory pool.
+        let consensus = Arc::new(MockConsensus { delay: Duration::from_millis(500), ..Default::default() });
+        let ledger_api = Arc::new(MockLedger::new(sample_ledger(), Duration::ZERO, false));
+        let mut validator = sample_validator(consensus.clone(), ledger_api, rng).await;
+        let (transaction_sender, transaction_receiver) = mpsc::channel(CAPACITY);
+        validator.transaction_sender = transaction_sender;
+        validator.initialize_transaction_writer(transaction_receiver);
+
+        // Prepare a transaction.
+        let transaction = sample_transaction();
+        let message = transaction_message(&transaction);
+
+        // Handle a transaction, and let the writer pass it on to the consensus.
+        let start = Instant::now();
+        assert!(validator.unconfirmed_transaction(peer_ip, message.clone(), transaction.clone()).await);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        // Handle more transactions than the queue holds, while the consensus is busy.
+        for _ in 0..CAPACITY + 2 {
+            assert!(validator.unconfirmed_transaction(peer_ip, message.clone(), transaction.clone()).await);
+        }
+
+        // Check that the handlers did not wait for the consensus, and the overflowing transactions were counted.
+        assert!(start.elapsed() < Duration::from_millis(250));
+        assert!(consensus.transactions.lock().is_empty());
+        assert_eq!(validator.number_of_transaction_queue_overflows(), 2);
+
+        // Check that the queued transactions eventually reach the consensus.
+        wait_for_transactions(&consensus, 1 + CAPACITY).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(consensus.transactions.lock().len(), 1 + CAPACITY);
+    }
+}
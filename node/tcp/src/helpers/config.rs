@@ -15,6 +15,7 @@
 use std::{
     io::{self, ErrorKind::*},
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
 };
 
 #[cfg(doc)]
@@ -52,6 +53,23 @@ pub struct Config {
     pub max_connections: u16,
     /// The maximum time (in milliseconds) allowed to establish a raw (before the [`Handshake`] protocol) TCP connection.
     pub connection_timeout_ms: u16,
+    /// If `true`, `TCP_NODELAY` is set on every connection, disabling Nagle's algorithm.
+    pub nodelay: bool,
+    /// The TCP keepalive parameters set on every connection.
+    ///
+    /// note: If set to `None`, the operating system defaults are used.
+    pub keepalive: Option<Keepalive>,
+}
+
+/// The TCP keepalive parameters of a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keepalive {
+    /// The duration a connection is idle, before the first keepalive probe is sent.
+    pub idle: Duration,
+    /// The duration in between keepalive probes.
+    ///
+    /// note: This is ignored on platforms that do not support it.
+    pub interval: Duration,
 }
 
 impl Config {
@@ -88,6 +106,8 @@ impl Default for Config {
             fatal_io_errors: vec![ConnectionReset, ConnectionAborted, BrokenPipe, InvalidData, UnexpectedEof],
             max_connections: 100,
             connection_timeout_ms: 1_000,
+            nodelay: true,
+            keepalive: Some(Keepalive { idle: Duration::from_secs(60), interval: Duration::from_secs(10) }),
         }
     }
 }
@@ -0,0 +1,72 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+};
+
+use parking_lot::Mutex;
+
+/// Keeps track of the bytes buffered for each connection, which await the rest of an inbound message.
+#[derive(Default)]
+pub struct InboundBuffers(Mutex<InnerInboundBuffers>);
+
+#[derive(Default)]
+struct InnerInboundBuffers {
+    /// The number of bytes buffered for each connection.
+    buffered: HashMap<SocketAddr, usize>,
+    /// The connections that were evicted, and are about to be dropped.
+    evicted: HashSet<SocketAddr>,
+}
+
+impl InboundBuffers {
+    /// Registers the number of bytes currently buffered for the given address.
+    pub fn update(&self, addr: SocketAddr, num_bytes: usize) {
+        let mut inner = self.0.lock();
+        if !inner.evicted.contains(&addr) {
+            inner.buffered.insert(addr, num_bytes);
+        }
+    }
+
+    /// Returns the number of bytes buffered for the given address.
+    pub fn get(&self, addr: SocketAddr) -> usize {
+        self.0.lock().buffered.get(&addr).copied().unwrap_or_default()
+    }
+
+    /// Returns the total number of bytes buffered across all connections.
+    pub fn total(&self) -> usize {
+        self.0.lock().buffered.values().sum()
+    }
+
+    /// Removes the given address, once its connection is dropped.
+    pub fn remove(&self, addr: SocketAddr) {
+        let mut inner = self.0.lock();
+        inner.buffered.remove(&addr);
+        inner.evicted.remove(&addr);
+    }
+
+    /// If the total number of buffered bytes exceeds the given limit, evicts the connection with the largest
+    /// buffer, and returns its address. The bytes of an evicted connection are no longer counted towards the total.
+    pub fn evict_largest_above(&self, limit: usize) -> Option<SocketAddr> {
+        let mut inner = self.0.lock();
+        if inner.buffered.values().sum::<usize>() <= limit {
+            return None;
+        }
+        let addr = inner.buffered.iter().max_by_key(|(_, num_bytes)| **num_bytes).map(|(addr, _)| *addr)?;
+        inner.buffered.remove(&addr);
+        inner.evicted.insert(addr);
+        Some(addr)
+    }
+}
@@ -13,11 +13,14 @@
 // limitations under the License.
 
 mod config;
-pub use config::Config;
+pub use config::{Config, Keepalive};
 
 pub mod connections;
 pub use connections::{Connection, ConnectionSide};
 
+mod inbound_buffers;
+pub use inbound_buffers::InboundBuffers;
+
 mod known_peers;
 pub use known_peers::KnownPeers;
 
@@ -29,7 +29,6 @@ use crate::{protocols::Handshake, Config};
 use crate::{
     protocols::{ProtocolHandler, ReturnableConnection},
     ConnectionSide,
-    Tcp,
     P2P,
 };
 
@@ -40,6 +39,10 @@ use crate::{
 /// which is immediately queued (with a [`Reading::MESSAGE_QUEUE_DEPTH`] limit) to be processed by
 /// [`Reading::process_message`]. The configured fatal IO errors result in an immediate disconnect
 /// (in order to e.g. avoid accidentally reading "borked" messages).
+///
+/// The messages from a single connection are processed sequentially, in the order they arrived, by a dedicated
+/// task; the next message is only processed once [`Reading::process_message`] returns for the previous one. The
+/// messages from different connections are processed concurrently.
 #[async_trait]
 pub trait Reading: P2P
 where
@@ -94,7 +97,18 @@ where
     /// The `side` param indicates the connection side **from the node's perspective**.
     fn codec(&self, addr: SocketAddr, side: ConnectionSide) -> Self::Codec;
 
+    /// Returns the maximum number of bytes buffered across all connections, awaiting the rest of an inbound message;
+    /// if it is exceeded, the connection with the largest buffer is dropped. The default is `None`, i.e. no limit.
+    fn max_inbound_buffer_bytes(&self) -> Option<usize> {
+        None
+    }
+
+    /// Called when the specified [`SocketAddr`] holds the largest buffer once [`Reading::max_inbound_buffer_bytes`]
+    /// is exceeded, right before the connection is dropped. Does nothing by default.
+    fn on_inbound_buffer_overflow(&self, _addr: SocketAddr) {}
+
     /// Processes an inbound message. Can be used to update state, send replies etc.
+    /// It is never called concurrently for the same connection, so it may await without reordering its messages.
     async fn process_message(&self, source: SocketAddr, message: Self::Message) -> io::Result<()>;
 }
 
@@ -109,7 +123,7 @@ trait ReadingInternal: Reading {
         &self,
         framed: FramedRead<T, Self::Codec>,
         addr: SocketAddr,
-    ) -> FramedRead<T, CountingCodec<Self::Codec>>;
+    ) -> FramedRead<T, CountingCodec<Self>>;
 }
 
 #[async_trait]
@@ -134,7 +148,7 @@ impl<R: Reading> ReadingInternal for R {
         // use a channel to know when the processing task is ready
         let (tx_processing, rx_processing) = oneshot::channel::<()>();
 
-        // the task for processing parsed messages
+        // the task for processing parsed messages, one at a time, in the order they arrived
         let self_clone = self.clone();
         let inbound_processing_task = tokio::spawn(async move {
             let node = self_clone.tcp();
@@ -198,36 +212,61 @@ impl<R: Reading> ReadingInternal for R {
         &self,
         framed: FramedRead<T, Self::Codec>,
         addr: SocketAddr,
-    ) -> FramedRead<T, CountingCodec<Self::Codec>> {
-        framed.map_decoder(|codec| CountingCodec { codec, node: self.tcp().clone(), addr, acc: 0 })
+    ) -> FramedRead<T, CountingCodec<Self>> {
+        framed.map_decoder(|codec| CountingCodec { codec, reader: self.clone(), addr, acc: 0 })
     }
 }
 
-/// A wrapper [`Decoder`] that also counts the inbound messages.
-struct CountingCodec<D: Decoder> {
-    codec: D,
-    node: Tcp,
+/// A wrapper [`Decoder`] that also counts the inbound messages, and the bytes buffered for them.
+struct CountingCodec<R: Reading> {
+    codec: R::Codec,
+    reader: R,
     addr: SocketAddr,
     acc: usize,
 }
 
-impl<D: Decoder> Decoder for CountingCodec<D> {
-    type Error = D::Error;
-    type Item = D::Item;
+impl<R: Reading> CountingCodec<R> {
+    /// Registers the bytes left in the buffer, and drops the connection with the largest buffer
+    /// if the limit on the bytes buffered across all connections is exceeded.
+    fn register_buffered_bytes(&self, num_bytes: usize) {
+        let node = self.reader.tcp();
+        node.inbound_buffers().update(self.addr, num_bytes);
+
+        let Some(limit) = self.reader.max_inbound_buffer_bytes() else {
+            return;
+        };
+        if let Some(addr) = node.inbound_buffers().evict_largest_above(limit) {
+            warn!(parent: node.span(), "the inbound buffers exceed {}B; dropping the largest one, of {}", limit, addr);
+            node.stats().register_failure();
+            self.reader.on_inbound_buffer_overflow(addr);
+            // the disconnect aborts the reader task, so it can't be awaited from within it
+            let node = node.clone();
+            tokio::spawn(async move {
+                let _ = node.disconnect(addr).await;
+            });
+        }
+    }
+}
+
+impl<R: Reading> Decoder for CountingCodec<R> {
+    type Error = io::Error;
+    type Item = R::Message;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         let initial_buf_len = src.len();
         let ret = self.codec.decode(src)?;
         let final_buf_len = src.len();
+        self.register_buffered_bytes(final_buf_len);
         let read_len = initial_buf_len - final_buf_len + self.acc;
 
         if read_len != 0 {
-            trace!(parent: self.node.span(), "read {}B from {}", read_len, self.addr);
+            let node = self.reader.tcp();
+            trace!(parent: node.span(), "read {}B from {}", read_len, self.addr);
 
             if ret.is_some() {
                 self.acc = 0;
-                self.node.known_peers().register_received_message(self.addr, read_len);
-                self.node.stats().register_received_message(read_len);
+                node.known_peers().register_received_message(self.addr, read_len);
+                node.stats().register_received_message(read_len);
             } else {
                 self.acc = read_len;
             }
@@ -12,13 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{any::Any, collections::HashMap, io, net::SocketAddr, sync::Arc};
+use std::{any::Any, collections::HashMap, future::poll_fn, io, net::SocketAddr, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
+use bytes::BytesMut;
 use futures_util::sink::SinkExt;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use tokio::{
-    io::AsyncWrite,
+    io::{AsyncWrite, AsyncWriteExt},
     sync::{mpsc, oneshot},
 };
 use tokio_util::codec::{Encoder, FramedWrite};
@@ -33,7 +34,45 @@ use crate::{
     P2P,
 };
 
-type WritingSenders = Arc<RwLock<HashMap<SocketAddr, mpsc::Sender<WrappedMessage>>>>;
+type WritingSenders = Arc<RwLock<HashMap<SocketAddr, MessageSenders>>>;
+
+/// The depth of per-connection queues used to send priority messages.
+const PRIORITY_MESSAGE_QUEUE_DEPTH: usize = 16;
+
+/// A queue of outbound messages, shared between the writer task and the methods that cancel the queued messages.
+/// The lock is only held while polling the queue, never across a write.
+type SharedReceiver = Arc<Mutex<mpsc::Receiver<WrappedMessage>>>;
+
+/// The senders of the outbound messages for a single connection.
+#[derive(Clone)]
+struct MessageSenders {
+    /// The sender of the messages that are written in the order they were queued in.
+    regular: mpsc::Sender<WrappedMessage>,
+    /// The sender of the messages that are written ahead of the regular ones.
+    priority: mpsc::Sender<WrappedMessage>,
+    /// The receivers of the regular and priority messages, used to cancel the messages still queued.
+    receivers: [SharedReceiver; 2],
+}
+
+impl MessageSenders {
+    /// Returns the number of messages queued for the connection, that have not yet been written to the stream.
+    fn num_queued(&self) -> usize {
+        [&self.regular, &self.priority].iter().map(|sender| sender.max_capacity() - sender.capacity()).sum()
+    }
+
+    /// Drops the messages queued for the connection, failing their delivery, and returns their number.
+    fn cancel_queued(&self) -> usize {
+        let mut num_cancelled = 0;
+        for receiver in &self.receivers {
+            let mut receiver = receiver.lock();
+            while let Ok(cancelled_msg) = receiver.try_recv() {
+                let _ = cancelled_msg.delivery_notification.send(Err(io::ErrorKind::Interrupted.into()));
+                num_cancelled += 1;
+            }
+        }
+        num_cancelled
+    }
+}
 
 /// Can be used to specify and enable writing, i.e. sending outbound messages. If the [`Handshake`]
 /// protocol is enabled too, it goes into force only after the handshake has been concluded.
@@ -92,6 +131,22 @@ where
     /// The `side` param indicates the connection side **from the node's perspective**.
     fn codec(&self, addr: SocketAddr, side: ConnectionSide) -> Self::Codec;
 
+    /// Returns the maximum time allowed to write a single message to a stream; if it is exceeded, the stream
+    /// is considered stuck, and the connection is dropped. The default is `None`, i.e. no timeout.
+    fn write_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Called when writing a message to the specified [`SocketAddr`] exceeded [`Writing::write_timeout`],
+    /// right before the connection is dropped. Does nothing by default.
+    fn on_write_timeout(&self, _addr: SocketAddr) {}
+
+    /// Returns the window over which queued messages are coalesced, in order to be written to a stream with a single
+    /// flush; it bounds the extra latency of each message. The default is `None`, i.e. each message is flushed.
+    fn coalescing_window(&self) -> Option<Duration> {
+        None
+    }
+
     /// Sends the provided message to the specified [`SocketAddr`]. Returns as soon as the message is queued to
     /// be sent, without waiting for the actual delivery; instead, the caller is provided with a [`oneshot::Receiver`]
     /// which can be used to determine when and whether the message has been delivered.
@@ -106,7 +161,7 @@ where
         // access the protocol handler
         if let Some(handler) = self.tcp().protocols.writing.get() {
             // find the message sender for the given address
-            if let Some(sender) = handler.senders.read().get(&addr).cloned() {
+            if let Some(sender) = handler.senders.read().get(&addr).map(|senders| senders.regular.clone()) {
                 let (msg, delivery) = WrappedMessage::new(Box::new(message));
                 sender
                     .try_send(msg)
@@ -124,6 +179,71 @@ where
         }
     }
 
+    /// Returns the number of outbound messages queued for the specified [`SocketAddr`] that have not yet been
+    /// written to the stream, or `None` if the node is not connected to it or [`Writing::enable_writing`]
+    /// hadn't been called yet. A growing backlog indicates that the peer is not reading its messages.
+    fn outbound_backlog(&self, addr: SocketAddr) -> Option<usize> {
+        let handler = self.tcp().protocols.writing.get()?;
+        let sender = handler.senders.read().get(&addr).map(|senders| senders.regular.clone())?;
+        Some(Self::MESSAGE_QUEUE_DEPTH.saturating_sub(sender.capacity()))
+    }
+
+    /// Returns the number of outbound messages queued for all the connections that have not yet been written to
+    /// their streams, or `0` if [`Writing::enable_writing`] hadn't been called yet. The messages that are being
+    /// written at the moment are not included.
+    fn inflight_sends(&self) -> usize {
+        let Some(handler) = self.tcp().protocols.writing.get() else {
+            return 0;
+        };
+        handler.senders.read().values().map(|senders| senders.num_queued()).sum()
+    }
+
+    /// Cancels the outbound messages queued for all the connections, and returns their number; their delivery
+    /// fails with [`io::ErrorKind::Interrupted`]. The connections are kept, and a message that is being written
+    /// at the moment is left to complete, so that no stream is left with a partially written message.
+    fn cancel_inflight_sends(&self) -> usize {
+        let Some(handler) = self.tcp().protocols.writing.get() else {
+            return 0;
+        };
+        let senders = handler.senders.read().values().cloned().collect::<Vec<_>>();
+        senders.iter().map(|senders| senders.cancel_queued()).sum()
+    }
+
+    /// Sends the provided message to the specified [`SocketAddr`] ahead of the messages queued for it, even if
+    /// its outbound message queue is full. It is meant for a message that precedes closing the connection, so
+    /// the messages that are still queued once it is dequeued are dropped, and their delivery fails.
+    /// Like [`Writing::unicast`], it returns as soon as the message is queued to be sent.
+    ///
+    /// # Errors
+    ///
+    /// The following errors can be returned:
+    /// - [`io::ErrorKind::NotConnected`] if the node is not connected to the provided address
+    /// - [`io::ErrorKind::Other`] if the priority message queue for this address is full
+    /// - [`io::ErrorKind::Unsupported`] if [`Writing::enable_writing`] hadn't been called yet
+    fn unicast_priority(
+        &self,
+        addr: SocketAddr,
+        message: Self::Message,
+    ) -> io::Result<oneshot::Receiver<io::Result<()>>> {
+        // access the protocol handler
+        let Some(handler) = self.tcp().protocols.writing.get() else {
+            return Err(io::ErrorKind::Unsupported.into());
+        };
+        // find the priority message sender for the given address
+        let Some(sender) = handler.senders.read().get(&addr).map(|senders| senders.priority.clone()) else {
+            return Err(io::ErrorKind::NotConnected.into());
+        };
+        let (msg, delivery) = WrappedMessage::new(Box::new(message));
+        sender
+            .try_send(msg)
+            .map_err(|e| {
+                error!(parent: self.tcp().span(), "can't send a priority message to {}: {}", addr, e);
+                self.tcp().stats().register_failure();
+                io::ErrorKind::Other.into()
+            })
+            .map(|_| delivery)
+    }
+
     /// Broadcasts the provided message to all connected peers. Returns as soon as the message is queued to
     /// be sent to all the peers, without waiting for the actual delivery. This method doesn't provide the
     /// means to check when and if the messages actually get delivered; you can achieve that by calling
@@ -139,9 +259,9 @@ where
         // access the protocol handler
         if let Some(handler) = self.tcp().protocols.writing.get() {
             let senders = handler.senders.read().clone();
-            for (addr, message_sender) in senders {
+            for (addr, message_senders) in senders {
                 let (msg, _delivery) = WrappedMessage::new(Box::new(message.clone()));
-                let _ = message_sender.try_send(msg).map_err(|e| {
+                let _ = message_senders.regular.try_send(msg).map_err(|e| {
                     error!(parent: self.tcp().span(), "can't send a message to {}: {}", addr, e);
                     self.tcp().stats().register_failure();
                 });
@@ -164,6 +284,14 @@ trait WritingInternal: Writing {
         writer: &mut FramedWrite<W, Self::Codec>,
     ) -> Result<usize, <Self::Codec as Encoder<Self::Message>>::Error>;
 
+    /// Writes the given messages to the network stream with a single flush, and returns the number of written bytes
+    /// for each message.
+    async fn write_batch_to_stream<W: AsyncWrite + Unpin + Send>(
+        &self,
+        messages: Vec<Self::Message>,
+        writer: &mut FramedWrite<W, Self::Codec>,
+    ) -> io::Result<Vec<usize>>;
+
     /// Applies the [`Writing`] protocol to a single connection.
     async fn handle_new_connection(&self, (conn, conn_returner): ReturnableConnection, conn_senders: &WritingSenders);
 }
@@ -182,6 +310,27 @@ impl<W: Writing> WritingInternal for W {
         Ok(len)
     }
 
+    async fn write_batch_to_stream<A: AsyncWrite + Unpin + Send>(
+        &self,
+        messages: Vec<Self::Message>,
+        writer: &mut FramedWrite<A, Self::Codec>,
+    ) -> io::Result<Vec<usize>> {
+        // encode all the messages into a single buffer
+        let mut buffer = BytesMut::new();
+        let mut lens = Vec::with_capacity(messages.len());
+        for message in messages {
+            let initial_len = buffer.len();
+            writer.encoder_mut().encode(message, &mut buffer)?;
+            lens.push(buffer.len() - initial_len);
+        }
+
+        // the framed writer is flushed after every write, so its own buffer is empty
+        writer.get_mut().write_all(&buffer).await?;
+        writer.get_mut().flush().await?;
+
+        Ok(lens)
+    }
+
     async fn handle_new_connection(
         &self,
         (mut conn, conn_returner): ReturnableConnection,
@@ -192,10 +341,18 @@ impl<W: Writing> WritingInternal for W {
         let writer = conn.writer.take().expect("missing connection writer!");
         let mut framed = FramedWrite::new(writer, codec);
 
-        let (outbound_message_sender, mut outbound_message_receiver) = mpsc::channel(Self::MESSAGE_QUEUE_DEPTH);
+        let (outbound_message_sender, outbound_message_receiver) = mpsc::channel(Self::MESSAGE_QUEUE_DEPTH);
+        let (priority_message_sender, priority_message_receiver) = mpsc::channel(PRIORITY_MESSAGE_QUEUE_DEPTH);
+        let outbound_message_receiver = Arc::new(Mutex::new(outbound_message_receiver));
+        let priority_message_receiver = Arc::new(Mutex::new(priority_message_receiver));
 
-        // register the connection's message sender with the Writing protocol handler
-        conn_senders.write().insert(addr, outbound_message_sender);
+        // register the connection's message senders with the Writing protocol handler
+        let senders = MessageSenders {
+            regular: outbound_message_sender,
+            priority: priority_message_sender,
+            receivers: [outbound_message_receiver.clone(), priority_message_receiver.clone()],
+        };
+        conn_senders.write().insert(addr, senders);
 
         // this will automatically drop the sender upon a disconnect
         let auto_cleanup = SenderCleanup { addr, senders: Arc::clone(conn_senders) };
@@ -213,21 +370,84 @@ impl<W: Writing> WritingInternal for W {
             // move the cleanup into the task that gets aborted on disconnect
             let _auto_cleanup = auto_cleanup;
 
-            while let Some(wrapped_msg) = outbound_message_receiver.recv().await {
-                let msg = wrapped_msg.msg.downcast().unwrap();
-
-                match self_clone.write_to_stream(*msg, &mut framed).await {
-                    Ok(len) => {
-                        let _ = wrapped_msg.delivery_notification.send(Ok(()));
-                        node.known_peers().register_sent_message(addr, len);
-                        node.stats().register_sent_message(len);
-                        trace!(parent: node.span(), "sent {}B to {}", len, addr);
+            loop {
+                // a priority message is written first, and the regular messages still queued behind it are dropped
+                let (wrapped_msg, is_priority) = tokio::select! {
+                    biased;
+                    Some(wrapped_msg) = poll_fn(|cx| priority_message_receiver.lock().poll_recv(cx)) => {
+                        while let Ok(dropped_msg) = outbound_message_receiver.lock().try_recv() {
+                            let dropped = Err(io::ErrorKind::ConnectionAborted.into());
+                            let _ = dropped_msg.delivery_notification.send(dropped);
+                        }
+                        (wrapped_msg, true)
+                    }
+                    wrapped_msg = poll_fn(|cx| outbound_message_receiver.lock().poll_recv(cx)) => match wrapped_msg {
+                        Some(wrapped_msg) => (wrapped_msg, false),
+                        None => break,
+                    },
+                };
+
+                // collect the messages queued within the coalescing window, if there is one
+                let mut batch = vec![wrapped_msg];
+                if let Some(window) = self_clone.coalescing_window().filter(|_| !is_priority) {
+                    let deadline = tokio::time::Instant::now() + window;
+                    while let Ok(Some(wrapped_msg)) = tokio::time::timeout_at(
+                        deadline,
+                        poll_fn(|cx| outbound_message_receiver.lock().poll_recv(cx)),
+                    )
+                    .await
+                    {
+                        batch.push(wrapped_msg);
+                    }
+                }
+                let (msgs, notifications): (Vec<_>, Vec<_>) = batch
+                    .into_iter()
+                    .map(|wrapped_msg| (*wrapped_msg.msg.downcast().unwrap(), wrapped_msg.delivery_notification))
+                    .unzip();
+
+                // write a single message as is, and a batch of messages with a single flush
+                let write = async {
+                    if msgs.len() == 1 {
+                        let msg = msgs.into_iter().next().unwrap();
+                        self_clone.write_to_stream(msg, &mut framed).await.map(|len| vec![len])
+                    } else {
+                        self_clone.write_batch_to_stream(msgs, &mut framed).await
+                    }
+                };
+
+                // bound the write with the timeout, if there is one
+                let result = match self_clone.write_timeout() {
+                    Some(timeout) => match tokio::time::timeout(timeout, write).await {
+                        Ok(result) => result,
+                        Err(_) => {
+                            node.known_peers().register_failure(addr);
+                            warn!(parent: node.span(), "writing a message to {} timed out; disconnecting", addr);
+                            for notification in notifications {
+                                let _ = notification.send(Err(io::ErrorKind::TimedOut.into()));
+                            }
+                            self_clone.on_write_timeout(addr);
+                            break;
+                        }
+                    },
+                    None => write.await,
+                };
+
+                match result {
+                    Ok(lens) => {
+                        for (notification, len) in notifications.into_iter().zip(lens) {
+                            let _ = notification.send(Ok(()));
+                            node.known_peers().register_sent_message(addr, len);
+                            node.stats().register_sent_message(len);
+                            trace!(parent: node.span(), "sent {}B to {}", len, addr);
+                        }
                     }
                     Err(e) => {
                         node.known_peers().register_failure(addr);
                         error!(parent: node.span(), "couldn't send a message to {}: {}", addr, e);
                         let is_fatal = node.config().fatal_io_errors.contains(&e.kind());
-                        let _ = wrapped_msg.delivery_notification.send(Err(e));
+                        for notification in notifications {
+                            let _ = notification.send(Err(io::Error::new(e.kind(), e.to_string())));
+                        }
                         if is_fatal {
                             break;
                         }
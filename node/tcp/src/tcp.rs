@@ -27,6 +27,7 @@ use std::{
 
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
+use socket2::{SockRef, TcpKeepalive};
 use tokio::{
     io::split,
     net::{TcpListener, TcpStream},
@@ -40,6 +41,7 @@ use crate::{
     connections::{Connection, ConnectionSide, Connections},
     protocols::{Protocol, Protocols},
     Config,
+    InboundBuffers,
     KnownPeers,
     Stats,
 };
@@ -67,12 +69,16 @@ pub struct InnerTcp {
     config: Config,
     /// The node's listening address.
     listening_addr: OnceCell<SocketAddr>,
+    /// The node's additional listening addresses.
+    additional_listening_addrs: Mutex<Vec<SocketAddr>>,
     /// Contains objects used by the protocols implemented by the node.
     pub(crate) protocols: Protocols,
     /// A list of connections that have not been finalized yet.
     connecting: Mutex<HashSet<SocketAddr>>,
     /// Contains objects related to the node's active connections.
     connections: Connections,
+    /// Keeps track of the bytes buffered for the node's connections, awaiting the rest of an inbound message.
+    inbound_buffers: InboundBuffers,
     /// Collects statistics related to the node's peers.
     known_peers: KnownPeers,
     /// Collects statistics related to the node itself.
@@ -97,9 +103,11 @@ impl Tcp {
             span,
             config,
             listening_addr: Default::default(),
+            additional_listening_addrs: Default::default(),
             protocols: Default::default(),
             connecting: Default::default(),
             connections: Default::default(),
+            inbound_buffers: Default::default(),
             known_peers: Default::default(),
             stats: Default::default(),
             tasks: Default::default(),
@@ -129,6 +137,11 @@ impl Tcp {
         self.listening_addr.get().copied().ok_or_else(|| io::ErrorKind::AddrNotAvailable.into())
     }
 
+    /// Returns the listening address, followed by the additional listening addresses.
+    pub fn listening_addrs(&self) -> Vec<SocketAddr> {
+        self.listening_addr.get().copied().into_iter().chain(self.additional_listening_addrs.lock().clone()).collect()
+    }
+
     /// Checks whether the provided address is connected.
     pub fn is_connected(&self, addr: SocketAddr) -> bool {
         self.connections.is_connected(addr)
@@ -159,6 +172,12 @@ impl Tcp {
         self.connecting.lock().iter().copied().collect()
     }
 
+    /// Returns a reference to the bytes buffered for the connections, awaiting the rest of an inbound message.
+    #[inline]
+    pub fn inbound_buffers(&self) -> &InboundBuffers {
+        &self.inbound_buffers
+    }
+
     /// Returns a reference to the collection of statistics of known peers.
     #[inline]
     pub fn known_peers(&self) -> &KnownPeers {
@@ -204,7 +223,8 @@ impl Tcp {
     pub async fn connect(&self, addr: SocketAddr) -> io::Result<()> {
         if let Ok(listening_addr) = self.listening_addr() {
             // TODO(nkls): maybe this first check can be dropped; though it might be best to keep just in case.
-            if addr == listening_addr || self.is_self_connect(addr) {
+            let is_additional_listener = self.additional_listening_addrs.lock().contains(&addr);
+            if addr == listening_addr || is_additional_listener || self.is_self_connect(addr) {
                 error!(parent: self.span(), "Attempted to self-connect ({addr})");
                 return Err(io::ErrorKind::AddrInUse.into());
             }
@@ -263,6 +283,7 @@ impl Tcp {
         }
 
         let conn = self.connections.remove(addr);
+        self.inbound_buffers.remove(addr);
 
         if let Some(ref conn) = conn {
             debug!(parent: self.span(), "Disconnecting from {}", conn.addr());
@@ -305,6 +326,32 @@ impl Tcp {
         let listening_addr = (listener_ip, port).into();
         self.listening_addr.set(listening_addr).expect("The node's listener was started more than once");
 
+        // Accept the incoming connections.
+        self.spawn_listening_task(listener).await;
+        debug!(parent: self.span(), "Listening on {listening_addr}");
+
+        Ok(listening_addr)
+    }
+
+    /// Spawns a task that listens for incoming connections on the given address, in addition to the
+    /// listening address. The connections are handled by the same protocols as those on the main listener.
+    /// If the port is `0`, a random available port is used. Returns the bound address.
+    pub async fn enable_additional_listener(&self, addr: SocketAddr) -> io::Result<SocketAddr> {
+        // Initialize the TCP listener.
+        debug!("Creating an additional TCP listener on {addr}...");
+        let listener = TcpListener::bind(addr).await?;
+        let listening_addr = listener.local_addr()?;
+        self.additional_listening_addrs.lock().push(listening_addr);
+
+        // Accept the incoming connections.
+        self.spawn_listening_task(listener).await;
+        debug!(parent: self.span(), "Also listening on {listening_addr}");
+
+        Ok(listening_addr)
+    }
+
+    /// Spawns a task that accepts the incoming connections on the given listener.
+    async fn spawn_listening_task(&self, listener: TcpListener) {
         // Use a channel to know when the listening task is ready.
         let (tx, rx) = oneshot::channel();
 
@@ -323,9 +370,6 @@ impl Tcp {
         });
         self.tasks.lock().push(listening_task);
         let _ = rx.await;
-        debug!(parent: self.span(), "Listening on {listening_addr}");
-
-        Ok(listening_addr)
     }
 
     /// Creates an instance of `TcpListener` based on the node's configuration.
@@ -430,6 +474,9 @@ impl Tcp {
             }
         }
 
+        // Apply the configured socket options.
+        configure_stream(&stream, self.config())?;
+
         let connection = Connection::new(peer_addr, stream, !own_side);
 
         // Enact the enabled protocols.
@@ -499,11 +546,40 @@ impl fmt::Debug for Tcp {
     }
 }
 
+/// Applies the socket options of the given configuration to the given stream.
+fn configure_stream(stream: &TcpStream, config: &Config) -> io::Result<()> {
+    stream.set_nodelay(config.nodelay)?;
+
+    if let Some(keepalive) = config.keepalive {
+        let params = TcpKeepalive::new().with_time(keepalive.idle);
+        #[cfg(any(
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "ios",
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "netbsd",
+            target_os = "windows"
+        ))]
+        let params = params.with_interval(keepalive.interval);
+        SockRef::from(stream).set_tcp_keepalive(&params)?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        protocols::{Reading, Writing},
+        P2P,
+    };
 
+    use bytes::{Bytes, BytesMut};
     use std::net::{IpAddr, Ipv4Addr};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_util::codec::{Decoder, Encoder, LengthDelimitedCodec};
 
     #[tokio::test]
     async fn test_new() {
@@ -705,4 +781,163 @@ mod tests {
         assert!(tcp.is_connected(peer_ip));
         assert!(!tcp.is_connecting(peer_ip));
     }
+
+    #[tokio::test]
+    async fn test_configure_stream() {
+        // Initialize the peer.
+        let peer = Tcp::new(Config {
+            listener_ip: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            desired_listening_port: Some(0),
+            max_connections: 1,
+            ..Default::default()
+        });
+        let peer_ip = peer.enable_listener().await.unwrap();
+
+        // Check that the default configuration enables nodelay and keepalive.
+        let stream = TcpStream::connect(peer_ip).await.unwrap();
+        configure_stream(&stream, &Config::default()).unwrap();
+        assert!(stream.nodelay().unwrap());
+        assert!(SockRef::from(&stream).keepalive().unwrap());
+
+        // Check that the socket options can be disabled.
+        let stream = TcpStream::connect(peer_ip).await.unwrap();
+        configure_stream(&stream, &Config { nodelay: false, keepalive: None, ..Default::default() }).unwrap();
+        assert!(!stream.nodelay().unwrap());
+    }
+
+    /// A node that coalesces the messages queued within the given window.
+    #[derive(Clone)]
+    struct CoalescingNode(Tcp, Duration);
+
+    impl P2P for CoalescingNode {
+        fn tcp(&self) -> &Tcp {
+            &self.0
+        }
+    }
+
+    impl Writing for CoalescingNode {
+        type Codec = LengthDelimitedCodec;
+        type Message = Bytes;
+
+        fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+            Default::default()
+        }
+
+        fn coalescing_window(&self) -> Option<Duration> {
+            Some(self.1)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_coalesced_messages_are_written_at_once() {
+        const WINDOW: Duration = Duration::from_millis(200);
+
+        // Initialize a raw peer.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let peer_ip = listener.local_addr().unwrap();
+
+        // Initialize the node, and connect it to the peer.
+        let node = CoalescingNode(Tcp::new(Config::default()), WINDOW);
+        node.enable_writing().await;
+        node.tcp().connect(peer_ip).await.unwrap();
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        // Queue three messages within the window.
+        let messages = [&b"first"[..], &b"second"[..], &b"third"[..]];
+        let mut deliveries = Vec::new();
+        for message in messages {
+            deliveries.push(node.unicast(peer_ip, Bytes::from_static(message)).unwrap());
+        }
+
+        // Check that nothing is written before the window elapses.
+        let mut buffer = vec![0u8; 1024];
+        assert!(timeout(WINDOW / 2, stream.read(&mut buffer)).await.is_err());
+
+        // Check that all the messages arrive with a single write.
+        let len = timeout(WINDOW * 5, stream.read(&mut buffer)).await.unwrap().unwrap();
+        let expected_len = messages.iter().map(|message| 4 + message.len()).sum::<usize>();
+        assert_eq!(len, expected_len);
+        for delivery in deliveries {
+            delivery.await.unwrap().unwrap();
+        }
+
+        // Check that the bytes still decode as three messages.
+        let mut bytes = BytesMut::from(&buffer[..len]);
+        let mut codec = LengthDelimitedCodec::new();
+        for message in messages {
+            assert_eq!(&codec.decode(&mut bytes).unwrap().unwrap()[..], message);
+        }
+        assert!(bytes.is_empty());
+    }
+
+    /// The processing of a message by an `OrderingNode`: the sender, the message, and when it started and ended.
+    type ProcessingRecord = (SocketAddr, Bytes, std::time::Instant, std::time::Instant);
+
+    /// A node that takes the given time to process each message, and records the processing of every message.
+    #[derive(Clone)]
+    struct OrderingNode(Tcp, Duration, Arc<Mutex<Vec<ProcessingRecord>>>);
+
+    impl P2P for OrderingNode {
+        fn tcp(&self) -> &Tcp {
+            &self.0
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Reading for OrderingNode {
+        type Codec = LengthDelimitedCodec;
+        type Message = BytesMut;
+
+        fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+            Default::default()
+        }
+
+        async fn process_message(&self, source: SocketAddr, message: Self::Message) -> io::Result<()> {
+            let start = std::time::Instant::now();
+            tokio::time::sleep(self.1).await;
+            self.2.lock().push((source, message.freeze(), start, std::time::Instant::now()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_messages_are_processed_in_order_per_connection() {
+        const PROCESSING_TIME: Duration = Duration::from_millis(50);
+        const NUM_MESSAGES: u8 = 5;
+
+        // Initialize the node.
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let node = OrderingNode(Tcp::new(Config::default()), PROCESSING_TIME, records.clone());
+        node.enable_reading().await;
+        let node_ip = node.tcp().enable_listener().await.unwrap();
+
+        // Connect two raw peers, and send interleaved messages from both, all at once.
+        let mut peers = [TcpStream::connect(node_ip).await.unwrap(), TcpStream::connect(node_ip).await.unwrap()];
+        for i in 0..NUM_MESSAGES {
+            for peer in &mut peers {
+                let mut frame = BytesMut::new();
+                LengthDelimitedCodec::new().encode(Bytes::from(vec![i]), &mut frame).unwrap();
+                peer.write_all(&frame).await.unwrap();
+            }
+        }
+        // Sleep until all the messages are processed.
+        tokio::time::sleep(PROCESSING_TIME * (NUM_MESSAGES as u32 + 4)).await;
+
+        let records = records.lock().clone();
+        assert_eq!(records.len(), 2 * NUM_MESSAGES as usize);
+        for peer in &peers {
+            let peer_addr = peer.local_addr().unwrap();
+            let peer_records = records.iter().filter(|(source, ..)| *source == peer_addr).collect::<Vec<_>>();
+            // Check that the messages from the peer were processed in the order they were sent.
+            let messages = peer_records.iter().map(|(_, message, ..)| message[0]).collect::<Vec<_>>();
+            assert_eq!(messages, (0..NUM_MESSAGES).collect::<Vec<_>>());
+            // Check that each message was only processed after the previous one.
+            for pair in peer_records.windows(2) {
+                assert!(pair[1].2 >= pair[0].3);
+            }
+        }
+        // Check that the messages from the two peers were processed concurrently.
+        let (first, second) = records.iter().partition::<Vec<_>, _>(|(source, ..)| *source == records[0].0);
+        assert!(first[0].2 < second[0].3 && second[0].2 < first[0].3);
+    }
 }
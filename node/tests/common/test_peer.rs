@@ -168,13 +168,22 @@ impl Handshake for TestPeer {
     }
 }
 
+/// Returns a codec oriented for the given side of the connection, as the test peers are on the latest version.
+fn oriented_codec(side: ConnectionSide) -> MessageCodec<CurrentNetwork> {
+    let side = match side {
+        ConnectionSide::Initiator => snarkos_node_tcp::ConnectionSide::Initiator,
+        ConnectionSide::Responder => snarkos_node_tcp::ConnectionSide::Responder,
+    };
+    MessageCodec::default().with_side(Some(side))
+}
+
 #[async_trait::async_trait]
 impl Writing for TestPeer {
     type Codec = MessageCodec<CurrentNetwork>;
     type Message = Message<CurrentNetwork>;
 
-    fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, _addr: SocketAddr, side: ConnectionSide) -> Self::Codec {
+        oriented_codec(side)
     }
 }
 
@@ -183,8 +192,8 @@ impl Reading for TestPeer {
     type Codec = MessageCodec<CurrentNetwork>;
     type Message = Message<CurrentNetwork>;
 
-    fn codec(&self, _peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, _peer_addr: SocketAddr, side: ConnectionSide) -> Self::Codec {
+        oriented_codec(side)
     }
 
     async fn process_message(&self, _peer_ip: SocketAddr, _message: Self::Message) -> io::Result<()> {